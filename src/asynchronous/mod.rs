@@ -0,0 +1,45 @@
+pub mod resolver;
+pub mod server;
+
+use crate::packet::framing::frame;
+use std::io::{self, ErrorKind};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Reads one length prefixed message, like read_frame. Returns None when
+/// the peer closed the stream between messages.
+pub(crate) async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 2];
+    match stream.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+
+    let mut message = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message).await?;
+
+    Ok(Some(message))
+}
+
+/// Writes one length prefixed message in a single write and flushes it.
+pub(crate) async fn write_frame(stream: &mut (impl AsyncWrite + Unpin), message: &[u8]) -> io::Result<()> {
+    stream.write_all(&frame(message)?).await?;
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod asynchronous_unittest {
+    use crate::asynchronous::{read_frame, write_frame};
+
+    #[tokio::test]
+    async fn round_trip_frames() {
+        let mut stream = Vec::new();
+        write_frame(&mut stream, &[1, 2, 3]).await.unwrap();
+        assert_eq!(stream, vec![0, 3, 1, 2, 3]);
+
+        let mut reader = &stream[..];
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+        assert!(read_frame(&mut &[0, 3, 1][..]).await.is_err());
+    }
+}
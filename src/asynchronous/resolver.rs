@@ -0,0 +1,283 @@
+use crate::asynchronous::{read_frame, write_frame};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::message::Message;
+use crate::records::query_type::QueryType;
+use crate::resolver::exchange::parse_response;
+use crate::resolver::{build_query, random_id, ResolverError};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+
+/// Random ids tried for a query before giving up, so a server with every
+/// id in flight fails the query instead of looping forever.
+const ID_ATTEMPTS: usize = 64;
+
+/// Pause after a failed receive, so a socket that keeps failing does not
+/// spin the receiving task.
+const RECEIVE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Queries waiting for a datagram, by server and the id they went out
+/// with.
+type Pending = Mutex<HashMap<(SocketAddr, u16), (Message, oneshot::Sender<Message>)>>;
+
+/// A stub resolver asking recursive servers on behalf of async code. All
+/// queries share one UDP socket: each goes out with an id no other query
+/// to the same server is using, and a background task hands every
+/// datagram to the query it answers. Truncated responses are retried
+/// over TCP.
+///
+/// Dropping a query future at any await point forgets the query, and a
+/// late response is then dropped like a spoofed one.
+pub struct Resolver {
+    socket: Arc<UdpSocket>,
+    pending: Arc<Pending>,
+    receiver: JoinHandle<()>,
+    upstreams: Vec<SocketAddr>,
+    timeout: Duration,
+}
+
+impl Resolver {
+    /// Binds the shared socket, which must be of the family of the
+    /// servers asked. Must be called within a runtime.
+    pub async fn bind(address: impl ToSocketAddrs, upstreams: Vec<SocketAddr>) -> io::Result<Self> {
+        let socket = Arc::new(UdpSocket::bind(address).await?);
+        let pending = Arc::new(Pending::default());
+        let receiver = tokio::spawn(receive(Arc::clone(&socket), Arc::clone(&pending)));
+
+        Ok(Self {
+            socket,
+            pending,
+            receiver,
+            upstreams,
+            timeout: Duration::from_secs(2),
+        })
+    }
+
+    /// How long to wait for each server before giving up on it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Asks the upstreams in order, with recursion desired, until one
+    /// responds.
+    pub async fn query(&self, qname: &Fqdn, qtype: QueryType) -> io::Result<Message> {
+        let query = build_query(qname, qtype, true);
+
+        let mut error = io::Error::new(ErrorKind::NotFound, "no upstream to ask");
+        for upstream in &self.upstreams {
+            match self.exchange(*upstream, &query).await {
+                Ok(response) => return Ok(response),
+                Err(failure) => error = failure,
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Sends one query to one server, like an Exchanger. The response
+    /// carries the id of the query whatever id went on the wire.
+    pub async fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let response = timeout(self.timeout, self.exchange_udp(server, query)).await.map_err(|_| timed_out())??;
+        if !response.header().flags().is_truncated() {
+            return Ok(response);
+        }
+
+        timeout(self.timeout, exchange_tcp(server, query)).await.map_err(|_| timed_out())?
+    }
+
+    async fn exchange_udp(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let (id, response) = self.register(server, query)?;
+        let mut wire = query.wire();
+        wire[..2].copy_from_slice(&id.to_be_bytes());
+
+        // Dropped on any return, so cancelled queries do not linger
+        let _registration = Registration {
+            pending: &self.pending,
+            key: (server, id),
+        };
+        self.socket.send_to(&wire, server).await?;
+
+        response.await.map_err(|_| io::Error::new(ErrorKind::BrokenPipe, "resolver stopped receiving"))
+    }
+
+    /// Picks an id free for the server and waits on it.
+    fn register(&self, server: SocketAddr, query: &Message) -> io::Result<(u16, oneshot::Receiver<Message>)> {
+        let (sender, receiver) = oneshot::channel();
+        let mut pending = self.pending.lock().unwrap();
+
+        let id = std::iter::repeat_with(random_id)
+            .take(ID_ATTEMPTS)
+            .find(|id| !pending.contains_key(&(server, *id)))
+            .ok_or_else(|| io::Error::other(ResolverError::NoFreeId(server)))?;
+        pending.insert((server, id), (query.clone(), sender));
+
+        Ok((id, receiver))
+    }
+}
+
+impl Drop for Resolver {
+    fn drop(&mut self) {
+        self.receiver.abort();
+    }
+}
+
+/// Removes a query from the pending ones when its future ends or is
+/// dropped.
+struct Registration<'a> {
+    pending: &'a Pending,
+    key: (SocketAddr, u16),
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Hands datagrams to the queries they answer. Datagrams with an unknown
+/// id or another question are dropped, as they are either late or
+/// spoofed.
+async fn receive(socket: Arc<UdpSocket>, pending: Arc<Pending>) {
+    let mut buffer = vec![0u8; u16::MAX as usize];
+    loop {
+        let Ok((length, peer)) = socket.recv_from(&mut buffer).await else {
+            sleep(RECEIVE_BACKOFF).await;
+            continue;
+        };
+        let Some((id, _)) = buffer[..length].split_first_chunk::<2>() else { continue };
+        let key = (peer, u16::from_be_bytes(*id));
+
+        let mut pending = pending.lock().unwrap();
+        let Some((query, _)) = pending.get(&key) else { continue };
+        buffer[..2].copy_from_slice(&query.header().id().to_be_bytes());
+        if let Some(response) = parse_response(&buffer[..length], query) {
+            let (_, sender) = pending.remove(&key).unwrap();
+            let _ = sender.send(response);
+        }
+    }
+}
+
+async fn exchange_tcp(server: SocketAddr, query: &Message) -> io::Result<Message> {
+    let mut stream = TcpStream::connect(server).await?;
+    write_frame(&mut stream, &query.wire()).await?;
+
+    let wire = read_frame(&mut stream).await?.ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+    parse_response(&wire, query).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "response does not match the query"))
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(ErrorKind::TimedOut, "no response from name server")
+}
+
+#[cfg(test)]
+mod async_resolver_unittest {
+    use crate::asynchronous::resolver::Resolver;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::records::query_type::QueryType;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::resolver_unittest::query;
+    use crate::resolver::ResolverError;
+    use crate::server::handler::handler_unittest::catalog;
+    use crate::server::Server;
+    use crate::zone::zone_unittest::{name, records};
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::Arc;
+    use std::thread;
+    use std::io::ErrorKind;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+    use tokio::task::JoinSet;
+
+    fn start() -> SocketAddr {
+        let mut catalog = catalog();
+        let mut zone = catalog.get(&name("example.com.")).unwrap().clone();
+        let text: String = (1..=40).map(|host| format!("big A 192.0.2.{}\n", host)).collect();
+        for record in records(&text) {
+            zone.insert(record).unwrap();
+        }
+        catalog.insert(zone);
+        let server = Server::bind("127.0.0.1:0", catalog).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        address
+    }
+
+    #[tokio::test]
+    async fn multiplex_queries_on_one_socket() {
+        let address = start();
+        let resolver = Arc::new(Resolver::bind("127.0.0.1:0", vec![address]).await.unwrap());
+
+        let mut queries = JoinSet::new();
+        for round in 0..50 {
+            let resolver = Arc::clone(&resolver);
+            let qname = ["www.example.com.", "missing.example.com."][round % 2];
+            queries.spawn(async move { (qname, resolver.query(&name(qname), QueryType::Type(RecordType::A)).await.unwrap()) });
+        }
+
+        while let Some(answered) = queries.join_next().await {
+            let (qname, response) = answered.unwrap();
+            assert_eq!(response.question().qname(), &name(qname));
+            let expected = if qname.starts_with("www") { Rcode::NoError } else { Rcode::NameError };
+            assert_eq!(response.header().flags().response_code(), expected);
+        }
+        assert!(resolver.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_truncated_over_tcp() {
+        let address = start();
+        let resolver = Resolver::bind("127.0.0.1:0", vec![address]).await.unwrap();
+
+        let query = query("big.example.com.", RecordType::A);
+        let response = resolver.exchange(address, &query).await.unwrap();
+        assert_eq!(response.header().id(), query.header().id());
+        assert!(!response.header().flags().is_truncated());
+        assert_eq!(response.answers().len(), 40);
+    }
+
+    #[tokio::test]
+    async fn forget_cancelled_queries() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = start();
+        let resolver = Resolver::bind("127.0.0.1:0", vec![silent.local_addr().unwrap(), address]).await.unwrap();
+
+        let query = query("www.example.com.", RecordType::A);
+        let cancelled = tokio::time::timeout(Duration::from_millis(50), resolver.exchange(silent.local_addr().unwrap(), &query)).await;
+        assert!(cancelled.is_err());
+        assert!(resolver.pending.lock().unwrap().is_empty());
+
+        let resolver = resolver.timeout(Duration::from_millis(100));
+        let response = resolver.query(&name("www.example.com."), QueryType::Type(RecordType::A)).await.unwrap();
+        assert_eq!(response.answers().len(), 1);
+        assert!(resolver.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fail_when_every_id_is_in_flight() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let resolver = Resolver::bind("127.0.0.1:0", vec![silent]).await.unwrap();
+        let query = query("www.example.com.", RecordType::A);
+        {
+            let mut pending = resolver.pending.lock().unwrap();
+            for id in 0..=u16::MAX {
+                pending.insert((silent, id), (query.clone(), oneshot::channel().0));
+            }
+        }
+
+        let error = resolver.exchange(silent, &query).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        assert_eq!(error.into_inner().unwrap().downcast_ref(), Some(&ResolverError::NoFreeId(silent)));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::asynchronous::{read_frame, write_frame};
+use crate::server::handler::{respond_datagram, respond_stream, Handler};
+use crate::server::{ServerResult, TCP_IDLE_TIMEOUT};
+use std::future::{self, Future};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout};
+
+/// Datagrams answered at once, and separately connections open at once,
+/// unless set otherwise. Past it datagrams are dropped, for the client to
+/// retry, and connections closed.
+const MAX_IN_FLIGHT: usize = 1024;
+
+/// Pause after a failed receive or accept, so a socket that keeps failing
+/// does not spin the serving loop.
+const RECEIVE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// The async counterpart of the server: a handler answering over UDP and
+/// TCP on the same address, with every datagram and connection in a task
+/// of its own and the handler itself on blocking threads.
+pub struct Server {
+    udp: Arc<UdpSocket>,
+    tcp: TcpListener,
+    handler: Arc<dyn Handler>,
+    max_in_flight: usize,
+}
+
+impl Server {
+    /// Binds TCP first and UDP on the address TCP got, so port 0 gives
+    /// both sockets the same ephemeral port.
+    pub async fn bind(address: impl ToSocketAddrs, handler: impl Handler + 'static) -> ServerResult<Self> {
+        let tcp = TcpListener::bind(address).await?;
+        let udp = UdpSocket::bind(tcp.local_addr()?).await?;
+
+        Ok(Self {
+            udp: Arc::new(udp),
+            tcp,
+            handler: Arc::new(handler),
+            max_in_flight: MAX_IN_FLIGHT,
+        })
+    }
+
+    /// Bounds the datagrams being answered, and separately the open
+    /// connections, so a flood cannot pile up tasks without limit.
+    pub fn max_in_flight(mut self, limit: usize) -> Self {
+        self.max_in_flight = limit;
+        self
+    }
+
+    pub fn local_addr(&self) -> ServerResult<SocketAddr> {
+        Ok(self.tcp.local_addr()?)
+    }
+
+    /// Serves forever.
+    pub async fn run(self) -> ServerResult<()> {
+        self.run_until(future::pending()).await
+    }
+
+    /// Serves until the shutdown future completes, then shuts down
+    /// gracefully: no new datagram or connection is taken, queries already
+    /// read are answered, idle connections are closed, and this returns
+    /// once every task is done.
+    pub async fn run_until(self, shutdown: impl Future<Output = ()>) -> ServerResult<()> {
+        let (stop, stopping) = watch::channel(false);
+        let mut tasks = JoinSet::new();
+        let mut buffer = vec![0u8; u16::MAX as usize];
+        let datagrams = Arc::new(Semaphore::new(self.max_in_flight));
+        let connections = Arc::new(Semaphore::new(self.max_in_flight));
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                received = self.udp.recv_from(&mut buffer) => {
+                    // A failed receive only loses that datagram
+                    let Ok((length, peer)) = received else {
+                        sleep(RECEIVE_BACKOFF).await;
+                        continue;
+                    };
+                    let Ok(permit) = Arc::clone(&datagrams).try_acquire_owned() else { continue };
+                    tasks.spawn(serve_datagram(Arc::clone(&self.udp), buffer[..length].to_vec(), peer, Arc::clone(&self.handler), permit));
+                }
+                accepted = self.tcp.accept() => {
+                    // A failed accept only loses that connection
+                    let Ok((stream, _)) = accepted else {
+                        sleep(RECEIVE_BACKOFF).await;
+                        continue;
+                    };
+                    let Ok(permit) = Arc::clone(&connections).try_acquire_owned() else { continue };
+                    tasks.spawn(serve_tcp(stream, Arc::clone(&self.handler), stopping.clone(), permit));
+                }
+                // Reaps finished tasks so they do not pile up
+                Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+            }
+        }
+
+        drop(self.tcp);
+        let _ = stop.send(true);
+        while tasks.join_next().await.is_some() {}
+
+        Ok(())
+    }
+}
+
+/// Holds its permit until the response is sent.
+async fn serve_datagram(
+    socket: Arc<UdpSocket>,
+    wire: Vec<u8>,
+    peer: SocketAddr,
+    handler: Arc<dyn Handler>,
+    _permit: OwnedSemaphorePermit,
+) -> io::Result<()> {
+    // The handler may take its time, so it runs off the runtime
+    let response = tokio::task::spawn_blocking(move || respond_datagram(handler.as_ref(), &wire, peer.ip()))
+        .await
+        .map_err(io::Error::other)?;
+    if let Some(response) = response {
+        // The client may be gone; that is no reason to stop serving
+        let _ = socket.send_to(&response, peer).await;
+    }
+
+    Ok(())
+}
+
+/// Answers length prefixed queries until the peer closes the stream, it
+/// stays idle too long or the server shuts down between queries.
+async fn serve_tcp(
+    mut stream: TcpStream,
+    handler: Arc<dyn Handler>,
+    mut stopping: watch::Receiver<bool>,
+    _permit: OwnedSemaphorePermit,
+) -> io::Result<()> {
+    let peer = stream.peer_addr()?.ip();
+    loop {
+        let query = tokio::select! {
+            query = timeout(TCP_IDLE_TIMEOUT, read_frame(&mut stream)) => match query {
+                Ok(query) => query?,
+                Err(_) => None,
+            },
+            _ = stopping.wait_for(|stop| *stop) => None,
+        };
+        let Some(query) = query else { return Ok(()) };
+
+        let handler = Arc::clone(&handler);
+        let responses = tokio::task::spawn_blocking(move || respond_stream(handler.as_ref(), &query, peer))
+            .await
+            .map_err(io::Error::other)?;
+        for response in responses {
+            write_frame(&mut stream, &response).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod async_server_unittest {
+    use crate::asynchronous::resolver::Resolver;
+    use crate::asynchronous::server::Server;
+    use crate::asynchronous::{read_frame, write_frame};
+    use crate::packet::headers::header_flags::{Opcode, Rcode};
+    use crate::records::query_type::QueryType;
+    use crate::records::record_type::RecordType;
+    use crate::packet::message::Message;
+    use crate::server::handler::handler_unittest::{catalog, parse, query};
+    use crate::server::handler::Handler;
+    use crate::zone::zone_unittest::name;
+    use std::thread;
+    use std::time::Duration;
+    use tokio::net::{TcpStream, UdpSocket};
+    use tokio::sync::oneshot;
+    use tokio::time::timeout;
+
+    /// Takes its time over names at and below slow.example.com, like a
+    /// forwarder waiting on an upstream.
+    struct Slow;
+
+    impl Handler for Slow {
+        fn handle(&self, query: &Message) -> Message {
+            if query.question().qname().is_subdomain_of(&name("slow.example.com.")) {
+                thread::sleep(Duration::from_secs(2));
+            }
+            catalog().handle(query)
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_udp_and_tcp() {
+        let server = Server::bind("127.0.0.1:0", catalog()).await.unwrap();
+        let address = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let resolver = Resolver::bind("127.0.0.1:0", vec![address]).await.unwrap();
+        let response = resolver.query(&name("www.example.com."), QueryType::Type(RecordType::A)).await.unwrap();
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        for (id, qname) in [(1, "www.example.com."), (2, "example.org.")] {
+            write_frame(&mut stream, &query(id, Opcode::Query, qname, RecordType::A)).await.unwrap();
+        }
+        let found = parse(&read_frame(&mut stream).await.unwrap().unwrap());
+        let refused = parse(&read_frame(&mut stream).await.unwrap().unwrap());
+        assert_eq!(found.header().id(), 1);
+        assert_eq!(found.answers().len(), 1);
+        assert_eq!(refused.header().id(), 2);
+        assert_eq!(refused.header().flags().response_code(), Rcode::Refused);
+    }
+
+    #[tokio::test]
+    async fn shut_down_gracefully() {
+        let server = Server::bind("127.0.0.1:0", catalog()).await.unwrap();
+        let address = server.local_addr().unwrap();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let running = tokio::spawn(server.run_until(async move {
+            let _ = signal.await;
+        }));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        write_frame(&mut stream, &query(1, Opcode::Query, "www.example.com.", RecordType::A)).await.unwrap();
+        assert_eq!(parse(&read_frame(&mut stream).await.unwrap().unwrap()).answers().len(), 1);
+
+        shutdown.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), running).await.unwrap().unwrap().unwrap();
+        assert_eq!(read_frame(&mut stream).await.unwrap(), None);
+        assert!(TcpStream::connect(address).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn slow_query_holds_up_no_other() {
+        let server = Server::bind("127.0.0.1:0", Slow).await.unwrap();
+        let address = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(&query(1, Opcode::Query, "slow.example.com.", RecordType::A), address).await.unwrap();
+        socket.send_to(&query(2, Opcode::Query, "www.example.com.", RecordType::A), address).await.unwrap();
+
+        let mut buffer = [0u8; 512];
+        let (length, _) = timeout(Duration::from_secs(1), socket.recv_from(&mut buffer)).await.unwrap().unwrap();
+        assert_eq!(parse(&buffer[..length]).header().id(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn drop_datagrams_past_the_limit() {
+        let server = Server::bind("127.0.0.1:0", Slow).await.unwrap().max_in_flight(1);
+        let address = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(&query(1, Opcode::Query, "slow.example.com.", RecordType::A), address).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        socket.send_to(&query(2, Opcode::Query, "www.example.com.", RecordType::A), address).await.unwrap();
+
+        let mut buffer = [0u8; 512];
+        let (length, _) = timeout(Duration::from_secs(5), socket.recv_from(&mut buffer)).await.unwrap().unwrap();
+        assert_eq!(parse(&buffer[..length]).header().id(), 1);
+        assert!(timeout(Duration::from_millis(500), socket.recv_from(&mut buffer)).await.is_err());
+    }
+}
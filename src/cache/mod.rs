@@ -0,0 +1,611 @@
+use crate::clock::Clock;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Rcode};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::record::Record;
+use crate::records::query_class::QueryClass;
+use crate::records::query_type::QueryType;
+use crate::records::record_class::RecordClass;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// TTL of records served stale (RFC 8767 section 4).
+const STALE_TTL: u32 = 30;
+
+/// Share of the original TTL left when a popular entry is due for a
+/// prefetch, in percent.
+const PREFETCH_PERCENT: u64 = 10;
+
+/// CNAMEs followed when answering a query from the cache.
+const MAX_CNAME_CHAIN: usize = 8;
+
+/// What the cache holds for a name and type.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Cached {
+    Records(Vec<Record>),
+    /// The name does not exist, proven by the SOA of its zone.
+    NameError(Record),
+    /// The name exists without records of the type.
+    NoData(Record),
+}
+
+impl Cached {
+    fn with_ttl(&self, ttl: u32) -> Self {
+        let with_ttl = |record: &Record| {
+            let mut record = record.clone();
+            record.set_ttl(ttl);
+            record
+        };
+
+        match self {
+            Cached::Records(records) => Cached::Records(records.iter().map(with_ttl).collect()),
+            Cached::NameError(soa) => Cached::NameError(with_ttl(soa)),
+            Cached::NoData(soa) => Cached::NoData(with_ttl(soa)),
+        }
+    }
+}
+
+/// A cache lookup result with TTLs counted down to the time of the
+/// lookup.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CacheHit {
+    cached: Cached,
+    stale: bool,
+}
+
+impl CacheHit {
+    pub fn cached(&self) -> &Cached {
+        &self.cached
+    }
+
+    pub fn into_cached(self) -> Cached {
+        self.cached
+    }
+
+    /// Whether the entry had expired and is served under RFC 8767.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+}
+
+/// An RRset or negative answer. A name error is held once for the whole
+/// name, without a type, as it applies to every type (RFC 2308 section 5).
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Key {
+    name: Fqdn,
+    class: RecordClass,
+    record_type: Option<RecordType>,
+}
+
+struct Entry {
+    cached: Cached,
+    stored_at: u64,
+    ttl: u32,
+    hits: u32,
+    tick: u64,
+    prefetch_queued: bool,
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<Key, Entry>,
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+    prefetch: Vec<(Fqdn, RecordType, RecordClass)>,
+}
+
+/// A TTL-aware cache of RRsets and negative answers, safe to share
+/// between threads.
+///
+/// Entries count down from the time they were stored and come out with
+/// the remaining TTL. Once expired they may still be served stale for a
+/// while when fresh data cannot be had. The least recently used entry
+/// makes room when the cache is full. Entries looked up often that are
+/// close to expiry are queued so that the owner of the cache can refresh
+/// them before they expire.
+pub struct Cache {
+    clock: Arc<dyn Clock>,
+    capacity: usize,
+    max_ttl: u32,
+    stale_window: u32,
+    prefetch_hits: u32,
+    entries: Mutex<Entries>,
+}
+
+impl Cache {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            capacity: 10_000,
+            max_ttl: 604_800,
+            stale_window: 86_400,
+            prefetch_hits: 3,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Number of RRsets and negative answers kept.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Longest TTL honoured; longer ones are cut down to this.
+    pub fn max_ttl(mut self, max_ttl: u32) -> Self {
+        self.max_ttl = max_ttl;
+        self
+    }
+
+    /// How long after expiry an entry may be served stale. Zero turns
+    /// serve-stale off.
+    pub fn serve_stale(mut self, stale_window: u32) -> Self {
+        self.stale_window = stale_window;
+        self
+    }
+
+    /// Hits after which an entry near expiry is queued for a prefetch.
+    pub fn prefetch_hits(mut self, prefetch_hits: u32) -> Self {
+        self.prefetch_hits = prefetch_hits;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stores records, grouped into RRsets by owner, type and class. An
+    /// RRset lives as long as its shortest TTL and zero TTLs are not
+    /// stored. A name that gets records no longer has a name error.
+    pub fn insert_records(&self, records: &[Record]) {
+        let mut rrsets: Vec<(Key, Vec<Record>)> = Vec::new();
+        for record in records {
+            let key = Key {
+                name: record.owner_name().clone(),
+                class: record.class(),
+                record_type: Some(record.record_type()),
+            };
+            match rrsets.iter_mut().find(|(existing, _)| existing == &key) {
+                Some((_, rrset)) => rrset.push(record.clone()),
+                None => rrsets.push((key, vec![record.clone()])),
+            }
+        }
+
+        for (key, rrset) in rrsets {
+            let ttl = rrset.iter().map(Record::ttl).min().unwrap_or(0);
+            self.remove(&Key {
+                record_type: None,
+                ..key.clone()
+            });
+            self.store(key, Cached::Records(rrset), ttl);
+        }
+    }
+
+    /// Stores a name error or no data answer. It lives for the lower of
+    /// the SOA TTL and its MINIMUM field (RFC 2308 section 5).
+    pub fn insert_negative(&self, name: &Fqdn, record_type: RecordType, class: RecordClass, response_code: Rcode, soa: &Record) {
+        let RecordData::SOA(soa_data) = soa.data() else {
+            return;
+        };
+        let ttl = soa.ttl().min(soa_data.minimum());
+
+        let (record_type, cached) = match response_code {
+            Rcode::NameError => (None, Cached::NameError(soa.clone())),
+            _ => (Some(record_type), Cached::NoData(soa.clone())),
+        };
+        let key = Key {
+            name: name.clone(),
+            class,
+            record_type,
+        };
+        self.store(key, cached, ttl);
+    }
+
+    /// Stores what a response says about its question: the RRsets along
+    /// the CNAME chain from the question name and, when the authority
+    /// section has the SOA of the zone at the end of the chain, the
+    /// negative answer for that name. Other records in the answer are not
+    /// trusted, as any server could have added them (RFC 2181 section 5.4.1).
+    pub fn insert_response(&self, response: &Message) {
+        let response_code = response.header().flags().response_code();
+        if !matches!(response_code, Rcode::NoError | Rcode::NameError) {
+            return;
+        }
+
+        let question = response.question();
+        let (QueryType::Type(record_type), QueryClass::Class(class)) = (question.qtype(), question.qclass()) else {
+            return;
+        };
+        let mut chain: Vec<Record> = Vec::new();
+        let mut name = question.qname().clone();
+        for _ in 0..MAX_CNAME_CHAIN {
+            let owned = |wanted: RecordType| -> Vec<Record> {
+                response
+                    .answers()
+                    .iter()
+                    .filter(|record| record.owner_name() == &name && record.record_type() == wanted && record.class() == class)
+                    .cloned()
+                    .collect()
+            };
+            let rrset = owned(record_type);
+            if !rrset.is_empty() {
+                chain.extend(rrset);
+                self.insert_records(&chain);
+                return;
+            }
+            let cnames = owned(RecordType::CNAME);
+            match cnames.first().map(Record::data) {
+                Some(RecordData::CNAME(cname)) => name = cname.canonical_name().clone(),
+                _ => break,
+            }
+            chain.extend(cnames);
+        }
+        self.insert_records(&chain);
+
+        let soa = response
+            .authorities()
+            .iter()
+            .find(|record| record.record_type() == RecordType::SOA && name.is_subdomain_of(record.owner_name()));
+        if let Some(soa) = soa {
+            self.insert_negative(&name, record_type, class, response_code, soa);
+        }
+    }
+
+    /// The fresh entry for the name and type, or the name error for the
+    /// name.
+    pub fn get(&self, name: &Fqdn, record_type: RecordType, class: RecordClass) -> Option<CacheHit> {
+        self.lookup(name, record_type, class, false)
+    }
+
+    /// Like `get`, but falls back to expired entries within the stale
+    /// window. For use when fresh data could not be fetched.
+    pub fn get_stale(&self, name: &Fqdn, record_type: RecordType, class: RecordClass) -> Option<CacheHit> {
+        self.lookup(name, record_type, class, true)
+    }
+
+    /// A response to the query built from fresh entries only, following
+    /// CNAMEs through the cache. None if the cache cannot answer it all.
+    pub fn answer(&self, query: &Message) -> Option<Message> {
+        let question = query.question();
+        let (QueryType::Type(record_type), QueryClass::Class(class)) = (question.qtype(), question.qclass()) else {
+            return None;
+        };
+
+        let mut answers: Vec<Record> = Vec::new();
+        let mut name = question.qname().clone();
+        for _ in 0..=MAX_CNAME_CHAIN {
+            if let Some(hit) = self.get(&name, record_type, class) {
+                let (response_code, authorities) = match hit.into_cached() {
+                    Cached::Records(records) => {
+                        answers.extend(records);
+                        (Rcode::NoError, Vec::new())
+                    }
+                    Cached::NameError(soa) => (Rcode::NameError, vec![soa]),
+                    Cached::NoData(soa) => (Rcode::NoError, vec![soa]),
+                };
+                return Some(response(query, response_code, answers, authorities));
+            }
+
+            let Some(Cached::Records(cnames)) = self.get(&name, RecordType::CNAME, class).map(CacheHit::into_cached) else {
+                return None;
+            };
+            let RecordData::CNAME(cname) = cnames[0].data() else {
+                return None;
+            };
+            name = cname.canonical_name().clone();
+            answers.extend(cnames);
+        }
+
+        None
+    }
+
+    /// Entries found worth refreshing since the last call.
+    pub fn take_prefetch(&self) -> Vec<(Fqdn, RecordType, RecordClass)> {
+        std::mem::take(&mut self.lock().prefetch)
+    }
+
+    fn lookup(&self, name: &Fqdn, record_type: RecordType, class: RecordClass, allow_stale: bool) -> Option<CacheHit> {
+        let now = self.clock.now();
+        let mut guard = self.lock();
+        let Entries {
+            entries,
+            recency,
+            tick,
+            prefetch,
+        } = &mut *guard;
+
+        for record_type in [Some(record_type), None] {
+            let key = Key {
+                name: name.clone(),
+                class,
+                record_type,
+            };
+            let Some(entry) = entries.get_mut(&key) else {
+                continue;
+            };
+
+            let elapsed = now.saturating_sub(entry.stored_at);
+            let fresh = elapsed < u64::from(entry.ttl);
+            if !fresh && elapsed >= u64::from(entry.ttl) + u64::from(self.stale_window) {
+                recency.remove(&entry.tick);
+                entries.remove(&key);
+                continue;
+            }
+            if !fresh && !allow_stale {
+                continue;
+            }
+
+            recency.remove(&entry.tick);
+            *tick += 1;
+            entry.tick = *tick;
+            recency.insert(*tick, key.clone());
+            entry.hits = entry.hits.saturating_add(1);
+
+            let remaining = match fresh {
+                true => entry.ttl - elapsed as u32,
+                false => STALE_TTL,
+            };
+            let due = u64::from(remaining) * 100 <= u64::from(entry.ttl) * PREFETCH_PERCENT;
+            if fresh && due && entry.hits >= self.prefetch_hits && !entry.prefetch_queued {
+                entry.prefetch_queued = true;
+                if let Some(record_type) = key.record_type {
+                    prefetch.push((key.name.clone(), record_type, class));
+                }
+            }
+
+            return Some(CacheHit {
+                cached: entry.cached.with_ttl(remaining),
+                stale: !fresh,
+            });
+        }
+
+        None
+    }
+
+    fn store(&self, key: Key, cached: Cached, ttl: u32) {
+        let ttl = ttl.min(self.max_ttl);
+        if ttl == 0 {
+            return;
+        }
+
+        let stored_at = self.clock.now();
+        let mut entries = self.lock();
+        entries.tick += 1;
+        let tick = entries.tick;
+        let entry = Entry {
+            cached,
+            stored_at,
+            ttl,
+            hits: 0,
+            tick,
+            prefetch_queued: false,
+        };
+        if let Some(replaced) = entries.entries.insert(key.clone(), entry) {
+            entries.recency.remove(&replaced.tick);
+        }
+        entries.recency.insert(tick, key);
+
+        while entries.entries.len() > self.capacity {
+            let Some((_, oldest)) = entries.recency.pop_first() else {
+                break;
+            };
+            entries.entries.remove(&oldest);
+        }
+    }
+
+    fn remove(&self, key: &Key) {
+        let mut entries = self.lock();
+        if let Some(removed) = entries.entries.remove(key) {
+            entries.recency.remove(&removed.tick);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn response(query: &Message, response_code: Rcode, answers: Vec<Record>, authorities: Vec<Record>) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .response(query.header().flags())
+        .authoritative_answer(false)
+        .truncation(false)
+        .recursion_available(true)
+        .response_code(response_code)
+        .build();
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(flags)
+        .question_count(1)
+        .answer_count(answers.len() as u16)
+        .authoritative_count(authorities.len() as u16)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .answer(answers)
+        .authority(authorities)
+        .build()
+}
+
+#[cfg(test)]
+mod cache_unittest {
+    use crate::cache::{Cache, Cached};
+    use crate::clock::ManualClock;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::message::MessageBuilder;
+    use crate::packet::record::Record;
+    use crate::records::record_class::RecordClass;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::resolver_unittest::query;
+    use crate::zone::zone_unittest::{example_zone, name};
+    use crate::zonefile::parser::ZoneParser;
+    use std::sync::Arc;
+
+    fn records(text: &str) -> Vec<Record> {
+        ZoneParser::new(name("example.com.")).parse_str("cache", text).unwrap()
+    }
+
+    fn cache(clock: &Arc<ManualClock>) -> Cache {
+        Cache::new(clock.clone())
+    }
+
+    fn ttls(cache: &Cache, owner: &str, record_type: RecordType) -> Option<Vec<u32>> {
+        match cache.get(&name(owner), record_type, RecordClass::IN)?.into_cached() {
+            Cached::Records(records) => Some(records.iter().map(Record::ttl).collect()),
+            Cached::NameError(soa) | Cached::NoData(soa) => Some(vec![soa.ttl()]),
+        }
+    }
+
+    #[test]
+    fn decay_ttl() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock).serve_stale(0);
+        cache.insert_records(&records("www 300 A 192.0.2.1\nwww 600 A 192.0.2.2\nwww 60 AAAA 2001:db8::1\n"));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(ttls(&cache, "www.example.com.", RecordType::A), Some(vec![300, 300]));
+        clock.advance(100);
+        assert_eq!(ttls(&cache, "www.example.com.", RecordType::A), Some(vec![200, 200]));
+        assert_eq!(ttls(&cache, "www.example.com.", RecordType::AAAA), None);
+        clock.advance(200);
+        assert_eq!(ttls(&cache, "www.example.com.", RecordType::A), None);
+        assert!(cache.is_empty());
+
+        cache.insert_records(&records("zero 0 A 192.0.2.1\n"));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cap_ttl() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock).max_ttl(3600);
+        cache.insert_records(&records("www 86400 A 192.0.2.1\n"));
+
+        assert_eq!(ttls(&cache, "www.example.com.", RecordType::A), Some(vec![3600]));
+    }
+
+    #[test]
+    fn serve_stale() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock).serve_stale(3600);
+        cache.insert_records(&records("www 300 A 192.0.2.1\n"));
+        let www = name("www.example.com.");
+
+        clock.advance(400);
+        assert!(cache.get(&www, RecordType::A, RecordClass::IN).is_none());
+        let stale = cache.get_stale(&www, RecordType::A, RecordClass::IN).unwrap();
+        assert!(stale.is_stale());
+        assert_eq!(stale.cached(), &Cached::Records(records("www 30 A 192.0.2.1\n")));
+
+        clock.advance(3600);
+        assert!(cache.get_stale(&www, RecordType::A, RecordClass::IN).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn negative_caching() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock);
+        let soa = records("@ 3600 SOA ns1 hostmaster 1 7200 3600 1209600 300\n").remove(0);
+
+        cache.insert_negative(&name("missing.example.com."), RecordType::A, RecordClass::IN, Rcode::NameError, &soa);
+        cache.insert_negative(&name("www.example.com."), RecordType::AAAA, RecordClass::IN, Rcode::NoError, &soa);
+
+        let missing = cache.get(&name("missing.example.com."), RecordType::DS, RecordClass::IN).unwrap();
+        assert!(matches!(missing.cached(), Cached::NameError(soa) if soa.ttl() == 300));
+        let nodata = cache.get(&name("www.example.com."), RecordType::AAAA, RecordClass::IN).unwrap();
+        assert!(matches!(nodata.cached(), Cached::NoData(_)));
+        assert!(cache.get(&name("www.example.com."), RecordType::A, RecordClass::IN).is_none());
+
+        cache.insert_records(&records("missing 60 A 192.0.2.1\n"));
+        assert_eq!(ttls(&cache, "missing.example.com.", RecordType::A), Some(vec![60]));
+        assert!(cache.get(&name("missing.example.com."), RecordType::DS, RecordClass::IN).is_none());
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock).capacity(2);
+
+        cache.insert_records(&records("a 300 A 192.0.2.1\nb 300 A 192.0.2.2\n"));
+        assert!(ttls(&cache, "a.example.com.", RecordType::A).is_some());
+        cache.insert_records(&records("c 300 A 192.0.2.3\n"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(ttls(&cache, "a.example.com.", RecordType::A).is_some());
+        assert!(ttls(&cache, "b.example.com.", RecordType::A).is_none());
+        assert!(ttls(&cache, "c.example.com.", RecordType::A).is_some());
+    }
+
+    #[test]
+    fn queue_popular_entries_for_prefetch() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock).prefetch_hits(2);
+        cache.insert_records(&records("www 100 A 192.0.2.1\nrare 100 A 192.0.2.2\n"));
+
+        ttls(&cache, "www.example.com.", RecordType::A);
+        clock.advance(91);
+        ttls(&cache, "rare.example.com.", RecordType::A);
+        assert!(cache.take_prefetch().is_empty());
+
+        ttls(&cache, "www.example.com.", RecordType::A);
+        ttls(&cache, "www.example.com.", RecordType::A);
+        assert_eq!(cache.take_prefetch(), vec![(name("www.example.com."), RecordType::A, RecordClass::IN)]);
+        assert!(cache.take_prefetch().is_empty());
+    }
+
+    #[test]
+    fn answer_from_cached_responses() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock);
+        let zone = example_zone();
+
+        let alias = query("alias.example.com.", RecordType::A);
+        cache.insert_response(&zone.answer(&alias));
+        let missing = query("missing.example.com.", RecordType::A);
+        cache.insert_response(&zone.answer(&missing));
+        clock.advance(10);
+
+        let response = cache.answer(&alias).unwrap();
+        assert_eq!(response.header().id(), alias.header().id());
+        assert!(response.header().flags().is_recursion_available());
+        assert_eq!(response.answers().len(), 2);
+        assert!(response.answers().iter().all(|record| record.ttl() == 3590));
+
+        let response = cache.answer(&missing).unwrap();
+        assert_eq!(response.header().flags().response_code(), Rcode::NameError);
+        assert_eq!(response.authorities()[0].ttl(), 290);
+
+        assert!(cache.answer(&query("chain.example.com.", RecordType::A)).is_none());
+    }
+
+    #[test]
+    fn ignore_records_off_the_chain() {
+        let clock = Arc::new(ManualClock::new(1000));
+        let cache = cache(&clock);
+        let alias = query("alias.example.com.", RecordType::A);
+        let response = example_zone().answer(&alias);
+
+        let mut answers = response.answers().to_vec();
+        answers.extend(records("victim 3600 A 6.6.6.6\nexample.com. 3600 NS ns.evil.example.net.\n"));
+        let poisoned = MessageBuilder::new()
+            .header(response.header().clone())
+            .question(response.question().clone())
+            .answer(answers)
+            .build();
+        cache.insert_response(&poisoned);
+
+        assert_eq!(cache.answer(&alias).unwrap().answers(), response.answers());
+        assert!(cache.get(&name("victim.example.com."), RecordType::A, RecordClass::IN).is_none());
+        assert!(cache.get(&name("example.com."), RecordType::NS, RecordClass::IN).is_none());
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current time in seconds since the Unix epoch. Anything
+/// that compares against wall-clock time takes one of these so tests can
+/// pin the time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// Clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: AtomicU64::new(now),
+        }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, seconds: u64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod clock_unittest {
+    use crate::clock::{Clock, ManualClock, SystemClock};
+
+    #[test]
+    fn manual_clock_only_moves_when_told() {
+        let clock = ManualClock::new(100);
+        assert_eq!(clock.now(), 100);
+
+        clock.advance(20);
+        assert_eq!(clock.now(), 120);
+
+        clock.set(5);
+        assert_eq!(clock.now(), 5);
+    }
+
+    #[test]
+    fn system_clock_is_past_2020() {
+        assert!(SystemClock.now() > 1_577_836_800);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::record::Record;
+use crate::packet::seder::serializer::Serialize;
+use crate::records::rdata::rrsig::RRSIG;
+use crate::records::record_data::RecordData;
+
+/// RDATA in canonical wire form: embedded names uncompressed and lowercase
+/// (RFC 4034 section 6.2).
+pub fn canonical_rdata(data: &RecordData) -> Vec<u8> {
+    let mut encoder = Serialize::canonical();
+    data.rdata_to_bytes(&mut encoder);
+
+    encoder.bin_data()
+}
+
+/// Owner name as it was signed. Names synthesised from a wildcard carry
+/// more labels than the RRSIG, so the signed name is `*.` followed by the
+/// closest encloser (RFC 4035 section 5.3.2).
+pub fn signed_owner_name(owner_name: &Fqdn, rrsig_labels: u8) -> Fqdn {
+    match rrsig_labels < owner_name.label_count() {
+        true => owner_name.ancestor(rrsig_labels as usize).prepend_label("*"),
+        false => owner_name.clone(),
+    }
+}
+
+/// The data an RRSIG signature covers: the RRSIG RDATA without the
+/// signature followed by every RR of the RRset in canonical form and
+/// order (RFC 4034 section 3.1.8.1). Duplicate RRs are only counted once.
+/// Only the RRSIG fields are used, so a signer can pass an RRSIG whose
+/// signature is still empty.
+pub fn signed_data(rrsig: &RRSIG, rrset: &[&Record]) -> Vec<u8> {
+    let mut encoder = Serialize::canonical();
+    rrsig.to_bytes_without_signature(&mut encoder);
+
+    let mut rdatas: Vec<Vec<u8>> = rrset
+        .iter()
+        .map(|record| canonical_rdata(record.data()))
+        .collect();
+    rdatas.sort();
+    rdatas.dedup();
+
+    if let Some(record) = rrset.first() {
+        let owner_name = signed_owner_name(record.owner_name(), rrsig.labels());
+
+        for rdata in rdatas {
+            owner_name.to_bytes_uncompressed(&mut encoder);
+            encoder.write_u16(record.record_type().into());
+            encoder.write_u16(record.class().into());
+            encoder.write_u32(rrsig.original_ttl());
+            encoder.write_u16(rdata.len() as u16);
+            encoder.write_n_bytes(rdata);
+        }
+    }
+
+    encoder.bin_data()
+}
+
+#[cfg(test)]
+mod canonical_unittest {
+    use crate::dnssec::canonical::{signed_data, signed_owner_name};
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::packet::record::RecordBuilder;
+    use crate::records::rdata::a::A;
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+    use crate::records::rdata::rrsig::RRSIG;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn wildcard_owner_is_restored() {
+        let owner_name = FqdnBuilder::new()
+            .generate_from_string(String::from("a.b.example.com"))
+            .build();
+
+        assert_eq!(signed_owner_name(&owner_name, 4), owner_name);
+        assert_eq!(
+            signed_owner_name(&owner_name, 2).to_string(),
+            "*.example.com."
+        );
+    }
+
+    #[test]
+    fn rrset_is_sorted_and_deduplicated() {
+        let owner_name = FqdnBuilder::new()
+            .generate_from_string(String::from("WWW.Example.com"))
+            .build();
+        let signer_name = FqdnBuilder::new()
+            .generate_from_string(String::from("example.com"))
+            .build();
+
+        let record = |address: [u8; 4]| {
+            RecordBuilder::new()
+                .owner_name(owner_name.clone())
+                .record_type(RecordType::A)
+                .data(RecordData::A(A::new(Ipv4Addr::from(address))))
+                .ttl(60)
+                .build()
+        };
+        let high = record([192, 0, 2, 2]);
+        let low = record([192, 0, 2, 1]);
+
+        let rrsig = RRSIG::new(
+            RecordType::A,
+            DnssecAlgorithm::ED25519,
+            3,
+            300,
+            0,
+            0,
+            1,
+            signer_name,
+            Vec::new(),
+        );
+
+        let data = signed_data(&rrsig, &[&high, &low, &high]);
+        let rrsig_length = 18 + 13;
+        let rr_length = 17 + 10 + 4;
+        assert_eq!(data.len(), rrsig_length + 2 * rr_length);
+
+        let first = &data[rrsig_length..rrsig_length + rr_length];
+        assert_eq!(&first[..17], b"\x03www\x07example\x03com\x00");
+        assert_eq!(&first[21..25], &300u32.to_be_bytes());
+        assert_eq!(&first[27..], &[192, 0, 2, 1]);
+    }
+}
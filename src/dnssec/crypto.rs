@@ -0,0 +1,152 @@
+use crate::dnssec::{DnssecError, DnssecResult};
+use crate::records::rdata::algorithm::DnssecAlgorithm;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+/// Checks `signature` over `message` with a key in DNSKEY wire format.
+pub fn verify_signature(
+    algorithm: DnssecAlgorithm,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> DnssecResult<()> {
+    let result = match algorithm {
+        DnssecAlgorithm::RSASHA1 | DnssecAlgorithm::RSASHA1NSEC3SHA1 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA1_FOR_LEGACY_USE_ONLY,
+            public_key,
+            message,
+            signature,
+        )?,
+        DnssecAlgorithm::RSASHA256 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY,
+            public_key,
+            message,
+            signature,
+        )?,
+        DnssecAlgorithm::RSASHA512 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY,
+            public_key,
+            message,
+            signature,
+        )?,
+        DnssecAlgorithm::ECDSAP256SHA256 => verify_ecdsa(
+            &signature::ECDSA_P256_SHA256_FIXED,
+            64,
+            public_key,
+            message,
+            signature,
+        )?,
+        DnssecAlgorithm::ECDSAP384SHA384 => verify_ecdsa(
+            &signature::ECDSA_P384_SHA384_FIXED,
+            96,
+            public_key,
+            message,
+            signature,
+        )?,
+        DnssecAlgorithm::ED25519 => {
+            if public_key.len() != 32 {
+                return Err(DnssecError::InvalidPublicKey);
+            }
+
+            UnparsedPublicKey::new(&signature::ED25519, public_key).verify(message, signature)
+        }
+        algorithm => return Err(DnssecError::UnsupportedAlgorithm(algorithm.into())),
+    };
+
+    result.map_err(|_| DnssecError::BadSignature)
+}
+
+pub fn is_supported_algorithm(algorithm: DnssecAlgorithm) -> bool {
+    matches!(
+        algorithm,
+        DnssecAlgorithm::RSASHA1
+            | DnssecAlgorithm::RSASHA1NSEC3SHA1
+            | DnssecAlgorithm::RSASHA256
+            | DnssecAlgorithm::RSASHA512
+            | DnssecAlgorithm::ECDSAP256SHA256
+            | DnssecAlgorithm::ECDSAP384SHA384
+            | DnssecAlgorithm::ED25519
+    )
+}
+
+/// Splits an RSA public key into exponent and modulus (RFC 3110 section 2).
+pub fn parse_rsa_public_key(public_key: &[u8]) -> DnssecResult<(&[u8], &[u8])> {
+    let (exponent_length, rest) = match public_key {
+        [0, high, low, rest @ ..] => (u16::from_be_bytes([*high, *low]) as usize, rest),
+        [length, rest @ ..] => (*length as usize, rest),
+        [] => return Err(DnssecError::InvalidPublicKey),
+    };
+
+    if exponent_length == 0 || rest.len() <= exponent_length {
+        return Err(DnssecError::InvalidPublicKey);
+    }
+
+    Ok(rest.split_at(exponent_length))
+}
+
+fn verify_rsa(
+    parameters: &signature::RsaParameters,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> DnssecResult<Result<(), ring::error::Unspecified>> {
+    let (e, n) = parse_rsa_public_key(public_key)?;
+
+    Ok(RsaPublicKeyComponents { n, e }.verify(parameters, message, signature))
+}
+
+// DNSKEY stores the bare X and Y coordinates (RFC 6605 section 4), ring
+// wants an uncompressed SEC1 point
+fn verify_ecdsa(
+    algorithm: &'static signature::EcdsaVerificationAlgorithm,
+    key_length: usize,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> DnssecResult<Result<(), ring::error::Unspecified>> {
+    if public_key.len() != key_length {
+        return Err(DnssecError::InvalidPublicKey);
+    }
+
+    let mut point = Vec::with_capacity(key_length + 1);
+    point.push(0x04);
+    point.extend_from_slice(public_key);
+
+    Ok(UnparsedPublicKey::new(algorithm, point).verify(message, signature))
+}
+
+#[cfg(test)]
+mod crypto_unittest {
+    use crate::dnssec::crypto::{parse_rsa_public_key, verify_signature};
+    use crate::dnssec::DnssecError;
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+
+    #[test]
+    fn rsa_key_with_short_exponent_length() {
+        let (exponent, modulus) = parse_rsa_public_key(&[3, 1, 0, 1, 0xab, 0xcd]).unwrap();
+        assert_eq!(exponent, &[1, 0, 1]);
+        assert_eq!(modulus, &[0xab, 0xcd]);
+    }
+
+    #[test]
+    fn rsa_key_with_long_exponent_length() {
+        let (exponent, modulus) = parse_rsa_public_key(&[0, 0, 1, 3, 0xab]).unwrap();
+        assert_eq!(exponent, &[3]);
+        assert_eq!(modulus, &[0xab]);
+    }
+
+    #[test]
+    fn error_rsa_key_without_modulus() {
+        assert_eq!(
+            parse_rsa_public_key(&[3, 1, 0, 1]),
+            Err(DnssecError::InvalidPublicKey)
+        );
+    }
+
+    #[test]
+    fn error_unsupported_algorithm() {
+        assert_eq!(
+            verify_signature(DnssecAlgorithm::RSAMD5, &[], &[], &[]),
+            Err(DnssecError::UnsupportedAlgorithm(1))
+        );
+    }
+}
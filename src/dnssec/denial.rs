@@ -0,0 +1,709 @@
+use crate::dnssec::digest::nsec3_hash;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header_flags::Rcode;
+use crate::packet::message::Message;
+use crate::packet::record::Record;
+use crate::records::rdata::algorithm::Nsec3HashAlgorithm;
+use crate::records::rdata::nsec::NSEC;
+use crate::records::rdata::nsec3::NSEC3;
+use crate::records::rdata::type_bitmap::TypeBitmap;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use data_encoding::BASE32HEX_NOPAD;
+use thiserror::Error;
+
+/// Responses using more NSEC3 iterations than this are treated as
+/// insecure (RFC 9276 section 3.2).
+pub const MAX_NSEC3_ITERATIONS: u16 = 150;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum DenialError {
+    #[error("Response is neither a name error nor an empty answer")]
+    NotANegativeResponse,
+    #[error("Response carries no NSEC or NSEC3 records")]
+    NoDenialRecords,
+    #[error("No NSEC or NSEC3 record covers the name")]
+    NameNotCovered,
+    #[error("The name exists, so it cannot be a name error")]
+    NameExists,
+    #[error("Type {0} exists at the name")]
+    TypeExists(RecordType),
+    #[error("A wildcard at the closest encloser is not denied")]
+    WildcardNotDenied,
+    #[error("The name does not exist and no wildcard proves the type is absent")]
+    MissingWildcard,
+    #[error("No NSEC3 closest encloser proof for the name")]
+    NoClosestEncloser,
+    #[error("Proof comes from the wrong side of a zone cut")]
+    DelegationProof,
+}
+
+/// Outcome of checking a negative response. The NSEC and NSEC3 records are
+/// taken at face value, their RRSIGs must be verified separately.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DenialResult {
+    SecureNxDomain,
+    SecureNoData,
+    /// Denial that cannot be proven secure by design, such as an NSEC3
+    /// opt-out span or an NSEC3 chain using too many iterations.
+    Insecure,
+    Bogus(DenialError),
+}
+
+/// Checks the proof in the authority section of a negative response to a
+/// query for `qname`/`qtype`.
+pub fn prove_denial(message: &Message, qname: &Fqdn, qtype: RecordType) -> DenialResult {
+    prove_denial_from(message, message.authorities(), qname, qtype)
+}
+
+/// Like `prove_denial`, with the proof taken from `authority` rather than
+/// the whole authority section, for callers that drop records they cannot
+/// trust.
+pub fn prove_denial_from(message: &Message, authority: &[Record], qname: &Fqdn, qtype: RecordType) -> DenialResult {
+    match message.header().flags().response_code() {
+        Rcode::NameError => prove_nxdomain(authority, qname),
+        Rcode::NoError => {
+            let answered = message
+                .answers()
+                .iter()
+                .any(|record| record.owner_name() == qname && record.record_type() == qtype);
+
+            match answered {
+                true => DenialResult::Bogus(DenialError::NotANegativeResponse),
+                false => prove_nodata(authority, qname, qtype),
+            }
+        }
+        _ => DenialResult::Bogus(DenialError::NotANegativeResponse),
+    }
+}
+
+pub fn prove_nxdomain(authority: &[Record], qname: &Fqdn) -> DenialResult {
+    let nsec3s = nsec3_records(authority);
+    if !nsec3s.is_empty() {
+        return match usable_nsec3_records(nsec3s) {
+            Some(nsec3s) => nsec3_nxdomain(&nsec3s, qname),
+            None => DenialResult::Insecure,
+        };
+    }
+
+    let nsecs = nsec_records(authority);
+    if nsecs.is_empty() {
+        return DenialResult::Bogus(DenialError::NoDenialRecords);
+    }
+
+    nsec_nxdomain(&nsecs, qname)
+}
+
+pub fn prove_nodata(authority: &[Record], qname: &Fqdn, qtype: RecordType) -> DenialResult {
+    let nsec3s = nsec3_records(authority);
+    if !nsec3s.is_empty() {
+        return match usable_nsec3_records(nsec3s) {
+            Some(nsec3s) => nsec3_nodata(&nsec3s, qname, qtype),
+            None => DenialResult::Insecure,
+        };
+    }
+
+    let nsecs = nsec_records(authority);
+    if nsecs.is_empty() {
+        return DenialResult::Bogus(DenialError::NoDenialRecords);
+    }
+
+    nsec_nodata(&nsecs, qname, qtype)
+}
+
+/// Checks that an answer synthesised from a wildcard had no closer match:
+/// `qname` itself, or for NSEC3 its next closer name, must be denied
+/// (RFC 4035 section 5.3.4, RFC 5155 section 8.8).
+pub fn prove_wildcard_answer(authority: &[Record], qname: &Fqdn, rrsig_labels: u8) -> Result<(), DenialError> {
+    let nsec3s = nsec3_records(authority);
+    if !nsec3s.is_empty() {
+        let next_closer = qname.ancestor(rrsig_labels as usize + 1);
+
+        return match nsec3s.iter().any(|record| record.covers(&next_closer)) {
+            true => Ok(()),
+            false => Err(DenialError::NameNotCovered),
+        };
+    }
+
+    match nsec_records(authority).iter().any(|nsec| nsec.covers(qname)) {
+        true => Ok(()),
+        false => Err(DenialError::NameNotCovered),
+    }
+}
+
+/// True when the authority section holds an NSEC or NSEC3 record showing
+/// that `name` is a delegation, i.e. has NS but no SOA.
+pub fn is_delegation(authority: &[Record], name: &Fqdn) -> bool {
+    let delegation = |type_bitmap: &TypeBitmap| {
+        type_bitmap.contains(RecordType::NS.into()) && !type_bitmap.contains(RecordType::SOA.into())
+    };
+
+    nsec_records(authority)
+        .iter()
+        .any(|nsec| nsec.matches(name) && delegation(nsec.nsec.type_bitmap()))
+        || nsec3_records(authority)
+            .iter()
+            .any(|record| record.matches(name) && delegation(record.nsec3.type_bitmap()))
+}
+
+// RFC 4035 section 5.4
+
+struct NsecRecord<'a> {
+    owner_name: &'a Fqdn,
+    nsec: &'a NSEC,
+}
+
+impl NsecRecord<'_> {
+    fn matches(&self, name: &Fqdn) -> bool {
+        self.owner_name == name
+    }
+
+    /// True when `name` sorts strictly between the owner and next name and
+    /// is not hidden below a zone cut or DNAME at the owner.
+    fn covers(&self, name: &Fqdn) -> bool {
+        let owner_name = self.owner_name;
+        let next_name = self.nsec.next_domain_name();
+
+        let between = match owner_name < next_name {
+            true => owner_name < name && name < next_name,
+            // The last NSEC in the zone points back to the apex, and only
+            // covers names in the zone
+            false => (name > owner_name || name < next_name) && name.is_subdomain_of(next_name),
+        };
+
+        between && !(name.is_subdomain_of(owner_name) && is_cut(self.nsec.type_bitmap()))
+    }
+
+    fn closest_encloser(&self, name: &Fqdn) -> Fqdn {
+        let from_owner = name.common_ancestor(self.owner_name);
+        let from_next = name.common_ancestor(self.nsec.next_domain_name());
+
+        match from_owner.labels().len() >= from_next.labels().len() {
+            true => from_owner,
+            false => from_next,
+        }
+    }
+
+    fn is_empty_non_terminal(&self, name: &Fqdn) -> bool {
+        let next_name = self.nsec.next_domain_name();
+        next_name != name && next_name.is_subdomain_of(name)
+    }
+}
+
+fn nsec_records(authority: &[Record]) -> Vec<NsecRecord<'_>> {
+    authority
+        .iter()
+        .filter_map(|record| match record.data() {
+            RecordData::NSEC(nsec) => Some(NsecRecord {
+                owner_name: record.owner_name(),
+                nsec,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn nsec_nxdomain(nsecs: &[NsecRecord], qname: &Fqdn) -> DenialResult {
+    if nsecs.iter().any(|nsec| nsec.matches(qname)) {
+        return DenialResult::Bogus(DenialError::NameExists);
+    }
+
+    let Some(covering) = nsecs.iter().find(|nsec| nsec.covers(qname)) else {
+        return DenialResult::Bogus(DenialError::NameNotCovered);
+    };
+
+    if covering.is_empty_non_terminal(qname) {
+        return DenialResult::Bogus(DenialError::NameExists);
+    }
+
+    let wildcard = covering.closest_encloser(qname).prepend_label("*");
+    match nsecs.iter().any(|nsec| nsec.covers(&wildcard)) {
+        true => DenialResult::SecureNxDomain,
+        false => DenialResult::Bogus(DenialError::WildcardNotDenied),
+    }
+}
+
+fn nsec_nodata(nsecs: &[NsecRecord], qname: &Fqdn, qtype: RecordType) -> DenialResult {
+    if let Some(matching) = nsecs.iter().find(|nsec| nsec.matches(qname)) {
+        return match check_type_absent(matching.nsec.type_bitmap(), qname, qtype) {
+            Ok(()) => DenialResult::SecureNoData,
+            Err(error) => DenialResult::Bogus(error),
+        };
+    }
+
+    let Some(covering) = nsecs.iter().find(|nsec| nsec.covers(qname)) else {
+        return DenialResult::Bogus(DenialError::NameNotCovered);
+    };
+
+    if covering.is_empty_non_terminal(qname) {
+        return DenialResult::SecureNoData;
+    }
+
+    // Wildcard NODATA: the name is synthesised from a wildcard lacking the type
+    let wildcard = covering.closest_encloser(qname).prepend_label("*");
+    match nsecs.iter().find(|nsec| nsec.matches(&wildcard)) {
+        Some(matching) => match check_type_absent(matching.nsec.type_bitmap(), &wildcard, qtype) {
+            Ok(()) => DenialResult::SecureNoData,
+            Err(error) => DenialResult::Bogus(error),
+        },
+        None => DenialResult::Bogus(DenialError::MissingWildcard),
+    }
+}
+
+// RFC 5155 section 8
+
+struct Nsec3Record<'a> {
+    owner_hash: Vec<u8>,
+    zone: Fqdn,
+    nsec3: &'a NSEC3,
+}
+
+impl Nsec3Record<'_> {
+    fn hash(&self, name: &Fqdn) -> Option<Vec<u8>> {
+        if !name.is_subdomain_of(&self.zone) {
+            return None;
+        }
+
+        nsec3_hash(
+            name,
+            self.nsec3.hash_algorithm(),
+            self.nsec3.iterations(),
+            self.nsec3.salt(),
+        )
+        .ok()
+    }
+
+    fn matches(&self, name: &Fqdn) -> bool {
+        self.hash(name).is_some_and(|hash| hash == self.owner_hash)
+    }
+
+    fn covers(&self, name: &Fqdn) -> bool {
+        let Some(hash) = self.hash(name) else {
+            return false;
+        };
+        let owner_hash = &self.owner_hash[..];
+        let next_hash = self.nsec3.next_hashed_owner_name();
+
+        match owner_hash < next_hash {
+            true => owner_hash < &hash[..] && &hash[..] < next_hash,
+            false => &hash[..] > owner_hash || &hash[..] < next_hash,
+        }
+    }
+}
+
+fn nsec3_records(authority: &[Record]) -> Vec<Nsec3Record<'_>> {
+    authority
+        .iter()
+        .filter_map(|record| {
+            let RecordData::NSEC3(nsec3) = record.data() else {
+                return None;
+            };
+
+            let label = record.owner_name().labels().first()?;
+            let owner_hash = BASE32HEX_NOPAD
+                .decode(label.to_uppercase().as_bytes())
+                .ok()?;
+
+            Some(Nsec3Record {
+                owner_hash,
+                zone: record.owner_name().parent()?,
+                nsec3,
+            })
+        })
+        .collect()
+}
+
+// Records with an unknown hash algorithm are ignored (RFC 5155 section 8.1)
+fn usable_nsec3_records(nsec3s: Vec<Nsec3Record>) -> Option<Vec<Nsec3Record>> {
+    let nsec3s: Vec<Nsec3Record> = nsec3s
+        .into_iter()
+        .filter(|record| record.nsec3.hash_algorithm() == Nsec3HashAlgorithm::SHA1)
+        .collect();
+
+    let too_expensive = nsec3s
+        .iter()
+        .any(|record| record.nsec3.iterations() > MAX_NSEC3_ITERATIONS);
+
+    match nsec3s.is_empty() || too_expensive {
+        true => None,
+        false => Some(nsec3s),
+    }
+}
+
+/// Finds the closest encloser of `qname` and the NSEC3 covering the next
+/// closer name (RFC 5155 section 8.3).
+fn closest_encloser_proof<'a, 'b>(
+    nsec3s: &'a [Nsec3Record<'b>],
+    qname: &Fqdn,
+) -> Result<(Fqdn, &'a Nsec3Record<'b>), DenialError> {
+    for label_count in (0..qname.labels().len()).rev() {
+        let candidate = qname.ancestor(label_count);
+        let Some(matching) = nsec3s.iter().find(|record| record.matches(&candidate)) else {
+            continue;
+        };
+
+        if is_cut(matching.nsec3.type_bitmap()) {
+            return Err(DenialError::DelegationProof);
+        }
+
+        let next_closer = qname.ancestor(label_count + 1);
+        return match nsec3s.iter().find(|record| record.covers(&next_closer)) {
+            Some(covering) => Ok((candidate, covering)),
+            None => Err(DenialError::NameNotCovered),
+        };
+    }
+
+    Err(DenialError::NoClosestEncloser)
+}
+
+fn nsec3_nxdomain(nsec3s: &[Nsec3Record], qname: &Fqdn) -> DenialResult {
+    if nsec3s.iter().any(|record| record.matches(qname)) {
+        return DenialResult::Bogus(DenialError::NameExists);
+    }
+
+    let (closest_encloser, next_closer) = match closest_encloser_proof(nsec3s, qname) {
+        Ok(proof) => proof,
+        Err(error) => return DenialResult::Bogus(error),
+    };
+
+    let wildcard = closest_encloser.prepend_label("*");
+    if !nsec3s.iter().any(|record| record.covers(&wildcard)) {
+        return DenialResult::Bogus(DenialError::WildcardNotDenied);
+    }
+
+    // An opt-out span may hide an unsigned delegation (RFC 5155 section 9.2)
+    match next_closer.nsec3.is_opt_out() {
+        true => DenialResult::Insecure,
+        false => DenialResult::SecureNxDomain,
+    }
+}
+
+fn nsec3_nodata(nsec3s: &[Nsec3Record], qname: &Fqdn, qtype: RecordType) -> DenialResult {
+    if let Some(matching) = nsec3s.iter().find(|record| record.matches(qname)) {
+        return match check_type_absent(matching.nsec3.type_bitmap(), qname, qtype) {
+            Ok(()) => DenialResult::SecureNoData,
+            Err(error) => DenialResult::Bogus(error),
+        };
+    }
+
+    let (closest_encloser, next_closer) = match closest_encloser_proof(nsec3s, qname) {
+        Ok(proof) => proof,
+        Err(error) => return DenialResult::Bogus(error),
+    };
+
+    let wildcard = closest_encloser.prepend_label("*");
+    if let Some(matching) = nsec3s.iter().find(|record| record.matches(&wildcard)) {
+        return match check_type_absent(matching.nsec3.type_bitmap(), &wildcard, qtype) {
+            Ok(()) => DenialResult::SecureNoData,
+            Err(error) => DenialResult::Bogus(error),
+        };
+    }
+
+    // No DS for an unsigned delegation inside an opt-out span (RFC 5155 section 8.6)
+    match qtype == RecordType::DS && next_closer.nsec3.is_opt_out() {
+        true => DenialResult::Insecure,
+        false => DenialResult::Bogus(DenialError::MissingWildcard),
+    }
+}
+
+fn is_cut(type_bitmap: &TypeBitmap) -> bool {
+    const DNAME: u16 = 39;
+
+    let delegation = type_bitmap.contains(RecordType::NS.into())
+        && !type_bitmap.contains(RecordType::SOA.into());
+
+    delegation || type_bitmap.contains(DNAME)
+}
+
+fn check_type_absent(type_bitmap: &TypeBitmap, name: &Fqdn, qtype: RecordType) -> Result<(), DenialError> {
+    for present in [qtype, RecordType::CNAME] {
+        if type_bitmap.contains(present.into()) {
+            return Err(DenialError::TypeExists(present));
+        }
+    }
+
+    let has_soa = type_bitmap.contains(RecordType::SOA.into());
+    let has_ns = type_bitmap.contains(RecordType::NS.into());
+
+    // DS lives on the parent side of a cut, every other type on the child side
+    let wrong_side = match qtype == RecordType::DS {
+        true => has_soa && !name.is_root(),
+        false => has_ns && !has_soa,
+    };
+
+    match wrong_side {
+        true => Err(DenialError::DelegationProof),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod denial_unittest {
+    use crate::dnssec::denial::{prove_denial, prove_nodata, prove_nxdomain, DenialError, DenialResult};
+    use crate::dnssec::digest::nsec3_hash;
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::headers::header_flags::header_flags_unittest::generate_response_header_flag;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::message::MessageBuilder;
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::record::{Record, RecordBuilder};
+    use crate::records::rdata::algorithm::Nsec3HashAlgorithm;
+    use crate::records::rdata::nsec::NSEC;
+    use crate::records::rdata::nsec3::NSEC3;
+    use crate::records::rdata::type_bitmap::TypeBitmap;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use data_encoding::BASE32HEX_NOPAD;
+
+    const APEX: &[RecordType] = &[RecordType::SOA, RecordType::NS, RecordType::DNSKEY];
+    const HOST: &[RecordType] = &[RecordType::A];
+    const DELEGATION: &[RecordType] = &[RecordType::NS];
+
+    // example.com with a wildcard below w, and an empty non-terminal at
+    // b.example.com above x.b.example.com
+    const ZONE: &[(&str, &[RecordType])] = &[
+        ("example.com", APEX),
+        ("a.example.com", HOST),
+        ("x.b.example.com", HOST),
+        ("insecure.example.com", DELEGATION),
+        ("*.w.example.com", HOST),
+        ("z.example.com", HOST),
+    ];
+
+    fn name(name: &str) -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    fn nsec_chain() -> Vec<Record> {
+        let mut names: Vec<(Fqdn, &[RecordType])> =
+            ZONE.iter().map(|(owner, types)| (name(owner), *types)).collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+
+        (0..names.len())
+            .map(|i| {
+                let (owner_name, types) = &names[i];
+                let next_name = names[(i + 1) % names.len()].0.clone();
+                let mut types = types.to_vec();
+                types.extend([RecordType::RRSIG, RecordType::NSEC]);
+
+                RecordBuilder::new()
+                    .owner_name(owner_name.clone())
+                    .record_type(RecordType::NSEC)
+                    .data(RecordData::NSEC(NSEC::new(next_name, TypeBitmap::from(&types[..]))))
+                    .build()
+            })
+            .collect()
+    }
+
+    fn nsec3_chain(iterations: u16, opt_out: bool) -> Vec<Record> {
+        let zone = name("example.com");
+        let salt = vec![0xab, 0xcd];
+        let hash = |owner: &Fqdn| nsec3_hash(owner, Nsec3HashAlgorithm::SHA1, iterations, &salt).unwrap();
+
+        // NSEC3 hashes empty non-terminals too, and opt-out leaves out the
+        // insecure delegation
+        let mut hashes: Vec<(Vec<u8>, &[RecordType])> = ZONE
+            .iter()
+            .filter(|(_, types)| !(opt_out && *types == DELEGATION))
+            .map(|(owner, types)| (hash(&name(owner)), *types))
+            .chain([(hash(&name("b.example.com")), &[][..]), (hash(&name("w.example.com")), &[][..])])
+            .collect();
+        hashes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        (0..hashes.len())
+            .map(|i| {
+                let (owner_hash, types) = &hashes[i];
+                let next_hash = hashes[(i + 1) % hashes.len()].0.clone();
+                let mut types = types.to_vec();
+                if !types.is_empty() {
+                    types.push(RecordType::RRSIG);
+                }
+                let nsec3 = NSEC3::new(
+                    Nsec3HashAlgorithm::SHA1,
+                    opt_out as u8,
+                    iterations,
+                    salt.clone(),
+                    next_hash,
+                    TypeBitmap::from(&types[..]),
+                );
+
+                RecordBuilder::new()
+                    .owner_name(zone.prepend_label(&BASE32HEX_NOPAD.encode(owner_hash)))
+                    .record_type(RecordType::NSEC3)
+                    .data(RecordData::NSEC3(nsec3))
+                    .build()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nsec_nxdomain() {
+        assert_eq!(
+            prove_nxdomain(&nsec_chain(), &name("c.example.com")),
+            DenialResult::SecureNxDomain
+        );
+    }
+
+    #[test]
+    fn nsec_nodata() {
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("a.example.com"), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("a.example.com"), RecordType::A),
+            DenialResult::Bogus(DenialError::TypeExists(RecordType::A))
+        );
+    }
+
+    #[test]
+    fn nsec_empty_non_terminal_nodata() {
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("b.example.com"), RecordType::A),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nxdomain(&nsec_chain(), &name("b.example.com")),
+            DenialResult::Bogus(DenialError::NameExists)
+        );
+    }
+
+    #[test]
+    fn nsec_wildcard_nodata() {
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("host.w.example.com"), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nxdomain(&nsec_chain(), &name("host.w.example.com")),
+            DenialResult::Bogus(DenialError::WildcardNotDenied)
+        );
+    }
+
+    #[test]
+    fn nsec_cannot_deny_below_delegation() {
+        assert_eq!(
+            prove_nxdomain(&nsec_chain(), &name("www.insecure.example.com")),
+            DenialResult::Bogus(DenialError::NameNotCovered)
+        );
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("insecure.example.com"), RecordType::A),
+            DenialResult::Bogus(DenialError::DelegationProof)
+        );
+        assert_eq!(
+            prove_nodata(&nsec_chain(), &name("insecure.example.com"), RecordType::DS),
+            DenialResult::SecureNoData
+        );
+    }
+
+    #[test]
+    fn nsec_cannot_deny_outside_zone() {
+        for qname in ["victim.com", "zz.example.net", "com"] {
+            assert_eq!(
+                prove_nxdomain(&nsec_chain(), &name(qname)),
+                DenialResult::Bogus(DenialError::NameNotCovered)
+            );
+        }
+    }
+
+    #[test]
+    fn error_nsec_nxdomain_without_wildcard_proof() {
+        let chain = nsec_chain();
+        let qname = name("c.example.com");
+        let covering: Vec<Record> = chain
+            .into_iter()
+            .filter(|record| record.owner_name() == &name("x.b.example.com"))
+            .collect();
+
+        assert_eq!(
+            prove_nxdomain(&covering, &qname),
+            DenialResult::Bogus(DenialError::WildcardNotDenied)
+        );
+    }
+
+    #[test]
+    fn nsec3_nxdomain() {
+        assert_eq!(
+            prove_nxdomain(&nsec3_chain(10, false), &name("c.example.com")),
+            DenialResult::SecureNxDomain
+        );
+        assert_eq!(
+            prove_nxdomain(&nsec3_chain(10, false), &name("b.example.com")),
+            DenialResult::Bogus(DenialError::NameExists)
+        );
+    }
+
+    #[test]
+    fn nsec3_nodata() {
+        assert_eq!(
+            prove_nodata(&nsec3_chain(10, false), &name("z.example.com"), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nodata(&nsec3_chain(10, false), &name("b.example.com"), RecordType::A),
+            DenialResult::SecureNoData
+        );
+    }
+
+    #[test]
+    fn nsec3_wildcard_nodata() {
+        assert_eq!(
+            prove_nodata(&nsec3_chain(10, false), &name("host.w.example.com"), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nxdomain(&nsec3_chain(10, false), &name("host.w.example.com")),
+            DenialResult::Bogus(DenialError::WildcardNotDenied)
+        );
+    }
+
+    #[test]
+    fn nsec3_opt_out_is_insecure() {
+        assert_eq!(
+            prove_nodata(&nsec3_chain(10, true), &name("insecure.example.com"), RecordType::DS),
+            DenialResult::Insecure
+        );
+        assert_eq!(
+            prove_nxdomain(&nsec3_chain(10, true), &name("c.example.com")),
+            DenialResult::Insecure
+        );
+    }
+
+    #[test]
+    fn nsec3_too_many_iterations_is_insecure() {
+        assert_eq!(
+            prove_nxdomain(&nsec3_chain(151, false), &name("c.example.com")),
+            DenialResult::Insecure
+        );
+    }
+
+    #[test]
+    fn error_no_denial_records() {
+        assert_eq!(
+            prove_nxdomain(&[], &name("c.example.com")),
+            DenialResult::Bogus(DenialError::NoDenialRecords)
+        );
+    }
+
+    #[test]
+    fn denial_from_name_error_message() {
+        let flags = generate_response_header_flag(true, false, true, false, Rcode::NameError);
+        let header = HeaderBuilder::new()
+            .id(1)
+            .flags(flags)
+            .question_count(1)
+            .build();
+
+        let message = MessageBuilder::new()
+            .header(header)
+            .question(generate_question("c.example.com", RecordType::A))
+            .authority(nsec_chain())
+            .build();
+
+        assert_eq!(
+            prove_denial(&message, &name("c.example.com"), RecordType::A),
+            DenialResult::SecureNxDomain
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::dnssec::{DnssecError, DnssecResult};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::records::rdata::algorithm::{DigestType, Nsec3HashAlgorithm};
+use crate::records::rdata::dnskey::DNSKEY;
+use crate::records::rdata::ds::DS;
+use ring::digest;
+
+/// Digest of the owner name and DNSKEY RDATA, as carried in a DS record
+/// (RFC 4034 section 5.1.4).
+pub fn dnskey_digest(owner_name: &Fqdn, dnskey: &DNSKEY, digest_type: DigestType) -> DnssecResult<Vec<u8>> {
+    let algorithm = match digest_type {
+        DigestType::SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        DigestType::SHA256 => &digest::SHA256,
+        DigestType::SHA384 => &digest::SHA384,
+        digest_type => return Err(DnssecError::UnsupportedDigestType(digest_type.into())),
+    };
+
+    let mut encoder = Serialize::canonical();
+    owner_name.to_bytes_uncompressed(&mut encoder);
+    dnskey.to_bytes(&mut encoder);
+
+    Ok(digest::digest(algorithm, &encoder.bin_data()).as_ref().to_vec())
+}
+
+pub fn create_ds(owner_name: &Fqdn, dnskey: &DNSKEY, digest_type: DigestType) -> DnssecResult<DS> {
+    let digest = dnskey_digest(owner_name, dnskey, digest_type)?;
+
+    Ok(DS::new(dnskey.key_tag(), dnskey.algorithm(), digest_type, digest))
+}
+
+/// Checks that `ds` refers to `dnskey` published at `owner_name`.
+pub fn verify_ds(owner_name: &Fqdn, dnskey: &DNSKEY, ds: &DS) -> DnssecResult<()> {
+    if ds.key_tag() != dnskey.key_tag() {
+        return Err(DnssecError::KeyTagMismatch);
+    }
+
+    if ds.algorithm() != dnskey.algorithm() {
+        return Err(DnssecError::AlgorithmMismatch);
+    }
+
+    match dnskey_digest(owner_name, dnskey, ds.digest_type())? == ds.digest() {
+        true => Ok(()),
+        false => Err(DnssecError::DigestMismatch),
+    }
+}
+
+/// Iterated, salted hash of a name for NSEC3 owner names (RFC 5155 section 5).
+pub fn nsec3_hash(
+    name: &Fqdn,
+    algorithm: Nsec3HashAlgorithm,
+    iterations: u16,
+    salt: &[u8],
+) -> DnssecResult<Vec<u8>> {
+    if algorithm != Nsec3HashAlgorithm::SHA1 {
+        return Err(DnssecError::UnsupportedHashAlgorithm(algorithm.into()));
+    }
+
+    let mut encoder = Serialize::canonical();
+    name.to_bytes_uncompressed(&mut encoder);
+    let mut input = encoder.bin_data();
+
+    for _ in 0..=iterations {
+        input.extend_from_slice(salt);
+        input = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &input)
+            .as_ref()
+            .to_vec();
+    }
+
+    Ok(input)
+}
+
+#[cfg(test)]
+mod digest_unittest {
+    use crate::dnssec::digest::{create_ds, nsec3_hash, verify_ds};
+    use crate::dnssec::DnssecError;
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm, Nsec3HashAlgorithm};
+    use crate::records::rdata::dnskey::dnskey_unittest::get_rfc4034_dnskey;
+    use crate::records::rdata::dnskey::DNSKEY;
+    use crate::records::rdata::ds::DS;
+    use data_encoding::{BASE32HEX_NOPAD, BASE64, HEXLOWER};
+
+    const RFC6605_P256_KEY: &str =
+        "GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edbkrSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA==";
+
+    #[test]
+    fn rfc4034_sha1_ds() {
+        let owner_name = FqdnBuilder::new()
+            .generate_from_string(String::from("dskey.example.com."))
+            .build();
+
+        let ds = create_ds(&owner_name, &get_rfc4034_dnskey(), DigestType::SHA1).unwrap();
+        assert_eq!(ds.to_string(), "60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118");
+    }
+
+    #[test]
+    fn rfc6605_sha256_ds() {
+        let owner_name = FqdnBuilder::new()
+            .generate_from_string(String::from("example.net."))
+            .build();
+        let dnskey = DNSKEY::new(
+            257,
+            DnssecAlgorithm::ECDSAP256SHA256,
+            BASE64.decode(RFC6605_P256_KEY.as_bytes()).unwrap(),
+        );
+        let digest = HEXLOWER
+            .decode(b"b4c8c1fe2e7477127b27115656ad6256f424625bf5c1e2770ce6d6e37df61d17")
+            .unwrap();
+        let ds = DS::new(55648, DnssecAlgorithm::ECDSAP256SHA256, DigestType::SHA256, digest);
+
+        assert_eq!(verify_ds(&owner_name, &dnskey, &ds), Ok(()));
+    }
+
+    #[test]
+    fn error_ds_for_other_owner() {
+        let owner_name = FqdnBuilder::new()
+            .generate_from_string(String::from("other.example.com."))
+            .build();
+        let dnskey = get_rfc4034_dnskey();
+        let digest = HEXLOWER
+            .decode(b"2bb183af5f22588179a53b0a98631fad1a292118")
+            .unwrap();
+        let ds = DS::new(60485, DnssecAlgorithm::RSASHA1, DigestType::SHA1, digest);
+
+        assert_eq!(
+            verify_ds(&owner_name, &dnskey, &ds),
+            Err(DnssecError::DigestMismatch)
+        );
+    }
+
+    #[test]
+    fn rfc5155_nsec3_hashes() {
+        let salt = HEXLOWER.decode(b"aabbccdd").unwrap();
+        let hash = |name: &str| {
+            let name = FqdnBuilder::new().generate_from_string(name.to_string()).build();
+            let hash = nsec3_hash(&name, Nsec3HashAlgorithm::SHA1, 12, &salt).unwrap();
+
+            BASE32HEX_NOPAD.encode(&hash).to_lowercase()
+        };
+
+        assert_eq!(hash("example"), "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom");
+        assert_eq!(hash("a.example"), "35mthgpgcu1qg68fab165klnsnk3dpvl");
+        assert_eq!(hash("*.w.example"), "r53bq7cc2uvmubfu5ocmm6pers9tk9en");
+    }
+}
@@ -0,0 +1,367 @@
+use crate::records::rdata::algorithm::DnssecAlgorithm;
+use crate::records::rdata::dnskey::DNSKEY;
+use data_encoding::BASE64;
+use ring::rand::SystemRandom;
+use ring::rsa::{KeyPairComponents, PublicKeyComponents};
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum KeyError {
+    #[error("DNSSEC algorithm {0} is not supported for signing")]
+    UnsupportedAlgorithm(u8),
+    #[error("Key file does not contain a DNSKEY record")]
+    MissingDnskey,
+    #[error("Private key file is missing the {0} field")]
+    MissingField(&'static str),
+    #[error("Private key file field {0} is malformed")]
+    MalformedField(String),
+    #[error("Private key is for algorithm {0} but the DNSKEY is for algorithm {1}")]
+    AlgorithmMismatch(u8, u8),
+    #[error("Private key does not match the public key")]
+    KeyMismatch,
+    #[error("Private key was rejected: {0}")]
+    Rejected(String),
+    #[error("Unable to sign data")]
+    SigningFailed,
+}
+
+pub type KeyResult<T> = Result<T, KeyError>;
+
+#[derive(Debug)]
+enum KeyPairKind {
+    Rsa(RsaKeyPair),
+    Ecdsa(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+}
+
+/// A private zone key together with the DNSKEY it publishes.
+#[derive(Debug)]
+pub struct SigningKey {
+    dnskey: DNSKEY,
+    key_pair: KeyPairKind,
+}
+
+impl SigningKey {
+    /// Loads a key from a PKCS#8 document. ECDSA documents must carry the
+    /// public key, as produced by `openssl genpkey`.
+    pub fn from_pkcs8(flags: u16, algorithm: DnssecAlgorithm, pkcs8: &[u8]) -> KeyResult<Self> {
+        let key_pair = match algorithm {
+            DnssecAlgorithm::RSASHA256 | DnssecAlgorithm::RSASHA512 => {
+                KeyPairKind::Rsa(RsaKeyPair::from_pkcs8(pkcs8).map_err(rejected)?)
+            }
+            DnssecAlgorithm::ECDSAP256SHA256 | DnssecAlgorithm::ECDSAP384SHA384 => {
+                let key_pair = EcdsaKeyPair::from_pkcs8(ecdsa_algorithm(algorithm), pkcs8, &SystemRandom::new())
+                    .map_err(rejected)?;
+                KeyPairKind::Ecdsa(key_pair)
+            }
+            DnssecAlgorithm::ED25519 => {
+                KeyPairKind::Ed25519(Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8).map_err(rejected)?)
+            }
+            algorithm => return Err(KeyError::UnsupportedAlgorithm(algorithm.into())),
+        };
+
+        let dnskey = DNSKEY::new(flags, algorithm, public_key(&key_pair));
+
+        Ok(SigningKey { dnskey, key_pair })
+    }
+
+    /// Loads a key from the `K<zone>+<alg>+<tag>.key` and `.private` pair
+    /// written by `dnssec-keygen`. The public file supplies the flags, and
+    /// the public point ECDSA private files leave out.
+    pub fn from_bind(public_file: &str, private_file: &str) -> KeyResult<Self> {
+        let dnskey = parse_public_file(public_file)?;
+        let fields = parse_private_file(private_file);
+
+        let algorithm = field(&fields, "Algorithm")?
+            .split_whitespace()
+            .next()
+            .and_then(|number| number.parse::<u8>().ok())
+            .ok_or_else(|| KeyError::MalformedField(String::from("Algorithm")))?;
+        if algorithm != u8::from(dnskey.algorithm()) {
+            return Err(KeyError::AlgorithmMismatch(algorithm, dnskey.algorithm().into()));
+        }
+
+        let key_pair = match dnskey.algorithm() {
+            DnssecAlgorithm::RSASHA256 | DnssecAlgorithm::RSASHA512 => {
+                let components = KeyPairComponents {
+                    public_key: PublicKeyComponents {
+                        n: decoded_field(&fields, "Modulus")?,
+                        e: decoded_field(&fields, "PublicExponent")?,
+                    },
+                    d: decoded_field(&fields, "PrivateExponent")?,
+                    p: decoded_field(&fields, "Prime1")?,
+                    q: decoded_field(&fields, "Prime2")?,
+                    dP: decoded_field(&fields, "Exponent1")?,
+                    dQ: decoded_field(&fields, "Exponent2")?,
+                    qInv: decoded_field(&fields, "Coefficient")?,
+                };
+                KeyPairKind::Rsa(RsaKeyPair::from_components(&components).map_err(rejected)?)
+            }
+            DnssecAlgorithm::ECDSAP256SHA256 | DnssecAlgorithm::ECDSAP384SHA384 => {
+                let private_key = decoded_field(&fields, "PrivateKey")?;
+                let mut point = vec![0x04];
+                point.extend_from_slice(dnskey.public_key());
+
+                let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+                    ecdsa_algorithm(dnskey.algorithm()),
+                    &private_key,
+                    &point,
+                    &SystemRandom::new(),
+                )
+                .map_err(|_| KeyError::KeyMismatch)?;
+                KeyPairKind::Ecdsa(key_pair)
+            }
+            DnssecAlgorithm::ED25519 => {
+                let seed = decoded_field(&fields, "PrivateKey")?;
+                KeyPairKind::Ed25519(Ed25519KeyPair::from_seed_unchecked(&seed).map_err(rejected)?)
+            }
+            algorithm => return Err(KeyError::UnsupportedAlgorithm(algorithm.into())),
+        };
+
+        if public_key(&key_pair) != dnskey.public_key() {
+            return Err(KeyError::KeyMismatch);
+        }
+
+        Ok(SigningKey { dnskey, key_pair })
+    }
+
+    pub fn dnskey(&self) -> &DNSKEY {
+        &self.dnskey
+    }
+
+    pub fn algorithm(&self) -> DnssecAlgorithm {
+        self.dnskey.algorithm()
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.dnskey.key_tag()
+    }
+
+    /// Keys with the SEP flag are treated as key signing keys.
+    pub fn is_key_signing_key(&self) -> bool {
+        self.dnskey.is_secure_entry_point()
+    }
+
+    /// Signs `message` in the format RRSIG signatures use. RSA and Ed25519
+    /// signatures are deterministic; ECDSA ones use a random nonce.
+    pub fn sign(&self, message: &[u8]) -> KeyResult<Vec<u8>> {
+        match &self.key_pair {
+            KeyPairKind::Rsa(key_pair) => {
+                let encoding = match self.dnskey.algorithm() {
+                    DnssecAlgorithm::RSASHA512 => &signature::RSA_PKCS1_SHA512,
+                    _ => &signature::RSA_PKCS1_SHA256,
+                };
+
+                let mut signature = vec![0; key_pair.public().modulus_len()];
+                key_pair
+                    .sign(encoding, &SystemRandom::new(), message, &mut signature)
+                    .map_err(|_| KeyError::SigningFailed)?;
+
+                Ok(signature)
+            }
+            KeyPairKind::Ecdsa(key_pair) => key_pair
+                .sign(&SystemRandom::new(), message)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| KeyError::SigningFailed),
+            KeyPairKind::Ed25519(key_pair) => Ok(key_pair.sign(message).as_ref().to_vec()),
+        }
+    }
+}
+
+fn rejected(error: ring::error::KeyRejected) -> KeyError {
+    KeyError::Rejected(error.to_string())
+}
+
+fn ecdsa_algorithm(algorithm: DnssecAlgorithm) -> &'static signature::EcdsaSigningAlgorithm {
+    match algorithm {
+        DnssecAlgorithm::ECDSAP384SHA384 => &signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+        _ => &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+    }
+}
+
+/// Public key in DNSKEY wire format (RFC 3110 section 2, RFC 6605
+/// section 4, RFC 8080 section 3).
+fn public_key(key_pair: &KeyPairKind) -> Vec<u8> {
+    match key_pair {
+        KeyPairKind::Rsa(key_pair) => {
+            let components: PublicKeyComponents<Vec<u8>> = key_pair.public().into();
+            let mut public_key = match components.e.len() {
+                length @ 1..=255 => vec![length as u8],
+                length => vec![0, (length >> 8) as u8, length as u8],
+            };
+            public_key.extend_from_slice(&components.e);
+            public_key.extend_from_slice(&components.n);
+
+            public_key
+        }
+        // Drop the uncompressed point prefix
+        KeyPairKind::Ecdsa(key_pair) => key_pair.public_key().as_ref()[1..].to_vec(),
+        KeyPairKind::Ed25519(key_pair) => key_pair.public_key().as_ref().to_vec(),
+    }
+}
+
+fn parse_public_file(public_file: &str) -> KeyResult<DNSKEY> {
+    for line in public_file.lines() {
+        let line = line.split(';').next().unwrap_or_default();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some(position) = tokens.iter().position(|token| token.eq_ignore_ascii_case("DNSKEY")) else {
+            continue;
+        };
+
+        let [flags, protocol, algorithm, key @ ..] = &tokens[position + 1..] else {
+            return Err(KeyError::MissingDnskey);
+        };
+
+        let flags = flags
+            .parse::<u16>()
+            .map_err(|_| KeyError::MalformedField(String::from("flags")))?;
+        if protocol.parse::<u8>() != Ok(DNSKEY::PROTOCOL) {
+            return Err(KeyError::MalformedField(String::from("protocol")));
+        }
+        let algorithm = algorithm
+            .parse::<u8>()
+            .map_err(|_| KeyError::MalformedField(String::from("algorithm")))?;
+        let key = BASE64
+            .decode(key.concat().as_bytes())
+            .map_err(|_| KeyError::MalformedField(String::from("public key")))?;
+
+        return Ok(DNSKEY::new(flags, DnssecAlgorithm::from(algorithm), key));
+    }
+
+    Err(KeyError::MissingDnskey)
+}
+
+fn parse_private_file(private_file: &str) -> HashMap<&str, &str> {
+    private_file
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect()
+}
+
+fn field<'a>(fields: &HashMap<&str, &'a str>, name: &'static str) -> KeyResult<&'a str> {
+    fields.get(name).copied().ok_or(KeyError::MissingField(name))
+}
+
+fn decoded_field(fields: &HashMap<&str, &str>, name: &'static str) -> KeyResult<Vec<u8>> {
+    BASE64
+        .decode(field(fields, name)?.as_bytes())
+        .map_err(|_| KeyError::MalformedField(String::from(name)))
+}
+
+#[cfg(test)]
+pub mod keys_unittest {
+    use crate::dnssec::keys::{KeyError, SigningKey};
+    use crate::dnssec::verify::verify_unittest::{ED25519_KEY, P384_KEY, RSASHA256_KEY};
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+    use data_encoding::BASE64;
+
+    pub const RSASHA256_PUBLIC_FILE: &str = "; This is a zone-signing key, keyid 38116, for example.net.
+example.net. 3600 IN DNSKEY 256 3 8 AwEAAeVI3mMKHIC5N1sIOd5cqYsghfhKiMIw0xJJXDIrpFhhJ0bw5k2E YepT9c/BJqvrdgV9E7xeIX+Flpgp/dQGD9CpQBLLXCh3a2yMC5OeJOLw vDiyf3wNe6y6PWaUP+qj6UE1Q0AH9Woo3GeUU9IMTIZNJCsov4F76KhZ sLT8iaGFDiSSNWTigxo9OmhZB7SRdNMNWTwc5AR4MCReZdSdRqHLpzhP BG4yyUIhYI2SB60ITghN2cGvHgHGGi7npqnK+fEkmwaSxdT3UMTgeV4c 6aFqTLJWtse+n6+OQTejiFBp8CNjPJEl+fev91G8lEwMpe7w/zmoF8LK 8sWln2Mjfoc=
+";
+    pub const RSASHA256_PRIVATE_FILE: &str = "Private-key-format: v1.3
+Algorithm: 8 (RSASHA256)
+Modulus: 5UjeYwocgLk3Wwg53lypiyCF+EqIwjDTEklcMiukWGEnRvDmTYRh6lP1z8Emq+t2BX0TvF4hf4WWmCn91AYP0KlAEstcKHdrbIwLk54k4vC8OLJ/fA17rLo9ZpQ/6qPpQTVDQAf1aijcZ5RT0gxMhk0kKyi/gXvoqFmwtPyJoYUOJJI1ZOKDGj06aFkHtJF00w1ZPBzkBHgwJF5l1J1GocunOE8EbjLJQiFgjZIHrQhOCE3Zwa8eAcYaLuemqcr58SSbBpLF1PdQxOB5XhzpoWpMsla2x76fr45BN6OIUGnwI2M8kSX596/3UbyUTAyl7vD/OagXwsryxaWfYyN+hw==
+PublicExponent: AQAB
+PrivateExponent: D2RSZYSmwC9u22IUfGxtB0VyKOZcAulDKh2ZnlaWrEYtXFJMq5SA/DmRrVYykeT3IcwxOHp7NjPSFbnv671Sb3gFMJ3NPHwzznAiUNETedaeVBILg9jl3gUMnSfDmK1TOcjpEFHYdD10+lEXRWkE9BkWnf0Yygj25+JJfgTb+mczMW/DeMmiIWfNOIB8+dPr/ItCsfTXU6Mom7x8YimMoYZeHlDMl2FoVURDixF84+N6Kry9UJIlFzAKGmI677CPsCyCHTfAsDi96P5ActbMalEOe9nFzsDkZFlvznvZDZWXb9OYDvtxRWHQgOfBGkDOiOIAuOvA6eMH/WXStDeESQ==
+Prime1: 9WSl8L7fEZtmUIq9IeK5Ej6YDz3sS7mxGTfKiNmyhiNVgkd0Jm/2Ss9R6NLxnJfiYjE14DKXOGBsXKxsdlhCORKRJjAL9a37+WY+DhH/LnUSWmNp9jS3OlVQKPguoukAPJc3RriW5zLzVxGa9CU3dVJbkXQJ4Ci6Nw1j1Ur7CYk=
+Prime2: 7zH5ilrxus+FjwuB6vrnNHBAJipS2fWA3vP2SC8HVaBqHrus7sySkkBibwKw8juAhHS7RxO1BoQ4PGTffKqXe2/cb+D6q/XnZ3HxjwyAdkzuQkO82py0pLwYq6TP16zpGou7x5xc5nBqtA0G4zai3oadijsDvI3gyw55WnyUE48=
+Exponent1: r8wI7S1wVQbFB5I86FeqSh25hcHQ1HcVHoHPgLakpGAP1CMoXJj9Ep7viSIFoS9WKQ4kEJdtqh+5wbJsiWO1HXnlrjvhdYHM7+CFOENAUqHgIgzyTkV5tjqEELWrg130o3OZT/rRcJQZrBiar6kxz8TOgKdmo8XJkcLv8E+lBDE=
+Exponent2: 48z1eiW42BAxg5UTCwYfUZqiDXb7Zubf7/XqFL7tPkhDKLfWL6sW4EB/PngSqu+uKve9MCj9m5IquKHdEi6pi/5xsv9R3JmZ47PpuTHP2lrUArneT1k01TrqR/Xzl9TuP8DaenVZKIHhrU5Gw/fRzxnnSbGAkn+QmfJT8rB4mdM=
+Coefficient: TO+V+6COZ8am2AYcQQsY76PZ77chzTANK32V+m2RTt7V1ZrNGrBP+CRG6IpOP+1Sjp7LYFtGDwxof3iUI5kTdtpR1K66SEXgDjFZMnAOwi2ODoAMYCMWH9ROYhgorrf7lUbfzSM/Z3gPbVPGCMvdR7lxDlkFcJbq289wAdVa87c=
+Created: 20000101000000
+";
+    pub const ED25519_PUBLIC_FILE: &str =
+        "example.net. IN DNSKEY 257 3 15 A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdyGZBJVMbg=\n";
+    pub const ED25519_PRIVATE_FILE: &str = "Private-key-format: v1.3
+Algorithm: 15 (ED25519)
+PrivateKey: AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=
+";
+    const RSASHA256_PKCS8: &str = "MIIEvgIBADANBgkqhkiG9w0BAQEFAASCBKgwggSkAgEAAoIBAQDlSN5jChyAuTdbCDneXKmLIIX4SojCMNMSSVwyK6RYYSdG8OZNhGHqU/XPwSar63YFfRO8XiF/hZaYKf3UBg/QqUASy1wod2tsjAuTniTi8Lw4sn98DXusuj1mlD/qo+lBNUNAB/VqKNxnlFPSDEyGTSQrKL+Be+ioWbC0/ImhhQ4kkjVk4oMaPTpoWQe0kXTTDVk8HOQEeDAkXmXUnUahy6c4TwRuMslCIWCNkgetCE4ITdnBrx4Bxhou56apyvnxJJsGksXU91DE4HleHOmhakyyVrbHvp+vjkE3o4hQafAjYzyRJfn3r/dRvJRMDKXu8P85qBfCyvLFpZ9jI36HAgMBAAECggEAD2RSZYSmwC9u22IUfGxtB0VyKOZcAulDKh2ZnlaWrEYtXFJMq5SA/DmRrVYykeT3IcwxOHp7NjPSFbnv671Sb3gFMJ3NPHwzznAiUNETedaeVBILg9jl3gUMnSfDmK1TOcjpEFHYdD10+lEXRWkE9BkWnf0Yygj25+JJfgTb+mczMW/DeMmiIWfNOIB8+dPr/ItCsfTXU6Mom7x8YimMoYZeHlDMl2FoVURDixF84+N6Kry9UJIlFzAKGmI677CPsCyCHTfAsDi96P5ActbMalEOe9nFzsDkZFlvznvZDZWXb9OYDvtxRWHQgOfBGkDOiOIAuOvA6eMH/WXStDeESQKBgQD1ZKXwvt8Rm2ZQir0h4rkSPpgPPexLubEZN8qI2bKGI1WCR3Qmb/ZKz1Ho0vGcl+JiMTXgMpc4YGxcrGx2WEI5EpEmMAv1rfv5Zj4OEf8udRJaY2n2NLc6VVAo+C6i6QA8lzdGuJbnMvNXEZr0JTd1UluRdAngKLo3DWPVSvsJiQKBgQDvMfmKWvG6z4WPC4Hq+uc0cEAmKlLZ9YDe8/ZILwdVoGoeu6zuzJKSQGJvArDyO4CEdLtHE7UGhDg8ZN98qpd7b9xv4Pqr9edncfGPDIB2TO5CQ7zanLSkvBirpM/XrOkai7vHnFzmcGq0DQbjNqLehp2KOwO8jeDLDnlafJQTjwKBgQCvzAjtLXBVBsUHkjzoV6pKHbmFwdDUdxUegc+AtqSkYA/UIyhcmP0Snu+JIgWhL1YpDiQQl22qH7nBsmyJY7UdeeWuO+F1gczv4IU4Q0BSoeAiDPJORXm2OoQQtauDXfSjc5lP+tFwlBmsGJqvqTHPxM6Ap2ajxcmRwu/wT6UEMQKBgQDjzPV6JbjYEDGDlRMLBh9RmqINdvtm5t/v9eoUvu0+SEMot9YvqxbgQH8+eBKq764q970wKP2bkiq4od0SLqmL/nGy/1HcmZnjs+m5Mc/aWtQCud5PWTTVOupH9fOX1O4/wNp6dVkogeGtTkbD99HPGedJsYCSf5CZ8lPysHiZ0wKBgEzvlfugjmfGptgGHEELGO+j2e+3Ic0wDSt9lfptkU7e1dWazRqwT/gkRuiKTj/tUo6ey2BbRg8MaH94lCOZE3baUdSuukhF4A4xWTJwDsItjg6ADGAjFh/UTmIYKK63+5VG380jP2d4D21TxgjL3Ue5cQ5ZBXCW6tvPcAHVWvO3";
+    const P384_PKCS8: &str = "MIG2AgEAMBAGByqGSM49AgEGBSuBBAAiBIGeMIGbAgEBBDAKPE3dNJb5wR35CKIIZCIzai4xuYDbWwdNHpLLSHfNI4Enr+l55rC6LdfCL6YgAluhZANiAARK95VPCblEEkdtCJmXbMyJIv3tLr3bBtl/y//pYSJMMQ7jFrjsFKNNrKL5o8UrL7mxyUjV4d5HpAkOa1fBSbcFtOA4uoGLft5wBgIRFg+1Av0w3H6SEFbFmmOpxipATZM=";
+    const P384_PUBLIC_FILE: &str = "example.net. IN DNSKEY 257 3 14 SveVTwm5RBJHbQiZl2zMiSL97S692wbZf8v/6WEiTDEO4xa47BSjTayi+aPFKy+5sclI1eHeR6QJDmtXwUm3BbTgOLqBi37ecAYCERYPtQL9MNx+khBWxZpjqcYqQE2T\n";
+    const P384_PRIVATE_FILE: &str = "Private-key-format: v1.3
+Algorithm: 14 (ECDSAP384SHA384)
+PrivateKey: CjxN3TSW+cEd+QiiCGQiM2ouMbmA21sHTR6Sy0h3zSOBJ6/peeawui3Xwi+mIAJb
+";
+
+    pub fn ed25519_key() -> SigningKey {
+        SigningKey::from_bind(ED25519_PUBLIC_FILE, ED25519_PRIVATE_FILE).unwrap()
+    }
+
+    #[test]
+    fn rsa_from_bind_files() {
+        let key = SigningKey::from_bind(RSASHA256_PUBLIC_FILE, RSASHA256_PRIVATE_FILE).unwrap();
+
+        assert_eq!(key.key_tag(), 38116);
+        assert_eq!(key.dnskey().flags(), 256);
+        assert!(!key.is_key_signing_key());
+        assert_eq!(BASE64.encode(key.dnskey().public_key()), RSASHA256_KEY);
+    }
+
+    #[test]
+    fn rsa_from_pkcs8() {
+        let pkcs8 = BASE64.decode(RSASHA256_PKCS8.as_bytes()).unwrap();
+        let key = SigningKey::from_pkcs8(256, DnssecAlgorithm::RSASHA256, &pkcs8).unwrap();
+
+        assert_eq!(key.key_tag(), 38116);
+        assert_eq!(BASE64.encode(key.dnskey().public_key()), RSASHA256_KEY);
+    }
+
+    #[test]
+    fn ecdsa_from_bind_files_and_pkcs8() {
+        let key = SigningKey::from_bind(P384_PUBLIC_FILE, P384_PRIVATE_FILE).unwrap();
+        assert_eq!(key.key_tag(), 62137);
+
+        let pkcs8 = BASE64.decode(P384_PKCS8.as_bytes()).unwrap();
+        let key = SigningKey::from_pkcs8(257, DnssecAlgorithm::ECDSAP384SHA384, &pkcs8).unwrap();
+        assert_eq!(key.key_tag(), 62137);
+        assert_eq!(BASE64.encode(key.dnskey().public_key()), P384_KEY);
+    }
+
+    #[test]
+    fn ed25519_from_bind_files() {
+        let key = ed25519_key();
+
+        assert_eq!(key.key_tag(), 34259);
+        assert!(key.is_key_signing_key());
+        assert_eq!(BASE64.encode(key.dnskey().public_key()), ED25519_KEY);
+    }
+
+    #[test]
+    fn error_private_key_does_not_match() {
+        let error = SigningKey::from_bind(
+            ED25519_PUBLIC_FILE,
+            "Algorithm: 15 (ED25519)\nPrivateKey: AQECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(error, KeyError::KeyMismatch);
+    }
+
+    #[test]
+    fn error_missing_and_mismatched_fields() {
+        assert_eq!(
+            SigningKey::from_bind(ED25519_PUBLIC_FILE, "Algorithm: 15 (ED25519)\n").unwrap_err(),
+            KeyError::MissingField("PrivateKey")
+        );
+        assert_eq!(
+            SigningKey::from_bind(ED25519_PUBLIC_FILE, RSASHA256_PRIVATE_FILE).unwrap_err(),
+            KeyError::AlgorithmMismatch(8, 15)
+        );
+        assert_eq!(
+            SigningKey::from_bind("; no key here\n", ED25519_PRIVATE_FILE).unwrap_err(),
+            KeyError::MissingDnskey
+        );
+    }
+
+    #[test]
+    fn error_unsupported_algorithm() {
+        let error = SigningKey::from_pkcs8(256, DnssecAlgorithm::RSASHA1, &[]).unwrap_err();
+
+        assert_eq!(error, KeyError::UnsupportedAlgorithm(5));
+    }
+}
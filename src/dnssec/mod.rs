@@ -0,0 +1,63 @@
+pub mod canonical;
+pub mod crypto;
+pub mod denial;
+pub mod digest;
+pub mod keys;
+pub mod signer;
+pub mod trust_anchor;
+pub mod validator;
+pub mod verify;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum DnssecError {
+    #[error("DNSSEC algorithm {0} is not supported")]
+    UnsupportedAlgorithm(u8),
+    #[error("DS digest type {0} is not supported")]
+    UnsupportedDigestType(u8),
+    #[error("NSEC3 hash algorithm {0} is not supported")]
+    UnsupportedHashAlgorithm(u8),
+    #[error("Record is not an RRSIG")]
+    NotAnRrsig,
+    #[error("Record is not a DNSKEY")]
+    NotADnskey,
+    #[error("Record is not a DS")]
+    NotADs,
+    #[error("RRset to verify is empty")]
+    EmptyRrset,
+    #[error("Records in the RRset do not share owner, class and type")]
+    RrsetMismatch,
+    #[error("RRSIG covers a different type than the RRset")]
+    TypeCoveredMismatch,
+    #[error("RRSIG labels field is larger than the owner name")]
+    InvalidLabelCount,
+    #[error("RRSIG signer is not the zone containing the RRset")]
+    SignerMismatch,
+    #[error("DNSKEY owner is not the RRSIG signer")]
+    KeyOwnerMismatch,
+    #[error("RRSIG key tag does not match the DNSKEY")]
+    KeyTagMismatch,
+    #[error("RRSIG algorithm does not match the DNSKEY")]
+    AlgorithmMismatch,
+    #[error("DNSKEY is not a zone key")]
+    NotAZoneKey,
+    #[error("DNSKEY has been revoked")]
+    RevokedKey,
+    #[error("DNSKEY protocol field is not 3")]
+    InvalidProtocol,
+    #[error("Signature has expired")]
+    SignatureExpired,
+    #[error("Signature is not yet valid")]
+    SignatureNotYetValid,
+    #[error("DNSKEY public key is malformed")]
+    InvalidPublicKey,
+    #[error("Signature does not verify")]
+    BadSignature,
+    #[error("No RRSIG verifies the RRset with the given keys")]
+    MissingSignature,
+    #[error("DS digest does not match the DNSKEY")]
+    DigestMismatch,
+}
+
+pub type DnssecResult<T> = Result<T, DnssecError>;
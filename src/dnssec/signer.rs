@@ -0,0 +1,718 @@
+use crate::clock::Clock;
+use crate::dnssec::canonical::{canonical_rdata, signed_data};
+use crate::dnssec::digest::{create_ds, nsec3_hash};
+use crate::dnssec::keys::{KeyError, SigningKey};
+use crate::dnssec::DnssecError;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::record::{Record, RecordBuilder};
+use crate::records::rdata::algorithm::{DigestType, Nsec3HashAlgorithm};
+use crate::records::rdata::nsec::NSEC;
+use crate::records::rdata::nsec3::NSEC3;
+use crate::records::rdata::nsec3param::NSEC3PARAM;
+use crate::records::rdata::rrsig::RRSIG;
+use crate::records::rdata::type_bitmap::TypeBitmap;
+use crate::records::record_class::RecordClass;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use data_encoding::BASE32HEX_NOPAD;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use thiserror::Error;
+
+const DEFAULT_VALIDITY: u32 = 30 * 24 * 3600;
+const DEFAULT_INCEPTION_OFFSET: u32 = 3600;
+const CDS_DIGEST_TYPE: DigestType = DigestType::SHA256;
+
+// Records the signer produces itself. Copies of them in the input are
+// dropped so re-signing a signed zone does not accumulate stale data.
+const GENERATED_TYPES: [RecordType; 6] = [
+    RecordType::RRSIG,
+    RecordType::NSEC,
+    RecordType::NSEC3,
+    RecordType::NSEC3PARAM,
+    RecordType::CDS,
+    RecordType::CDNSKEY,
+];
+
+// RRsets at the apex signed by the key signing keys
+const KEY_TYPES: [RecordType; 3] = [RecordType::DNSKEY, RecordType::CDS, RecordType::CDNSKEY];
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum SigningError {
+    #[error("No signing keys were given")]
+    NoKeys,
+    #[error("Zone has no SOA record at {0}")]
+    MissingSoa(Fqdn),
+    #[error("Record owner {0} is outside the zone")]
+    OutOfZone(Fqdn),
+    #[error(transparent)]
+    Key(#[from] KeyError),
+    #[error(transparent)]
+    Dnssec(#[from] DnssecError),
+}
+
+pub type SigningResult<T> = Result<T, SigningError>;
+
+/// NSEC3 chain parameters (RFC 5155 section 7.1).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Nsec3Config {
+    iterations: u16,
+    salt: Vec<u8>,
+    opt_out: bool,
+}
+
+impl Nsec3Config {
+    pub fn new(iterations: u16, salt: Vec<u8>, opt_out: bool) -> Self {
+        Self {
+            iterations,
+            salt,
+            opt_out,
+        }
+    }
+
+    pub fn iterations(&self) -> u16 {
+        self.iterations
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    pub fn is_opt_out(&self) -> bool {
+        self.opt_out
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum DenialOfExistence {
+    #[default]
+    Nsec,
+    Nsec3(Nsec3Config),
+}
+
+// Owner name, then type number, then the RRs of the RRset
+type Zone = BTreeMap<Fqdn, BTreeMap<u16, Vec<Record>>>;
+
+/// Signs a whole zone in memory. Signature validity is taken from the
+/// clock, so a fixed clock gives a reproducible zone for RSA and Ed25519
+/// keys.
+///
+/// Keys with the SEP flag sign the DNSKEY, CDS and CDNSKEY RRsets and the
+/// other keys sign the rest. When all keys are of one kind, each of them
+/// signs everything.
+pub struct ZoneSigner {
+    apex: Fqdn,
+    keys: Vec<SigningKey>,
+    clock: Arc<dyn Clock>,
+    validity: u32,
+    inception_offset: u32,
+    denial: DenialOfExistence,
+}
+
+impl ZoneSigner {
+    pub fn new(apex: Fqdn, keys: Vec<SigningKey>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            apex,
+            keys,
+            clock,
+            validity: DEFAULT_VALIDITY,
+            inception_offset: DEFAULT_INCEPTION_OFFSET,
+            denial: DenialOfExistence::default(),
+        }
+    }
+
+    /// Seconds from now until the signatures expire.
+    pub fn validity(self, validity: u32) -> Self {
+        Self { validity, ..self }
+    }
+
+    /// Seconds the inception is backdated by, to allow for clock skew
+    /// between the signer and validators.
+    pub fn inception_offset(self, inception_offset: u32) -> Self {
+        Self {
+            inception_offset,
+            ..self
+        }
+    }
+
+    pub fn denial(self, denial: DenialOfExistence) -> Self {
+        Self { denial, ..self }
+    }
+
+    pub fn apex(&self) -> &Fqdn {
+        &self.apex
+    }
+
+    pub fn keys(&self) -> &[SigningKey] {
+        &self.keys
+    }
+
+    /// Signs the records of the zone. The result holds the input records,
+    /// the DNSKEY, CDS and CDNSKEY RRsets, the NSEC or NSEC3 chain and the
+    /// RRSIGs, sorted in canonical order. Delegation NS RRsets and glue are
+    /// kept but left unsigned and out of the chain.
+    pub fn sign_zone(&self, records: &[Record]) -> SigningResult<Vec<Record>> {
+        if self.keys.is_empty() {
+            return Err(SigningError::NoKeys);
+        }
+
+        let (soa, minimum) = records
+            .iter()
+            .filter(|record| record.owner_name() == &self.apex)
+            .find_map(|record| match record.data() {
+                RecordData::SOA(soa) => Some((record, soa.minimum())),
+                _ => None,
+            })
+            .ok_or_else(|| SigningError::MissingSoa(self.apex.clone()))?;
+
+        // Negative answers are cached for the lower of the two (RFC 9077)
+        let negative_ttl = soa.ttl().min(minimum);
+
+        let mut zone = Zone::new();
+        for record in records {
+            if !record.owner_name().is_subdomain_of(&self.apex) {
+                return Err(SigningError::OutOfZone(record.owner_name().clone()));
+            }
+
+            if !GENERATED_TYPES.contains(&record.record_type()) {
+                insert(&mut zone, record.clone());
+            }
+        }
+
+        for record in self.key_records(soa.ttl(), soa.class())? {
+            insert(&mut zone, record);
+        }
+
+        let delegations: BTreeSet<Fqdn> = zone
+            .iter()
+            .filter(|(name, rrsets)| *name != &self.apex && rrsets.contains_key(&RecordType::NS.into()))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let chain = match &self.denial {
+            DenialOfExistence::Nsec => self.nsec_chain(&zone, &delegations, negative_ttl, soa.class()),
+            DenialOfExistence::Nsec3(config) => {
+                self.nsec3_chain(&zone, &delegations, config, negative_ttl, soa.class())?
+            }
+        };
+        for record in chain {
+            insert(&mut zone, record);
+        }
+
+        let mut signatures = Vec::new();
+        for (name, rrsets) in &zone {
+            if self.is_occluded(name, &delegations) {
+                continue;
+            }
+
+            let is_delegation = delegations.contains(name);
+            for rrset in rrsets.values() {
+                let record_type = rrset[0].record_type();
+                if is_delegation && !matches!(record_type, RecordType::DS | RecordType::NSEC) {
+                    continue;
+                }
+
+                let rrset: Vec<&Record> = rrset.iter().collect();
+                for key in self.keys_for(record_type) {
+                    signatures.push(self.sign_rrset(&rrset, key)?);
+                }
+            }
+        }
+        for record in signatures {
+            insert(&mut zone, record);
+        }
+
+        let signed = zone
+            .into_values()
+            .flat_map(|rrsets| rrsets.into_values())
+            .flat_map(|mut rrset| {
+                rrset.sort_by_cached_key(|record| canonical_rdata(record.data()));
+                rrset
+            })
+            .collect();
+
+        Ok(signed)
+    }
+
+    /// Creates the RRSIG over one RRset with the given key.
+    pub fn sign_rrset(&self, rrset: &[&Record], key: &SigningKey) -> SigningResult<Record> {
+        let first = rrset.first().ok_or(DnssecError::EmptyRrset)?;
+        let ttl = rrset.iter().map(|record| record.ttl()).min().unwrap_or_default();
+        let now = self.clock.now() as u32;
+
+        let rrsig = |signature: Vec<u8>| {
+            RRSIG::new(
+                first.record_type(),
+                key.algorithm(),
+                first.owner_name().label_count(),
+                ttl,
+                now.wrapping_add(self.validity),
+                now.wrapping_sub(self.inception_offset),
+                key.key_tag(),
+                self.apex.clone(),
+                signature,
+            )
+        };
+
+        let signature = key.sign(&signed_data(&rrsig(Vec::new()), rrset))?;
+
+        Ok(self.record(first.owner_name().clone(), first.class(), ttl, RecordData::RRSIG(rrsig(signature))))
+    }
+
+    /// DNSKEY records for every key, plus CDS and CDNSKEY records for the
+    /// key signing keys so the parent can pick them up (RFC 7344).
+    pub fn key_records(&self, ttl: u32, class: RecordClass) -> SigningResult<Vec<Record>> {
+        let mut records: Vec<Record> = self
+            .keys
+            .iter()
+            .map(|key| self.record(self.apex.clone(), class, ttl, RecordData::DNSKEY(key.dnskey().clone())))
+            .collect();
+
+        for key in self.keys_for(RecordType::DNSKEY) {
+            let cds = create_ds(&self.apex, key.dnskey(), CDS_DIGEST_TYPE)?;
+            records.push(self.record(self.apex.clone(), class, ttl, RecordData::CDS(cds)));
+            records.push(self.record(
+                self.apex.clone(),
+                class,
+                ttl,
+                RecordData::CDNSKEY(key.dnskey().clone()),
+            ));
+        }
+
+        Ok(records)
+    }
+
+    fn keys_for(&self, record_type: RecordType) -> Vec<&SigningKey> {
+        let wants_key_signing_key = KEY_TYPES.contains(&record_type);
+        let keys: Vec<&SigningKey> = self
+            .keys
+            .iter()
+            .filter(|key| key.is_key_signing_key() == wants_key_signing_key)
+            .collect();
+
+        match keys.is_empty() {
+            true => self.keys.iter().collect(),
+            false => keys,
+        }
+    }
+
+    fn is_below_apex(&self, name: &Fqdn) -> bool {
+        name != &self.apex && name.is_subdomain_of(&self.apex)
+    }
+
+    /// Names below a zone cut hold glue, which the zone is not
+    /// authoritative for.
+    fn is_occluded(&self, name: &Fqdn, delegations: &BTreeSet<Fqdn>) -> bool {
+        let mut ancestor = name.parent();
+
+        while let Some(name) = ancestor.filter(|name| self.is_below_apex(name)) {
+            if delegations.contains(&name) {
+                return true;
+            }
+            ancestor = name.parent();
+        }
+
+        false
+    }
+
+    /// Types present at an authoritative name. A zone cut only owns its
+    /// NS and DS RRsets.
+    fn types_at(&self, name: &Fqdn, rrsets: &BTreeMap<u16, Vec<Record>>, delegations: &BTreeSet<Fqdn>) -> Vec<u16> {
+        let is_delegation = delegations.contains(name);
+
+        rrsets
+            .keys()
+            .copied()
+            .filter(|&record_type| {
+                !is_delegation || record_type == u16::from(RecordType::NS) || record_type == u16::from(RecordType::DS)
+            })
+            .collect()
+    }
+
+    fn nsec_chain(&self, zone: &Zone, delegations: &BTreeSet<Fqdn>, ttl: u32, class: RecordClass) -> Vec<Record> {
+        let names: Vec<&Fqdn> = zone
+            .keys()
+            .filter(|name| !self.is_occluded(name, delegations))
+            .collect();
+
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| {
+                let next_domain_name = names[(index + 1) % names.len()].clone();
+
+                let mut types = self.types_at(name, &zone[name], delegations);
+                types.extend([u16::from(RecordType::RRSIG), u16::from(RecordType::NSEC)]);
+
+                let nsec = NSEC::new(next_domain_name, TypeBitmap::new(types));
+                self.record(name.clone(), class, ttl, RecordData::NSEC(nsec))
+            })
+            .collect()
+    }
+
+    /// Hashes every authoritative name and empty non-terminal. With opt-out,
+    /// delegations without a DS are left out of the chain.
+    fn nsec3_chain(
+        &self,
+        zone: &Zone,
+        delegations: &BTreeSet<Fqdn>,
+        config: &Nsec3Config,
+        ttl: u32,
+        class: RecordClass,
+    ) -> SigningResult<Vec<Record>> {
+        let hash = |name: &Fqdn| nsec3_hash(name, Nsec3HashAlgorithm::SHA1, config.iterations, &config.salt);
+        let mut hashed: BTreeMap<Vec<u8>, Vec<u16>> = BTreeMap::new();
+
+        for (name, rrsets) in zone {
+            if self.is_occluded(name, delegations) {
+                continue;
+            }
+
+            let is_insecure_delegation =
+                delegations.contains(name) && !rrsets.contains_key(&RecordType::DS.into());
+            if is_insecure_delegation && config.opt_out {
+                continue;
+            }
+
+            let mut types = self.types_at(name, rrsets, delegations);
+            if !is_insecure_delegation {
+                types.push(RecordType::RRSIG.into());
+            }
+            if name == &self.apex {
+                types.push(RecordType::NSEC3PARAM.into());
+            }
+            hashed.insert(hash(name)?, types);
+
+            let mut ancestor = name.parent();
+            while let Some(empty_non_terminal) = ancestor.filter(|ancestor| self.is_below_apex(ancestor)) {
+                if !zone.contains_key(&empty_non_terminal) {
+                    hashed.entry(hash(&empty_non_terminal)?).or_default();
+                }
+                ancestor = empty_non_terminal.parent();
+            }
+        }
+
+        let hashes: Vec<&Vec<u8>> = hashed.keys().collect();
+        let flags = config.opt_out as u8;
+
+        let mut chain: Vec<Record> = hashed
+            .iter()
+            .enumerate()
+            .map(|(index, (owner_hash, types))| {
+                let owner_name = self
+                    .apex
+                    .prepend_label(&BASE32HEX_NOPAD.encode(owner_hash).to_lowercase());
+                let nsec3 = NSEC3::new(
+                    Nsec3HashAlgorithm::SHA1,
+                    flags,
+                    config.iterations,
+                    config.salt.clone(),
+                    hashes[(index + 1) % hashes.len()].clone(),
+                    TypeBitmap::new(types.iter().copied()),
+                );
+
+                self.record(owner_name, class, ttl, RecordData::NSEC3(nsec3))
+            })
+            .collect();
+
+        // NSEC3PARAM is only read by the primary, so it is not cached
+        let nsec3param = NSEC3PARAM::new(Nsec3HashAlgorithm::SHA1, 0, config.iterations, config.salt.clone());
+        chain.push(self.record(self.apex.clone(), class, 0, RecordData::NSEC3PARAM(nsec3param)));
+
+        Ok(chain)
+    }
+
+    fn record(&self, owner_name: Fqdn, class: RecordClass, ttl: u32, data: RecordData) -> Record {
+        RecordBuilder::new()
+            .owner_name(owner_name)
+            .record_type(data.record_type())
+            .data(data)
+            .class(class)
+            .ttl(ttl)
+            .build()
+    }
+}
+
+fn insert(zone: &mut Zone, record: Record) {
+    let rrset = zone
+        .entry(record.owner_name().clone())
+        .or_default()
+        .entry(record.record_type().into())
+        .or_default();
+
+    if !rrset.iter().any(|existing| existing.data() == record.data()) {
+        rrset.push(record);
+    }
+}
+
+#[cfg(test)]
+mod signer_unittest {
+    use crate::clock::ManualClock;
+    use crate::dnssec::denial::{prove_nodata, prove_nxdomain, DenialResult};
+    use crate::dnssec::keys::keys_unittest::{ed25519_key, RSASHA256_PRIVATE_FILE, RSASHA256_PUBLIC_FILE};
+    use crate::dnssec::keys::SigningKey;
+    use crate::dnssec::signer::{DenialOfExistence, Nsec3Config, SigningError, ZoneSigner};
+    use crate::dnssec::verify::verify_rrsig;
+    use crate::dnssec::verify::verify_unittest::{
+        www_a_record, ED25519_SIGNATURE, EXPIRATION, INCEPTION, RSASHA256_SIGNATURE,
+    };
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::record::{Record, RecordBuilder};
+    use crate::records::rdata::a::A;
+    use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+    use crate::records::rdata::ds::DS;
+    use crate::records::rdata::ns::NS;
+    use crate::records::rdata::soa::SOA;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use data_encoding::BASE64;
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    const NOW: u64 = INCEPTION as u64 + 3600;
+
+    fn name(name: &str) -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    fn record(owner_name: &str, data: RecordData) -> Record {
+        RecordBuilder::new()
+            .owner_name(name(owner_name))
+            .record_type(data.record_type())
+            .data(data)
+            .ttl(3600)
+            .build()
+    }
+
+    fn a(owner_name: &str, address: [u8; 4]) -> Record {
+        record(owner_name, RecordData::A(A::new(Ipv4Addr::from(address))))
+    }
+
+    fn ns(owner_name: &str, name_server: &str) -> Record {
+        record(owner_name, RecordData::NS(NS::new(name(name_server))))
+    }
+
+    // Ed25519 PKCS#8 v1 document around a fixed seed
+    fn zone_signing_key(seed: u8) -> SigningKey {
+        let mut pkcs8 = BASE64.decode(b"MC4CAQAwBQYDK2VwBCIEIA==").unwrap();
+        pkcs8.extend_from_slice(&[seed; 32]);
+
+        SigningKey::from_pkcs8(256, DnssecAlgorithm::ED25519, &pkcs8).unwrap()
+    }
+
+    fn signer(keys: Vec<SigningKey>) -> ZoneSigner {
+        ZoneSigner::new(name("example.net."), keys, Arc::new(ManualClock::new(NOW)))
+            .validity(EXPIRATION - NOW as u32)
+    }
+
+    fn zone() -> Vec<Record> {
+        let soa = SOA::new(
+            name("ns1.example.net."),
+            name("hostmaster.example.net."),
+            2024010101,
+            7200,
+            3600,
+            1209600,
+            300,
+        );
+
+        vec![
+            record("example.net.", RecordData::SOA(soa)),
+            ns("example.net.", "ns1.example.net."),
+            a("ns1.example.net.", [192, 0, 2, 53]),
+            a("www.example.net.", [192, 0, 2, 1]),
+            a("host.deep.example.net.", [192, 0, 2, 2]),
+            ns("insecure.example.net.", "ns.insecure.example.net."),
+            a("ns.insecure.example.net.", [192, 0, 2, 54]),
+            ns("secure.example.net.", "ns1.example.net."),
+            record(
+                "secure.example.net.",
+                RecordData::DS(DS::new(
+                    1234,
+                    DnssecAlgorithm::ED25519,
+                    DigestType::SHA256,
+                    vec![0xab; 32],
+                )),
+            ),
+        ]
+    }
+
+    fn of_type(records: &[Record], record_type: RecordType) -> Vec<&Record> {
+        records
+            .iter()
+            .filter(|record| record.record_type() == record_type)
+            .collect()
+    }
+
+    fn rrsig_types(records: &[Record], owner_name: &str) -> Vec<RecordType> {
+        records
+            .iter()
+            .filter(|record| record.owner_name() == &name(owner_name))
+            .filter_map(|record| match record.data() {
+                RecordData::RRSIG(rrsig) => Some(rrsig.type_covered()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn verify_all(records: &[Record]) {
+        let clock = ManualClock::new(NOW);
+        let dnskeys = of_type(records, RecordType::DNSKEY);
+
+        for rrsig in of_type(records, RecordType::RRSIG) {
+            let RecordData::RRSIG(rrsig_data) = rrsig.data() else {
+                unreachable!()
+            };
+            let rrset: Vec<&Record> = records
+                .iter()
+                .filter(|record| record.owner_name() == rrsig.owner_name())
+                .filter(|record| record.record_type() == rrsig_data.type_covered())
+                .collect();
+
+            assert!(
+                dnskeys
+                    .iter()
+                    .any(|dnskey| verify_rrsig(&rrset, rrsig, dnskey, &clock).is_ok()),
+                "{} {} does not verify",
+                rrsig.owner_name(),
+                rrsig_data.type_covered()
+            );
+        }
+    }
+
+    #[test]
+    fn signatures_match_reference() {
+        let rsa = SigningKey::from_bind(RSASHA256_PUBLIC_FILE, RSASHA256_PRIVATE_FILE).unwrap();
+        let signer = signer(vec![]);
+        let rrset = [www_a_record([192, 0, 2, 1])];
+        let rrset: Vec<&Record> = rrset.iter().collect();
+
+        for (key, expected) in [(rsa, RSASHA256_SIGNATURE), (ed25519_key(), ED25519_SIGNATURE)] {
+            let rrsig = signer.sign_rrset(&rrset, &key).unwrap();
+            let RecordData::RRSIG(rrsig) = rrsig.data() else {
+                unreachable!()
+            };
+
+            assert_eq!(rrsig.signature_inception(), INCEPTION);
+            assert_eq!(rrsig.signature_expiration(), EXPIRATION);
+            assert_eq!(rrsig.labels(), 3);
+            assert_eq!(BASE64.encode(rrsig.signature()), expected);
+        }
+    }
+
+    #[test]
+    fn sign_zone_with_nsec() {
+        let signed = signer(vec![ed25519_key(), zone_signing_key(7)])
+            .sign_zone(&zone())
+            .unwrap();
+
+        verify_all(&signed);
+
+        let chain: Vec<String> = of_type(&signed, RecordType::NSEC)
+            .iter()
+            .map(|record| match record.data() {
+                RecordData::NSEC(nsec) => format!("{} {} {}", record.owner_name(), nsec.next_domain_name(), nsec.type_bitmap()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                "example.net. host.deep.example.net. NS SOA RRSIG NSEC DNSKEY CDS CDNSKEY",
+                "host.deep.example.net. insecure.example.net. A RRSIG NSEC",
+                "insecure.example.net. ns1.example.net. NS RRSIG NSEC",
+                "ns1.example.net. secure.example.net. A RRSIG NSEC",
+                "secure.example.net. www.example.net. NS DS RRSIG NSEC",
+                "www.example.net. example.net. A RRSIG NSEC",
+            ]
+        );
+
+        assert_eq!(
+            rrsig_types(&signed, "example.net."),
+            vec![
+                RecordType::NS,
+                RecordType::SOA,
+                RecordType::NSEC,
+                RecordType::DNSKEY,
+                RecordType::CDS,
+                RecordType::CDNSKEY
+            ]
+        );
+        assert_eq!(rrsig_types(&signed, "insecure.example.net."), vec![RecordType::NSEC]);
+        assert_eq!(rrsig_types(&signed, "secure.example.net."), vec![RecordType::DS, RecordType::NSEC]);
+        assert!(rrsig_types(&signed, "ns.insecure.example.net.").is_empty());
+        assert!(signed.contains(&a("ns.insecure.example.net.", [192, 0, 2, 54])));
+
+        assert_eq!(of_type(&signed, RecordType::DNSKEY).len(), 2);
+        assert_eq!(of_type(&signed, RecordType::CDS).len(), 1);
+        assert_eq!(of_type(&signed, RecordType::CDNSKEY).len(), 1);
+
+        assert_eq!(prove_nxdomain(&signed, &name("nope.example.net.")), DenialResult::SecureNxDomain);
+        assert_eq!(
+            prove_nodata(&signed, &name("www.example.net."), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+    }
+
+    #[test]
+    fn sign_zone_with_nsec3() {
+        let denial = DenialOfExistence::Nsec3(Nsec3Config::new(0, vec![0xaa, 0xbb], false));
+        let signed = signer(vec![ed25519_key()]).denial(denial).sign_zone(&zone()).unwrap();
+
+        verify_all(&signed);
+
+        // Six names in the zone plus the empty non-terminal deep.example.net.
+        assert_eq!(of_type(&signed, RecordType::NSEC3).len(), 7);
+        assert_eq!(of_type(&signed, RecordType::NSEC3PARAM).len(), 1);
+        assert!(of_type(&signed, RecordType::NSEC).is_empty());
+
+        assert_eq!(prove_nxdomain(&signed, &name("nope.example.net.")), DenialResult::SecureNxDomain);
+        assert_eq!(
+            prove_nodata(&signed, &name("deep.example.net."), RecordType::A),
+            DenialResult::SecureNoData
+        );
+        assert_eq!(
+            prove_nodata(&signed, &name("example.net."), RecordType::AAAA),
+            DenialResult::SecureNoData
+        );
+    }
+
+    #[test]
+    fn sign_zone_with_nsec3_opt_out() {
+        let denial = DenialOfExistence::Nsec3(Nsec3Config::new(0, vec![], true));
+        let signed = signer(vec![ed25519_key()]).denial(denial).sign_zone(&zone()).unwrap();
+
+        verify_all(&signed);
+
+        let nsec3s = of_type(&signed, RecordType::NSEC3);
+        assert_eq!(nsec3s.len(), 6);
+        assert!(nsec3s.iter().all(|record| match record.data() {
+            RecordData::NSEC3(nsec3) => nsec3.is_opt_out(),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn sign_zone_is_deterministic() {
+        let first = signer(vec![ed25519_key()]).sign_zone(&zone()).unwrap();
+        let second = signer(vec![ed25519_key()]).sign_zone(&first).unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn error_unsignable_zones() {
+        assert_eq!(signer(vec![]).sign_zone(&zone()).unwrap_err(), SigningError::NoKeys);
+        assert_eq!(
+            signer(vec![ed25519_key()]).sign_zone(&zone()[1..]).unwrap_err(),
+            SigningError::MissingSoa(name("example.net."))
+        );
+
+        let mut records = zone();
+        records.push(a("www.example.org.", [192, 0, 2, 9]));
+        assert_eq!(
+            signer(vec![ed25519_key()]).sign_zone(&records).unwrap_err(),
+            SigningError::OutOfZone(name("www.example.org."))
+        );
+    }
+}
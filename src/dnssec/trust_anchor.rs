@@ -0,0 +1,312 @@
+use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+use crate::records::rdata::ds::DS;
+use crate::records::rdata::rrsig::parse_timestamp;
+use data_encoding::HEXUPPER_PERMISSIVE;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TrustAnchorError {
+    #[error("Trust anchor XML has no <{0}> element")]
+    MissingElement(&'static str),
+    #[error("Trust anchor XML has an invalid {0} value")]
+    InvalidElement(&'static str),
+    #[error("Line {0} is not a DS record")]
+    MalformedDsLine(usize),
+    #[error("No trust anchor found")]
+    Empty,
+}
+
+type TrustAnchorResult = Result<TrustAnchor, TrustAnchorError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TrustAnchorEntry {
+    owner_name: Fqdn,
+    ds: DS,
+    valid_from: Option<u64>,
+    valid_until: Option<u64>,
+}
+
+/// DS records the validator trusts without further proof.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TrustAnchor {
+    entries: Vec<TrustAnchorEntry>,
+}
+
+impl TrustAnchorEntry {
+    pub fn new(owner_name: Fqdn, ds: DS) -> Self {
+        Self {
+            owner_name,
+            ds,
+            valid_from: None,
+            valid_until: None,
+        }
+    }
+
+    pub fn owner_name(&self) -> &Fqdn {
+        &self.owner_name
+    }
+
+    pub fn ds(&self) -> &DS {
+        &self.ds
+    }
+
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        self.valid_from.is_none_or(|valid_from| now >= valid_from)
+            && self.valid_until.is_none_or(|valid_until| now < valid_until)
+    }
+}
+
+impl TrustAnchor {
+    pub fn new(entries: Vec<TrustAnchorEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Reads the IANA root anchor format (RFC 7958 section 2), keeping the
+    /// validFrom and validUntil window of every KeyDigest.
+    pub fn from_rfc7958_xml(xml: &str) -> TrustAnchorResult {
+        let zone = element_text(xml, "Zone").ok_or(TrustAnchorError::MissingElement("Zone"))?;
+        let owner_name = FqdnBuilder::new().generate_from_string(zone.to_string()).build();
+
+        let mut entries = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find("<KeyDigest") {
+            let end = rest[start..]
+                .find("</KeyDigest>")
+                .ok_or(TrustAnchorError::MissingElement("/KeyDigest"))?;
+            let key_digest = &rest[start..start + end];
+            rest = &rest[start + end..];
+
+            let opening_tag = &key_digest[..key_digest.find('>').unwrap_or(key_digest.len())];
+            let valid_from = match attribute(opening_tag, "validFrom") {
+                Some(value) => Some(parse_xml_datetime(value).ok_or(TrustAnchorError::InvalidElement("validFrom"))?),
+                None => None,
+            };
+            let valid_until = match attribute(opening_tag, "validUntil") {
+                Some(value) => Some(parse_xml_datetime(value).ok_or(TrustAnchorError::InvalidElement("validUntil"))?),
+                None => None,
+            };
+
+            let field = |name: &'static str| element_text(key_digest, name).ok_or(TrustAnchorError::MissingElement(name));
+            let key_tag = field("KeyTag")?
+                .parse::<u16>()
+                .map_err(|_| TrustAnchorError::InvalidElement("KeyTag"))?;
+            let algorithm = field("Algorithm")?
+                .parse::<u8>()
+                .map_err(|_| TrustAnchorError::InvalidElement("Algorithm"))?;
+            let digest_type = field("DigestType")?
+                .parse::<u8>()
+                .map_err(|_| TrustAnchorError::InvalidElement("DigestType"))?;
+            let digest = HEXUPPER_PERMISSIVE
+                .decode(field("Digest")?.as_bytes())
+                .map_err(|_| TrustAnchorError::InvalidElement("Digest"))?;
+
+            entries.push(TrustAnchorEntry {
+                owner_name: owner_name.clone(),
+                ds: DS::new(key_tag, DnssecAlgorithm::from(algorithm), DigestType::from(digest_type), digest),
+                valid_from,
+                valid_until,
+            });
+        }
+
+        match entries.is_empty() {
+            true => Err(TrustAnchorError::Empty),
+            false => Ok(TrustAnchor { entries }),
+        }
+    }
+
+    /// Reads DS records in presentation format, one per line, such as
+    /// `. 86400 IN DS 20326 8 2 E06D44B8...`. Comments and blank lines are
+    /// skipped and the TTL and class are optional.
+    pub fn from_ds_lines(text: &str) -> TrustAnchorResult {
+        let mut entries = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let entry = parse_ds_line(line).ok_or(TrustAnchorError::MalformedDsLine(index + 1))?;
+            entries.push(entry);
+        }
+
+        match entries.is_empty() {
+            true => Err(TrustAnchorError::Empty),
+            false => Ok(TrustAnchor { entries }),
+        }
+    }
+
+    pub fn entries(&self) -> &[TrustAnchorEntry] {
+        &self.entries
+    }
+
+    /// The anchor owner closest to `name`, at or above it, that has
+    /// entries valid at `now`.
+    pub fn closest(&self, name: &Fqdn, now: u64) -> Option<&Fqdn> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_valid_at(now) && name.is_subdomain_of(&entry.owner_name))
+            .map(|entry| &entry.owner_name)
+            .max_by_key(|owner_name| owner_name.labels().len())
+    }
+
+    /// DS records valid at `now` for exactly `owner_name`.
+    pub fn ds_for(&self, owner_name: &Fqdn, now: u64) -> Vec<&DS> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_valid_at(now) && &entry.owner_name == owner_name)
+            .map(|entry| &entry.ds)
+            .collect()
+    }
+}
+
+fn parse_ds_line(line: &str) -> Option<TrustAnchorEntry> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let ds_index = tokens.iter().position(|token| token.eq_ignore_ascii_case("DS"))?;
+
+    // Owner, then optional TTL and class before the type
+    if !(1..=3).contains(&ds_index) {
+        return None;
+    }
+
+    let fields = &tokens[ds_index + 1..];
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let key_tag = fields[0].parse::<u16>().ok()?;
+    let algorithm = fields[1].parse::<u8>().ok()?;
+    let digest_type = fields[2].parse::<u8>().ok()?;
+    let digest = HEXUPPER_PERMISSIVE.decode(fields[3..].concat().as_bytes()).ok()?;
+
+    let owner_name = FqdnBuilder::new().generate_from_string(tokens[0].to_string()).build();
+    let ds = DS::new(key_tag, DnssecAlgorithm::from(algorithm), DigestType::from(digest_type), digest);
+
+    Some(TrustAnchorEntry::new(owner_name, ds))
+}
+
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+
+    Some(xml[start..end].trim())
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!("{}=\"", name);
+    let start = tag.find(&key)? + key.len();
+    let end = tag[start..].find('"')? + start;
+
+    Some(&tag[start..end])
+}
+
+// xsd:dateTime as used by RFC 7958, e.g. 2017-02-02T00:00:00+00:00
+fn parse_xml_datetime(value: &str) -> Option<u64> {
+    if value.len() < 19 || !value.is_char_boundary(19) {
+        return None;
+    }
+
+    let (datetime, zone) = value.split_at(19);
+    let digits: String = datetime.chars().filter(char::is_ascii_digit).collect();
+    let seconds = parse_timestamp(&digits)? as i64;
+
+    let offset = match zone {
+        "" | "Z" => 0,
+        _ => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = zone[1..].split_once(':')?;
+
+            sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60)
+        }
+    };
+
+    u64::try_from(seconds - offset).ok()
+}
+
+#[cfg(test)]
+mod trust_anchor_unittest {
+    use crate::dnssec::trust_anchor::{TrustAnchor, TrustAnchorError};
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+
+    // https://data.iana.org/root-anchors/root-anchors.xml
+    const ROOT_ANCHORS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrustAnchor id="380DC50D-484E-40D0-A3AE-68F2B18F61C7" source="http://data.iana.org/root-anchors/root-anchors.xml">
+<Zone>.</Zone>
+<KeyDigest id="Kjqmt7v" validFrom="2010-07-15T00:00:00+00:00" validUntil="2019-01-11T00:00:00+00:00">
+<KeyTag>19036</KeyTag>
+<Algorithm>8</Algorithm>
+<DigestType>2</DigestType>
+<Digest>49AAC11D7B6F6446702E54A1607371607A1A41855200FD2CE1CDDE32F24E8FB5</Digest>
+</KeyDigest>
+<KeyDigest id="Klajeyz" validFrom="2017-02-02T00:00:00+00:00">
+<KeyTag>20326</KeyTag>
+<Algorithm>8</Algorithm>
+<DigestType>2</DigestType>
+<Digest>E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D</Digest>
+</KeyDigest>
+</TrustAnchor>
+"#;
+
+    const JAN_2024: u64 = 1_704_067_200;
+
+    #[test]
+    fn iana_root_anchors() {
+        let anchor = TrustAnchor::from_rfc7958_xml(ROOT_ANCHORS_XML).unwrap();
+        assert_eq!(anchor.entries().len(), 2);
+
+        let current = anchor.ds_for(&Fqdn::root(), JAN_2024);
+        assert_eq!(current.len(), 1);
+        assert_eq!(
+            current[0].to_string(),
+            "20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D"
+        );
+
+        // KSK-2010 was the only key in 2015
+        let before_rollover = anchor.ds_for(&Fqdn::root(), 1_420_070_400);
+        assert_eq!(before_rollover.len(), 1);
+        assert_eq!(before_rollover[0].key_tag(), 19036);
+    }
+
+    #[test]
+    fn ds_lines() {
+        let text = "; root KSK-2017\n\
+                    . 86400 IN DS 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D084 58E880409BBC683457104237C7F8EC8D\n\
+                    \n\
+                    example.com. DS 31589 8 1 3490A6806D47F17A34C29E2CE80E8A999FFBE4BE\n";
+        let anchor = TrustAnchor::from_ds_lines(text).unwrap();
+        assert_eq!(anchor.entries().len(), 2);
+
+        let name = FqdnBuilder::new().generate_from_string(String::from("www.example.com")).build();
+        assert_eq!(anchor.closest(&name, JAN_2024).unwrap().to_string(), "example.com.");
+
+        let name = FqdnBuilder::new().generate_from_string(String::from("example.org")).build();
+        assert!(anchor.closest(&name, JAN_2024).unwrap().is_root());
+        assert_eq!(anchor.ds_for(&Fqdn::root(), JAN_2024)[0].key_tag(), 20326);
+    }
+
+    #[test]
+    fn error_malformed_ds_line() {
+        let text = ". 86400 IN DS 20326 8 2 E06D44B8\nexample.com. IN DNSKEY 257 3 8 AwEAAa==\n";
+
+        assert_eq!(
+            TrustAnchor::from_ds_lines(text),
+            Err(TrustAnchorError::MalformedDsLine(2))
+        );
+    }
+
+    #[test]
+    fn error_xml_without_key_digest() {
+        assert_eq!(
+            TrustAnchor::from_rfc7958_xml("<TrustAnchor><Zone>.</Zone></TrustAnchor>"),
+            Err(TrustAnchorError::Empty)
+        );
+    }
+}
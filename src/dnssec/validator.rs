@@ -0,0 +1,937 @@
+use crate::clock::Clock;
+use crate::dnssec::denial::{
+    is_delegation, prove_denial, prove_denial_from, prove_wildcard_answer, DenialError, DenialResult,
+};
+use crate::dnssec::digest::verify_ds;
+use crate::dnssec::trust_anchor::TrustAnchor;
+use crate::dnssec::verify::{find_rrset, verify_message_rrset};
+use crate::dnssec::DnssecError;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header_flags::Rcode;
+use crate::packet::message::Message;
+use crate::packet::record::Record;
+use crate::records::query_type::QueryType;
+use crate::records::rdata::ds::DS;
+use crate::records::rdata::rrsig::RRSIG;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ValidationError {
+    #[error("No response to the {1} query for {0}")]
+    FetchFailed(Fqdn, RecordType),
+    #[error("No DNSKEY at {0} matches its trust anchor or DS records")]
+    NoMatchingKey(Fqdn),
+    #[error("RRset {0} {1} in a signed zone has no RRSIG")]
+    MissingSignature(Fqdn, RecordType),
+    #[error("RRSIG signer {0} is not a zone above the signed data")]
+    InvalidSigner(Fqdn),
+    #[error(transparent)]
+    Signature(#[from] DnssecError),
+    #[error(transparent)]
+    Denial(#[from] DenialError),
+}
+
+/// Security status of a response (RFC 4033 section 5).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SecurityStatus {
+    Secure,
+    Insecure,
+    Bogus(ValidationError),
+    /// No trust anchor covers the name.
+    Indeterminate,
+}
+
+/// Source of the DS and DNSKEY responses the validator needs to walk down
+/// from a trust anchor. Resolvers fetch from the network, tests from an
+/// in-memory zone.
+pub trait Fetcher {
+    fn fetch(&self, name: &Fqdn, record_type: RecordType) -> Option<Message>;
+}
+
+#[derive(Debug)]
+pub struct ValidatedMessage {
+    message: Message,
+    status: SecurityStatus,
+}
+
+impl ValidatedMessage {
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn status(&self) -> &SecurityStatus {
+        &self.status
+    }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
+}
+
+pub struct Validator {
+    trust_anchor: TrustAnchor,
+    clock: Arc<dyn Clock>,
+}
+
+type ZoneKeys = Result<Vec<Record>, SecurityStatus>;
+
+// State for validating one message, so each zone's keys are fetched once
+struct Walk<'a> {
+    validator: &'a Validator,
+    fetcher: &'a dyn Fetcher,
+    zone_keys: HashMap<Fqdn, ZoneKeys>,
+    // The RRSIG that verified each RRset of the message
+    verified: Vec<((Fqdn, RecordType), RRSIG)>,
+}
+
+impl Validator {
+    pub fn new(trust_anchor: TrustAnchor, clock: Arc<dyn Clock>) -> Self {
+        Self { trust_anchor, clock }
+    }
+
+    /// Validates `message` and sets its AD bit only when it is secure.
+    pub fn validate(&self, mut message: Message, fetcher: &dyn Fetcher) -> ValidatedMessage {
+        let status = self.status(&message, fetcher);
+        message
+            .header_mut()
+            .flags_mut()
+            .authentic_data(status == SecurityStatus::Secure);
+
+        ValidatedMessage { message, status }
+    }
+
+    pub fn status(&self, message: &Message, fetcher: &dyn Fetcher) -> SecurityStatus {
+        let mut walk = Walk {
+            validator: self,
+            fetcher,
+            zone_keys: HashMap::new(),
+            verified: Vec::new(),
+        };
+
+        walk.message_status(message)
+    }
+
+    fn now(&self) -> u64 {
+        self.clock.now()
+    }
+}
+
+impl Walk<'_> {
+    fn message_status(&mut self, message: &Message) -> SecurityStatus {
+        // Only a single type can be answered or denied
+        let QueryType::Type(qtype) = message.question().qtype() else {
+            return SecurityStatus::Indeterminate;
+        };
+
+        let qname = message.question().qname();
+        let chain = match qtype {
+            RecordType::CNAME => vec![qname.clone()],
+            _ => cname_chain(message.answers(), qname),
+        };
+        let target = chain[chain.len() - 1].clone();
+
+        // Insecure data elsewhere in the message says nothing about the answer
+        let mut insecure = false;
+        for (index, section) in [message.answers(), message.authorities()].into_iter().enumerate() {
+            for (owner_name, record_type) in rrset_keys(section) {
+                match self.rrset_status(message, section, &owner_name, record_type) {
+                    SecurityStatus::Secure => {}
+                    SecurityStatus::Insecure => insecure |= index == 0 && chain.contains(&owner_name),
+                    status => return status,
+                }
+            }
+        }
+
+        if let Err(error) = self.prove_wildcards(message) {
+            return SecurityStatus::Bogus(error.into());
+        }
+
+        let negative = message.header().flags().response_code() == Rcode::NameError
+            || find_rrset(message.answers(), &target, qtype).is_empty();
+        let status = match negative {
+            true => self.denial_status(message, &target, qtype),
+            false => SecurityStatus::Secure,
+        };
+
+        match status {
+            SecurityStatus::Secure if insecure => SecurityStatus::Insecure,
+            status => status,
+        }
+    }
+
+    /// Verifies an RRset with the keys of each zone above it that signed
+    /// it, so a bogus RRSIG cannot hide one that verifies.
+    fn rrset_status(
+        &mut self,
+        message: &Message,
+        section: &[Record],
+        owner_name: &Fqdn,
+        record_type: RecordType,
+    ) -> SecurityStatus {
+        let rrsigs = rrsigs_for(section, owner_name, record_type);
+
+        let Some(rrsig) = rrsigs.first() else {
+            return match self.zone_status(owner_name) {
+                SecurityStatus::Secure => {
+                    SecurityStatus::Bogus(ValidationError::MissingSignature(owner_name.clone(), record_type))
+                }
+                status => status,
+            };
+        };
+
+        let mut signers: Vec<Fqdn> = Vec::new();
+        for signer_name in rrsigs.iter().map(|rrsig| rrsig.signer_name()) {
+            if owner_name.is_subdomain_of(signer_name) && !signers.contains(signer_name) {
+                signers.push(signer_name.clone());
+            }
+        }
+        if signers.is_empty() {
+            return SecurityStatus::Bogus(ValidationError::InvalidSigner(rrsig.signer_name().clone()));
+        }
+
+        let mut failure = None;
+        for signer_name in &signers {
+            let keys = match self.zone_keys(signer_name) {
+                Ok(keys) => keys,
+                Err(status) => {
+                    failure = failure.or(Some(status));
+                    continue;
+                }
+            };
+            let keys: Vec<&Record> = keys.iter().collect();
+
+            match verify_message_rrset(message, owner_name, record_type, &keys, self.clock()) {
+                Ok(rrsig) => {
+                    self.verified.push(((owner_name.clone(), record_type), rrsig.clone()));
+                    return SecurityStatus::Secure;
+                }
+                Err(error) => match failure {
+                    Some(SecurityStatus::Bogus(_)) => {}
+                    _ => failure = Some(SecurityStatus::Bogus(error.into())),
+                },
+            }
+        }
+
+        // Signers whose keys cannot be trusted leave it to the zone of the data
+        match failure {
+            Some(SecurityStatus::Bogus(error)) => SecurityStatus::Bogus(error),
+            _ => match self.zone_status(owner_name) {
+                SecurityStatus::Secure => SecurityStatus::Bogus(ValidationError::InvalidSigner(signers[0].clone())),
+                status => status,
+            },
+        }
+    }
+
+    /// Checks that every RRset expanded from a wildcard, as its verified
+    /// RRSIG has fewer labels than its owner, had no closer match.
+    fn prove_wildcards(&self, message: &Message) -> Result<(), DenialError> {
+        for ((owner_name, _), rrsig) in &self.verified {
+            if rrsig.labels() < owner_name.label_count() {
+                let authority = denial_records(message.authorities(), owner_name, &self.verified);
+                prove_wildcard_answer(&authority, owner_name, rrsig.labels())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Proves the target absent from the verified denial records. Without
+    /// any, the target must be in a zone that is not signed.
+    fn denial_status(&mut self, message: &Message, target: &Fqdn, qtype: RecordType) -> SecurityStatus {
+        let authority = denial_records(message.authorities(), target, &self.verified);
+        let has_denial = authority
+            .iter()
+            .any(|record| matches!(record.record_type(), RecordType::NSEC | RecordType::NSEC3));
+
+        if !has_denial {
+            return match self.zone_status(target) {
+                status @ (SecurityStatus::Insecure | SecurityStatus::Indeterminate) => status,
+                _ => SecurityStatus::Bogus(DenialError::NoDenialRecords.into()),
+            };
+        }
+
+        match prove_denial_from(message, &authority, target, qtype) {
+            DenialResult::SecureNxDomain | DenialResult::SecureNoData => SecurityStatus::Secure,
+            DenialResult::Insecure => SecurityStatus::Insecure,
+            DenialResult::Bogus(error) => SecurityStatus::Bogus(error.into()),
+        }
+    }
+
+    /// Trusted DNSKEY RRset of `zone`, proven from a trust anchor, or the
+    /// reason none can be trusted.
+    fn zone_keys(&mut self, zone: &Fqdn) -> ZoneKeys {
+        if let Some(keys) = self.zone_keys.get(zone) {
+            return keys.clone();
+        }
+
+        let keys = self.prove_zone_keys(zone);
+        self.zone_keys.insert(zone.clone(), keys.clone());
+
+        keys
+    }
+
+    fn prove_zone_keys(&mut self, zone: &Fqdn) -> ZoneKeys {
+        let now = self.validator.now();
+        if self.validator.trust_anchor.closest(zone, now).is_none() {
+            return Err(SecurityStatus::Indeterminate);
+        }
+
+        let anchor_ds = self.validator.trust_anchor.ds_for(zone, now);
+        if !anchor_ds.is_empty() {
+            return self.dnskeys_matching(zone, &anchor_ds.into_iter().cloned().collect::<Vec<_>>());
+        }
+
+        let ds_message = self.fetch(zone, RecordType::DS)?;
+        let ds_rrset = find_rrset(ds_message.answers(), zone, RecordType::DS);
+
+        let Some(parent) = signer_of(&ds_message) else {
+            return Err(self.unsigned_status(zone));
+        };
+        if parent == *zone || !zone.is_subdomain_of(&parent) {
+            return Err(SecurityStatus::Bogus(ValidationError::InvalidSigner(parent)));
+        }
+
+        let parent_keys = self.zone_keys(&parent)?;
+        self.verify_sections(&ds_message, &parent_keys)?;
+
+        if ds_rrset.is_empty() {
+            // A signed zone whose parent proves there is no DS is an island
+            // nobody vouches for
+            return match prove_denial(&ds_message, zone, RecordType::DS) {
+                DenialResult::SecureNoData if is_delegation(ds_message.authorities(), zone) => {
+                    Err(SecurityStatus::Insecure)
+                }
+                DenialResult::Insecure => Err(SecurityStatus::Insecure),
+                DenialResult::Bogus(error) => Err(SecurityStatus::Bogus(error.into())),
+                _ => Err(SecurityStatus::Bogus(ValidationError::InvalidSigner(zone.clone()))),
+            };
+        }
+
+        let ds_set = ds_rrset
+            .iter()
+            .filter_map(|record| match record.data() {
+                RecordData::DS(ds) => Some(ds.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        self.dnskeys_matching(zone, &ds_set)
+    }
+
+    fn dnskeys_matching(&mut self, zone: &Fqdn, ds_set: &[DS]) -> ZoneKeys {
+        let dnskey_message = self.fetch(zone, RecordType::DNSKEY)?;
+        let dnskeys: Vec<Record> = find_rrset(dnskey_message.answers(), zone, RecordType::DNSKEY)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let mut supported = false;
+        let mut matching: Vec<&Record> = Vec::new();
+        for record in &dnskeys {
+            let RecordData::DNSKEY(dnskey) = record.data() else {
+                continue;
+            };
+
+            for ds in ds_set {
+                match verify_ds(zone, dnskey, ds) {
+                    Ok(()) => {
+                        supported = true;
+                        matching.push(record);
+                    }
+                    Err(DnssecError::UnsupportedDigestType(_)) => {}
+                    Err(_) => supported = true,
+                }
+            }
+        }
+
+        // Only unknown digest types means the zone is treated as unsigned
+        // (RFC 4035 section 5.2)
+        if !supported && !ds_set.is_empty() && !dnskeys.is_empty() {
+            return Err(SecurityStatus::Insecure);
+        }
+
+        if matching.is_empty() {
+            return Err(SecurityStatus::Bogus(ValidationError::NoMatchingKey(zone.clone())));
+        }
+
+        verify_message_rrset(&dnskey_message, zone, RecordType::DNSKEY, &matching, self.clock())
+            .map_err(|error| SecurityStatus::Bogus(error.into()))?;
+
+        Ok(dnskeys)
+    }
+
+    /// Whether `name` belongs to a signed zone, so unsigned data at it is
+    /// bogus, or sits below an insecure delegation.
+    fn zone_status(&mut self, name: &Fqdn) -> SecurityStatus {
+        let now = self.validator.now();
+        let Some(anchor) = self.validator.trust_anchor.closest(name, now) else {
+            return SecurityStatus::Indeterminate;
+        };
+        if anchor == name {
+            return SecurityStatus::Secure;
+        }
+
+        let ds_message = match self.fetch(name, RecordType::DS) {
+            Ok(message) => message,
+            Err(status) => return status,
+        };
+
+        if !find_rrset(ds_message.answers(), name, RecordType::DS).is_empty() {
+            return match self.zone_keys(name) {
+                Ok(_) => SecurityStatus::Secure,
+                Err(status) => status,
+            };
+        }
+
+        let Some(signer_name) = signer_of(&ds_message) else {
+            return self.unsigned_status(name);
+        };
+        if !name.is_subdomain_of(&signer_name) {
+            return SecurityStatus::Bogus(ValidationError::InvalidSigner(signer_name));
+        }
+
+        let keys = match self.zone_keys(&signer_name) {
+            Ok(keys) => keys,
+            Err(status) => return status,
+        };
+        if let Err(status) = self.verify_sections(&ds_message, &keys) {
+            return status;
+        }
+
+        match prove_denial(&ds_message, name, RecordType::DS) {
+            DenialResult::SecureNoData if is_delegation(ds_message.authorities(), name) => {
+                SecurityStatus::Insecure
+            }
+            DenialResult::SecureNoData | DenialResult::SecureNxDomain => SecurityStatus::Secure,
+            DenialResult::Insecure => SecurityStatus::Insecure,
+            DenialResult::Bogus(error) => SecurityStatus::Bogus(error.into()),
+        }
+    }
+
+    // An unsigned response is only acceptable below an insecure delegation
+    fn unsigned_status(&mut self, name: &Fqdn) -> SecurityStatus {
+        let Some(parent) = name.parent() else {
+            return SecurityStatus::Bogus(ValidationError::MissingSignature(name.clone(), RecordType::DS));
+        };
+
+        match self.zone_status(&parent) {
+            SecurityStatus::Secure => {
+                SecurityStatus::Bogus(ValidationError::MissingSignature(name.clone(), RecordType::DS))
+            }
+            status => status,
+        }
+    }
+
+    fn verify_sections(&self, message: &Message, keys: &[Record]) -> Result<(), SecurityStatus> {
+        let keys: Vec<&Record> = keys.iter().collect();
+
+        for section in [message.answers(), message.authorities()] {
+            for (owner_name, record_type) in rrset_keys(section) {
+                verify_message_rrset(message, &owner_name, record_type, &keys, self.clock())
+                    .map_err(|error| SecurityStatus::Bogus(error.into()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fetch(&self, name: &Fqdn, record_type: RecordType) -> Result<Message, SecurityStatus> {
+        self.fetcher
+            .fetch(name, record_type)
+            .ok_or_else(|| SecurityStatus::Bogus(ValidationError::FetchFailed(name.clone(), record_type)))
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.validator.clock.as_ref()
+    }
+}
+
+// Owner and type of every RRset in a section, RRSIGs excluded
+fn rrset_keys(section: &[Record]) -> Vec<(Fqdn, RecordType)> {
+    let mut keys: Vec<(Fqdn, RecordType)> = Vec::new();
+
+    for record in section.iter().filter(|record| record.record_type() != RecordType::RRSIG) {
+        let key = (record.owner_name().clone(), record.record_type());
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    keys
+}
+
+fn rrsigs_for<'a>(
+    section: &'a [Record],
+    owner_name: &Fqdn,
+    record_type: RecordType,
+) -> Vec<&'a RRSIG> {
+    section
+        .iter()
+        .filter(|record| record.owner_name() == owner_name)
+        .filter_map(|record| match record.data() {
+            RecordData::RRSIG(rrsig) if rrsig.type_covered() == record_type => Some(rrsig),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The authority records, less the NSEC and NSEC3 RRsets not verified by
+/// `name`'s zone or one above it, which cannot deny anything about it
+/// (RFC 4035 section 5.4).
+fn denial_records(authority: &[Record], name: &Fqdn, verified: &[((Fqdn, RecordType), RRSIG)]) -> Vec<Record> {
+    let verified_by = |record: &Record| {
+        verified
+            .iter()
+            .find(|((owner_name, record_type), _)| owner_name == record.owner_name() && *record_type == record.record_type())
+            .map(|(_, rrsig)| rrsig.signer_name())
+    };
+
+    authority
+        .iter()
+        .filter(|record| match record.record_type() {
+            RecordType::NSEC | RecordType::NSEC3 => {
+                verified_by(record).is_some_and(|signer_name| name.is_subdomain_of(signer_name))
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+fn signer_of(message: &Message) -> Option<Fqdn> {
+    message
+        .answers()
+        .iter()
+        .chain(message.authorities())
+        .find_map(|record| match record.data() {
+            RecordData::RRSIG(rrsig) => Some(rrsig.signer_name().clone()),
+            _ => None,
+        })
+}
+
+/// The names from `qname` along the CNAMEs in the answers, ending with
+/// the one the answer is for. A loop ends the chain.
+fn cname_chain(answers: &[Record], qname: &Fqdn) -> Vec<Fqdn> {
+    let mut chain = vec![qname.clone()];
+
+    loop {
+        let target = &chain[chain.len() - 1];
+        let next = answers.iter().find_map(|record| match record.data() {
+            RecordData::CNAME(cname) if record.owner_name() == target => Some(cname.canonical_name().clone()),
+            _ => None,
+        });
+
+        match next {
+            Some(next) if !chain.contains(&next) => chain.push(next),
+            _ => return chain,
+        }
+    }
+}
+
+#[cfg(test)]
+mod validator_unittest {
+    use crate::clock::ManualClock;
+    use crate::dnssec::canonical::signed_data;
+    use crate::dnssec::denial::DenialError;
+    use crate::dnssec::digest::create_ds;
+    use crate::dnssec::trust_anchor::{TrustAnchor, TrustAnchorEntry};
+    use crate::dnssec::validator::{Fetcher, SecurityStatus, ValidationError, Validator};
+    use crate::dnssec::DnssecError;
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::headers::header_flags::header_flags_unittest::generate_response_header_flag;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::question::QuestionBuilder;
+    use crate::packet::record::{Record, RecordBuilder};
+    use crate::packet::seder::deserializer::Deserialize;
+    use crate::packet::seder::TryFromBytes;
+    use crate::records::query_type::QueryType;
+    use crate::records::rdata::a::A;
+    use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+    use crate::records::rdata::cname::CNAME;
+    use crate::records::rdata::dnskey::DNSKEY;
+    use crate::records::rdata::nsec::NSEC;
+    use crate::records::rdata::rrsig::RRSIG;
+    use crate::records::rdata::type_bitmap::TypeBitmap;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use ring::signature::Ed25519KeyPair;
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    const NOW: u64 = 1_700_000_000;
+
+    struct InMemoryFetcher {
+        responses: HashMap<(Fqdn, RecordType), Message>,
+    }
+
+    impl Fetcher for InMemoryFetcher {
+        fn fetch(&self, name: &Fqdn, record_type: RecordType) -> Option<Message> {
+            self.responses
+                .get(&(name.clone(), record_type))
+                .cloned()
+        }
+    }
+
+    struct ZoneKey {
+        zone: Fqdn,
+        key_pair: Ed25519KeyPair,
+    }
+
+    impl ZoneKey {
+        fn new(zone: &str, seed: u8) -> Self {
+            Self {
+                zone: name(zone),
+                key_pair: Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap(),
+            }
+        }
+
+        fn dnskey(&self) -> DNSKEY {
+            use ring::signature::KeyPair;
+
+            DNSKEY::new(257, DnssecAlgorithm::ED25519, self.key_pair.public_key().as_ref().to_vec())
+        }
+
+        fn dnskey_record(&self) -> Record {
+            record(&self.zone, RecordData::DNSKEY(self.dnskey()))
+        }
+
+        fn ds_record(&self) -> Record {
+            let ds = create_ds(&self.zone, &self.dnskey(), DigestType::SHA256).unwrap();
+            record(&self.zone, RecordData::DS(ds))
+        }
+
+        // Appends an RRSIG for every RRset in `records`
+        fn sign(&self, records: Vec<Record>) -> Vec<Record> {
+            self.sign_with_labels(records, None)
+        }
+
+        // Signs as if expanded from a wildcard with `labels` labels
+        fn sign_with_labels(&self, mut records: Vec<Record>, labels: Option<u8>) -> Vec<Record> {
+            let mut rrsets: Vec<Vec<&Record>> = Vec::new();
+            for record in &records {
+                match rrsets.iter_mut().find(|rrset| {
+                    rrset[0].owner_name() == record.owner_name() && rrset[0].record_type() == record.record_type()
+                }) {
+                    Some(rrset) => rrset.push(record),
+                    None => rrsets.push(vec![record]),
+                }
+            }
+
+            let rrsigs: Vec<Record> = rrsets
+                .iter()
+                .map(|rrset| {
+                    let labels = labels.unwrap_or(rrset[0].owner_name().label_count());
+                    let unsigned = self.rrsig(rrset[0], labels, Vec::new());
+                    let signature = self.key_pair.sign(&signed_data(&unsigned, rrset));
+                    let rrsig = self.rrsig(rrset[0], labels, signature.as_ref().to_vec());
+
+                    record(rrset[0].owner_name(), RecordData::RRSIG(rrsig))
+                })
+                .collect();
+
+            records.extend(rrsigs);
+            records
+        }
+
+        fn rrsig(&self, covered: &Record, labels: u8, signature: Vec<u8>) -> RRSIG {
+            RRSIG::new(
+                covered.record_type(),
+                DnssecAlgorithm::ED25519,
+                labels,
+                3600,
+                NOW as u32 + 86400,
+                NOW as u32 - 86400,
+                self.dnskey().key_tag(),
+                self.zone.clone(),
+                signature,
+            )
+        }
+    }
+
+    fn name(name: &str) -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    fn record(owner_name: &Fqdn, data: RecordData) -> Record {
+        RecordBuilder::new()
+            .owner_name(owner_name.clone())
+            .record_type(data.record_type())
+            .data(data)
+            .ttl(3600)
+            .build()
+    }
+
+    // An RRSIG over `covered` that no key verifies
+    fn junk_rrsig(signer: &ZoneKey, covered: &Record, labels: u8) -> Record {
+        record(covered.owner_name(), RecordData::RRSIG(signer.rrsig(covered, labels, vec![0; 64])))
+    }
+
+    fn a_record(owner_name: &str, address: [u8; 4]) -> Record {
+        record(&name(owner_name), RecordData::A(A::new(Ipv4Addr::from(address))))
+    }
+
+    fn nsec_record(owner_name: &str, next_name: &str, types: &[RecordType]) -> Record {
+        let mut types = types.to_vec();
+        types.extend([RecordType::RRSIG, RecordType::NSEC]);
+
+        record(
+            &name(owner_name),
+            RecordData::NSEC(NSEC::new(name(next_name), TypeBitmap::from(&types[..]))),
+        )
+    }
+
+    fn response(qname: &str, qtype: RecordType, rcode: Rcode, answer: Vec<Record>, authority: Vec<Record>) -> Message {
+        let flags = generate_response_header_flag(true, false, true, false, rcode);
+        let header = HeaderBuilder::new()
+            .id(1)
+            .flags(flags)
+            .question_count(1)
+            .answer_count(answer.len() as u16)
+            .authoritative_count(authority.len() as u16)
+            .build();
+
+        MessageBuilder::new()
+            .header(header)
+            .question(generate_question(qname, qtype))
+            .answer(answer)
+            .authority(authority)
+            .build()
+    }
+
+    // A signed root delegating to a signed example., which delegates to an
+    // unsigned insecure.example.
+    fn fixture() -> (Validator, InMemoryFetcher, ZoneKey) {
+        let root = ZoneKey::new(".", 1);
+        let example = ZoneKey::new("example", 2);
+
+        let anchor = TrustAnchor::new(vec![TrustAnchorEntry::new(
+            Fqdn::root(),
+            create_ds(&Fqdn::root(), &root.dnskey(), DigestType::SHA256).unwrap(),
+        )]);
+        let validator = Validator::new(anchor, Arc::new(ManualClock::new(NOW)));
+
+        let mut responses = HashMap::new();
+        let mut add = |qname: &str, qtype: RecordType, message: Message| {
+            responses.insert((name(qname), qtype), message);
+        };
+
+        add(".", RecordType::DNSKEY, response(".", RecordType::DNSKEY, Rcode::NoError, root.sign(vec![root.dnskey_record()]), vec![]));
+        add("example", RecordType::DS, response("example", RecordType::DS, Rcode::NoError, root.sign(vec![example.ds_record()]), vec![]));
+        add("example", RecordType::DNSKEY, response("example", RecordType::DNSKEY, Rcode::NoError, example.sign(vec![example.dnskey_record()]), vec![]));
+
+        let delegation = example.sign(vec![nsec_record("insecure.example", "www.example", &[RecordType::NS])]);
+        add("insecure.example", RecordType::DS, response("insecure.example", RecordType::DS, Rcode::NoError, vec![], delegation));
+        add("www.insecure.example", RecordType::DS, response("www.insecure.example", RecordType::DS, Rcode::NoError, vec![], vec![]));
+
+        let www = example.sign(vec![nsec_record("www.example", "example", &[RecordType::A])]);
+        add("www.example", RecordType::DS, response("www.example", RecordType::DS, Rcode::NoError, vec![], www));
+
+        (validator, InMemoryFetcher { responses }, example)
+    }
+
+    #[test]
+    fn secure_answer_sets_ad() {
+        let (validator, fetcher, example) = fixture();
+        let answer = example.sign(vec![a_record("www.example", [192, 0, 2, 1])]);
+
+        let validated = validator.validate(
+            response("www.example", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(validated.status(), &SecurityStatus::Secure);
+        assert!(validated.message().header().flags().is_authentic_data());
+    }
+
+    #[test]
+    fn parsed_response_carries_ad_on_the_wire() {
+        let (validator, fetcher, example) = fixture();
+        let answer = example.sign(vec![a_record("www.example", [192, 0, 2, 1])]);
+        let wire = response("www.example", RecordType::A, Rcode::NoError, answer, vec![]).wire();
+        let parsed = Message::try_from_bytes(&mut Deserialize::new(&wire)).unwrap();
+
+        let validated = validator.validate(parsed, &fetcher).into_message();
+        let wire = validated.wire();
+        let reparsed = Message::try_from_bytes(&mut Deserialize::new(&wire)).unwrap();
+
+        assert!(reparsed.header().flags().is_authentic_data());
+        assert_eq!(reparsed.answers(), validated.answers());
+    }
+
+    #[test]
+    fn bogus_tampered_answer() {
+        let (validator, fetcher, example) = fixture();
+        let mut answer = example.sign(vec![a_record("www.example", [192, 0, 2, 1])]);
+        answer[0] = a_record("www.example", [192, 0, 2, 66]);
+
+        let validated = validator.validate(
+            response("www.example", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(
+            validated.status(),
+            &SecurityStatus::Bogus(ValidationError::Signature(DnssecError::BadSignature))
+        );
+        assert!(!validated.message().header().flags().is_authentic_data());
+    }
+
+    #[test]
+    fn bogus_unsigned_answer_in_signed_zone() {
+        let (validator, fetcher, _) = fixture();
+        let answer = vec![a_record("www.example", [192, 0, 2, 1])];
+
+        let status = validator.status(
+            &response("www.example", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(
+            status,
+            SecurityStatus::Bogus(ValidationError::MissingSignature(name("www.example"), RecordType::A))
+        );
+    }
+
+    #[test]
+    fn insecure_below_unsigned_delegation() {
+        let (validator, fetcher, _) = fixture();
+        let answer = vec![a_record("www.insecure.example", [192, 0, 2, 1])];
+
+        let validated = validator.validate(
+            response("www.insecure.example", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(validated.status(), &SecurityStatus::Insecure);
+        assert!(!validated.message().header().flags().is_authentic_data());
+    }
+
+    #[test]
+    fn secure_nxdomain() {
+        let (validator, fetcher, example) = fixture();
+        let authority = example.sign(vec![
+            nsec_record("example", "insecure.example", &[RecordType::SOA, RecordType::NS, RecordType::DNSKEY]),
+            nsec_record("insecure.example", "www.example", &[RecordType::NS]),
+        ]);
+
+        let status = validator.status(
+            &response("nope.example", RecordType::A, Rcode::NameError, vec![], authority),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Secure);
+    }
+
+    #[test]
+    fn bogus_denial_from_another_zone() {
+        let (validator, fetcher, example) = fixture();
+        // Signed by example., yet spanning a.zzz. and *.zzz.
+        let authority = example.sign(vec![nsec_record("zz.example", "b.zzz", &[RecordType::A])]);
+
+        let status = validator.status(
+            &response("a.zzz", RecordType::A, Rcode::NameError, vec![], authority),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Bogus(DenialError::NoDenialRecords.into()));
+
+        // A junk RRSIG from the root, placed first, does not vouch for it
+        let mut authority = example.sign(vec![nsec_record("zz.example", "b.zzz", &[RecordType::A])]);
+        let junk = junk_rrsig(&ZoneKey::new(".", 1), &authority[0], 2);
+        authority.insert(1, junk);
+
+        let status = validator.status(
+            &response("a.zzz", RecordType::A, Rcode::NameError, vec![], authority),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Bogus(DenialError::NoDenialRecords.into()));
+    }
+
+    #[test]
+    fn bogus_wildcard_answer_behind_junk_rrsig() {
+        let (validator, fetcher, example) = fixture();
+        let expanded = example.sign_with_labels(vec![a_record("evil.example", [6, 6, 6, 6])], Some(1));
+        let status = validator.status(
+            &response("evil.example", RecordType::A, Rcode::NoError, expanded.clone(), vec![]),
+            &fetcher,
+        );
+        assert_eq!(status, SecurityStatus::Bogus(DenialError::NameNotCovered.into()));
+
+        // A junk RRSIG claiming no expansion, placed first
+        let mut answer = expanded;
+        answer.insert(1, junk_rrsig(&example, &answer[0], 2));
+        let status = validator.status(
+            &response("evil.example", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+        assert_eq!(status, SecurityStatus::Bogus(DenialError::NameNotCovered.into()));
+    }
+
+    #[test]
+    fn bogus_nxdomain_beside_insecure_authority() {
+        let (validator, fetcher, _) = fixture();
+        // Unsigned data of the insecure zone cannot stand in for a denial
+        let authority = vec![a_record("www.insecure.example", [192, 0, 2, 1])];
+
+        let status = validator.status(
+            &response("nope.example", RecordType::A, Rcode::NameError, vec![], authority),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Bogus(DenialError::NoDenialRecords.into()));
+    }
+
+    #[test]
+    fn secure_cname_answer() {
+        let (validator, fetcher, example) = fixture();
+        let answer = example.sign(vec![record(
+            &name("alias.example"),
+            RecordData::CNAME(CNAME::new(name("www.example"))),
+        )]);
+
+        let status = validator.status(
+            &response("alias.example", RecordType::CNAME, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Secure);
+    }
+
+    #[test]
+    fn indeterminate_any_query() {
+        let (validator, fetcher, example) = fixture();
+        let signed = response("www.example", RecordType::A, Rcode::NoError, example.sign(vec![a_record("www.example", [192, 0, 2, 1])]), vec![]);
+        let question = QuestionBuilder::new().question_name(name("www.example")).question_type(QueryType::ANY).build();
+        let message = MessageBuilder::new()
+            .header(signed.header().clone())
+            .question(question)
+            .answer(signed.answers().to_vec())
+            .build();
+
+        assert_eq!(validator.status(&message, &fetcher), SecurityStatus::Indeterminate);
+    }
+
+    #[test]
+    fn indeterminate_without_trust_anchor() {
+        let (_, fetcher, _) = fixture();
+        let anchor = TrustAnchor::from_ds_lines("example. DS 1 15 2 00").unwrap();
+        let validator = Validator::new(anchor, Arc::new(ManualClock::new(NOW)));
+        let answer = vec![a_record("www.other", [192, 0, 2, 1])];
+
+        let status = validator.status(
+            &response("www.other", RecordType::A, Rcode::NoError, answer, vec![]),
+            &fetcher,
+        );
+
+        assert_eq!(status, SecurityStatus::Indeterminate);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::clock::Clock;
+use crate::dnssec::canonical::signed_data;
+use crate::dnssec::crypto::verify_signature;
+use crate::dnssec::{DnssecError, DnssecResult};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::message::Message;
+use crate::packet::record::Record;
+use crate::records::rdata::dnskey::DNSKEY;
+use crate::records::rdata::rrsig::RRSIG;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+
+/// Verifies one RRSIG over an RRset with one DNSKEY, following the checks
+/// of RFC 4035 section 5.3.1.
+pub fn verify_rrsig(
+    rrset: &[&Record],
+    rrsig: &Record,
+    dnskey: &Record,
+    clock: &dyn Clock,
+) -> DnssecResult<()> {
+    let first = rrset.first().ok_or(DnssecError::EmptyRrset)?;
+    let same_rrset = |record: &&Record| {
+        record.owner_name() == first.owner_name()
+            && record.class() == first.class()
+            && record.record_type() == first.record_type()
+    };
+    if !rrset.iter().all(same_rrset) {
+        return Err(DnssecError::RrsetMismatch);
+    }
+
+    let RecordData::RRSIG(rrsig_data) = rrsig.data() else {
+        return Err(DnssecError::NotAnRrsig);
+    };
+    let RecordData::DNSKEY(dnskey_data) = dnskey.data() else {
+        return Err(DnssecError::NotADnskey);
+    };
+
+    if rrsig.owner_name() != first.owner_name() || rrsig.class() != first.class() {
+        return Err(DnssecError::RrsetMismatch);
+    }
+
+    if rrsig_data.type_covered() != first.record_type() {
+        return Err(DnssecError::TypeCoveredMismatch);
+    }
+
+    if rrsig_data.labels() > first.owner_name().label_count() {
+        return Err(DnssecError::InvalidLabelCount);
+    }
+
+    if !first.owner_name().is_subdomain_of(rrsig_data.signer_name()) {
+        return Err(DnssecError::SignerMismatch);
+    }
+
+    if dnskey.owner_name() != rrsig_data.signer_name() {
+        return Err(DnssecError::KeyOwnerMismatch);
+    }
+
+    check_key(rrsig_data, dnskey_data)?;
+    check_validity_period(rrsig_data.signature_inception(), rrsig_data.signature_expiration(), clock)?;
+
+    verify_signature(
+        rrsig_data.algorithm(),
+        dnskey_data.public_key(),
+        &signed_data(rrsig_data, rrset),
+        rrsig_data.signature(),
+    )
+}
+
+/// Verifies the `owner_name`/`record_type` RRset of a message against the
+/// RRSIGs in the same section. Returns the first RRSIG that verifies with
+/// one of `dnskeys`; otherwise the last failure.
+pub fn verify_message_rrset<'a>(
+    message: &'a Message,
+    owner_name: &Fqdn,
+    record_type: RecordType,
+    dnskeys: &[&Record],
+    clock: &dyn Clock,
+) -> DnssecResult<&'a RRSIG> {
+    let sections = [message.answers(), message.authorities(), message.additionals()];
+
+    let Some(section) = sections
+        .into_iter()
+        .find(|section| !find_rrset(section, owner_name, record_type).is_empty())
+    else {
+        return Err(DnssecError::EmptyRrset);
+    };
+
+    let rrset = find_rrset(section, owner_name, record_type);
+    let mut error = DnssecError::MissingSignature;
+
+    for rrsig in find_rrsigs(section, owner_name, record_type) {
+        let RecordData::RRSIG(rrsig_data) = rrsig.data() else {
+            unreachable!("find_rrsigs only returns RRSIGs");
+        };
+        for dnskey in dnskeys {
+            match verify_rrsig(&rrset, rrsig, dnskey, clock) {
+                Ok(()) => return Ok(rrsig_data),
+                Err(failure) => error = failure,
+            }
+        }
+    }
+
+    Err(error)
+}
+
+pub fn find_rrset<'a>(records: &'a [Record], owner_name: &Fqdn, record_type: RecordType) -> Vec<&'a Record> {
+    records
+        .iter()
+        .filter(|record| record.owner_name() == owner_name && record.record_type() == record_type)
+        .collect()
+}
+
+pub fn find_rrsigs<'a>(records: &'a [Record], owner_name: &Fqdn, type_covered: RecordType) -> Vec<&'a Record> {
+    records
+        .iter()
+        .filter(|record| record.owner_name() == owner_name)
+        .filter(|record| match record.data() {
+            RecordData::RRSIG(rrsig) => rrsig.type_covered() == type_covered,
+            _ => false,
+        })
+        .collect()
+}
+
+fn check_key(rrsig: &RRSIG, dnskey: &DNSKEY) -> DnssecResult<()> {
+    if dnskey.protocol() != DNSKEY::PROTOCOL {
+        return Err(DnssecError::InvalidProtocol);
+    }
+
+    if !dnskey.is_zone_key() {
+        return Err(DnssecError::NotAZoneKey);
+    }
+
+    if dnskey.is_revoked() {
+        return Err(DnssecError::RevokedKey);
+    }
+
+    if rrsig.algorithm() != dnskey.algorithm() {
+        return Err(DnssecError::AlgorithmMismatch);
+    }
+
+    if rrsig.key_tag() != dnskey.key_tag() {
+        return Err(DnssecError::KeyTagMismatch);
+    }
+
+    Ok(())
+}
+
+// Timestamps wrap every 136 years, so they are compared with serial number
+// arithmetic (RFC 4034 section 3.1.5)
+pub(crate) fn check_validity_period(inception: u32, expiration: u32, clock: &dyn Clock) -> DnssecResult<()> {
+    let now = clock.now() as u32;
+
+    if (now.wrapping_sub(inception) as i32) < 0 {
+        return Err(DnssecError::SignatureNotYetValid);
+    }
+
+    if (expiration.wrapping_sub(now) as i32) < 0 {
+        return Err(DnssecError::SignatureExpired);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod verify_unittest {
+    use crate::clock::ManualClock;
+    use crate::dnssec::verify::{verify_message_rrset, verify_rrsig};
+    use crate::dnssec::DnssecError;
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::headers::header::header_unittest::get_response_header;
+    use crate::packet::message::MessageBuilder;
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::record::{Record, RecordBuilder};
+    use crate::records::rdata::a::A;
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+    use crate::records::rdata::dnskey::DNSKEY;
+    use crate::records::rdata::rrsig::RRSIG;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use data_encoding::BASE64;
+    use std::net::Ipv4Addr;
+
+    // RFC 6605 section 6.1
+    pub const RFC6605_P256_KEY: &str =
+        "GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edbkrSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA==";
+    const RFC6605_P256_SIGNATURE: &str =
+        "qx6wLYqmh+l9oCKTN6qIc+bw6ya+KJ8oMz0YP107epXAyGmt+3SNruPFKG7tZoLBLlUzGGus7ZwmwWep666VCw==";
+    const RFC6605_EXPIRATION: u32 = 1_284_026_679;
+    const RFC6605_INCEPTION: u32 = 1_281_607_479;
+
+    // Generated keys signing the same RRset, valid from 2000-01-01 to 2030-01-01
+    pub const RSASHA256_KEY: &str = "AwEAAeVI3mMKHIC5N1sIOd5cqYsghfhKiMIw0xJJXDIrpFhhJ0bw5k2EYepT9c/BJqvrdgV9E7xeIX+Flpgp/dQGD9CpQBLLXCh3a2yMC5OeJOLwvDiyf3wNe6y6PWaUP+qj6UE1Q0AH9Woo3GeUU9IMTIZNJCsov4F76KhZsLT8iaGFDiSSNWTigxo9OmhZB7SRdNMNWTwc5AR4MCReZdSdRqHLpzhPBG4yyUIhYI2SB60ITghN2cGvHgHGGi7npqnK+fEkmwaSxdT3UMTgeV4c6aFqTLJWtse+n6+OQTejiFBp8CNjPJEl+fev91G8lEwMpe7w/zmoF8LK8sWln2Mjfoc=";
+    pub const RSASHA256_SIGNATURE: &str = "pUnBMcZYzksf0Z3/RbOkaNi1ZiKddhuhaDRP7xcuEhAS2hLywe8/7SKqX3U2CtWM+7EtbBszzTu3o9x2vsR381Tgy6IydJOqf4ZYGWlhfy4u0ZCu0jGNij38LkIb28eOblK0CBULYS+tdD/6Qszc73xEF8NJAhaG7G5DO0UTRDFzpoDaHWhzwixVfFVqvsv5kEKt/UVbWjDEUfl6VchG6pY9/NOdhS3DzD3RmVu+NznzHn6DOxcjW+Anji2TzOjEA85g9D6aKq4JrpkC9YVZUUNGtLOCS5MQxusl9WfjjPmDY6lJY7+koHR8hjjwereZCzkKlOIrDXaFlYX3ByJ3kg==";
+    pub const P384_KEY: &str = "SveVTwm5RBJHbQiZl2zMiSL97S692wbZf8v/6WEiTDEO4xa47BSjTayi+aPFKy+5sclI1eHeR6QJDmtXwUm3BbTgOLqBi37ecAYCERYPtQL9MNx+khBWxZpjqcYqQE2T";
+    const P384_SIGNATURE: &str = "GJZw5gLHcUtBnWJKArbYKMQNLCTSIhCBBu6rjP9Yp+s3Nu44ds3PQWPucrrLwiwItZ31g8yGnjmdGHab+QWH9sJYytTp85mLL9LLdlMb5OXPlN3WR23x3hTvbIO3sdkw";
+    pub const ED25519_KEY: &str = "A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdyGZBJVMbg=";
+    pub const ED25519_SIGNATURE: &str =
+        "rJYGYL2Oif8LnIl+TKK1X1kYZahRar/1M42ttsLbIE72Tq7MLDjytkwImYza1f34RRU9LHqyn+VzqilzLZoLAA==";
+    pub const EXPIRATION: u32 = 1_893_456_000;
+    pub const INCEPTION: u32 = 946_684_800;
+
+    fn name(name: &str) -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    pub fn www_a_record(address: [u8; 4]) -> Record {
+        RecordBuilder::new()
+            .owner_name(name("www.example.net."))
+            .record_type(RecordType::A)
+            .data(RecordData::A(A::new(Ipv4Addr::from(address))))
+            .ttl(3600)
+            .build()
+    }
+
+    pub fn dnskey_record(flags: u16, algorithm: DnssecAlgorithm, key: &str) -> Record {
+        let dnskey = DNSKEY::new(flags, algorithm, BASE64.decode(key.as_bytes()).unwrap());
+
+        RecordBuilder::new()
+            .owner_name(name("example.net."))
+            .record_type(RecordType::DNSKEY)
+            .data(RecordData::DNSKEY(dnskey))
+            .ttl(3600)
+            .build()
+    }
+
+    fn rrsig_record(
+        algorithm: DnssecAlgorithm,
+        validity: (u32, u32),
+        key_tag: u16,
+        signature: &str,
+    ) -> Record {
+        let rrsig = RRSIG::new(
+            RecordType::A,
+            algorithm,
+            3,
+            3600,
+            validity.1,
+            validity.0,
+            key_tag,
+            name("example.net."),
+            BASE64.decode(signature.as_bytes()).unwrap(),
+        );
+
+        RecordBuilder::new()
+            .owner_name(name("www.example.net."))
+            .record_type(RecordType::RRSIG)
+            .data(RecordData::RRSIG(rrsig))
+            .ttl(3600)
+            .build()
+    }
+
+    fn rfc6605_records() -> (Record, Record, Record) {
+        let dnskey = dnskey_record(257, DnssecAlgorithm::ECDSAP256SHA256, RFC6605_P256_KEY);
+        let rrsig = rrsig_record(
+            DnssecAlgorithm::ECDSAP256SHA256,
+            (RFC6605_INCEPTION, RFC6605_EXPIRATION),
+            55648,
+            RFC6605_P256_SIGNATURE,
+        );
+
+        (www_a_record([192, 0, 2, 1]), rrsig, dnskey)
+    }
+
+    fn verify_generated(flags: u16, algorithm: DnssecAlgorithm, key: &str, key_tag: u16, signature: &str) {
+        let dnskey = dnskey_record(flags, algorithm, key);
+        let rrsig = rrsig_record(algorithm, (INCEPTION, EXPIRATION), key_tag, signature);
+        let clock = ManualClock::new(1_700_000_000);
+
+        assert_eq!(
+            verify_rrsig(&[&www_a_record([192, 0, 2, 1])], &rrsig, &dnskey, &clock),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rfc6605_ecdsa_p256() {
+        let (a, rrsig, dnskey) = rfc6605_records();
+        let clock = ManualClock::new(1_283_000_000);
+
+        assert_eq!(verify_rrsig(&[&a], &rrsig, &dnskey, &clock), Ok(()));
+    }
+
+    #[test]
+    fn rsasha256() {
+        verify_generated(256, DnssecAlgorithm::RSASHA256, RSASHA256_KEY, 38116, RSASHA256_SIGNATURE);
+    }
+
+    #[test]
+    fn ecdsa_p384() {
+        verify_generated(257, DnssecAlgorithm::ECDSAP384SHA384, P384_KEY, 62137, P384_SIGNATURE);
+    }
+
+    #[test]
+    fn ed25519() {
+        verify_generated(257, DnssecAlgorithm::ED25519, ED25519_KEY, 34259, ED25519_SIGNATURE);
+    }
+
+    #[test]
+    fn error_signature_expired() {
+        let (a, rrsig, dnskey) = rfc6605_records();
+        let clock = ManualClock::new(RFC6605_EXPIRATION as u64 + 1);
+
+        assert_eq!(
+            verify_rrsig(&[&a], &rrsig, &dnskey, &clock),
+            Err(DnssecError::SignatureExpired)
+        );
+    }
+
+    #[test]
+    fn error_signature_not_yet_valid() {
+        let (a, rrsig, dnskey) = rfc6605_records();
+        let clock = ManualClock::new(RFC6605_INCEPTION as u64 - 1);
+
+        assert_eq!(
+            verify_rrsig(&[&a], &rrsig, &dnskey, &clock),
+            Err(DnssecError::SignatureNotYetValid)
+        );
+    }
+
+    #[test]
+    fn error_tampered_rdata() {
+        let (_, rrsig, dnskey) = rfc6605_records();
+        let clock = ManualClock::new(1_283_000_000);
+
+        assert_eq!(
+            verify_rrsig(&[&www_a_record([192, 0, 2, 2])], &rrsig, &dnskey, &clock),
+            Err(DnssecError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn error_algorithm_mismatch() {
+        let (a, rrsig, _) = rfc6605_records();
+        let dnskey = dnskey_record(257, DnssecAlgorithm::ED25519, ED25519_KEY);
+        let clock = ManualClock::new(1_283_000_000);
+
+        assert_eq!(
+            verify_rrsig(&[&a], &rrsig, &dnskey, &clock),
+            Err(DnssecError::AlgorithmMismatch)
+        );
+    }
+
+    #[test]
+    fn verify_rrset_from_message() {
+        let (a, rrsig, dnskey) = rfc6605_records();
+        let message = MessageBuilder::new()
+            .header(get_response_header(1))
+            .question(generate_question("www.example.net", RecordType::A))
+            .answer(vec![a, rrsig.clone()])
+            .build();
+        let clock = ManualClock::new(1_283_000_000);
+        let RecordData::RRSIG(expected) = rrsig.data() else { panic!() };
+
+        let result = verify_message_rrset(
+            &message,
+            &name("www.example.net."),
+            RecordType::A,
+            &[&dnskey],
+            &clock,
+        );
+        assert_eq!(result, Ok(expected));
+
+        let result = verify_message_rrset(&message, &name("www.example.net."), RecordType::A, &[], &clock);
+        assert_eq!(result, Err(DnssecError::MissingSignature));
+    }
+}
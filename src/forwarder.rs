@@ -0,0 +1,451 @@
+use crate::cache::Cache;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Rcode};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::record::Record;
+use crate::resolver::exchange::Exchanger;
+use crate::resolver::random_id;
+use crate::server::handler::Handler;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// A timeout, an error or SERVFAIL counts as a round trip this slow, which
+/// moves the upstream behind every one that answers.
+const FAILURE_RTT: Duration = Duration::from_secs(2);
+
+/// Weight of the previous estimate when smoothing round trip times, in
+/// eighths, as TCP does (RFC 6298).
+const RTT_SMOOTHING: u32 = 7;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ForwarderError {
+    #[error("No upstream is configured for {0}")]
+    NoUpstream(Fqdn),
+    #[error("No upstream for {0} gave a usable response")]
+    UpstreamsFailed(Fqdn),
+}
+
+pub type ForwarderResult<T> = Result<T, ForwarderError>;
+
+/// Relays client queries to upstream recursive servers. Upstreams are tried
+/// fastest first by smoothed round trip time, failing over on errors,
+/// timeouts and SERVFAIL. Queries go upstream under a fresh random id, so
+/// client ids never leave the box.
+///
+/// Zones can be forwarded to upstreams of their own; the most specific
+/// zone containing the name wins over the default upstreams.
+pub struct Forwarder {
+    exchanger: Arc<dyn Exchanger>,
+    upstreams: Vec<SocketAddr>,
+    zones: BTreeMap<Fqdn, Vec<SocketAddr>>,
+    cache: Option<Arc<Cache>>,
+    rtts: Mutex<HashMap<SocketAddr, Duration>>,
+}
+
+impl Forwarder {
+    pub fn new(exchanger: Arc<dyn Exchanger>, upstreams: Vec<SocketAddr>) -> Self {
+        Self {
+            exchanger,
+            upstreams,
+            zones: BTreeMap::new(),
+            cache: None,
+            rtts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forwards names at and below the apex to these upstreams instead.
+    pub fn zone(mut self, apex: Fqdn, upstreams: Vec<SocketAddr>) -> Self {
+        self.zones.insert(apex, upstreams);
+        self
+    }
+
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The smoothed round trip time of the upstream, once it was asked.
+    pub fn rtt(&self, upstream: SocketAddr) -> Option<Duration> {
+        self.rtts().get(&upstream).copied()
+    }
+
+    /// Sends the query upstream and returns the response addressed to the
+    /// client.
+    pub fn forward(&self, query: &Message) -> ForwarderResult<Message> {
+        let qname = query.question().qname();
+        let upstreams = self.by_rtt(self.upstreams(qname));
+        if upstreams.is_empty() {
+            return Err(ForwarderError::NoUpstream(qname.clone()));
+        }
+
+        let upstream_query = upstream_query(query);
+        for upstream in upstreams {
+            let started = Instant::now();
+            match self.exchanger.exchange(upstream, &upstream_query) {
+                Ok(response) if response.header().flags().response_code() != Rcode::ServerFailure => {
+                    self.record_rtt(upstream, started.elapsed());
+                    return Ok(relay(query, &response));
+                }
+                _ => self.record_rtt(upstream, FAILURE_RTT),
+            }
+        }
+
+        Err(ForwarderError::UpstreamsFailed(qname.clone()))
+    }
+
+    /// Answers from the cache when it can, forwarding otherwise. Names
+    /// without upstreams are refused and failed forwards get SERVFAIL.
+    pub fn answer(&self, query: &Message) -> Message {
+        if let Some(response) = self.cache.as_ref().and_then(|cache| cache.answer(query)) {
+            return response;
+        }
+
+        match self.forward(query) {
+            Ok(response) => {
+                if let Some(cache) = &self.cache {
+                    cache.insert_response(&self.in_scope(&response));
+                }
+                response
+            }
+            Err(ForwarderError::NoUpstream(_)) => failure(query, Rcode::Refused),
+            Err(ForwarderError::UpstreamsFailed(_)) => failure(query, Rcode::ServerFailure),
+        }
+    }
+
+    /// The upstreams of the closest enclosing zone, or the default ones.
+    fn upstreams(&self, qname: &Fqdn) -> &[SocketAddr] {
+        (0..=qname.labels().len())
+            .rev()
+            .find_map(|labels| self.zones.get(&qname.ancestor(labels)))
+            .unwrap_or(&self.upstreams)
+    }
+
+    /// The answer and authority records of a response that its upstreams
+    /// would also be asked about. An upstream forwarded one zone has no
+    /// say over names forwarded elsewhere, and caching what it sends for
+    /// them would let it answer for those too.
+    fn in_scope(&self, response: &Message) -> Message {
+        let upstreams = self.upstreams(response.question().qname());
+        let within = |section: &[Record]| -> Vec<Record> {
+            section
+                .iter()
+                .filter(|record| self.upstreams(record.owner_name()) == upstreams)
+                .cloned()
+                .collect()
+        };
+
+        MessageBuilder::new()
+            .header(response.header().clone())
+            .question(response.question().clone())
+            .answer(within(response.answers()))
+            .authority(within(response.authorities()))
+            .build()
+    }
+
+    /// Upstreams never asked sort first, so each gets measured once.
+    fn by_rtt(&self, upstreams: &[SocketAddr]) -> Vec<SocketAddr> {
+        let rtts = self.rtts();
+        let mut upstreams = upstreams.to_vec();
+        upstreams.sort_by_key(|upstream| rtts.get(upstream).copied().unwrap_or_default());
+
+        upstreams
+    }
+
+    fn record_rtt(&self, upstream: SocketAddr, sample: Duration) {
+        let mut rtts = self.rtts();
+        let rtt = match rtts.get(&upstream) {
+            Some(previous) => (*previous * RTT_SMOOTHING + sample) / (RTT_SMOOTHING + 1),
+            None => sample,
+        };
+        rtts.insert(upstream, rtt);
+    }
+
+    /// Round trip times stay usable even if a thread panicked holding them.
+    fn rtts(&self) -> MutexGuard<'_, HashMap<SocketAddr, Duration>> {
+        self.rtts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Handler for Forwarder {
+    fn handle(&self, query: &Message) -> Message {
+        self.answer(query)
+    }
+}
+
+/// The client question under a fresh id, asking for recursion. Records
+/// the client added, such as a TSIG, stay behind.
+fn upstream_query(query: &Message) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .query()
+        .checking_disabled(query.header().flags().is_checking_disabled())
+        .recursion_desired(true)
+        .build();
+
+    MessageBuilder::new()
+        .header(HeaderBuilder::new().id(random_id()).flags(flags).question_count(1).build())
+        .question(query.question().clone())
+        .build()
+}
+
+/// The upstream response under the client's id and flags.
+fn relay(query: &Message, response: &Message) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .response(query.header().flags())
+        .authentic_data(response.header().flags().is_authentic_data())
+        .authoritative_answer(false)
+        .truncation(false)
+        .recursion_available(true)
+        .response_code(response.header().flags().response_code())
+        .build();
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(flags)
+        .question_count(1)
+        .answer_count(response.answers().len() as u16)
+        .authoritative_count(response.authorities().len() as u16)
+        .additional_count(response.additionals().len() as u16)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .answer(response.answers().to_vec())
+        .authority(response.authorities().to_vec())
+        .additional(response.additionals().to_vec())
+        .build()
+}
+
+fn failure(query: &Message, response_code: Rcode) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .response(query.header().flags())
+        .authoritative_answer(false)
+        .truncation(false)
+        .recursion_available(true)
+        .response_code(response_code)
+        .build();
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(flags)
+        .question_count(1)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .build()
+}
+
+#[cfg(test)]
+mod forwarder_unittest {
+    use crate::cache::Cache;
+    use crate::clock::ManualClock;
+    use crate::forwarder::Forwarder;
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::headers::header_flags::{Opcode, Rcode};
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::record::Record;
+    use crate::records::record_class::RecordClass;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::NetworkExchanger;
+    use crate::server::handler::handler_unittest::{catalog, parse, query};
+    use crate::server::handler::{error_response, Handler};
+    use crate::server::Server;
+    use crate::zone::catalog::Catalog;
+    use crate::zone::zone_unittest::name;
+    use crate::zone::Zone;
+    use crate::zonefile::parser::ZoneParser;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    const CORP: &str = "\
+@       SOA ns.corp.example. hostmaster.corp.example. 1 7200 3600 1209600 300
+@       NS  ns
+ns      A   10.0.0.53
+intranet A  10.0.0.80
+";
+
+    /// Stand-in upstream that notes the id of every query it answers.
+    struct Recording {
+        catalog: Catalog,
+        ids: Arc<Mutex<Vec<u16>>>,
+    }
+
+    impl Handler for Recording {
+        fn handle(&self, query: &Message) -> Message {
+            self.ids.lock().unwrap().push(query.header().id());
+            self.catalog.handle(query)
+        }
+    }
+
+    struct Failing;
+
+    impl Handler for Failing {
+        fn handle(&self, query: &Message) -> Message {
+            error_response(query, Rcode::ServerFailure)
+        }
+    }
+
+    /// Stand-in upstream that answers every query with the same records.
+    struct Forging(Vec<Record>);
+
+    impl Handler for Forging {
+        fn handle(&self, query: &Message) -> Message {
+            let header = HeaderBuilder::new()
+                .id(query.header().id())
+                .flags(error_response(query, Rcode::NoError).header().flags().clone())
+                .question_count(1)
+                .answer_count(self.0.len() as u16)
+                .build();
+
+            MessageBuilder::new()
+                .header(header)
+                .question(query.question().clone())
+                .answer(self.0.clone())
+                .build()
+        }
+    }
+
+    fn start(handler: impl Handler + 'static) -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        address
+    }
+
+    fn recording(catalog: Catalog) -> (SocketAddr, Arc<Mutex<Vec<u16>>>) {
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let address = start(Recording {
+            catalog,
+            ids: ids.clone(),
+        });
+
+        (address, ids)
+    }
+
+    fn exchanger() -> Arc<NetworkExchanger> {
+        Arc::new(NetworkExchanger::new().timeout(Duration::from_millis(200)))
+    }
+
+    fn ask(forwarder: &Forwarder, id: u16, qname: &str) -> Message {
+        forwarder.answer(&parse(&query(id, Opcode::Query, qname, RecordType::A)))
+    }
+
+    #[test]
+    fn rewrite_ids() {
+        let (upstream, ids) = recording(catalog());
+        let forwarder = Forwarder::new(exchanger(), vec![upstream]);
+
+        let client_ids = [0x1234, 0x5678, 0x9abc];
+        for id in client_ids {
+            let response = ask(&forwarder, id, "www.example.com.");
+            assert_eq!(response.header().id(), id);
+            assert!(response.header().flags().is_recursion_available());
+            assert_eq!(response.answers().len(), 1);
+        }
+
+        let upstream_ids = ids.lock().unwrap().clone();
+        assert_eq!(upstream_ids.len(), 3);
+        assert_ne!(upstream_ids, client_ids);
+    }
+
+    #[test]
+    fn fail_over_to_working_upstream() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let failing = start(Failing);
+        let (working, ids) = recording(catalog());
+        let forwarder = Forwarder::new(exchanger(), vec![silent.local_addr().unwrap(), failing, working]);
+
+        let response = ask(&forwarder, 1, "www.example.com.");
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert!(forwarder.rtt(failing).unwrap() > forwarder.rtt(working).unwrap());
+        assert!(forwarder.rtt(silent.local_addr().unwrap()).unwrap() > forwarder.rtt(working).unwrap());
+
+        // The working upstream is now known to be fastest and asked first
+        ask(&forwarder, 2, "www.example.com.");
+        assert_eq!(ids.lock().unwrap().len(), 2);
+        assert!(forwarder.rtt(failing).unwrap() > Duration::from_secs(1));
+
+        let dead = Forwarder::new(exchanger(), vec![failing]);
+        assert_eq!(ask(&dead, 3, "www.example.com.").header().flags().response_code(), Rcode::ServerFailure);
+    }
+
+    #[test]
+    fn forward_zones_to_their_upstreams() {
+        let records = ZoneParser::new(name("corp.example.")).default_ttl(3600).parse_str("corp", CORP).unwrap();
+        let mut corp = Catalog::new();
+        corp.insert(Zone::new(name("corp.example."), records).unwrap());
+        let (public, public_ids) = recording(catalog());
+        let (internal, internal_ids) = recording(corp);
+        let forwarder = Forwarder::new(exchanger(), vec![public]).zone(name("corp.example."), vec![internal]);
+
+        assert_eq!(ask(&forwarder, 1, "intranet.corp.example.").answers().len(), 1);
+        assert_eq!(ask(&forwarder, 2, "www.example.com.").answers().len(), 1);
+        assert_eq!(public_ids.lock().unwrap().len(), 1);
+        assert_eq!(internal_ids.lock().unwrap().len(), 1);
+
+        let internal_only = Forwarder::new(exchanger(), Vec::new()).zone(name("corp.example."), vec![internal]);
+        assert_eq!(ask(&internal_only, 3, "www.example.com.").header().flags().response_code(), Rcode::Refused);
+    }
+
+    #[test]
+    fn answer_repeated_queries_from_cache() {
+        let (upstream, ids) = recording(catalog());
+        let cache = Arc::new(Cache::new(Arc::new(ManualClock::new(0))));
+        let forwarder = Forwarder::new(exchanger(), vec![upstream]).cache(cache);
+
+        ask(&forwarder, 1, "www.example.com.");
+        let cached = ask(&forwarder, 2, "www.example.com.");
+        assert_eq!(cached.header().id(), 2);
+        assert_eq!(cached.answers().len(), 1);
+
+        let missing = ask(&forwarder, 3, "missing.example.com.");
+        assert_eq!(missing.header().flags().response_code(), Rcode::NameError);
+        ask(&forwarder, 4, "missing.example.com.");
+        assert_eq!(ids.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn cache_only_names_forwarded_to_the_same_upstreams() {
+        let forged = ZoneParser::new(name("corp.example."))
+            .default_ttl(3600)
+            .parse_str("forged", "portal CNAME www.example.com.\nwww.example.com. A 6.6.6.6\n")
+            .unwrap();
+        let internal = start(Forging(forged));
+        let (public, ids) = recording(catalog());
+        let cache = Arc::new(Cache::new(Arc::new(ManualClock::new(0))));
+        let forwarder = Forwarder::new(exchanger(), vec![public])
+            .zone(name("corp.example."), vec![internal])
+            .cache(cache.clone());
+
+        assert_eq!(ask(&forwarder, 1, "portal.corp.example.").answers().len(), 2);
+        assert!(cache.get(&name("portal.corp.example."), RecordType::CNAME, RecordClass::IN).is_some());
+        assert!(cache.get(&name("www.example.com."), RecordType::A, RecordClass::IN).is_none());
+
+        let www = ask(&forwarder, 2, "www.example.com.");
+        assert_ne!(www.answers()[0].data().to_string(), "6.6.6.6");
+        assert_eq!(ids.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn serve_forwarded_answers() {
+        let (upstream, _) = recording(catalog());
+        let proxy = start(Forwarder::new(exchanger(), vec![upstream]));
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        client.send_to(&query(7, Opcode::Query, "www.example.com.", RecordType::A), proxy).unwrap();
+        let mut buffer = [0u8; 512];
+        let (length, _) = client.recv_from(&mut buffer).unwrap();
+
+        let response = parse(&buffer[..length]);
+        assert_eq!(response.header().id(), 7);
+        assert_eq!(response.answers().len(), 1);
+    }
+}
@@ -1,2 +1,13 @@
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod cache;
+pub mod clock;
+pub mod dnssec;
+pub mod forwarder;
 pub mod packet;
 pub mod records;
+pub mod resolver;
+pub mod server;
+pub mod tls;
+pub mod zone;
+pub mod zonefile;
@@ -1,3 +1,334 @@
-fn main() {
-    println!("Hello World!");
+use data_encoding::BASE64;
+#[cfg(feature = "tokio")]
+use rdns::asynchronous::server::Server as AsyncServer;
+use rdns::cache::Cache;
+use rdns::clock::SystemClock;
+use rdns::forwarder::Forwarder;
+use rdns::packet::fqdn::Fqdn;
+use rdns::packet::tsig::{TsigAlgorithm, TsigKey};
+use rdns::resolver::exchange::NetworkExchanger;
+use rdns::server::handler::Handler;
+#[cfg(feature = "doh")]
+use rdns::server::https::{HttpsServer, DEFAULT_PATH};
+#[cfg(feature = "doq")]
+use rdns::server::quic::QuicServer;
+use rdns::server::tls::TlsServer;
+use rdns::server::transfer::{Primary, TransferAcl};
+use rdns::server::{load_zone, ServerResult};
+#[cfg(not(feature = "tokio"))]
+use rdns::server::Server;
+use rdns::tls::{load_certificates, load_private_key};
+use rdns::zone::catalog::Catalog;
+use rdns::zonefile::rdata::parse_name;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::process::{self, ExitCode};
+use std::slice::Iter;
+use std::sync::Arc;
+use std::thread;
+#[cfg(feature = "tokio")]
+use tokio::runtime::Runtime;
+
+const USAGE: &str = "\
+usage: rdns serve [LISTEN...] [TRANSFER...] ZONE=FILE...
+       rdns forward [LISTEN...] [--zone ZONE=UPSTREAM[,UPSTREAM...]]... UPSTREAM...
+
+  --listen ADDRESS      address to answer on over UDP and TCP (default 0.0.0.0:53)
+  --tls-listen ADDRESS  also answer DNS over TLS on ADDRESS, usually port 853
+  --https-listen ADDRESS
+                        also answer DNS over HTTPS on ADDRESS, usually port 443
+  --quic-listen ADDRESS also answer DNS over QUIC on ADDRESS, usually UDP port 853
+  --https-path PATH     path to answer DNS over HTTPS on (default /dns-query)
+  --cert FILE           PEM certificate chain for TLS, end entity first
+  --key FILE            PEM private key for TLS
+  --allow-transfer ADDRESS[/PREFIX]
+                        let these addresses transfer the zones
+  --transfer-key-file FILE
+                        let holders of the HMAC-SHA256 keys in FILE, one
+                        NAME=SECRET line each with a base64 secret, transfer
+                        the zones from anywhere
+  ZONE=FILE             serve the zone with apex ZONE from master file FILE
+  --zone ZONE=...       forward names at and below ZONE to these upstreams instead
+  UPSTREAM              recursive server to forward to, as IP or IP:PORT";
+
+const DEFAULT_LISTEN: &str = "0.0.0.0:53";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("serve") => serve(&args[1..]),
+        Some("forward") => forward(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("rdns: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The addresses to answer on, shared by every command that answers.
+struct Listen {
+    address: String,
+    tls_address: Option<String>,
+    https_address: Option<String>,
+    https_path: Option<String>,
+    quic_address: Option<String>,
+    certificates: Option<String>,
+    key: Option<String>,
+}
+
+impl Default for Listen {
+    fn default() -> Self {
+        Self {
+            address: String::from(DEFAULT_LISTEN),
+            tls_address: None,
+            https_address: None,
+            https_path: None,
+            quic_address: None,
+            certificates: None,
+            key: None,
+        }
+    }
+}
+
+impl Listen {
+    /// Takes the argument and its value when it is a listener option.
+    fn option(&mut self, arg: &str, args: &mut Iter<String>) -> Result<bool, Box<dyn Error>> {
+        let field = match arg {
+            "--listen" => {
+                self.address = args.next().ok_or("--listen needs an address")?.clone();
+                return Ok(true);
+            }
+            "--tls-listen" => &mut self.tls_address,
+            "--https-listen" => &mut self.https_address,
+            "--https-path" => &mut self.https_path,
+            "--quic-listen" => &mut self.quic_address,
+            "--cert" => &mut self.certificates,
+            "--key" => &mut self.key,
+            _ => return Ok(false),
+        };
+        *field = Some(args.next().ok_or_else(|| format!("{} needs a value", arg))?.clone());
+
+        Ok(true)
+    }
+
+    /// The certificate chain and key the TLS based listeners present.
+    fn credentials(&self, option: &str) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), Box<dyn Error>> {
+        let (Some(certificates), Some(key)) = (&self.certificates, &self.key) else {
+            return Err(format!("{} needs --cert and --key", option).into());
+        };
+
+        Ok((load_certificates(certificates)?, load_private_key(key)?))
+    }
+
+    fn run(self, handler: impl Handler + 'static, serving: &str) -> Result<(), Box<dyn Error>> {
+        let handler = Arc::new(handler);
+
+        if let Some(tls_address) = &self.tls_address {
+            let (certificates, key) = self.credentials("--tls-listen")?;
+            let server = TlsServer::bind(tls_address.as_str(), certificates, key, Arc::clone(&handler))?;
+            eprintln!("rdns: {} over TLS on {}", serving, server.local_addr()?);
+            spawn_listener("TLS", move || server.run());
+        }
+
+        if let Some(https_address) = &self.https_address {
+            #[cfg(feature = "doh")]
+            {
+                let (certificates, key) = self.credentials("--https-listen")?;
+                let path = self.https_path.as_deref().unwrap_or(DEFAULT_PATH);
+                let server = HttpsServer::bind(https_address.as_str(), certificates, key, Arc::clone(&handler))?.path(path);
+                eprintln!("rdns: {} over HTTPS on {}", serving, server.local_addr()?);
+                spawn_listener("HTTPS", move || server.run());
+            }
+            #[cfg(not(feature = "doh"))]
+            return Err(format!("cannot listen on {}: built without DNS over HTTPS", https_address).into());
+        }
+
+        if let Some(quic_address) = &self.quic_address {
+            #[cfg(feature = "doq")]
+            {
+                let (certificates, key) = self.credentials("--quic-listen")?;
+                let server = QuicServer::bind(quic_address.as_str(), certificates, key, Arc::clone(&handler))?;
+                eprintln!("rdns: {} over QUIC on {}", serving, server.local_addr()?);
+                spawn_listener("QUIC", move || server.run());
+            }
+            #[cfg(not(feature = "doq"))]
+            return Err(format!("cannot listen on {}: built without DNS over QUIC", quic_address).into());
+        }
+
+        run_plain(&self.address, handler, serving)
+    }
+}
+
+/// Serves on a thread of its own. A listener that stops takes the process
+/// down with it, rather than leaving it up without that transport.
+fn spawn_listener(transport: &'static str, run: impl FnOnce() -> ServerResult<()> + Send + 'static) {
+    thread::spawn(move || {
+        match run() {
+            Ok(()) => eprintln!("rdns: {} listener stopped", transport),
+            Err(error) => eprintln!("rdns: {} listener failed: {}", transport, error),
+        }
+        process::exit(1);
+    });
+}
+
+/// Answers over UDP and TCP. With tokio every datagram is a task of its
+/// own, so a query waiting on a slow upstream holds up no other.
+#[cfg(feature = "tokio")]
+fn run_plain(address: &str, handler: impl Handler + 'static, serving: &str) -> Result<(), Box<dyn Error>> {
+    let runtime = Runtime::new()?;
+    runtime.block_on(async {
+        let server = AsyncServer::bind(address, handler).await?;
+        eprintln!("rdns: {} on {}", serving, server.local_addr()?);
+        server.run().await
+    })?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "tokio"))]
+fn run_plain(address: &str, handler: impl Handler + 'static, serving: &str) -> Result<(), Box<dyn Error>> {
+    let server = Server::bind(address, handler)?;
+    eprintln!("rdns: {} on {}", serving, server.local_addr()?);
+    server.run()?;
+
+    Ok(())
+}
+
+fn serve(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut listen = Listen::default();
+    let mut catalog = Catalog::new();
+    let mut acl = TransferAcl::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if listen.option(arg, &mut args)? {
+            continue;
+        }
+
+        match arg.as_str() {
+            "--allow-transfer" => {
+                let network = args.next().ok_or("--allow-transfer needs an address")?;
+                let (address, prefix_length) = parse_network(network)?;
+                acl = acl.allow_network(address, prefix_length);
+                continue;
+            }
+            "--transfer-key-file" => {
+                let path = args.next().ok_or("--transfer-key-file needs a file")?;
+                acl = read_keys(path)?.into_iter().fold(acl, TransferAcl::allow_key);
+                continue;
+            }
+            _ => {}
+        }
+
+        let (apex, path) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("expected ZONE=FILE, got {}", arg))?;
+        let apex = parse_name(apex, &Fqdn::root())?;
+        let zone = load_zone(apex, path)?;
+        if let Some(replaced) = catalog.insert(zone) {
+            return Err(format!("zone {} is given twice", replaced.apex()).into());
+        }
+    }
+
+    if catalog.is_empty() {
+        return Err(format!("no zones to serve\n{}", USAGE).into());
+    }
+
+    listen.run(Primary::new(catalog).acl(acl), "serving")
+}
+
+fn forward(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut listen = Listen::default();
+    let mut upstreams: Vec<SocketAddr> = Vec::new();
+    let mut zones: Vec<(Fqdn, Vec<SocketAddr>)> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if listen.option(arg, &mut args)? {
+            continue;
+        }
+
+        match arg.as_str() {
+            "--zone" => {
+                let zone = args.next().ok_or("--zone needs ZONE=UPSTREAM")?;
+                let (apex, addresses) = zone
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected ZONE=UPSTREAM, got {}", zone))?;
+                let addresses = addresses.split(',').map(parse_upstream).collect::<Result<_, _>>()?;
+                zones.push((parse_name(apex, &Fqdn::root())?, addresses));
+            }
+            _ => upstreams.push(parse_upstream(arg)?),
+        }
+    }
+
+    if upstreams.is_empty() && zones.is_empty() {
+        return Err(format!("no upstreams to forward to\n{}", USAGE).into());
+    }
+
+    let cache = Arc::new(Cache::new(Arc::new(SystemClock)));
+    let forwarder = zones
+        .into_iter()
+        .fold(Forwarder::new(Arc::new(NetworkExchanger::new()), upstreams), |forwarder, (apex, addresses)| {
+            forwarder.zone(apex, addresses)
+        })
+        .cache(cache);
+
+    listen.run(forwarder, "forwarding")
+}
+
+/// A network as an address and prefix length, a single host without one.
+fn parse_network(text: &str) -> Result<(IpAddr, u8), String> {
+    let invalid = || format!("invalid network {}", text);
+    let (address, prefix_length) = match text.split_once('/') {
+        Some((address, prefix_length)) => (address, Some(prefix_length)),
+        None => (text, None),
+    };
+
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    let prefix_length = match prefix_length {
+        Some(prefix_length) => prefix_length.parse::<u8>().ok().filter(|length| *length <= bits).ok_or_else(invalid)?,
+        None => bits,
+    };
+
+    Ok((address, prefix_length))
+}
+
+/// The keys of a key file, kept out of the command line where other users
+/// could read the secrets. Blank lines and lines starting with # are
+/// skipped.
+fn read_keys(path: &str) -> Result<Vec<TsigKey>, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|error| format!("cannot read key file {}: {}", path, error))?;
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_key)
+        .collect()
+}
+
+/// An HMAC-SHA256 TSIG key as its name and base64 secret.
+fn parse_key(text: &str) -> Result<TsigKey, Box<dyn Error>> {
+    let (name, secret) = text.split_once('=').ok_or("expected NAME=SECRET in the key file")?;
+    let secret = BASE64.decode(secret.as_bytes()).map_err(|_| format!("invalid base64 secret for key {}", name))?;
+
+    Ok(TsigKey::new(parse_name(name, &Fqdn::root())?, TsigAlgorithm::HmacSha256, &secret))
+}
+
+/// An upstream given as an address, on port 53 unless it names one.
+fn parse_upstream(text: &str) -> Result<SocketAddr, String> {
+    text.parse::<SocketAddr>()
+        .or_else(|_| text.parse::<IpAddr>().map(|address| SocketAddr::new(address, 53)))
+        .map_err(|_| format!("invalid upstream address {}", text))
 }
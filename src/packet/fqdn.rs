@@ -1,4 +1,6 @@
 use thiserror::Error;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
 use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, TryFromBytes, ToBytes};
 
@@ -42,7 +44,7 @@ pub struct FqdnUnset;
 #[derive(Debug, Clone)]
 pub struct FqdnSet;
 
-trait FqdnState {}
+pub trait FqdnState {}
 impl FqdnState for FqdnUnset {}
 impl FqdnState for FqdnSet {}
 
@@ -55,7 +57,7 @@ enum FqdnParsingFSM {
     End,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Fqdn {
     // TODO: AOI, if fixed array of size 64 is performant and secure. Could be put on stack
     labels: Vec<String>
@@ -82,6 +84,11 @@ impl TryFromBytes for Fqdn {
 
 impl ToBytes for Fqdn {
     fn to_bytes(&self, encoder: &mut Serialize) {
+        if encoder.is_canonical() {
+            self.to_bytes_uncompressed(encoder);
+            return;
+        }
+
         let mut name_compressed = false;
 
         for i in 0..self.labels.len() {
@@ -105,11 +112,122 @@ impl ToBytes for Fqdn {
     }
 }
 
+/// Presentation format, with characters that are special in master files
+/// escaped (RFC 1035 section 5.1).
+impl Display for Fqdn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.labels.is_empty() {
+            return write!(f, ".");
+        }
+
+        for label in &self.labels {
+            write!(f, "{}.", escape_label(label))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Canonical DNS name order (RFC 4034 section 6.1): labels are compared
+/// from the root down as octet strings. Labels are already lowercase.
+impl Ord for Fqdn {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let labels = self.labels.iter().rev().map(|label| label.as_bytes());
+        let other_labels = other.labels.iter().rev().map(|label| label.as_bytes());
+
+        labels.cmp(other_labels)
+    }
+}
+
+impl PartialOrd for Fqdn {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Fqdn {
     pub fn to_owned_str(&self) -> String {
         self.convert_to_string(0)
     }
 
+    /// Writes the name without compression, as required for names inside
+    /// the RDATA of types defined after RFC 1035 (RFC 3597 section 4).
+    pub fn to_bytes_uncompressed(&self, encoder: &mut Serialize) {
+        for label in &self.labels {
+            encoder.write_string(label);
+        }
+
+        encoder.write_u8(0);
+    }
+
+    pub fn root() -> Fqdn {
+        Fqdn { labels: Vec::new() }
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.labels.first().is_some_and(|label| label == "*")
+    }
+
+    /// Label count as used by the RRSIG labels field, which excludes the
+    /// root and a leading wildcard label (RFC 4034 section 3.1.3).
+    pub fn label_count(&self) -> u8 {
+        match self.is_wildcard() {
+            true => (self.labels.len() - 1) as u8,
+            false => self.labels.len() as u8,
+        }
+    }
+
+    /// True when this name is `other` or lies below it.
+    pub fn is_subdomain_of(&self, other: &Fqdn) -> bool {
+        self.labels.len() >= other.labels.len()
+            && self.labels[self.labels.len() - other.labels.len()..] == other.labels[..]
+    }
+
+    /// The name made of the rightmost `label_count` labels.
+    pub fn ancestor(&self, label_count: usize) -> Fqdn {
+        let label_count = label_count.min(self.labels.len());
+
+        Fqdn {
+            labels: self.labels[self.labels.len() - label_count..].to_vec(),
+        }
+    }
+
+    /// The longest name that both names are equal to or below.
+    pub fn common_ancestor(&self, other: &Fqdn) -> Fqdn {
+        let common_labels = self
+            .labels
+            .iter()
+            .rev()
+            .zip(other.labels.iter().rev())
+            .take_while(|(label, other_label)| label == other_label)
+            .count();
+
+        self.ancestor(common_labels)
+    }
+
+    pub fn parent(&self) -> Option<Fqdn> {
+        match self.labels.is_empty() {
+            true => None,
+            false => Some(self.ancestor(self.labels.len() - 1)),
+        }
+    }
+
+    pub fn prepend_label(&self, label: &str) -> Fqdn {
+        let mut labels = Vec::with_capacity(self.labels.len() + 1);
+        labels.push(label.to_lowercase());
+        labels.extend(self.labels.iter().cloned());
+
+        Fqdn { labels }
+    }
+
     fn convert_to_string(&self, i: usize) -> String{
         let labels = &self.labels[i..];
 
@@ -125,14 +243,20 @@ impl Fqdn {
     }
 }
 
-impl FqdnBuilder<FqdnUnset> {
-    pub fn new() -> Self {
+impl Default for FqdnBuilder<FqdnUnset> {
+    fn default() -> Self {
         FqdnBuilder {
             labels: Vec::with_capacity(MAX_NUMBER_OF_LABELS as usize),
             fqdn_length: 0,
             state: PhantomData,
         }
     }
+}
+
+impl FqdnBuilder<FqdnUnset> {
+    pub fn new() -> Self {
+        FqdnBuilder::default()
+    }
 
     pub fn generate_from_bytes(
         mut self,
@@ -151,8 +275,9 @@ impl FqdnBuilder<FqdnUnset> {
         let labels: Vec<&str> = qname.split('.').collect();
         let mut final_labels: Vec<String> = Vec::new();
 
-        for label in labels {
-            final_labels.push(label.to_owned());
+        // Skipping empty labels accepts both relative and absolute forms, and "." as the root
+        for label in labels.into_iter().filter(|label| !label.is_empty()) {
+            final_labels.push(label.to_lowercase());
         }
 
         FqdnBuilder {
@@ -271,10 +396,29 @@ impl FqdnBuilder<FqdnSet> {
     }
 }
 
+/// Escapes a label for master files: delimiters and characters with a
+/// meaning at the start of a name as `\X`, anything unprintable as `\DDD`.
+pub(crate) fn escape_label(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+
+    for char in label.chars() {
+        match char {
+            '.' | '\\' | '"' | '(' | ')' | ';' | '@' | '$' => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            '!'..='~' => escaped.push(char),
+            _ => escaped.push_str(&format!("\\{:03}", char as u32)),
+        }
+    }
+
+    escaped
+}
+
 #[cfg(test)]
 mod fqdn_unittest {
     use crate::packet::seder::{deserializer::Deserialize, TryFromBytes};
-    use crate::packet::fqdn::{Fqdn, FqdnError};
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder, FqdnError};
 
     #[test]
     fn name_all_lowercase() {
@@ -447,4 +591,45 @@ mod fqdn_unittest {
 
         assert_eq!(Fqdn::try_from_bytes(&mut decoder), Err(FqdnError::IncorrectPointerOffset))
     }
+
+    #[test]
+    fn canonical_order_rfc4034() {
+        let ordered = [
+            "example", "a.example", "yljkjljk.a.example", "z.a.example",
+            "zabc.a.example", "z.example", "*.z.example",
+        ];
+        let names: Vec<Fqdn> = ordered
+            .iter()
+            .map(|name| FqdnBuilder::new().generate_from_string(name.to_string()).build())
+            .collect();
+
+        let mut sorted = names.clone();
+        sorted.reverse();
+        sorted.sort();
+
+        // RFC 4034 lists \001.z.example and \200.z.example after *.z.example,
+        // which needs the binary labels this builder cannot express
+        assert_eq!(sorted, names);
+    }
+
+    #[test]
+    fn common_ancestor() {
+        let a = FqdnBuilder::new().generate_from_string(String::from("a.b.example.com")).build();
+        let c = FqdnBuilder::new().generate_from_string(String::from("c.example.com")).build();
+
+        assert_eq!(a.common_ancestor(&c).to_string(), "example.com.");
+        assert!(Fqdn::root().common_ancestor(&a).is_root());
+    }
+
+    #[test]
+    fn display_escapes_special_characters() {
+        let name = FqdnBuilder::new()
+            .generate_from_string(String::from("example.com"))
+            .build()
+            .prepend_label("a.b")
+            .prepend_label("@ x;");
+
+        assert_eq!(name.to_string(), "\\@\\032x\\;.a\\.b.example.com.");
+        assert_eq!(Fqdn::root().to_string(), ".");
+    }
 }
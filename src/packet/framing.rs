@@ -0,0 +1,77 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Reads one message from a stream where each message is preceded by its
+/// length as two bytes (RFC 1035 section 4.2.2). Returns None when the
+/// peer closed the stream between messages.
+pub fn read_frame(stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 2];
+    match stream.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+
+    let mut message = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message)?;
+
+    Ok(Some(message))
+}
+
+/// Writes one length prefixed message in a single write, so the prefix
+/// and the message go out together.
+pub fn write_frame(stream: &mut impl Write, message: &[u8]) -> io::Result<()> {
+    stream.write_all(&frame(message)?)
+}
+
+/// The message preceded by its length.
+pub fn frame(message: &[u8]) -> io::Result<Vec<u8>> {
+    let length = u16::try_from(message.len()).map_err(|_| io::Error::new(ErrorKind::InvalidInput, "message is longer than 65535 bytes"))?;
+
+    let mut frame = Vec::with_capacity(message.len() + 2);
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(message);
+
+    Ok(frame)
+}
+
+/// The message of data holding exactly one frame, as a DNS over QUIC
+/// stream does (RFC 9250 section 4.2).
+pub fn unframe(data: &[u8]) -> Option<&[u8]> {
+    let (length, message) = data.split_first_chunk::<2>()?;
+
+    (usize::from(u16::from_be_bytes(*length)) == message.len()).then_some(message)
+}
+
+#[cfg(test)]
+mod framing_unittest {
+    use crate::packet::framing::{read_frame, unframe, write_frame};
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip_frames() {
+        let mut stream = Vec::new();
+        write_frame(&mut stream, &[1, 2, 3]).unwrap();
+        write_frame(&mut stream, &[]).unwrap();
+        assert_eq!(stream, vec![0, 3, 1, 2, 3, 0, 0]);
+
+        let mut reader = Cursor::new(stream);
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(vec![]));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn error_truncated_frame() {
+        assert!(read_frame(&mut Cursor::new(vec![0, 4, 1, 2])).is_err());
+        assert!(write_frame(&mut Vec::new(), &vec![0; 65536]).is_err());
+    }
+
+    #[test]
+    fn unframe_single_frame() {
+        assert_eq!(unframe(&[0, 3, 1, 2, 3]), Some(&[1, 2, 3][..]));
+        assert_eq!(unframe(&[0, 0]), Some(&[][..]));
+        assert_eq!(unframe(&[0, 3, 1, 2]), None);
+        assert_eq!(unframe(&[0, 1, 1, 2]), None);
+        assert_eq!(unframe(&[0]), None);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::packet::headers::header_flags::HeaderFlags;
 use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, TryFromBytes, ToBytes};
 use std::cmp::PartialEq;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
 type HeaderResult = Result<Header, HeaderError>;
@@ -19,24 +20,24 @@ pub enum HeaderError {
 
 // Different states for the Header builder
 #[derive(Default)]
-struct IdUnset;
+pub struct IdUnset;
 #[derive(Default)]
-struct IdSet(u16);
+pub struct IdSet(u16);
 
-trait IdState {}
+pub trait IdState {}
 impl IdState for IdUnset {}
 impl IdState for IdSet {}
 
 #[derive(Default)]
-struct FlagsUnset;
+pub struct FlagsUnset;
 #[derive(Default)]
-struct FlagsSet(HeaderFlags);
+pub struct FlagsSet(HeaderFlags);
 
-trait FlagState {}
+pub trait FlagState {}
 impl FlagState for FlagsUnset {}
 impl FlagState for FlagsSet {}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Header {
     id: u16,
     flags: HeaderFlags,
@@ -46,7 +47,7 @@ pub struct Header {
     additional_count: u16,
 }
 
-struct HeaderBuilder<I, F>
+pub struct HeaderBuilder<I, F>
 where
     I: IdState,
     F: FlagState,
@@ -59,18 +60,44 @@ where
     additional_count: u16,
 }
 
+/// The two header lines of dig output.
+impl Display for Header {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            ";; ->>HEADER<<- opcode: {}, status: {}, id: {}",
+            self.flags.opcode(),
+            self.flags.response_code(),
+            self.id
+        )?;
+        write!(
+            f,
+            ";; flags: {}; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}",
+            self.flags,
+            self.question_count,
+            self.answer_count,
+            self.authoritative_count,
+            self.additional_count
+        )
+    }
+}
+
 impl Header {
-    // pub fn id(&self) -> u16 {
-    //     self.id
-    // }
-    //
-    // pub fn flags(&self) -> &HeaderFlags {
-    //     &self.flags
-    // }
-    //
-    // pub fn question_count(&self) -> u16 {
-    //     self.question_count
-    // }
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn flags(&self) -> &HeaderFlags {
+        &self.flags
+    }
+
+    pub fn flags_mut(&mut self) -> &mut HeaderFlags {
+        &mut self.flags
+    }
+
+    pub fn question_count(&self) -> u16 {
+        self.question_count
+    }
 
     pub fn answer_count(&self) -> u16 {
         self.answer_count
@@ -83,6 +110,10 @@ impl Header {
     pub fn additional_count(&self) -> u16 {
         self.additional_count
     }
+
+    pub fn set_additional_count(&mut self, additional_count: u16) {
+        self.additional_count = additional_count;
+    }
 }
 
 impl Default for HeaderBuilder<IdUnset, FlagsUnset> {
@@ -1,5 +1,6 @@
 use thiserror::Error;
 use crate::packet::seder::{serializer::Serialize, ToBytes};
+use std::fmt::{Display, Formatter};
 
 // For serialization
 const SET_QUESTION: u16 = 0 << 15;
@@ -8,6 +9,8 @@ const SET_RESPONSE: u16 = 1 << 15;
 const SET_QUERY: u16 = 0 << 11;
 const SET_IQUERY: u16 = 1 << 11;
 const SET_STATUS: u16 = 2 << 11;
+const SET_NOTIFY: u16 = 4 << 11;
+const SET_UPDATE: u16 = 5 << 11;
 
 const SET_AA: u16 = 1 << 10;
 
@@ -17,21 +20,28 @@ const SET_RD: u16 = 1 << 8;
 
 const SET_RA: u16 = 1 << 7;
 
+const SET_AD: u16 = 1 << 5;
+
+const SET_CD: u16 = 1 << 4;
+
 const SET_NO_ERROR: u16 = 0;
 const SET_FORMAT_ERROR: u16 = 1;
 const SET_SERVER_FAILURE: u16 = 2;
 const SET_NAME_ERROR: u16 = 3;
 const SET_NOT_IMPLEMENTED: u16 = 4;
 const SET_REFUSED: u16 = 5;
+const SET_NOT_AUTH: u16 = 9;
 
 // For deserialization
 const QR_MASK: u16 = 1 << 15;
-const OPCODE_MASK: u16 = 1 << 11;
+const OPCODE_MASK: u16 = 15 << 11;
 const AA_MASK: u16 = 1 << 10;
 const TC_MASK: u16 = 1 << 9;
 const RD_MASK: u16 = 1 << 8;
 const RA_MASK: u16 = 1 << 7;
-const ZERO_MASK: u16 = 7 << 4;
+const ZERO_MASK: u16 = 1 << 6;
+const AD_MASK: u16 = 1 << 5;
+const CD_MASK: u16 = 1 << 4;
 const RC_MASK: u16 = 15;
 
 type HeaderFlagsBuilderUnset =
@@ -51,10 +61,12 @@ type HeaderFlagsBuilderRaSet =
 type HeaderFlagsBuilderSet =
     HeaderFlagsBuilder<QrSet, OpcodeSet, AaSet, TcSet, RdSet, RaSet, RcodeSet>;
 
-
-// TODO: This is the type state I would ideally like to have to response as well
 type HeaderFlagsBuildingQuery =
     HeaderFlagsBuilder<QrSet, OpcodeSet, AaSet, TcSet, RdUnset, RaSet, RcodeSet>;
+type HeaderFlagsBuildingResponse =
+    HeaderFlagsBuilder<QrSet, OpcodeSet, AaUnset, TcUnset, RdSet, RaUnset, RcodeUnset>;
+type HeaderFlagsBuildingResponseAaSet =
+    HeaderFlagsBuilder<QrSet, OpcodeSet, AaSet, TcUnset, RdSet, RaUnset, RcodeUnset>;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum HeaderFlagError {
@@ -72,19 +84,21 @@ pub enum HeaderFlagError {
     QueryWithTCBitSet,
 }
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
 pub enum QR {
     #[default]
     Query,
     Response,
 }
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
 pub enum Opcode {
     #[default]
     Query,
     Iquery,
     Status,
+    Notify,
+    Update,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug, Default)]
@@ -96,14 +110,15 @@ pub enum Rcode {
     NameError,
     NotImplemented,
     Refused,
+    NotAuth,
 }
 
 #[derive(Default)]
-struct QrUnset;
+pub struct QrUnset;
 #[derive(Default)]
 pub struct QrSet(QR);
 
-trait QrState {}
+pub trait QrState {}
 impl QrState for QrUnset {}
 impl QrState for QrSet {}
 
@@ -112,7 +127,7 @@ pub struct OpcodeUnset;
 #[derive(Default)]
 pub struct OpcodeSet(Opcode);
 
-trait OpcodeState {}
+pub trait OpcodeState {}
 impl OpcodeState for OpcodeUnset {}
 impl OpcodeState for OpcodeSet {}
 
@@ -121,7 +136,7 @@ pub struct AaUnset;
 #[derive(Default)]
 pub struct AaSet(bool);
 
-trait AaState {}
+pub trait AaState {}
 impl AaState for AaUnset {}
 impl AaState for AaSet {}
 
@@ -130,7 +145,7 @@ pub struct TcUnset;
 #[derive(Default)]
 pub struct TcSet(bool);
 
-trait TcState {}
+pub trait TcState {}
 impl TcState for TcUnset {}
 impl TcState for TcSet {}
 
@@ -139,7 +154,7 @@ pub struct RdUnset;
 #[derive(Default)]
 pub struct RdSet(bool);
 
-trait RdState {}
+pub trait RdState {}
 impl RdState for RdUnset {}
 impl RdState for RdSet {}
 
@@ -148,7 +163,7 @@ pub struct RaUnset;
 #[derive(Default)]
 pub struct RaSet(bool);
 
-trait RaState {}
+pub trait RaState {}
 impl RaState for RaUnset {}
 impl RaState for RaSet {}
 
@@ -157,11 +172,11 @@ pub struct RcodeUnset;
 #[derive(Default)]
 pub struct RcodeSet(Rcode);
 
-trait RcodeState {}
+pub trait RcodeState {}
 impl RcodeState for RcodeUnset {}
 impl RcodeState for RcodeSet {}
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct HeaderFlags {
     query_or_response: QR,
     opcode: Opcode,
@@ -169,11 +184,13 @@ pub struct HeaderFlags {
     truncation: bool,
     recursion_desired: bool,
     recursion_available: bool,
+    authentic_data: bool,
+    checking_disabled: bool,
     response_code: Rcode,
 }
 
 #[derive(Default)]
-struct HeaderFlagsBuilder<Q, O, A, T, RD, RA, RC>
+pub struct HeaderFlagsBuilder<Q, O, A, T, RD, RA, RC>
 where
     Q: QrState,
     O: OpcodeState,
@@ -189,13 +206,104 @@ where
     truncation: T,
     recursion_desired: RD,
     recursion_available: RA,
+    authentic_data: bool,
+    checking_disabled: bool,
     response_code: RC,
 }
 
 impl HeaderFlags {
+    pub fn query_or_response(&self) -> QR {
+        self.query_or_response
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    pub fn is_authoritative_answer(&self) -> bool {
+        self.authoritative_answer
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncation
+    }
+
+    pub fn is_recursion_desired(&self) -> bool {
+        self.recursion_desired
+    }
+
+    pub fn is_recursion_available(&self) -> bool {
+        self.recursion_available
+    }
+
     pub fn truncation(&mut self, tc: bool) {
         self.truncation = tc
     }
+
+    pub fn authentic_data(&mut self, ad: bool) {
+        self.authentic_data = ad
+    }
+
+    pub fn response_code(&self) -> Rcode {
+        self.response_code
+    }
+
+    pub fn is_authentic_data(&self) -> bool {
+        self.authentic_data
+    }
+
+    pub fn is_checking_disabled(&self) -> bool {
+        self.checking_disabled
+    }
+}
+
+// Mnemonics as dig prints them (RFC 6895 section 2.2 and 2.3)
+impl Display for Opcode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = match self {
+            Opcode::Query => "QUERY",
+            Opcode::Iquery => "IQUERY",
+            Opcode::Status => "STATUS",
+            Opcode::Notify => "NOTIFY",
+            Opcode::Update => "UPDATE",
+        };
+
+        write!(f, "{}", mnemonic)
+    }
+}
+
+impl Display for Rcode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = match self {
+            Rcode::NoError => "NOERROR",
+            Rcode::FormatError => "FORMERR",
+            Rcode::ServerFailure => "SERVFAIL",
+            Rcode::NameError => "NXDOMAIN",
+            Rcode::NotImplemented => "NOTIMP",
+            Rcode::Refused => "REFUSED",
+            Rcode::NotAuth => "NOTAUTH",
+        };
+
+        write!(f, "{}", mnemonic)
+    }
+}
+
+/// The flags that are set, such as `qr rd ra`.
+impl Display for HeaderFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let flags = [
+            (self.query_or_response == QR::Response, "qr"),
+            (self.authoritative_answer, "aa"),
+            (self.truncation, "tc"),
+            (self.recursion_desired, "rd"),
+            (self.recursion_available, "ra"),
+            (self.authentic_data, "ad"),
+            (self.checking_disabled, "cd"),
+        ];
+        let set: Vec<&str> = flags.iter().filter(|(is_set, _)| *is_set).map(|(_, flag)| *flag).collect();
+
+        write!(f, "{}", set.join(" "))
+    }
 }
 
 impl TryFrom<u16> for Opcode {
@@ -205,6 +313,8 @@ impl TryFrom<u16> for Opcode {
             0 => Ok(Opcode::Query),
             1 => Ok(Opcode::Iquery),
             2 => Ok(Opcode::Status),
+            4 => Ok(Opcode::Notify),
+            5 => Ok(Opcode::Update),
             _ => Err(HeaderFlagError::MalformedOpcode),
         }
     }
@@ -220,6 +330,7 @@ impl TryFrom<u16> for Rcode {
             3 => Ok(Rcode::NameError),
             4 => Ok(Rcode::NotImplemented),
             5 => Ok(Rcode::Refused),
+            9 => Ok(Rcode::NotAuth),
             _ => Err(HeaderFlagError::MalformedRcode),
         }
     }
@@ -245,8 +356,10 @@ impl TryFrom<u16> for HeaderFlags {
         let truncation = (value & TC_MASK) >> 9 == 1;
         let recursion_desired = (value & RD_MASK) >> 8 == 1;
         let recursion_available = (value & RA_MASK) >> 7 == 1;
+        let authentic_data = (value & AD_MASK) >> 5 == 1;
+        let checking_disabled = (value & CD_MASK) >> 4 == 1;
 
-        let zero = ((value & ZERO_MASK) >> 4) as u8;
+        let zero = ((value & ZERO_MASK) >> 6) as u8;
 
         let response_code = Rcode::try_from(value & RC_MASK)?;
 
@@ -269,6 +382,8 @@ impl TryFrom<u16> for HeaderFlags {
         let header_flags = HeaderFlagsBuilder::new()
             .query_or_response(query_or_response)
             .opcode(opcode)
+            .authentic_data(authentic_data)
+            .checking_disabled(checking_disabled)
             .authoritative_answer(authoritative_answer)
             .truncation(truncation)
             .recursion_desired(recursion_desired)
@@ -293,6 +408,8 @@ impl ToBytes for HeaderFlags {
             Opcode::Query => flags | SET_QUERY,
             Opcode::Iquery => flags | SET_IQUERY,
             Opcode::Status => flags | SET_STATUS,
+            Opcode::Notify => flags | SET_NOTIFY,
+            Opcode::Update => flags | SET_UPDATE,
         };
 
         flags = match self.authoritative_answer {
@@ -315,6 +432,16 @@ impl ToBytes for HeaderFlags {
             false => flags,
         };
 
+        flags = match self.authentic_data {
+            true => flags | SET_AD,
+            false => flags,
+        };
+
+        flags = match self.checking_disabled {
+            true => flags | SET_CD,
+            false => flags,
+        };
+
         flags = match self.response_code {
             Rcode::NoError => flags | SET_NO_ERROR,
             Rcode::FormatError => flags | SET_FORMAT_ERROR,
@@ -322,6 +449,7 @@ impl ToBytes for HeaderFlags {
             Rcode::NameError => flags | SET_NAME_ERROR,
             Rcode::NotImplemented => flags | SET_NOT_IMPLEMENTED,
             Rcode::Refused => flags | SET_REFUSED,
+            Rcode::NotAuth => flags | SET_NOT_AUTH,
         };
 
         encoder.write_u16(flags);
@@ -343,17 +471,25 @@ impl HeaderFlagsBuilderUnset {
             truncation: TcSet(false),
             recursion_desired: self.recursion_desired,
             recursion_available: RaSet(false),
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: RcodeSet(Rcode::NoError),
         }
     }
 
-    // TODO: Think about this if we want specific response builders such as No_Error, SERVFAIL, ...
-    // pub fn response(
-    //     self,
-    //     _query_header_flags: &HeaderFlags,
-    // ) -> HeaderFlagsBuilder<QrSet, OpcodeSet, AaUnset, TcSet, RdSet, RaUnset, RcodeUnset> {
-    //     todo!();
-    // }
+    pub fn response(self, query_header_flags: &HeaderFlags) -> HeaderFlagsBuildingResponse {
+        HeaderFlagsBuilder {
+            query_or_response: QrSet(QR::Response),
+            opcode: OpcodeSet(query_header_flags.opcode),
+            authoritative_answer: self.authoritative_answer,
+            truncation: self.truncation,
+            recursion_desired: RdSet(query_header_flags.recursion_desired),
+            recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: query_header_flags.checking_disabled,
+            response_code: self.response_code,
+        }
+    }
 
     pub fn query_or_response(self, qr: QR) -> HeaderFlagsBuilderQrSet {
         HeaderFlagsBuilder {
@@ -363,12 +499,19 @@ impl HeaderFlagsBuilderUnset {
             truncation: self.truncation,
             recursion_desired: self.recursion_desired,
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
 }
 
 impl HeaderFlagsBuildingQuery {
+    pub fn checking_disabled(mut self, checking_disabled: bool) -> Self {
+        self.checking_disabled = checking_disabled;
+        self
+    }
+
     pub fn recursion_desired(self, rd: bool) -> HeaderFlagsBuilderSet {
         HeaderFlagsBuilder {
             query_or_response: self.query_or_response,
@@ -377,6 +520,46 @@ impl HeaderFlagsBuildingQuery {
             truncation: self.truncation,
             recursion_desired: RdSet(rd),
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
+            response_code: self.response_code,
+        }
+    }
+}
+
+impl HeaderFlagsBuildingResponse {
+    /// Only a response vouches for its data; CD was copied from the query.
+    pub fn authentic_data(mut self, authentic_data: bool) -> Self {
+        self.authentic_data = authentic_data;
+        self
+    }
+
+    pub fn authoritative_answer(self, authoritative_answer: bool) -> HeaderFlagsBuildingResponseAaSet {
+        HeaderFlagsBuilder {
+            query_or_response: self.query_or_response,
+            opcode: self.opcode,
+            authoritative_answer: AaSet(authoritative_answer),
+            truncation: self.truncation,
+            recursion_desired: self.recursion_desired,
+            recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
+            response_code: self.response_code,
+        }
+    }
+}
+
+impl HeaderFlagsBuildingResponseAaSet {
+    pub fn truncation(self, truncation: bool) -> HeaderFlagsBuilderRdSet {
+        HeaderFlagsBuilder {
+            query_or_response: self.query_or_response,
+            opcode: self.opcode,
+            authoritative_answer: self.authoritative_answer,
+            truncation: TcSet(truncation),
+            recursion_desired: self.recursion_desired,
+            recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
@@ -391,12 +574,26 @@ impl HeaderFlagsBuilderQrSet {
             truncation: self.truncation,
             recursion_desired: self.recursion_desired,
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
 }
 
 impl HeaderFlagsBuilderOpcodeSet {
+    /// Flags as read off the wire, where queries may carry AD too (RFC 6840
+    /// section 5.7).
+    pub fn authentic_data(mut self, authentic_data: bool) -> Self {
+        self.authentic_data = authentic_data;
+        self
+    }
+
+    pub fn checking_disabled(mut self, checking_disabled: bool) -> Self {
+        self.checking_disabled = checking_disabled;
+        self
+    }
+
     pub fn authoritative_answer(self, authoritative_answer: bool) -> HeaderFlagsBuilderAaSet {
         HeaderFlagsBuilder {
             query_or_response: self.query_or_response,
@@ -405,6 +602,8 @@ impl HeaderFlagsBuilderOpcodeSet {
             truncation: self.truncation,
             recursion_desired: self.recursion_desired,
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
@@ -419,6 +618,8 @@ impl HeaderFlagsBuilderAaSet {
             truncation: TcSet(truncation),
             recursion_desired: self.recursion_desired,
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
@@ -433,6 +634,8 @@ impl HeaderFlagsBuilderTcSet {
             truncation: self.truncation,
             recursion_desired: RdSet(recursion_desired),
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
@@ -447,6 +650,8 @@ impl HeaderFlagsBuilderRdSet {
             truncation: self.truncation,
             recursion_desired: self.recursion_desired,
             recursion_available: RaSet(recursion_available),
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code,
         }
     }
@@ -461,6 +666,8 @@ impl HeaderFlagsBuilderRaSet {
             truncation: self.truncation,
             recursion_desired: self.recursion_desired,
             recursion_available: self.recursion_available,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: RcodeSet(response_code),
         }
     }
@@ -475,6 +682,8 @@ impl HeaderFlagsBuilderSet {
             truncation: self.truncation.0,
             recursion_desired: self.recursion_desired.0,
             recursion_available: self.recursion_available.0,
+            authentic_data: self.authentic_data,
+            checking_disabled: self.checking_disabled,
             response_code: self.response_code.0,
         }
     }
@@ -524,9 +733,117 @@ pub mod header_flags_unittest {
         assert_eq!(encoder.bin_data(), expected_bin_data);
     }
 
+    #[test]
+    fn response_copies_query_opcode_and_rd() {
+        let query_flags = generate_query_header_flags(true);
+
+        let response_flags = HeaderFlagsBuilder::new()
+            .response(&query_flags)
+            .authoritative_answer(false)
+            .truncation(false)
+            .recursion_available(true)
+            .response_code(Rcode::NoError)
+            .build();
+
+        assert_eq!(
+            response_flags,
+            generate_response_header_flag(false, false, true, true, Rcode::NoError)
+        );
+    }
+
+    #[test]
+    fn serialize_response_from_query() {
+        let expected_bin_data: Vec<u8> = vec![0x84, 0x03];
+
+        let query_flags = generate_query_header_flags(false);
+
+        let response_flags = HeaderFlagsBuilder::new()
+            .response(&query_flags)
+            .authoritative_answer(true)
+            .truncation(false)
+            .recursion_available(false)
+            .response_code(Rcode::NameError)
+            .build();
+
+        let mut encoder = Serialize::new();
+        response_flags.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), expected_bin_data);
+    }
+
+    #[test]
+    fn read_response_with_ad_bit() {
+        let header_flags: u16 = 0b1000_0001_1010_0000;
+
+        let expected_flags = HeaderFlagsBuilder::new()
+            .query_or_response(QR::Response)
+            .opcode(Opcode::Query)
+            .authentic_data(true)
+            .authoritative_answer(false)
+            .truncation(false)
+            .recursion_desired(true)
+            .recursion_available(true)
+            .response_code(Rcode::NoError)
+            .build();
+
+        assert_eq!(HeaderFlags::try_from(header_flags), Ok(expected_flags));
+    }
+
+    #[test]
+    fn read_opcodes() {
+        let status = HeaderFlags::try_from(0b0001_0000_0000_0000).unwrap();
+        let notify = HeaderFlags::try_from(0b0010_0000_0000_0000).unwrap();
+        let update = HeaderFlags::try_from(0b0010_1000_0000_0000).unwrap();
+
+        assert_eq!(status.opcode(), Opcode::Status);
+        assert_eq!(notify.opcode(), Opcode::Notify);
+        assert_eq!(update.opcode(), Opcode::Update);
+        assert_eq!(HeaderFlags::try_from(0b0001_1000_0000_0000), Err(HeaderFlagError::MalformedOpcode));
+
+        let mut encoder = Serialize::new();
+        update.to_bytes(&mut encoder);
+        assert_eq!(encoder.bin_data(), vec![0x28, 0x00]);
+    }
+
+    #[test]
+    fn read_query_with_cd_bit() {
+        let header_flags: u16 = 0b0000_0001_0001_0000;
+
+        let expected_flags = HeaderFlagsBuilder::new()
+            .query()
+            .checking_disabled(true)
+            .recursion_desired(true)
+            .build();
+
+        assert_eq!(HeaderFlags::try_from(header_flags), Ok(expected_flags));
+    }
+
+    #[test]
+    fn response_copies_query_cd_bit() {
+        let query_flags = HeaderFlagsBuilder::new()
+            .query()
+            .checking_disabled(true)
+            .recursion_desired(true)
+            .build();
+
+        let response_flags = HeaderFlagsBuilder::new()
+            .response(&query_flags)
+            .authentic_data(true)
+            .authoritative_answer(false)
+            .truncation(false)
+            .recursion_available(true)
+            .response_code(Rcode::NoError)
+            .build();
+
+        let mut encoder = Serialize::new();
+        response_flags.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), vec![0x81, 0xb0]);
+    }
+
     #[test]
     fn error_zero_flag_unset() {
-        let header_flags: u16 = 0b0000_0000_0001_0000;
+        let header_flags: u16 = 0b0000_0000_0100_0000;
 
         assert_eq!(
             HeaderFlags::try_from(header_flags),
@@ -572,4 +889,15 @@ pub mod header_flags_unittest {
 
         assert!(header_flag.truncation);
     }
+
+    #[test]
+    fn display_set_flags_and_mnemonics() {
+        let mut flags = generate_response_header_flag(true, false, true, false, Rcode::NameError);
+        flags.authentic_data(true);
+
+        assert_eq!(flags.to_string(), "qr aa rd ad");
+        assert_eq!(generate_query_header_flags(false).to_string(), "");
+        assert_eq!(flags.response_code().to_string(), "NXDOMAIN");
+        assert_eq!(Opcode::Status.to_string(), "STATUS");
+    }
 }
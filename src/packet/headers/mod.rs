@@ -1,2 +1,2 @@
 pub mod header;
-pub(crate) mod header_flags;
\ No newline at end of file
+pub mod header_flags;
\ No newline at end of file
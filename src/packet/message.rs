@@ -1,6 +1,11 @@
 use super::question::Question;
 use super::record::Record;
+use super::update_record::UpdateRecord;
 use crate::packet::headers::header::Header;
+use crate::packet::headers::header_flags::Opcode;
+use crate::packet::sig0::Sig0Record;
+use crate::packet::tsig::TsigRecord;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, TryFromBytes, ToBytes};
 
@@ -18,32 +23,52 @@ pub enum MessageError {
     InvalidAuthority,
     #[error("Invalid additional section")]
     InvalidAdditional,
+    #[error("Invalid prerequisite section")]
+    InvalidPrerequisite,
+    #[error("Invalid update section")]
+    InvalidUpdate,
+    #[error("Invalid TSIG record")]
+    InvalidTsig,
+    #[error("Invalid SIG(0) record")]
+    InvalidSig0,
+    #[error("TSIG or SIG(0) record is not the last additional record")]
+    SignatureNotLast,
 }
 
-trait HeaderState {}
-struct HeaderUnset;
-struct HeaderSet(Header);
+pub trait HeaderState {}
+pub struct HeaderUnset;
+pub struct HeaderSet(Header);
 
 impl HeaderState for HeaderUnset {}
 impl HeaderState for HeaderSet {}
 
-trait QuestionState {}
-struct QuestionUnset;
-struct QuestionSet(Question);
+pub trait QuestionState {}
+pub struct QuestionUnset;
+pub struct QuestionSet(Question);
 
 impl QuestionState for QuestionUnset {}
 impl QuestionState for QuestionSet {}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Message {
     header: Header,
     question: Question,
     answer_records: Vec<Record>,
     authority_records: Vec<Record>,
     additional_records: Vec<Record>,
+    // UPDATE messages carry these in place of the answer and authority
+    // sections (RFC 2136 section 2)
+    prerequisites: Vec<UpdateRecord>,
+    updates: Vec<UpdateRecord>,
+    tsig: Option<TsigRecord>,
+    sig0: Option<Sig0Record>,
+    // The message as it came off the wire and the offset of its TSIG or
+    // SIG(0) record. Signatures are checked over the bytes the sender
+    // signed, which re-encoding could change by compressing differently.
+    received: Option<(Vec<u8>, Option<usize>)>,
 }
 
-struct MessageBuilder<H, Q>
+pub struct MessageBuilder<H, Q>
 where
     H: HeaderState,
     Q: QuestionState,
@@ -53,6 +78,8 @@ where
     answer_records: Vec<Record>,
     authority_records: Vec<Record>,
     additional_records: Vec<Record>,
+    prerequisites: Vec<UpdateRecord>,
+    updates: Vec<UpdateRecord>,
 }
 
 impl Default for MessageBuilder<HeaderUnset, QuestionUnset> {
@@ -63,6 +90,8 @@ impl Default for MessageBuilder<HeaderUnset, QuestionUnset> {
             answer_records: vec![],
             authority_records: vec![],
             additional_records: vec![],
+            prerequisites: vec![],
+            updates: vec![],
         }
     }
 }
@@ -71,34 +100,73 @@ impl TryFromBytes for Message {
     type Error = MessageError;
 
     fn try_from_bytes(decoder: &mut Deserialize) -> MessageResult {
+        let message_start = decoder.cursor();
         let header =  Header::try_from_bytes(decoder).map_err(|_| MessageError::InvalidHeader)?;
         let question = Question::try_from_bytes(decoder).map_err(|_| MessageError::InvalidQuestion)?;
 
-        let mut answers: Vec<Record> = Vec::with_capacity(header.answer_count() as usize);
-        for _ in 0..header.answer_count() {
-            let answer = Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidAnswer)?;
-            answers.push(answer);
-        }
-
-        let mut authorities: Vec<Record> = Vec::with_capacity(header.authority_count() as usize);
-        for _ in 0..header.authority_count() {
-            let answer = Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidAuthority)?;
-            authorities.push(answer);
+        let mut answers: Vec<Record> = Vec::new();
+        let mut authorities: Vec<Record> = Vec::new();
+        let mut prerequisites: Vec<UpdateRecord> = Vec::new();
+        let mut updates: Vec<UpdateRecord> = Vec::new();
+        if header.flags().opcode() == Opcode::Update {
+            for _ in 0..header.answer_count() {
+                let prerequisite = UpdateRecord::try_from_bytes(decoder).map_err(|_| MessageError::InvalidPrerequisite)?;
+                prerequisites.push(prerequisite);
+            }
+
+            for _ in 0..header.authority_count() {
+                let update = UpdateRecord::try_from_bytes(decoder).map_err(|_| MessageError::InvalidUpdate)?;
+                updates.push(update);
+            }
+        } else {
+            for _ in 0..header.answer_count() {
+                let answer = Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidAnswer)?;
+                answers.push(answer);
+            }
+
+            for _ in 0..header.authority_count() {
+                let answer = Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidAuthority)?;
+                authorities.push(answer);
+            }
         }
 
         let mut additional: Vec<Record> = Vec::with_capacity(header.additional_count() as usize);
-        for _ in 0..header.additional_count() {
+        let mut tsig = None;
+        let mut sig0 = None;
+        let mut signature_start = None;
+        for index in 0..header.additional_count() {
+            let is_tsig = TsigRecord::is_next(decoder);
+            if is_tsig || Sig0Record::is_next(decoder) {
+                if index + 1 != header.additional_count() {
+                    return Err(MessageError::SignatureNotLast);
+                }
+
+                signature_start = Some((decoder.cursor() - message_start) as usize);
+                match is_tsig {
+                    true => tsig = Some(TsigRecord::try_from_bytes(decoder).map_err(|_| MessageError::InvalidTsig)?),
+                    false => sig0 = Some(Sig0Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidSig0)?),
+                }
+                continue;
+            }
+
             let answer = Record::try_from_bytes(decoder).map_err(|_| MessageError::InvalidAdditional)?;
             additional.push(answer);
         }
 
-        let message = MessageBuilder::new()
+        let wire = decoder.bytes_between(message_start, decoder.cursor()).to_vec();
+
+        let mut message = MessageBuilder::new()
             .header(header)
             .question(question)
             .answer(answers)
             .authority(authorities)
+            .prerequisites(prerequisites)
+            .updates(updates)
             .additional(additional)
             .build();
+        message.tsig = tsig;
+        message.sig0 = sig0;
+        message.received = Some((wire, signature_start));
 
         Ok(message)
     }
@@ -106,6 +174,14 @@ impl TryFromBytes for Message {
 
 impl ToBytes for Message {
     fn to_bytes(&self, encoder: &mut Serialize) {
+        self.encode(encoder);
+    }
+}
+
+impl Message {
+    /// Writes the message and returns where its TSIG or SIG(0) record
+    /// starts, if it has one.
+    fn encode(&self, encoder: &mut Serialize) -> Option<usize> {
         self.header.to_bytes(encoder);
         self.question.to_bytes(encoder);
 
@@ -117,9 +193,192 @@ impl ToBytes for Message {
             record.to_bytes(encoder);
         }
 
+        for record in self.prerequisites.iter().chain(&self.updates) {
+            record.to_bytes(encoder);
+        }
+
         for record in &self.additional_records {
             record.to_bytes(encoder);
         }
+
+        let signature_start = encoder.cursor() as usize;
+        if let Some(tsig) = &self.tsig {
+            tsig.to_bytes(encoder);
+        } else if let Some(sig0) = &self.sig0 {
+            sig0.to_bytes(encoder);
+        } else {
+            return None;
+        }
+
+        Some(signature_start)
+    }
+}
+
+/// The message as dig prints it: header lines, then each section that has
+/// records, with TSIG and SIG(0) in pseudosections.
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.header)?;
+        writeln!(f)?;
+        match self.header.flags().opcode() {
+            Opcode::Update => writeln!(f, ";; ZONE SECTION:")?,
+            _ => writeln!(f, ";; QUESTION SECTION:")?,
+        }
+        writeln!(f, ";{}", self.question)?;
+
+        write_section(f, "PREREQUISITE", &self.prerequisites)?;
+        write_section(f, "UPDATE", &self.updates)?;
+        write_section(f, "ANSWER", &self.answer_records)?;
+        write_section(f, "AUTHORITY", &self.authority_records)?;
+        write_section(f, "ADDITIONAL", &self.additional_records)?;
+
+        if let Some(tsig) = &self.tsig {
+            writeln!(f)?;
+            writeln!(f, ";; TSIG PSEUDOSECTION:")?;
+            writeln!(f, "{}", tsig)?;
+        }
+
+        if let Some(sig0) = &self.sig0 {
+            writeln!(f)?;
+            writeln!(f, ";; SIG0 PSEUDOSECTION:")?;
+            writeln!(f, "{}", sig0)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_section<T: Display>(f: &mut Formatter<'_>, name: &str, records: &[T]) -> std::fmt::Result {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f)?;
+    writeln!(f, ";; {} SECTION:", name)?;
+    for record in records {
+        writeln!(f, "{}", record)?;
+    }
+
+    Ok(())
+}
+
+// The received bytes are a cache of the wire form, not part of the message
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+            && self.question == other.question
+            && self.answer_records == other.answer_records
+            && self.authority_records == other.authority_records
+            && self.additional_records == other.additional_records
+            && self.prerequisites == other.prerequisites
+            && self.updates == other.updates
+            && self.tsig == other.tsig
+            && self.sig0 == other.sig0
+    }
+}
+
+impl Message {
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Drops the received bytes, which the changed header would no longer
+    /// match.
+    pub fn header_mut(&mut self) -> &mut Header {
+        self.received = None;
+        &mut self.header
+    }
+
+    pub fn question(&self) -> &Question {
+        &self.question
+    }
+
+    pub fn answers(&self) -> &[Record] {
+        &self.answer_records
+    }
+
+    pub fn authorities(&self) -> &[Record] {
+        &self.authority_records
+    }
+
+    pub fn additionals(&self) -> &[Record] {
+        &self.additional_records
+    }
+
+    /// The prerequisite section of an UPDATE message.
+    pub fn prerequisites(&self) -> &[UpdateRecord] {
+        &self.prerequisites
+    }
+
+    /// The update section of an UPDATE message.
+    pub fn updates(&self) -> &[UpdateRecord] {
+        &self.updates
+    }
+
+    /// The TSIG record, which is kept apart from the additional section.
+    pub fn tsig(&self) -> Option<&TsigRecord> {
+        self.tsig.as_ref()
+    }
+
+    /// The SIG(0) record, which is kept apart from the additional section.
+    pub fn sig0(&self) -> Option<&Sig0Record> {
+        self.sig0.as_ref()
+    }
+
+    pub(crate) fn set_tsig(&mut self, tsig: TsigRecord) {
+        self.remove_signature();
+        self.header.set_additional_count(self.header.additional_count() + 1);
+        self.tsig = Some(tsig);
+    }
+
+    pub(crate) fn set_sig0(&mut self, sig0: Sig0Record) {
+        self.remove_signature();
+        self.header.set_additional_count(self.header.additional_count() + 1);
+        self.sig0 = Some(sig0);
+    }
+
+    /// Drops the TSIG or SIG(0) record and the received bytes, which no
+    /// longer describe the message.
+    pub(crate) fn remove_signature(&mut self) {
+        if self.tsig.take().is_some() || self.sig0.take().is_some() {
+            self.header.set_additional_count(self.header.additional_count() - 1);
+        }
+        self.received = None;
+    }
+
+    /// The message on the wire: the received bytes, or the encoding of a
+    /// message built locally.
+    pub(crate) fn wire(&self) -> Vec<u8> {
+        self.wire_and_signature_start().0
+    }
+
+    /// The bytes a TSIG or SIG(0) signature covers: the message without
+    /// that record and with ARCOUNT lowered to match. TSIG also restores
+    /// the ID the message was signed with (RFC 8945 section 4.3.2).
+    pub(crate) fn signed_portion(&self) -> Vec<u8> {
+        let (mut wire, signature_start) = self.wire_and_signature_start();
+
+        if let Some(signature_start) = signature_start {
+            wire.truncate(signature_start);
+            wire[10..12].copy_from_slice(&(self.header.additional_count() - 1).to_be_bytes());
+        }
+
+        if let Some(tsig) = &self.tsig {
+            wire[0..2].copy_from_slice(&tsig.data().original_id().to_be_bytes());
+        }
+
+        wire
+    }
+
+    fn wire_and_signature_start(&self) -> (Vec<u8>, Option<usize>) {
+        match &self.received {
+            Some((wire, signature_start)) => (wire.clone(), *signature_start),
+            None => {
+                let mut encoder = Serialize::new();
+                let signature_start = self.encode(&mut encoder);
+                (encoder.bin_data(), signature_start)
+            }
+        }
     }
 }
 
@@ -135,6 +394,8 @@ impl MessageBuilder<HeaderUnset, QuestionUnset> {
             answer_records: self.answer_records,
             authority_records: self.authority_records,
             additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
         }
     }
 }
@@ -147,6 +408,8 @@ impl MessageBuilder<HeaderSet, QuestionUnset> {
             answer_records: self.answer_records,
             authority_records: self.authority_records,
             additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
         }
     }
 }
@@ -159,6 +422,11 @@ impl MessageBuilder<HeaderSet, QuestionSet> {
             answer_records: self.answer_records,
             authority_records: self.authority_records,
             additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
+            tsig: None,
+            sig0: None,
+            received: None,
         }
     }
 }
@@ -175,6 +443,8 @@ where
             answer_records: answer,
             authority_records: self.authority_records,
             additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
         }
     }
 
@@ -185,6 +455,8 @@ where
             answer_records: self.answer_records,
             authority_records: authority,
             additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
         }
     }
 
@@ -195,6 +467,32 @@ where
             answer_records: self.answer_records,
             authority_records: self.authority_records,
             additional_records: additional,
+            prerequisites: self.prerequisites,
+            updates: self.updates,
+        }
+    }
+
+    pub fn prerequisites(self, prerequisites: Vec<UpdateRecord>) -> Self {
+        MessageBuilder {
+            header: self.header,
+            question: self.question,
+            answer_records: self.answer_records,
+            authority_records: self.authority_records,
+            additional_records: self.additional_records,
+            prerequisites,
+            updates: self.updates,
+        }
+    }
+
+    pub fn updates(self, updates: Vec<UpdateRecord>) -> Self {
+        MessageBuilder {
+            header: self.header,
+            question: self.question,
+            answer_records: self.answer_records,
+            authority_records: self.authority_records,
+            additional_records: self.additional_records,
+            prerequisites: self.prerequisites,
+            updates,
         }
     }
 }
@@ -206,6 +504,9 @@ mod message_unittest {
     use crate::packet::record::record_unittest::{get_sample_a_record};
     use crate::packet::headers::header::header_unittest::get_response_header;
     use crate::packet::question::question_unittest::{generate_question};
+    use crate::packet::headers::header_flags::Opcode;
+    use crate::records::query_class::QueryClass;
+    use crate::records::query_type::QueryType;
     use crate::records::record_type::RecordType;
 
     #[test]
@@ -262,4 +563,65 @@ mod message_unittest {
 
         assert_eq!(encoder.bin_data(), expected_wire_data);
     }
+
+    #[test]
+    fn display_like_dig() {
+        let message = MessageBuilder::new()
+            .header(get_response_header(62184))
+            .question(generate_question("www.google.com", RecordType::A))
+            .answer(vec![get_sample_a_record()])
+            .build();
+
+        assert_eq!(
+            message.to_string(),
+            ";; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 62184\n\
+             ;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 0\n\
+             \n\
+             ;; QUESTION SECTION:\n\
+             ;www.google.com.\t\tIN\tA\n\
+             \n\
+             ;; ANSWER SECTION:\n\
+             www.google.com.\t104\tIN\tA\t172.217.14.196\n"
+        );
+    }
+
+    #[test]
+    fn update_message() {
+        // RFC 2136: zone example.com, prerequisites that www has A records
+        // and new is unused, updates deleting one A of www and all of old
+        let wire_data: [u8; 93] = [
+            0x12, 0x34, 0x28, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x07, 0x65,
+            0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x06, 0x00,
+            0x01, 0x03, 0x77, 0x77, 0x77, 0xc0, 0x0c, 0x00, 0x01, 0x00, 0xff, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x03, 0x6e, 0x65, 0x77, 0xc0, 0x0c, 0x00, 0xff, 0x00, 0xfe, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x1d, 0x00, 0x01, 0x00, 0xfe, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x04, 0xc0, 0x00, 0x02, 0x01, 0x03, 0x6f, 0x6c, 0x64, 0xc0, 0x0c, 0x00,
+            0xff, 0x00, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let message = Message::try_from_bytes(&mut Deserialize::new(&wire_data)).unwrap();
+        assert_eq!(message.header().flags().opcode(), Opcode::Update);
+
+        assert!(message.answers().is_empty() && message.authorities().is_empty());
+
+        let prerequisites = message.prerequisites();
+        assert_eq!((prerequisites[0].class(), prerequisites[0].record_type()), (QueryClass::ANY, QueryType::Type(RecordType::A)));
+        assert_eq!(prerequisites[0].data(), None);
+        assert_eq!((prerequisites[1].class(), prerequisites[1].record_type()), (QueryClass::NONE, QueryType::ANY));
+
+        let updates = message.updates();
+        assert_eq!(updates[0].class(), QueryClass::NONE);
+        assert_eq!(updates[0].data().map(|data| data.to_string()), Some(String::from("192.0.2.1")));
+        assert_eq!((updates[1].class(), updates[1].record_type()), (QueryClass::ANY, QueryType::ANY));
+
+        assert!(message.to_string().contains(
+            ";; PREREQUISITE SECTION:\n\
+             www.example.com.\t0\tANY\tA\n\
+             new.example.com.\t0\tNONE\tANY\n"
+        ));
+
+        let mut encoder = Serialize::new();
+        message.to_bytes(&mut encoder);
+        assert_eq!(encoder.bin_data(), wire_data.to_vec());
+    }
 }
@@ -1,6 +1,10 @@
 pub mod message;
 pub mod seder;
-mod fqdn;
-mod question;
-mod record;
-mod headers;
+pub mod fqdn;
+pub mod question;
+pub mod record;
+pub mod update_record;
+pub mod tsig;
+pub mod sig0;
+pub mod headers;
+pub mod framing;
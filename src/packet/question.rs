@@ -2,10 +2,14 @@ use crate::packet::fqdn::Fqdn;
 use crate::packet::seder::deserializer::Deserialize;
 use crate::packet::seder::serializer::Serialize;
 use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::query_class::QueryClass;
 use crate::records::record_class::RecordClass;
-use crate::records::record_type::RecordType;
+use crate::records::query_type::QueryType;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
+const UNICAST_RESPONSE_MASK: u16 = 1 << 15;
+
 #[derive(Error, Debug)]
 pub enum QuestionError {
     #[error("Could not read name from the packet")]
@@ -23,28 +27,29 @@ pub enum QuestionError {
 type QuestionResult<T> = Result<T, QuestionError>;
 
 #[derive(Debug, Clone)]
-struct FqdnUnset;
+pub struct FqdnUnset;
 #[derive(Debug, Clone)]
-struct FqdnSet(Fqdn);
+pub struct FqdnSet(Fqdn);
 
-trait FqdnState {}
+pub trait FqdnState {}
 impl FqdnState for FqdnUnset {}
 impl FqdnState for FqdnSet {}
 
 #[derive(Debug, Clone)]
-struct QuestionTypeUnset;
+pub struct QuestionTypeUnset;
 #[derive(Debug, Clone)]
-struct QuestionTypeSet(RecordType);
+pub struct QuestionTypeSet(QueryType);
 
-trait QuestionTypeState {}
+pub trait QuestionTypeState {}
 impl QuestionTypeState for QuestionTypeUnset {}
 impl QuestionTypeState for QuestionTypeSet {}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Question {
     qname: Fqdn,
-    qtype: RecordType,
-    qclass: RecordClass,
+    qtype: QueryType,
+    qclass: QueryClass,
+    unicast_response: bool,
 }
 
 pub struct QuestionBuilder<QN, QT>
@@ -54,7 +59,8 @@ where
 {
     qname: QN,
     qtype: QT,
-    qclass: RecordClass,
+    qclass: QueryClass,
+    unicast_response: bool,
 }
 
 impl Default for QuestionBuilder<FqdnUnset, QuestionTypeUnset> {
@@ -62,7 +68,8 @@ impl Default for QuestionBuilder<FqdnUnset, QuestionTypeUnset> {
         QuestionBuilder {
             qname: FqdnUnset,
             qtype: QuestionTypeUnset,
-            qclass: RecordClass::IN,
+            qclass: QueryClass::Class(RecordClass::IN),
+            unicast_response: false,
         }
     }
 }
@@ -76,17 +83,24 @@ impl TryFromBytes for Question {
         let qtype = decoder
             .read_u16()
             .map_err(|_| QuestionError::TypeReadingError)?;
-        let qtype = RecordType::try_from(qtype).map_err(|_| QuestionError::UnknownRecord)?;
+        let qtype = QueryType::try_from(qtype).map_err(|_| QuestionError::UnknownRecord)?;
 
-        let qclass = decoder
+        let mut qclass = decoder
             .read_u16()
             .map_err(|_| QuestionError::ClassReadingError)?;
-        let qclass = RecordClass::try_from(qclass).map_err(|_| QuestionError::UnknownClass)?;
+
+        let unicast_response = decoder.is_mdns() && qclass & UNICAST_RESPONSE_MASK != 0;
+        if decoder.is_mdns() {
+            qclass &= !UNICAST_RESPONSE_MASK;
+        }
+
+        let qclass = QueryClass::try_from(qclass).map_err(|_| QuestionError::UnknownClass)?;
 
         let question = QuestionBuilder::new()
             .question_name(qname)
             .question_type(qtype)
             .question_class(qclass)
+            .unicast_response(unicast_response)
             .build();
 
         Ok(question)
@@ -97,7 +111,37 @@ impl ToBytes for Question {
     fn to_bytes(&self, encoder: &mut Serialize) {
         self.qname.to_bytes(encoder);
         encoder.write_u16(self.qtype.into());
-        encoder.write_u16(self.qclass.into());
+
+        let qclass: u16 = self.qclass.into();
+        match self.unicast_response {
+            true => encoder.write_u16(qclass | UNICAST_RESPONSE_MASK),
+            false => encoder.write_u16(qclass),
+        }
+    }
+}
+
+/// Laid out like a record without a TTL, as in dig's question section.
+impl Display for Question {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t\t{}\t{}", self.qname, self.qclass, self.qtype)
+    }
+}
+
+impl Question {
+    pub fn qname(&self) -> &Fqdn {
+        &self.qname
+    }
+
+    pub fn qtype(&self) -> QueryType {
+        self.qtype
+    }
+
+    pub fn qclass(&self) -> QueryClass {
+        self.qclass
+    }
+
+    pub fn unicast_response(&self) -> bool {
+        self.unicast_response
     }
 }
 
@@ -111,16 +155,18 @@ impl QuestionBuilder<FqdnUnset, QuestionTypeUnset> {
             qname: FqdnSet(qname),
             qtype: self.qtype,
             qclass: self.qclass,
+            unicast_response: self.unicast_response,
         }
     }
 }
 
 impl QuestionBuilder<FqdnSet, QuestionTypeUnset> {
-    pub fn question_type(self, qtype: RecordType) -> QuestionBuilder<FqdnSet, QuestionTypeSet> {
+    pub fn question_type(self, qtype: QueryType) -> QuestionBuilder<FqdnSet, QuestionTypeSet> {
         QuestionBuilder {
             qname: self.qname,
             qtype: QuestionTypeSet(qtype),
             qclass: self.qclass,
+            unicast_response: self.unicast_response,
         }
     }
 }
@@ -131,6 +177,7 @@ impl QuestionBuilder<FqdnSet, QuestionTypeSet> {
             qname: self.qname.0,
             qtype: self.qtype.0,
             qclass: self.qclass,
+            unicast_response: self.unicast_response,
         }
     }
 }
@@ -140,11 +187,21 @@ where
     QN: FqdnState,
     QT: QuestionTypeState,
 {
-    pub fn question_class(self, qclass: RecordClass) -> QuestionBuilder<QN, QT> {
+    pub fn question_class(self, qclass: QueryClass) -> QuestionBuilder<QN, QT> {
         QuestionBuilder {
             qname: self.qname,
             qtype: self.qtype,
             qclass,
+            unicast_response: self.unicast_response,
+        }
+    }
+
+    pub fn unicast_response(self, unicast_response: bool) -> QuestionBuilder<QN, QT> {
+        QuestionBuilder {
+            qname: self.qname,
+            qtype: self.qtype,
+            qclass: self.qclass,
+            unicast_response,
         }
     }
 }
@@ -152,9 +209,11 @@ where
 #[cfg(test)]
 pub mod question_unittest {
     use crate::packet::fqdn::FqdnBuilder;
-    use crate::packet::question::RecordClass::IN;
+    use crate::packet::question::RecordClass::{self, CH, IN};
     use crate::packet::question::{Question, QuestionBuilder};
     use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::query_class::QueryClass;
+    use crate::records::query_type::QueryType;
     use crate::records::record_type::RecordType;
 
     pub fn generate_question(q_name: &str, q_type: RecordType) -> Question {
@@ -164,8 +223,21 @@ pub mod question_unittest {
 
         QuestionBuilder::new()
             .question_name(fqdn)
-            .question_type(q_type)
-            .question_class(IN)
+            .question_type(QueryType::Type(q_type))
+            .question_class(QueryClass::Class(IN))
+            .build()
+    }
+
+    fn generate_question_with_class(q_name: &str, q_class: QueryClass, unicast_response: bool) -> Question {
+        let fqdn = FqdnBuilder::new()
+            .generate_from_string(String::from(q_name))
+            .build();
+
+        QuestionBuilder::new()
+            .question_name(fqdn)
+            .question_type(QueryType::Type(RecordType::A))
+            .question_class(q_class)
+            .unicast_response(unicast_response)
             .build()
     }
 
@@ -198,4 +270,98 @@ pub mod question_unittest {
 
         assert_eq!(encoder.bin_data(), expected_serialization);
     }
+
+    #[test]
+    fn read_question_any_class() {
+        let packet_bytes: [u8; 13] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0xff,
+        ];
+
+        let expected_question = generate_question_with_class("foo.com", QueryClass::ANY, false);
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+        let actual_question = Question::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_question, expected_question);
+    }
+
+    #[test]
+    fn read_question_chaos_class() {
+        let packet_bytes: [u8; 18] = [
+            0x07, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x04, 0x62, 0x69, 0x6e, 0x64, 0x00,
+            0x00, 0x01, 0x00, 0x03,
+        ];
+
+        let expected_question =
+            generate_question_with_class("version.bind", QueryClass::Class(CH), false);
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+        let actual_question = Question::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_question, expected_question);
+    }
+
+    #[test]
+    fn read_mdns_unicast_response_question() {
+        let packet_bytes: [u8; 15] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x01, 0x80,
+            0x01,
+        ];
+
+        let expected_question =
+            generate_question_with_class("foo.local", QueryClass::Class(IN), true);
+
+        let mut decoder = Deserialize::new(&packet_bytes).mdns(true);
+        let actual_question = Question::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_question, expected_question);
+
+        let mut encoder = Serialize::new();
+        actual_question.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), packet_bytes);
+    }
+
+    #[test]
+    fn read_top_class_bit_outside_mdns() {
+        let packet_bytes: [u8; 15] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x01, 0x80,
+            0x01,
+        ];
+
+        let expected_question =
+            generate_question_with_class("foo.local", QueryClass::Class(RecordClass::try_from(0x8001).unwrap()), false);
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+        let actual_question = Question::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_question, expected_question);
+    }
+
+    #[test]
+    fn read_axfr_question() {
+        let packet_bytes: [u8; 17] = [
+            0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00,
+            0xfc, 0x00, 0x01,
+        ];
+
+        let fqdn = FqdnBuilder::new()
+            .generate_from_string(String::from("example.com"))
+            .build();
+
+        let expected_question = QuestionBuilder::new()
+            .question_name(fqdn)
+            .question_type(QueryType::AXFR)
+            .build();
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+        let actual_question = Question::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_question, expected_question);
+
+        let mut encoder = Serialize::new();
+        actual_question.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), packet_bytes);
+    }
 }
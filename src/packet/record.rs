@@ -1,10 +1,13 @@
 use crate::packet::seder::serializer::Serialize;
 use crate::packet::fqdn::Fqdn;
 use crate::records::{record_class::RecordClass, record_data::RecordData, record_type::RecordType};
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 use crate::packet::seder::deserializer::Deserialize;
 use crate::packet::seder::{TryFromBytes, ToBytes};
 
+const CACHE_FLUSH_MASK: u16 = 1 << 15;
+
 #[derive(Error, Debug)]
 pub enum RecordError {
     #[error("Invalid record name")]
@@ -35,29 +38,30 @@ pub struct RecordDataUnset;
 
 pub struct RecordDataSet(RecordData);
 
-trait RecordDataState {}
+pub trait RecordDataState {}
 impl RecordDataState for RecordDataUnset {}
 impl RecordDataState for RecordDataSet {}
 
 pub struct RecordOwnerUnset;
 pub struct RecordOwnerSet(Fqdn);
 
-trait RecordOwnerState {}
+pub trait RecordOwnerState {}
 impl RecordOwnerState for RecordOwnerUnset {}
 impl RecordOwnerState for RecordOwnerSet {}
 
 pub struct RecordTypeUnset;
 pub struct RecordTypeSet(RecordType);
 
-trait RecordTypeState {}
+pub trait RecordTypeState {}
 impl RecordTypeState for RecordTypeUnset {}
 impl RecordTypeState for RecordTypeSet {}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Record {
     owner_name: Fqdn,
     record_type: RecordType,
     class: RecordClass,
+    cache_flush: bool,
     ttl: u32,
     data: RecordData,
 }
@@ -71,6 +75,7 @@ where
     owner_name: O,
     record_type: T,
     class: RecordClass,
+    cache_flush: bool,
     ttl: u32,
     data: D,
 }
@@ -81,6 +86,7 @@ impl Default for RecordBuilderUnset {
             owner_name: RecordOwnerUnset,
             record_type: RecordTypeUnset,
             class: RecordClass::IN,
+            cache_flush: false,
             ttl: 0,
             data: RecordDataUnset,
         }
@@ -96,25 +102,30 @@ impl TryFromBytes for Record {
         let record_type = decoder.read_u16().map_err(|_| RecordError::InvalidType)?;
         let record_type = RecordType::try_from(record_type).map_err(|_| RecordError::UnknownRecord)?;
 
-        let class = decoder.read_u16().map_err(|_| RecordError::InvalidClass)?;
+        let mut class = decoder.read_u16().map_err(|_| RecordError::InvalidClass)?;
+
+        let cache_flush = decoder.is_mdns() && class & CACHE_FLUSH_MASK != 0;
+        if decoder.is_mdns() {
+            class &= !CACHE_FLUSH_MASK;
+        }
+
         let class = RecordClass::try_from(class).map_err(|_| RecordError::UnknownClass)?;
 
         let ttl = decoder.read_u32().map_err(|_| RecordError::InvalidTtl)?;
 
-        let _data_length = decoder
+        let data_length = decoder
             .read_u16()
             .map_err(|_| RecordError::InvalidDataLength)?;
 
-        // some checks on data length based on record type.
-
-        let data =
-            RecordData::from_bytes(decoder, &record_type).map_err(|_| RecordError::InvalidData)?;
+        let data = RecordData::from_bytes(decoder, &record_type, data_length)
+            .map_err(|_| RecordError::InvalidData)?;
 
         let record = RecordBuilder::new()
             .owner_name(owner_name)
             .record_type(record_type)
             .data(data)
             .class(class)
+            .cache_flush(cache_flush)
             .ttl(ttl)
             .build();
 
@@ -126,13 +137,60 @@ impl ToBytes for Record {
     fn to_bytes(&self, encoder: &mut Serialize) {
         self.owner_name.to_bytes(encoder);
         encoder.write_u16(self.record_type.into());
-        encoder.write_u16(self.class.into());
+
+        let class: u16 = self.class.into();
+        match self.cache_flush {
+            true => encoder.write_u16(class | CACHE_FLUSH_MASK),
+            false => encoder.write_u16(class),
+        }
+
         encoder.write_u32(self.ttl);
 
         self.data.to_bytes(encoder);
     }
 }
 
+/// One `name TTL CLASS TYPE rdata` line, tab separated as dig prints it.
+impl Display for Record {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.owner_name, self.ttl, self.class, self.record_type, self.data
+        )
+    }
+}
+
+impl Record {
+    pub fn owner_name(&self) -> &Fqdn {
+        &self.owner_name
+    }
+
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    pub fn class(&self) -> RecordClass {
+        self.class
+    }
+
+    pub fn cache_flush(&self) -> bool {
+        self.cache_flush
+    }
+
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    pub fn set_ttl(&mut self, ttl: u32) {
+        self.ttl = ttl
+    }
+
+    pub fn data(&self) -> &RecordData {
+        &self.data
+    }
+}
+
 impl RecordBuilderUnset {
     pub fn new() -> Self {
         RecordBuilder::default()
@@ -143,6 +201,7 @@ impl RecordBuilderUnset {
             owner_name: RecordOwnerSet(name),
             record_type: self.record_type,
             class: self.class,
+            cache_flush: self.cache_flush,
             ttl: self.ttl,
             data: self.data,
         }
@@ -155,6 +214,7 @@ impl RecordBuilderOwnerSet {
             owner_name: self.owner_name,
             record_type: RecordTypeSet(r_type),
             class: self.class,
+            cache_flush: self.cache_flush,
             ttl: self.ttl,
             data: self.data,
         }
@@ -167,6 +227,7 @@ impl RecordBuilderTypeSet {
             owner_name: self.owner_name,
             record_type: self.record_type,
             class: self.class,
+            cache_flush: self.cache_flush,
             ttl: self.ttl,
             data: RecordDataSet(rdata),
         }
@@ -179,6 +240,7 @@ impl RecordBuilderSet {
             owner_name: self.owner_name.0,
             record_type: self.record_type.0,
             class: self.class,
+            cache_flush: self.cache_flush,
             ttl: self.ttl,
             data: self.data.0,
         }
@@ -196,6 +258,18 @@ where
             owner_name: self.owner_name,
             record_type: self.record_type,
             class,
+            cache_flush: self.cache_flush,
+            ttl: self.ttl,
+            data: self.data,
+        }
+    }
+
+    pub fn cache_flush(self, cache_flush: bool) -> Self {
+        Self {
+            owner_name: self.owner_name,
+            record_type: self.record_type,
+            class: self.class,
+            cache_flush,
             ttl: self.ttl,
             data: self.data,
         }
@@ -206,6 +280,7 @@ where
             owner_name: self.owner_name,
             record_type: self.record_type,
             class: self.class,
+            cache_flush: self.cache_flush,
             ttl,
             data: self.data,
         }
@@ -216,9 +291,11 @@ where
 pub mod record_unittest {
     use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, TryFromBytes, ToBytes};
     use crate::packet::fqdn::FqdnBuilder;
-    use crate::packet::record::{Record, RecordBuilder};
+    use crate::packet::record::{Record, RecordBuilder, RecordError};
     use crate::records::rdata::a::A;
     use crate::records::rdata::aaaa::AAAA;
+    use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+    use crate::records::rdata::ds::DS;
     use crate::records::record_class::RecordClass;
     use crate::records::record_data::RecordData;
     use crate::records::record_type::RecordType;
@@ -349,4 +426,117 @@ pub mod record_unittest {
 
         assert_eq!(encoder.bin_data(), expected_serialization);
     }
+
+    #[test]
+    fn read_mdns_cache_flush_record() {
+        let packet_bytes: [u8; 25] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x01, 0x80,
+            0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8, 0x01, 0x0a,
+        ];
+
+        let expected_name = FqdnBuilder::new()
+            .generate_from_string(String::from("foo.local"))
+            .build();
+
+        let expected_record = RecordBuilder::new()
+            .owner_name(expected_name)
+            .record_type(RecordType::A)
+            .class(RecordClass::IN)
+            .cache_flush(true)
+            .ttl(120)
+            .data(RecordData::A(A::new(Ipv4Addr::new(192, 168, 1, 10))))
+            .build();
+
+        let mut decoder = Deserialize::new(&packet_bytes).mdns(true);
+        let actual_record = Record::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_record, expected_record);
+
+        let mut encoder = Serialize::new();
+        actual_record.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), packet_bytes);
+    }
+
+    #[test]
+    fn error_record_with_query_only_class() {
+        let packet_bytes: [u8; 23] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0xff, 0x00,
+            0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8, 0x01, 0x0a,
+        ];
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+
+        assert!(matches!(
+            Record::try_from_bytes(&mut decoder),
+            Err(RecordError::UnknownClass)
+        ));
+    }
+
+    #[test]
+    fn error_record_with_query_only_type() {
+        let packet_bytes: [u8; 23] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0xfc, 0x00, 0x01, 0x00,
+            0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8, 0x01, 0x0a,
+        ];
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+
+        assert!(matches!(
+            Record::try_from_bytes(&mut decoder),
+            Err(RecordError::UnknownRecord)
+        ));
+    }
+
+    #[test]
+    fn read_ds_record_successfully() {
+        let packet_bytes: [u8; 53] = [
+            0x05, 0x64, 0x73, 0x6b, 0x65, 0x79, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65,
+            0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x2b, 0x00, 0x01, 0x00, 0x01, 0x51, 0x80, 0x00,
+            0x18, 0xec, 0x45, 0x05, 0x01, 0x2b, 0xb1, 0x83, 0xaf, 0x5f, 0x22, 0x58, 0x81, 0x79,
+            0xa5, 0x3b, 0x0a, 0x98, 0x63, 0x1f, 0xad, 0x1a, 0x29, 0x21, 0x18,
+        ];
+
+        let expected_name = FqdnBuilder::new()
+            .generate_from_string(String::from("dskey.example.com."))
+            .build();
+
+        let digest = packet_bytes[33..].to_vec();
+        let expected_record = RecordBuilder::new()
+            .owner_name(expected_name)
+            .record_type(RecordType::DS)
+            .ttl(86400)
+            .data(RecordData::DS(DS::new(60485, DnssecAlgorithm::RSASHA1, DigestType::SHA1, digest)))
+            .build();
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+        let actual_record = Record::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual_record, expected_record);
+
+        let mut encoder = Serialize::new();
+        actual_record.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), packet_bytes);
+    }
+
+    #[test]
+    fn error_record_data_length_mismatch() {
+        let packet_bytes: [u8; 24] = [
+            0x03, 0x66, 0x6f, 0x6f, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x00, 0x00, 0x78, 0x00, 0x05, 0xc0, 0xa8, 0x01, 0x0a, 0x00,
+        ];
+
+        let mut decoder = Deserialize::new(&packet_bytes);
+
+        assert!(matches!(
+            Record::try_from_bytes(&mut decoder),
+            Err(RecordError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn display_presentation_line() {
+        assert_eq!(get_sample_a_record().to_string(), "www.google.com.\t104\tIN\tA\t172.217.14.196");
+    }
 }
@@ -13,6 +13,7 @@ pub type DeserializeResult<T> = Result<T, DeserializeError>;
 pub struct Deserialize<'a> {
     bin_data: &'a [u8],
     cursor: u16,
+    mdns: bool,
 }
 
 impl<'a> Deserialize<'a> {
@@ -20,17 +21,27 @@ impl<'a> Deserialize<'a> {
         Deserialize {
             bin_data,
             cursor: 0,
+            mdns: false,
         }
     }
 
+    /// Treat the top bit of question and record classes as the mDNS
+    /// unicast-response and cache-flush bits (RFC 6762) for this parse.
+    pub fn mdns(mut self, enabled: bool) -> Self {
+        self.mdns = enabled;
+        self
+    }
+
+    pub fn is_mdns(&self) -> bool {
+        self.mdns
+    }
+
     fn buf_len(&self) -> usize {
         self.bin_data.len()
     }
 
     pub fn read_n_bytes(&mut self, n: u16) -> DeserializeResult<&[u8]> {
-        let buf_len = self.buf_len() as u16;
-
-        if self.cursor >= buf_len || self.cursor + n > buf_len {
+        if self.cursor as usize + n as usize > self.buf_len() {
             return Err(DeserializeError::TooMuchDataRequested(n));
         }
 
@@ -79,10 +90,40 @@ impl<'a> Deserialize<'a> {
         Deserialize {
             bin_data: self.bin_data,
             cursor,
+            mdns: self.mdns,
         }
     }
 
     pub fn cursor(&self) -> u16 {
         self.cursor
     }
+
+    /// Raw bytes between two positions already read.
+    pub fn bytes_between(&self, start: u16, end: u16) -> &'a [u8] {
+        &self.bin_data[start as usize..end as usize]
+    }
+
+    pub fn remaining(&self) -> u16 {
+        (self.buf_len() as u16).saturating_sub(self.cursor)
+    }
+
+    /// Decoder over the next `n` bytes only, sharing the data before the
+    /// cursor so name compression pointers still resolve.
+    pub fn limit(&self, n: u16) -> DeserializeResult<Self> {
+        if n > self.remaining() {
+            return Err(DeserializeError::TooMuchDataRequested(n));
+        }
+
+        Ok(Deserialize {
+            bin_data: &self.bin_data[..(self.cursor + n) as usize],
+            cursor: self.cursor,
+            mdns: self.mdns,
+        })
+    }
+
+    pub fn skip(&mut self, n: u16) -> DeserializeResult<()> {
+        self.read_n_bytes(n)?;
+
+        Ok(())
+    }
 }
@@ -4,6 +4,7 @@ pub struct Serialize {
     bin_data: Vec<u8>,
     cursor: u16,
     name_compression: HashMap<String, u16>,
+    canonical: bool,
 }
 
 impl Default for Serialize {
@@ -18,9 +19,23 @@ impl Serialize {
             bin_data: Vec::new(),
             cursor: 0,
             name_compression: HashMap::new(),
+            canonical: false,
         }
     }
 
+    /// Encoder for the DNSSEC canonical wire form (RFC 4034 section 6.2),
+    /// in which names are never compressed.
+    pub fn canonical() -> Self {
+        Serialize {
+            canonical: true,
+            ..Serialize::new()
+        }
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
     pub fn write_u8(&mut self, data: u8) {
         self.cursor += 1;
         self.bin_data.push(data);
@@ -59,6 +74,15 @@ impl Serialize {
         None
     }
 
+    pub fn cursor(&self) -> u16 {
+        self.cursor
+    }
+
+    pub fn overwrite_u16(&mut self, position: u16, data: u16) {
+        let position = position as usize;
+        self.bin_data[position..position + 2].copy_from_slice(&data.to_be_bytes());
+    }
+
     pub fn bin_data(&self) -> Vec<u8> {
         self.bin_data.clone()
     }
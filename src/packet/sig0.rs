@@ -0,0 +1,411 @@
+use crate::clock::Clock;
+use crate::dnssec::crypto::verify_signature;
+use crate::dnssec::keys::{KeyError, SigningKey};
+use crate::dnssec::verify::check_validity_period;
+use crate::dnssec::DnssecError;
+use crate::packet::fqdn::Fqdn;
+use crate::packet::message::Message;
+use crate::packet::record::Record;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::DnssecAlgorithm;
+use crate::records::rdata::rrsig::format_timestamp;
+use crate::records::record_data::RecordData;
+use data_encoding::BASE64;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+pub const SIG_TYPE: u16 = 24;
+const ANY_CLASS: u16 = 255;
+
+// Short validity limits replay of a captured message (RFC 2931 section 3.1)
+const VALIDITY: u32 = 300;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum Sig0Error {
+    #[error("Message carries no SIG(0) record")]
+    Unsigned,
+    #[error("SIG(0) record is malformed")]
+    Malformed,
+    #[error("Record is not a KEY")]
+    NotAKey,
+    #[error(transparent)]
+    Dnssec(#[from] DnssecError),
+    #[error(transparent)]
+    Key(#[from] KeyError),
+}
+
+pub type Sig0Result<T> = Result<T, Sig0Error>;
+
+/// The SIG(0) pseudo-record closing a signed message (RFC 2931 section 3).
+/// The owner is the root, the class ANY and the type covered, labels and
+/// original TTL are all zero, so only the remaining SIG fields are kept.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Sig0Record {
+    algorithm: DnssecAlgorithm,
+    signature_expiration: u32,
+    signature_inception: u32,
+    key_tag: u16,
+    signer_name: Fqdn,
+    signature: Vec<u8>,
+}
+
+impl TryFromBytes for Sig0Record {
+    type Error = Sig0Error;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> Sig0Result<Self> {
+        let owner_name = Fqdn::try_from_bytes(decoder).map_err(|_| Sig0Error::Malformed)?;
+        let record_type = decoder.read_u16().map_err(|_| Sig0Error::Malformed)?;
+        let class = decoder.read_u16().map_err(|_| Sig0Error::Malformed)?;
+        let ttl = decoder.read_u32().map_err(|_| Sig0Error::Malformed)?;
+        if !owner_name.is_root() || record_type != SIG_TYPE || class != ANY_CLASS || ttl != 0 {
+            return Err(Sig0Error::Malformed);
+        }
+
+        let data_length = decoder.read_u16().map_err(|_| Sig0Error::Malformed)?;
+        let mut rdata = decoder.limit(data_length).map_err(|_| Sig0Error::Malformed)?;
+
+        let type_covered = rdata.read_u16().map_err(|_| Sig0Error::Malformed)?;
+        let algorithm = rdata.read_u8().map_err(|_| Sig0Error::Malformed)?;
+        let labels = rdata.read_u8().map_err(|_| Sig0Error::Malformed)?;
+        let original_ttl = rdata.read_u32().map_err(|_| Sig0Error::Malformed)?;
+        if type_covered != 0 || labels != 0 || original_ttl != 0 {
+            return Err(Sig0Error::Malformed);
+        }
+
+        let signature_expiration = rdata.read_u32().map_err(|_| Sig0Error::Malformed)?;
+        let signature_inception = rdata.read_u32().map_err(|_| Sig0Error::Malformed)?;
+        let key_tag = rdata.read_u16().map_err(|_| Sig0Error::Malformed)?;
+        let signer_name = Fqdn::try_from_bytes(&mut rdata).map_err(|_| Sig0Error::Malformed)?;
+        let signature = rdata
+            .read_n_bytes(rdata.remaining())
+            .map_err(|_| Sig0Error::Malformed)?
+            .to_vec();
+
+        decoder.skip(data_length).map_err(|_| Sig0Error::Malformed)?;
+
+        Ok(Sig0Record {
+            algorithm: DnssecAlgorithm::from(algorithm),
+            signature_expiration,
+            signature_inception,
+            key_tag,
+            signer_name,
+            signature,
+        })
+    }
+}
+
+impl ToBytes for Sig0Record {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        Fqdn::root().to_bytes_uncompressed(encoder);
+        encoder.write_u16(SIG_TYPE);
+        encoder.write_u16(ANY_CLASS);
+        encoder.write_u32(0);
+
+        let length_position = encoder.cursor();
+        encoder.write_u16(0);
+        self.to_bytes_without_signature(encoder);
+        encoder.write_n_bytes(self.signature.clone());
+        let data_length = encoder.cursor() - length_position - 2;
+        encoder.overwrite_u16(length_position, data_length);
+    }
+}
+
+// SIG RDATA is laid out as RRSIG RDATA, with zero for the type covered
+impl Display for Sig0Record {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            ".\t0\tANY\tSIG\tTYPE0 {} 0 0 {} {} {} {} {}",
+            self.algorithm,
+            format_timestamp(self.signature_expiration),
+            format_timestamp(self.signature_inception),
+            self.key_tag,
+            self.signer_name,
+            BASE64.encode(&self.signature)
+        )
+    }
+}
+
+impl Sig0Record {
+    pub fn new(
+        algorithm: DnssecAlgorithm,
+        signature_expiration: u32,
+        signature_inception: u32,
+        key_tag: u16,
+        signer_name: Fqdn,
+        signature: Vec<u8>,
+    ) -> Self {
+        Self {
+            algorithm,
+            signature_expiration,
+            signature_inception,
+            key_tag,
+            signer_name,
+            signature,
+        }
+    }
+
+    pub fn algorithm(&self) -> DnssecAlgorithm {
+        self.algorithm
+    }
+
+    pub fn signature_expiration(&self) -> u32 {
+        self.signature_expiration
+    }
+
+    pub fn signature_inception(&self) -> u32 {
+        self.signature_inception
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// Owner of the KEY record holding the public key.
+    pub fn signer_name(&self) -> &Fqdn {
+        &self.signer_name
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// True when the record at the decoder cursor is a SIG, without
+    /// consuming it.
+    pub(crate) fn is_next(decoder: &Deserialize) -> bool {
+        let mut peek = decoder.cheap_clone(decoder.cursor());
+
+        Fqdn::try_from_bytes(&mut peek).is_ok() && peek.read_u16().is_ok_and(|record_type| record_type == SIG_TYPE)
+    }
+
+    fn to_bytes_without_signature(&self, encoder: &mut Serialize) {
+        encoder.write_u16(0);
+        encoder.write_u8(self.algorithm.into());
+        encoder.write_u8(0);
+        encoder.write_u32(0);
+        encoder.write_u32(self.signature_expiration);
+        encoder.write_u32(self.signature_inception);
+        encoder.write_u16(self.key_tag);
+        self.signer_name.to_bytes_uncompressed(encoder);
+    }
+
+    /// The data the signature covers: the SIG RDATA without the signature,
+    /// the request when signing a response, then the message without the
+    /// SIG(0) record (RFC 2931 section 3.1).
+    fn signed_data(&self, message: &Message, request: Option<&Message>) -> Vec<u8> {
+        let mut encoder = Serialize::canonical();
+        self.to_bytes_without_signature(&mut encoder);
+
+        let mut data = encoder.bin_data();
+        if let Some(request) = request {
+            data.extend(request.wire());
+        }
+        data.extend(message.signed_portion());
+
+        data
+    }
+}
+
+impl Message {
+    /// Appends a SIG(0) record made with `key`, published as a KEY record
+    /// at `signer_name`. Responses pass the request they answer. The
+    /// message must not be changed after signing.
+    pub fn sign_sig0(
+        &mut self,
+        key: &SigningKey,
+        signer_name: &Fqdn,
+        request: Option<&Message>,
+        clock: &dyn Clock,
+    ) -> Sig0Result<()> {
+        self.remove_signature();
+
+        let now = clock.now() as u32;
+        let mut sig0 = Sig0Record::new(
+            key.algorithm(),
+            now.wrapping_add(VALIDITY),
+            now.wrapping_sub(VALIDITY),
+            key.key_tag(),
+            signer_name.clone(),
+            Vec::new(),
+        );
+        sig0.signature = key.sign(&sig0.signed_data(self, request))?;
+
+        self.set_sig0(sig0);
+
+        Ok(())
+    }
+
+    /// Checks the SIG(0) record against a KEY record. Responses pass the
+    /// request they answer.
+    pub fn verify_sig0(&self, key: &Record, request: Option<&Message>, clock: &dyn Clock) -> Sig0Result<()> {
+        let sig0 = self.sig0().ok_or(Sig0Error::Unsigned)?;
+        let RecordData::KEY(key_data) = key.data() else {
+            return Err(Sig0Error::NotAKey);
+        };
+
+        if key.owner_name() != sig0.signer_name() {
+            return Err(DnssecError::KeyOwnerMismatch.into());
+        }
+
+        if sig0.algorithm() != key_data.algorithm() {
+            return Err(DnssecError::AlgorithmMismatch.into());
+        }
+
+        if sig0.key_tag() != key_data.key_tag() {
+            return Err(DnssecError::KeyTagMismatch.into());
+        }
+
+        check_validity_period(sig0.signature_inception(), sig0.signature_expiration(), clock)?;
+
+        verify_signature(
+            sig0.algorithm(),
+            key_data.public_key(),
+            &sig0.signed_data(self, request),
+            sig0.signature(),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod sig0_unittest {
+    use crate::clock::ManualClock;
+    use crate::dnssec::keys::keys_unittest::{ed25519_key, RSASHA256_PRIVATE_FILE, RSASHA256_PUBLIC_FILE};
+    use crate::dnssec::keys::SigningKey;
+    use crate::dnssec::DnssecError;
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::headers::header::header_unittest::get_response_header;
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::record::record_unittest::get_sample_a_record;
+    use crate::packet::record::{Record, RecordBuilder};
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::packet::sig0::Sig0Error;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use data_encoding::HEXLOWER;
+
+    const SIGNED_AT: u64 = 1_700_000_000;
+
+    // Response from the message tests signed with the Ed25519 test key
+    // published at host.example.net. at SIGNED_AT, signed independently
+    const SIGNED_RESPONSE: &str = "f2e8818000010001000000010377777706676f6f676c6503636f6d0000010001c00c00010001000000680004acd90ec400001800ff00000000006400000f00000000006553f22c6553efd485d304686f7374076578616d706c65036e657400d15214f7fbec36494b21c9ca98c81346966d9038903f3592ca74a100670b5ae48e98c492c4ae5a3ae5655041281a5045c5a6c49f15a35c5a9a670cda65233b0d";
+
+    fn signer_name() -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from("host.example.net.")).build()
+    }
+
+    fn key_record(key: &SigningKey) -> Record {
+        RecordBuilder::new()
+            .owner_name(signer_name())
+            .record_type(RecordType::KEY)
+            .data(RecordData::KEY(key.dnskey().clone()))
+            .build()
+    }
+
+    fn response(id: u16) -> Message {
+        MessageBuilder::new()
+            .header(get_response_header(id))
+            .question(generate_question("www.google.com", RecordType::A))
+            .answer(vec![get_sample_a_record()])
+            .build()
+    }
+
+    fn wire(message: &Message) -> Vec<u8> {
+        let mut encoder = Serialize::new();
+        message.to_bytes(&mut encoder);
+
+        encoder.bin_data()
+    }
+
+    fn parse(wire_data: &[u8]) -> Message {
+        Message::try_from_bytes(&mut Deserialize::new(wire_data)).unwrap()
+    }
+
+    #[test]
+    fn sign_matches_reference() {
+        let mut message = response(62184);
+        message
+            .sign_sig0(&ed25519_key(), &signer_name(), None, &ManualClock::new(SIGNED_AT))
+            .unwrap();
+
+        assert_eq!(HEXLOWER.encode(&wire(&message)), SIGNED_RESPONSE);
+        assert_eq!(message.header().additional_count(), 1);
+        assert!(message.to_string().ends_with(
+            ";; SIG0 PSEUDOSECTION:\n\
+             .\t0\tANY\tSIG\tTYPE0 15 0 0 20231114221820 20231114220820 34259 host.example.net. \
+             0VIU9/vsNklLIcnKmMgTRpZtkDiQPzWSynShAGcLWuSOmMSSxK5aOuVlUEEoGlBFxabEnxWjXFqaZwzaZSM7DQ==\n"
+        ));
+    }
+
+    #[test]
+    fn verify_parsed_message() {
+        let key = key_record(&ed25519_key());
+        let message = parse(&HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap());
+
+        assert_eq!(message.sig0().unwrap().key_tag(), 34259);
+        assert_eq!(message.verify_sig0(&key, None, &ManualClock::new(SIGNED_AT + 200)), Ok(()));
+        assert_eq!(
+            message.verify_sig0(&key, None, &ManualClock::new(SIGNED_AT + 301)),
+            Err(Sig0Error::Dnssec(DnssecError::SignatureExpired))
+        );
+    }
+
+    #[test]
+    fn response_covers_request() {
+        let clock = ManualClock::new(SIGNED_AT);
+        let rsa = SigningKey::from_bind(RSASHA256_PUBLIC_FILE, RSASHA256_PRIVATE_FILE).unwrap();
+
+        let mut request = response(9);
+        request.sign_sig0(&rsa, &signer_name(), None, &clock).unwrap();
+        let request = parse(&wire(&request));
+        assert_eq!(request.verify_sig0(&key_record(&rsa), None, &clock), Ok(()));
+
+        let mut reply = response(9);
+        reply.sign_sig0(&ed25519_key(), &signer_name(), Some(&request), &clock).unwrap();
+        let reply = parse(&wire(&reply));
+
+        let key = key_record(&ed25519_key());
+        assert_eq!(reply.verify_sig0(&key, Some(&request), &clock), Ok(()));
+        assert_eq!(
+            reply.verify_sig0(&key, None, &clock),
+            Err(Sig0Error::Dnssec(DnssecError::BadSignature))
+        );
+    }
+
+    #[test]
+    fn error_tampered_message() {
+        let mut wire_data = HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap();
+        // Change the answer address
+        wire_data[47] ^= 1;
+        let message = parse(&wire_data);
+
+        assert_eq!(
+            message.verify_sig0(&key_record(&ed25519_key()), None, &ManualClock::new(SIGNED_AT)),
+            Err(Sig0Error::Dnssec(DnssecError::BadSignature))
+        );
+    }
+
+    #[test]
+    fn error_wrong_key() {
+        let message = parse(&HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap());
+        let clock = ManualClock::new(SIGNED_AT);
+        let rsa = SigningKey::from_bind(RSASHA256_PUBLIC_FILE, RSASHA256_PRIVATE_FILE).unwrap();
+
+        assert_eq!(
+            message.verify_sig0(&key_record(&rsa), None, &clock),
+            Err(Sig0Error::Dnssec(DnssecError::AlgorithmMismatch))
+        );
+        assert_eq!(
+            message.verify_sig0(&get_sample_a_record(), None, &clock),
+            Err(Sig0Error::NotAKey)
+        );
+        assert_eq!(
+            response(1).verify_sig0(&key_record(&rsa), None, &clock),
+            Err(Sig0Error::Unsigned)
+        );
+    }
+}
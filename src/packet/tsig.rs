@@ -0,0 +1,603 @@
+use crate::clock::Clock;
+use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+use crate::packet::message::Message;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::tsig::TSIG;
+use ring::hmac;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+pub const TSIG_TYPE: u16 = 250;
+const ANY_CLASS: u16 = 255;
+const DEFAULT_FUDGE: u16 = 300;
+
+// RFC 8945 section 5.3.1: at most 99 unsigned messages between two signed ones
+const MAX_UNSIGNED_MESSAGES: usize = 99;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum TsigError {
+    #[error("Message carries no TSIG record")]
+    Unsigned,
+    #[error("TSIG record is malformed")]
+    Malformed,
+    #[error("TSIG key name or algorithm is not the expected key")]
+    BadKey,
+    #[error("TSIG MAC does not verify")]
+    BadSignature,
+    #[error("TSIG MAC is truncated below the allowed length")]
+    BadTruncation,
+    #[error("TSIG was signed at {0}, outside the allowed time window")]
+    BadTime(u64),
+    #[error("Peer answered with TSIG error {0}")]
+    PeerError(u16),
+    #[error("More than 99 unsigned messages in a row")]
+    TooManyUnsigned,
+    #[error("Message stream ended with unsigned messages")]
+    UnsignedLastMessage,
+}
+
+impl TsigError {
+    /// Extended RCODE to put in the TSIG of the error response, for errors
+    /// that have one (RFC 8945 section 5.2).
+    pub fn rcode(&self) -> Option<u16> {
+        match self {
+            TsigError::BadSignature => Some(16),
+            TsigError::BadKey => Some(17),
+            TsigError::BadTime(_) => Some(18),
+            TsigError::BadTruncation => Some(22),
+            _ => None,
+        }
+    }
+}
+
+pub type TsigResult<T> = Result<T, TsigError>;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TsigAlgorithm {
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+impl TsigAlgorithm {
+    pub fn name(&self) -> Fqdn {
+        let name = match self {
+            TsigAlgorithm::HmacSha256 => "hmac-sha256.",
+            TsigAlgorithm::HmacSha384 => "hmac-sha384.",
+            TsigAlgorithm::HmacSha512 => "hmac-sha512.",
+        };
+
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    pub fn from_name(name: &Fqdn) -> Option<Self> {
+        [TsigAlgorithm::HmacSha256, TsigAlgorithm::HmacSha384, TsigAlgorithm::HmacSha512]
+            .into_iter()
+            .find(|algorithm| &algorithm.name() == name)
+    }
+
+    fn hmac_algorithm(&self) -> hmac::Algorithm {
+        match self {
+            TsigAlgorithm::HmacSha256 => hmac::HMAC_SHA256,
+            TsigAlgorithm::HmacSha384 => hmac::HMAC_SHA384,
+            TsigAlgorithm::HmacSha512 => hmac::HMAC_SHA512,
+        }
+    }
+
+    fn output_length(&self) -> usize {
+        self.hmac_algorithm().digest_algorithm().output_len()
+    }
+}
+
+/// Shared secret identified by its key name.
+#[derive(Debug, Clone)]
+pub struct TsigKey {
+    name: Fqdn,
+    algorithm: TsigAlgorithm,
+    key: hmac::Key,
+}
+
+impl TsigKey {
+    pub fn new(name: Fqdn, algorithm: TsigAlgorithm, secret: &[u8]) -> Self {
+        Self {
+            name,
+            algorithm,
+            key: hmac::Key::new(algorithm.hmac_algorithm(), secret),
+        }
+    }
+
+    pub fn name(&self) -> &Fqdn {
+        &self.name
+    }
+
+    pub fn algorithm(&self) -> TsigAlgorithm {
+        self.algorithm
+    }
+
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        hmac::sign(&self.key, data).as_ref().to_vec()
+    }
+
+    /// Checks a possibly truncated MAC. Truncation below half the output
+    /// or below 10 octets is refused (RFC 8945 section 5.2.2.1).
+    fn verify(&self, data: &[u8], mac: &[u8]) -> TsigResult<()> {
+        let output_length = self.algorithm.output_length();
+        if mac.len() > output_length {
+            return Err(TsigError::BadSignature);
+        }
+
+        let expected = self.sign(data);
+        let difference = expected
+            .iter()
+            .zip(mac)
+            .fold(0, |difference, (expected, actual)| difference | (expected ^ actual));
+        if mac.is_empty() || difference != 0 {
+            return Err(TsigError::BadSignature);
+        }
+
+        if mac.len() < (output_length / 2).max(10) {
+            return Err(TsigError::BadTruncation);
+        }
+
+        Ok(())
+    }
+}
+
+/// The TSIG pseudo-record closing a signed message. Its owner is the key
+/// name, its class ANY and its TTL zero.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TsigRecord {
+    key_name: Fqdn,
+    data: TSIG,
+}
+
+impl TryFromBytes for TsigRecord {
+    type Error = TsigError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> TsigResult<Self> {
+        let key_name = Fqdn::try_from_bytes(decoder).map_err(|_| TsigError::Malformed)?;
+        let record_type = decoder.read_u16().map_err(|_| TsigError::Malformed)?;
+        let class = decoder.read_u16().map_err(|_| TsigError::Malformed)?;
+        let ttl = decoder.read_u32().map_err(|_| TsigError::Malformed)?;
+        if record_type != TSIG_TYPE || class != ANY_CLASS || ttl != 0 {
+            return Err(TsigError::Malformed);
+        }
+
+        let data_length = decoder.read_u16().map_err(|_| TsigError::Malformed)?;
+        let mut rdata_decoder = decoder.limit(data_length).map_err(|_| TsigError::Malformed)?;
+        let data = TSIG::try_from_bytes(&mut rdata_decoder).map_err(|_| TsigError::Malformed)?;
+        if rdata_decoder.remaining() != 0 {
+            return Err(TsigError::Malformed);
+        }
+        decoder.skip(data_length).map_err(|_| TsigError::Malformed)?;
+
+        Ok(TsigRecord { key_name, data })
+    }
+}
+
+impl ToBytes for TsigRecord {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.key_name.to_bytes_uncompressed(encoder);
+        encoder.write_u16(TSIG_TYPE);
+        encoder.write_u16(ANY_CLASS);
+        encoder.write_u32(0);
+
+        let length_position = encoder.cursor();
+        encoder.write_u16(0);
+        self.data.to_bytes(encoder);
+        let data_length = encoder.cursor() - length_position - 2;
+        encoder.overwrite_u16(length_position, data_length);
+    }
+}
+
+impl Display for TsigRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t0\tANY\tTSIG\t{}", self.key_name, self.data)
+    }
+}
+
+impl TsigRecord {
+    pub fn new(key_name: Fqdn, data: TSIG) -> Self {
+        Self { key_name, data }
+    }
+
+    pub fn key_name(&self) -> &Fqdn {
+        &self.key_name
+    }
+
+    pub fn data(&self) -> &TSIG {
+        &self.data
+    }
+
+    pub fn mac(&self) -> &[u8] {
+        self.data.mac()
+    }
+
+    /// True when the record at the decoder cursor is a TSIG, without
+    /// consuming it.
+    pub(crate) fn is_next(decoder: &Deserialize) -> bool {
+        let mut peek = decoder.cheap_clone(decoder.cursor());
+
+        Fqdn::try_from_bytes(&mut peek).is_ok() && peek.read_u16().is_ok_and(|record_type| record_type == TSIG_TYPE)
+    }
+
+    /// TSIG variables appended to the message when computing the MAC
+    /// (RFC 8945 section 4.3.3). Messages after the first in a stream only
+    /// cover the timers (section 4.3.1).
+    fn variables(&self, timers_only: bool) -> Vec<u8> {
+        let mut encoder = Serialize::canonical();
+
+        if !timers_only {
+            self.key_name.to_bytes_uncompressed(&mut encoder);
+            encoder.write_u16(ANY_CLASS);
+            encoder.write_u32(0);
+            self.data.algorithm_name().to_bytes_uncompressed(&mut encoder);
+        }
+
+        encoder.write_u16((self.data.time_signed() >> 32) as u16);
+        encoder.write_u32(self.data.time_signed() as u32);
+        encoder.write_u16(self.data.fudge());
+
+        if !timers_only {
+            encoder.write_u16(self.data.error());
+            encoder.write_u16(self.data.other_data().len() as u16);
+            encoder.write_n_bytes(self.data.other_data().to_vec());
+        }
+
+        encoder.bin_data()
+    }
+}
+
+/// A MAC as it prefixes the digest of the next message: length then MAC.
+fn mac_prefix(mac: &[u8]) -> Vec<u8> {
+    let mut prefix = (mac.len() as u16).to_be_bytes().to_vec();
+    prefix.extend_from_slice(mac);
+
+    prefix
+}
+
+impl Message {
+    /// Appends a TSIG record signed with `key`, replacing any existing one.
+    /// Responses pass the MAC of the request they answer. The message must
+    /// not be changed after signing.
+    pub fn sign_tsig(&mut self, key: &TsigKey, request_mac: Option<&[u8]>, clock: &dyn Clock) -> TsigResult<()> {
+        let prior = request_mac.map(mac_prefix).unwrap_or_default();
+
+        self.sign_tsig_after(key, &prior, false, (0, Vec::new()), clock)
+    }
+
+    /// Signs a BADTIME answer to a request whose MAC verified but whose
+    /// time did not. The other data carries the server's time, so the
+    /// client can tell how far off its clock is (RFC 8945 section 5.2.3).
+    pub(crate) fn sign_tsig_bad_time(&mut self, key: &TsigKey, request_mac: &[u8], clock: &dyn Clock) -> TsigResult<()> {
+        let error = TsigError::BadTime(0).rcode().unwrap_or_default();
+        let server_time = clock.now().to_be_bytes()[2..].to_vec();
+
+        self.sign_tsig_after(key, &mac_prefix(request_mac), false, (error, server_time), clock)
+    }
+
+    /// Checks the TSIG record with `key`. Responses pass the MAC of the
+    /// request they answer.
+    pub fn verify_tsig(&self, key: &TsigKey, request_mac: Option<&[u8]>, clock: &dyn Clock) -> TsigResult<()> {
+        let prior = request_mac.map(mac_prefix).unwrap_or_default();
+
+        self.verify_tsig_after(key, &prior, false, clock)
+    }
+
+    /// Signs with the TSIG error and other data given, which are zero and
+    /// empty but for error answers.
+    fn sign_tsig_after(
+        &mut self,
+        key: &TsigKey,
+        prior: &[u8],
+        timers_only: bool,
+        (error, other_data): (u16, Vec<u8>),
+        clock: &dyn Clock,
+    ) -> TsigResult<()> {
+        self.remove_signature();
+        let signed_bytes = self.signed_portion();
+
+        let unsigned = TsigRecord::new(
+            key.name().clone(),
+            TSIG::new(key.algorithm().name(), clock.now(), DEFAULT_FUDGE, Vec::new(), self.header().id(), error, other_data),
+        );
+
+        let mut digest = prior.to_vec();
+        digest.extend_from_slice(&signed_bytes);
+        digest.extend(unsigned.variables(timers_only));
+
+        let data = unsigned.data;
+        let tsig = TSIG::new(
+            data.algorithm_name().clone(),
+            data.time_signed(),
+            data.fudge(),
+            key.sign(&digest),
+            data.original_id(),
+            data.error(),
+            data.other_data().to_vec(),
+        );
+
+        self.set_tsig(TsigRecord::new(unsigned.key_name, tsig));
+
+        Ok(())
+    }
+
+    fn verify_tsig_after(&self, key: &TsigKey, prior: &[u8], timers_only: bool, clock: &dyn Clock) -> TsigResult<()> {
+        let tsig = self.tsig().ok_or(TsigError::Unsigned)?;
+
+        if tsig.key_name() != key.name() || TsigAlgorithm::from_name(tsig.data().algorithm_name()) != Some(key.algorithm()) {
+            return Err(TsigError::BadKey);
+        }
+
+        // BADSIG and BADKEY answers carry no MAC
+        if tsig.data().error() != 0 && tsig.mac().is_empty() {
+            return Err(TsigError::PeerError(tsig.data().error()));
+        }
+
+        let mut digest = prior.to_vec();
+        digest.extend(self.signed_portion());
+        digest.extend(tsig.variables(timers_only));
+        key.verify(&digest, tsig.mac())?;
+
+        if tsig.data().error() != 0 {
+            return Err(TsigError::PeerError(tsig.data().error()));
+        }
+
+        let time_signed = tsig.data().time_signed();
+        if clock.now().abs_diff(time_signed) > tsig.data().fudge() as u64 {
+            return Err(TsigError::BadTime(time_signed));
+        }
+
+        Ok(())
+    }
+}
+
+/// Signs or verifies the messages of a TCP zone transfer, where each MAC
+/// chains to the previous one (RFC 8945 section 5.3.1).
+pub struct TsigStream<'a> {
+    key: &'a TsigKey,
+    clock: &'a dyn Clock,
+    prior: Vec<u8>,
+    unsigned_messages: usize,
+    started: bool,
+}
+
+impl<'a> TsigStream<'a> {
+    /// Starts a stream answering a request signed with `request_mac`.
+    pub fn new(key: &'a TsigKey, request_mac: &[u8], clock: &'a dyn Clock) -> Self {
+        Self {
+            key,
+            clock,
+            prior: mac_prefix(request_mac),
+            unsigned_messages: 0,
+            started: false,
+        }
+    }
+
+    /// Signs the next message. The first and last messages must be signed.
+    pub fn sign(&mut self, message: &mut Message) -> TsigResult<()> {
+        message.sign_tsig_after(self.key, &self.prior, self.started, (0, Vec::new()), self.clock)?;
+        self.advance(message);
+
+        Ok(())
+    }
+
+    /// Sends the next message unsigned. Its bytes are covered by the MAC
+    /// of the next signed message.
+    pub fn skip(&mut self, message: &Message) -> TsigResult<()> {
+        if !self.started {
+            return Err(TsigError::Unsigned);
+        }
+
+        if self.unsigned_messages == MAX_UNSIGNED_MESSAGES {
+            return Err(TsigError::TooManyUnsigned);
+        }
+
+        self.prior.extend(message.signed_portion());
+        self.unsigned_messages += 1;
+
+        Ok(())
+    }
+
+    /// Verifies the next message. The first message must be signed, later
+    /// ones may skip the TSIG up to 99 times in a row.
+    pub fn verify(&mut self, message: &Message) -> TsigResult<()> {
+        if message.tsig().is_none() {
+            return self.skip(message);
+        }
+
+        message.verify_tsig_after(self.key, &self.prior, self.started, self.clock)?;
+        self.advance(message);
+
+        Ok(())
+    }
+
+    /// Checks that the last message of the stream was signed.
+    pub fn finish(&self) -> TsigResult<()> {
+        match self.unsigned_messages {
+            0 => Ok(()),
+            _ => Err(TsigError::UnsignedLastMessage),
+        }
+    }
+
+    fn advance(&mut self, message: &Message) {
+        self.prior = message.tsig().map(|tsig| mac_prefix(tsig.mac())).unwrap_or_default();
+        self.unsigned_messages = 0;
+        self.started = true;
+    }
+}
+
+#[cfg(test)]
+mod tsig_unittest {
+    use crate::clock::ManualClock;
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::packet::headers::header::header_unittest::get_response_header;
+    use crate::packet::message::{Message, MessageBuilder, MessageError};
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::record::record_unittest::get_sample_a_record;
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::packet::tsig::{TsigAlgorithm, TsigError, TsigKey, TsigStream};
+    use crate::records::record_type::RecordType;
+    use data_encoding::HEXLOWER;
+
+    const SIGNED_AT: u64 = 1_700_000_000;
+
+    // Response from the message tests signed with key.example. and the
+    // secret "secret" at SIGNED_AT, MAC computed independently
+    const SIGNED_RESPONSE: &str = "f2e8818000010001000000010377777706676f6f676c6503636f6d0000010001c00c00010001000000680004\
+acd90ec4036b6579076578616d706c650000fa00ff00000000003d0b686d61632d7368613235360000006553\
+f100012c002096561840673f50d098d506e98b0c00f464bf94a5402157d30efcf7eafdaa7b80f2e800000000";
+
+    fn key(secret: &[u8]) -> TsigKey {
+        let name = FqdnBuilder::new().generate_from_string(String::from("key.example.")).build();
+
+        TsigKey::new(name, TsigAlgorithm::HmacSha256, secret)
+    }
+
+    fn response(id: u16) -> Message {
+        MessageBuilder::new()
+            .header(get_response_header(id))
+            .question(generate_question("www.google.com", RecordType::A))
+            .answer(vec![get_sample_a_record()])
+            .build()
+    }
+
+    fn wire(message: &Message) -> Vec<u8> {
+        let mut encoder = Serialize::new();
+        message.to_bytes(&mut encoder);
+
+        encoder.bin_data()
+    }
+
+    fn parse(wire_data: &[u8]) -> Message {
+        Message::try_from_bytes(&mut Deserialize::new(wire_data)).unwrap()
+    }
+
+    #[test]
+    fn sign_matches_reference() {
+        let mut message = response(62184);
+        message.sign_tsig(&key(b"secret"), None, &ManualClock::new(SIGNED_AT)).unwrap();
+
+        assert_eq!(HEXLOWER.encode(&wire(&message)), SIGNED_RESPONSE);
+        assert_eq!(message.header().additional_count(), 1);
+        assert!(message.additionals().is_empty());
+    }
+
+    #[test]
+    fn verify_parsed_message() {
+        let message = parse(&HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap());
+
+        assert!(message.tsig().is_some());
+        assert_eq!(message.verify_tsig(&key(b"secret"), None, &ManualClock::new(SIGNED_AT + 10)), Ok(()));
+    }
+
+    #[test]
+    fn verify_uses_original_id() {
+        let mut wire_data = HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap();
+        wire_data[0..2].copy_from_slice(&[0x12, 0x34]);
+        let message = parse(&wire_data);
+
+        assert_eq!(message.header().id(), 0x1234);
+        assert_eq!(message.verify_tsig(&key(b"secret"), None, &ManualClock::new(SIGNED_AT)), Ok(()));
+    }
+
+    #[test]
+    fn response_covers_request_mac() {
+        let clock = ManualClock::new(SIGNED_AT);
+        let mut request = response(1);
+        request.sign_tsig(&key(b"secret"), None, &clock).unwrap();
+        let request_mac = request.tsig().unwrap().mac().to_vec();
+
+        let mut reply = response(1);
+        reply.sign_tsig(&key(b"secret"), Some(&request_mac), &clock).unwrap();
+        let reply = parse(&wire(&reply));
+
+        assert_eq!(reply.verify_tsig(&key(b"secret"), Some(&request_mac), &clock), Ok(()));
+        assert_eq!(
+            reply.verify_tsig(&key(b"secret"), None, &clock),
+            Err(TsigError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn stream_with_unsigned_messages() {
+        let clock = ManualClock::new(SIGNED_AT);
+        let key = key(b"secret");
+        let request_mac = [0x42; 32];
+
+        let mut signer = TsigStream::new(&key, &request_mac, &clock);
+        let mut first = response(7);
+        signer.sign(&mut first).unwrap();
+        let middle = response(7);
+        let mut last = response(7);
+
+        signer.skip(&middle).unwrap();
+        signer.sign(&mut last).unwrap();
+
+        let mut verifier = TsigStream::new(&key, &request_mac, &clock);
+        verifier.verify(&parse(&wire(&first))).unwrap();
+        verifier.verify(&parse(&wire(&middle))).unwrap();
+        assert_eq!(verifier.finish(), Err(TsigError::UnsignedLastMessage));
+        verifier.verify(&parse(&wire(&last))).unwrap();
+        assert_eq!(verifier.finish(), Ok(()));
+    }
+
+    #[test]
+    fn stream_rejects_reordered_messages() {
+        let clock = ManualClock::new(SIGNED_AT);
+        let key = key(b"secret");
+
+        let mut signer = TsigStream::new(&key, &[0x42; 32], &clock);
+        let mut first = response(7);
+        let mut second = response(7);
+        signer.sign(&mut first).unwrap();
+        signer.sign(&mut second).unwrap();
+
+        let mut verifier = TsigStream::new(&key, &[0x42; 32], &clock);
+        assert_eq!(verifier.verify(&parse(&wire(&second))), Err(TsigError::BadSignature));
+
+        let mut verifier = TsigStream::new(&key, &[0x42; 32], &clock);
+        assert_eq!(verifier.verify(&response(7)), Err(TsigError::Unsigned));
+    }
+
+    #[test]
+    fn error_bad_key_and_signature() {
+        let message = parse(&HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap());
+        let clock = ManualClock::new(SIGNED_AT);
+
+        assert_eq!(message.verify_tsig(&key(b"other"), None, &clock), Err(TsigError::BadSignature));
+
+        let name = FqdnBuilder::new().generate_from_string(String::from("key.example.")).build();
+        let sha512 = TsigKey::new(name, TsigAlgorithm::HmacSha512, b"secret");
+        assert_eq!(message.verify_tsig(&sha512, None, &clock), Err(TsigError::BadKey));
+
+        assert_eq!(response(1).verify_tsig(&key(b"secret"), None, &clock), Err(TsigError::Unsigned));
+    }
+
+    #[test]
+    fn error_bad_time() {
+        let message = parse(&HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap());
+
+        assert_eq!(
+            message.verify_tsig(&key(b"secret"), None, &ManualClock::new(SIGNED_AT + 301)),
+            Err(TsigError::BadTime(SIGNED_AT))
+        );
+        assert_eq!(
+            message.verify_tsig(&key(b"secret"), None, &ManualClock::new(SIGNED_AT - 300)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn error_tsig_not_last() {
+        let mut wire_data = HEXLOWER.decode(SIGNED_RESPONSE.as_bytes()).unwrap();
+        // Claim a second additional record after the TSIG
+        wire_data[11] = 2;
+        wire_data.extend_from_within(32..48);
+
+        let error = Message::try_from_bytes(&mut Deserialize::new(&wire_data)).unwrap_err();
+        assert!(matches!(error, MessageError::SignatureNotLast));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::record::{Record, RecordError};
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::query_class::QueryClass;
+use crate::records::query_type::QueryType;
+use crate::records::record_data::RecordData;
+use std::fmt::{Display, Formatter};
+
+/// An entry of the prerequisite or update section of an UPDATE message
+/// (RFC 2136 section 2.4 and 2.5). Besides plain records these name whole
+/// RRsets or names with class NONE or ANY, type ANY and no RDATA.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UpdateRecord {
+    owner_name: Fqdn,
+    record_type: QueryType,
+    class: QueryClass,
+    ttl: u32,
+    data: Option<RecordData>,
+}
+
+impl UpdateRecord {
+    /// An entry carrying RDATA, such as a record to add or, with class
+    /// NONE, a record to delete.
+    pub fn new(owner_name: Fqdn, class: QueryClass, ttl: u32, data: RecordData) -> Self {
+        Self {
+            owner_name,
+            record_type: QueryType::Type(data.record_type()),
+            class,
+            ttl,
+            data: Some(data),
+        }
+    }
+
+    /// An entry naming an RRset, or every RRset with type ANY, whose TTL
+    /// is zero.
+    pub fn without_data(owner_name: Fqdn, record_type: QueryType, class: QueryClass) -> Self {
+        Self {
+            owner_name,
+            record_type,
+            class,
+            ttl: 0,
+            data: None,
+        }
+    }
+
+    pub fn owner_name(&self) -> &Fqdn {
+        &self.owner_name
+    }
+
+    pub fn record_type(&self) -> QueryType {
+        self.record_type
+    }
+
+    pub fn class(&self) -> QueryClass {
+        self.class
+    }
+
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    pub fn data(&self) -> Option<&RecordData> {
+        self.data.as_ref()
+    }
+}
+
+impl From<Record> for UpdateRecord {
+    fn from(record: Record) -> Self {
+        UpdateRecord::new(record.owner_name().clone(), record.class().into(), record.ttl(), record.data().clone())
+    }
+}
+
+impl TryFromBytes for UpdateRecord {
+    type Error = RecordError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> Result<Self, RecordError> {
+        let owner_name = Fqdn::try_from_bytes(decoder).map_err(|_| RecordError::InvalidName)?;
+
+        let record_type = decoder.read_u16().map_err(|_| RecordError::InvalidType)?;
+        let record_type = QueryType::try_from(record_type).map_err(|_| RecordError::UnknownRecord)?;
+
+        let class = decoder.read_u16().map_err(|_| RecordError::InvalidClass)?;
+        let class = QueryClass::try_from(class).map_err(|_| RecordError::UnknownClass)?;
+
+        let ttl = decoder.read_u32().map_err(|_| RecordError::InvalidTtl)?;
+
+        let data_length = decoder
+            .read_u16()
+            .map_err(|_| RecordError::InvalidDataLength)?;
+
+        // Only entries of class NONE or ANY may leave out the RDATA
+        let data = match (class, record_type, data_length) {
+            (QueryClass::NONE | QueryClass::ANY, _, 0) => None,
+            (_, QueryType::Type(record_type), _) => Some(
+                RecordData::from_bytes(decoder, &record_type, data_length)
+                    .map_err(|_| RecordError::InvalidData)?,
+            ),
+            _ => return Err(RecordError::UnknownRecord),
+        };
+
+        Ok(Self {
+            owner_name,
+            record_type,
+            class,
+            ttl,
+            data,
+        })
+    }
+}
+
+impl ToBytes for UpdateRecord {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.owner_name.to_bytes(encoder);
+        encoder.write_u16(self.record_type.into());
+        encoder.write_u16(self.class.into());
+        encoder.write_u32(self.ttl);
+
+        match &self.data {
+            Some(data) => data.to_bytes(encoder),
+            None => encoder.write_u16(0),
+        }
+    }
+}
+
+/// Like a record, with the RDATA column left out when there is none.
+impl Display for UpdateRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}\t{}", self.owner_name, self.ttl, self.class, self.record_type)?;
+        match &self.data {
+            Some(data) => write!(f, "\t{}", data),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod update_record_unittest {
+    use crate::packet::record::RecordError;
+    use crate::packet::seder::deserializer::Deserialize;
+    use crate::packet::seder::serializer::Serialize;
+    use crate::packet::seder::{ToBytes, TryFromBytes};
+    use crate::packet::update_record::UpdateRecord;
+    use crate::records::query_class::QueryClass;
+    use crate::records::query_type::QueryType;
+    use crate::records::record_type::RecordType;
+
+    #[test]
+    fn delete_every_rrset_of_a_name() {
+        // foo.com. 0 ANY ANY, without RDATA
+        let wire = [3, b'f', b'o', b'o', 3, b'c', b'o', b'm', 0, 0, 0xff, 0, 0xff, 0, 0, 0, 0, 0, 0];
+        let entry = UpdateRecord::try_from_bytes(&mut Deserialize::new(&wire)).unwrap();
+
+        assert_eq!(entry, UpdateRecord::without_data(entry.owner_name().clone(), QueryType::ANY, QueryClass::ANY));
+        assert_eq!(entry.data(), None);
+        assert_eq!(entry.to_string(), "foo.com.\t0\tANY\tANY");
+
+        let mut encoder = Serialize::new();
+        entry.to_bytes(&mut encoder);
+        assert_eq!(encoder.bin_data(), wire.to_vec());
+    }
+
+    #[test]
+    fn delete_one_record() {
+        // foo.com. 0 NONE A 192.168.1.10
+        let wire = [
+            3, b'f', b'o', b'o', 3, b'c', b'o', b'm', 0, 0, 1, 0, 0xfe, 0, 0, 0, 0, 0, 4, 192, 168, 1, 10,
+        ];
+        let entry = UpdateRecord::try_from_bytes(&mut Deserialize::new(&wire)).unwrap();
+
+        assert_eq!((entry.class(), entry.record_type()), (QueryClass::NONE, QueryType::Type(RecordType::A)));
+        assert_eq!(entry.to_string(), "foo.com.\t0\tNONE\tA\t192.168.1.10");
+    }
+
+    #[test]
+    fn error_entry_of_zone_class_without_rdata() {
+        // foo.com. 0 IN A, which would add a record with no address
+        let wire = [3, b'f', b'o', b'o', 3, b'c', b'o', b'm', 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0];
+
+        assert!(matches!(
+            UpdateRecord::try_from_bytes(&mut Deserialize::new(&wire)),
+            Err(RecordError::InvalidData)
+        ));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod rdata;
 pub mod record_type;
 pub mod record_class;
-pub(crate) mod record_data;
\ No newline at end of file
+pub mod query_type;
+pub mod query_class;
+pub mod record_data;
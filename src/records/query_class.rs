@@ -0,0 +1,49 @@
+use crate::records::record_class::{RecordClass, RecordClassError};
+use std::fmt::{Display, Formatter};
+
+/// QCLASS values, a superset of the record classes that also allows the
+/// QCLASS-only NONE and ANY from RFC 6895.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum QueryClass {
+    Class(RecordClass),
+    NONE,
+    ANY,
+}
+
+impl TryFrom<u16> for QueryClass {
+    type Error = RecordClassError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            254 => Ok(QueryClass::NONE),
+            255 => Ok(QueryClass::ANY),
+            _ => Ok(QueryClass::Class(RecordClass::try_from(value)?)),
+        }
+    }
+}
+
+impl From<QueryClass> for u16 {
+    fn from(val: QueryClass) -> Self {
+        match val {
+            QueryClass::Class(class) => class.into(),
+            QueryClass::NONE => 254,
+            QueryClass::ANY => 255,
+        }
+    }
+}
+
+impl Display for QueryClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryClass::Class(class) => write!(f, "{}", class),
+            QueryClass::NONE => write!(f, "NONE"),
+            QueryClass::ANY => write!(f, "ANY"),
+        }
+    }
+}
+
+impl From<RecordClass> for QueryClass {
+    fn from(val: RecordClass) -> Self {
+        QueryClass::Class(val)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::records::record_type::{RecordType, RecordTypeError};
+use std::fmt::{Display, Formatter};
+
+/// QTYPE values: every record type plus the meta types from RFC 1035 and
+/// RFC 1995 that may only appear in the question section.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum QueryType {
+    Type(RecordType),
+    IXFR,
+    AXFR,
+    MAILB,
+    MAILA,
+    ANY,
+}
+
+impl TryFrom<u16> for QueryType {
+    type Error = RecordTypeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            251 => Ok(QueryType::IXFR),
+            252 => Ok(QueryType::AXFR),
+            253 => Ok(QueryType::MAILB),
+            254 => Ok(QueryType::MAILA),
+            255 => Ok(QueryType::ANY),
+            _ => Ok(QueryType::Type(RecordType::try_from(value)?)),
+        }
+    }
+}
+
+impl From<QueryType> for u16 {
+    fn from(val: QueryType) -> Self {
+        match val {
+            QueryType::Type(record_type) => record_type.into(),
+            QueryType::IXFR => 251,
+            QueryType::AXFR => 252,
+            QueryType::MAILB => 253,
+            QueryType::MAILA => 254,
+            QueryType::ANY => 255,
+        }
+    }
+}
+
+impl Display for QueryType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryType::Type(record_type) => write!(f, "{}", record_type),
+            QueryType::IXFR => write!(f, "IXFR"),
+            QueryType::AXFR => write!(f, "AXFR"),
+            QueryType::MAILB => write!(f, "MAILB"),
+            QueryType::MAILA => write!(f, "MAILA"),
+            QueryType::ANY => write!(f, "ANY"),
+        }
+    }
+}
+
+impl From<RecordType> for QueryType {
+    fn from(val: RecordType) -> Self {
+        QueryType::Type(val)
+    }
+}
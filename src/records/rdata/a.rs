@@ -3,10 +3,11 @@ use crate::packet::seder::serializer::Serialize;
 use crate::records::record_data::RecordDataError;
 use std::net::Ipv4Addr;
 use crate::packet::seder::{TryFromBytes, ToBytes};
+use std::fmt::{Display, Formatter};
 
 type ARecordResult = Result<A, RecordDataError>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct A {
     address: Ipv4Addr,
 }
@@ -26,14 +27,22 @@ impl TryFromBytes for A {
 
 impl ToBytes for A {
     fn to_bytes(&self, encoder: &mut Serialize) {
-        encoder.write_u16(A::RECORD_DATA_LENGTH);
         encoder.write_n_bytes(self.address.octets().to_vec());
     }
 }
 
+impl Display for A {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
 impl A {
-    const RECORD_DATA_LENGTH: u16 = 4;
     pub fn new(address: Ipv4Addr) -> Self {
         Self { address }
     }
+
+    pub fn address(&self) -> Ipv4Addr {
+        self.address
+    }
 }
\ No newline at end of file
@@ -1,12 +1,13 @@
 use crate::packet::seder::deserializer::Deserialize;
 use crate::packet::seder::serializer::Serialize;
 use crate::packet::seder::{TryFromBytes, ToBytes};
+use std::fmt::{Display, Formatter};
 use crate::records::record_data::RecordDataError;
 use std::net::Ipv6Addr;
 
 type AAAARecordResult = Result<AAAA, RecordDataError>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AAAA {
     address: Ipv6Addr,
 }
@@ -26,9 +27,13 @@ impl TryFromBytes for AAAA {
 
 impl ToBytes for AAAA {
     fn to_bytes(&self, encoder: &mut Serialize) {
-        let aaaa_record = self.address.octets();
-        encoder.write_u16(aaaa_record.len() as u16);
-        encoder.write_n_bytes(aaaa_record.to_vec());
+        encoder.write_n_bytes(self.address.octets().to_vec());
+    }
+}
+
+impl Display for AAAA {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.address)
     }
 }
 
@@ -36,4 +41,8 @@ impl AAAA {
     pub fn new(address: Ipv6Addr) -> Self {
         Self { address }
     }
+
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
 }
@@ -0,0 +1,140 @@
+use std::fmt::{Display, Formatter};
+
+/// DNSSEC algorithm numbers (RFC 8624 section 3.1).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DnssecAlgorithm {
+    RSAMD5,
+    DH,
+    DSA,
+    RSASHA1,
+    DSANSEC3SHA1,
+    RSASHA1NSEC3SHA1,
+    RSASHA256,
+    RSASHA512,
+    ECCGOST,
+    ECDSAP256SHA256,
+    ECDSAP384SHA384,
+    ED25519,
+    ED448,
+    Unknown(u8),
+}
+
+/// DS digest types (RFC 8624 section 3.3).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DigestType {
+    SHA1,
+    SHA256,
+    GOST,
+    SHA384,
+    Unknown(u8),
+}
+
+/// NSEC3 hash algorithms (RFC 5155 section 11).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Nsec3HashAlgorithm {
+    SHA1,
+    Unknown(u8),
+}
+
+impl From<u8> for DnssecAlgorithm {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => DnssecAlgorithm::RSAMD5,
+            2 => DnssecAlgorithm::DH,
+            3 => DnssecAlgorithm::DSA,
+            5 => DnssecAlgorithm::RSASHA1,
+            6 => DnssecAlgorithm::DSANSEC3SHA1,
+            7 => DnssecAlgorithm::RSASHA1NSEC3SHA1,
+            8 => DnssecAlgorithm::RSASHA256,
+            10 => DnssecAlgorithm::RSASHA512,
+            12 => DnssecAlgorithm::ECCGOST,
+            13 => DnssecAlgorithm::ECDSAP256SHA256,
+            14 => DnssecAlgorithm::ECDSAP384SHA384,
+            15 => DnssecAlgorithm::ED25519,
+            16 => DnssecAlgorithm::ED448,
+            _ => DnssecAlgorithm::Unknown(value),
+        }
+    }
+}
+
+impl From<DnssecAlgorithm> for u8 {
+    fn from(val: DnssecAlgorithm) -> Self {
+        match val {
+            DnssecAlgorithm::RSAMD5 => 1,
+            DnssecAlgorithm::DH => 2,
+            DnssecAlgorithm::DSA => 3,
+            DnssecAlgorithm::RSASHA1 => 5,
+            DnssecAlgorithm::DSANSEC3SHA1 => 6,
+            DnssecAlgorithm::RSASHA1NSEC3SHA1 => 7,
+            DnssecAlgorithm::RSASHA256 => 8,
+            DnssecAlgorithm::RSASHA512 => 10,
+            DnssecAlgorithm::ECCGOST => 12,
+            DnssecAlgorithm::ECDSAP256SHA256 => 13,
+            DnssecAlgorithm::ECDSAP384SHA384 => 14,
+            DnssecAlgorithm::ED25519 => 15,
+            DnssecAlgorithm::ED448 => 16,
+            DnssecAlgorithm::Unknown(value) => value,
+        }
+    }
+}
+
+impl From<u8> for DigestType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => DigestType::SHA1,
+            2 => DigestType::SHA256,
+            3 => DigestType::GOST,
+            4 => DigestType::SHA384,
+            _ => DigestType::Unknown(value),
+        }
+    }
+}
+
+impl From<DigestType> for u8 {
+    fn from(val: DigestType) -> Self {
+        match val {
+            DigestType::SHA1 => 1,
+            DigestType::SHA256 => 2,
+            DigestType::GOST => 3,
+            DigestType::SHA384 => 4,
+            DigestType::Unknown(value) => value,
+        }
+    }
+}
+
+impl From<u8> for Nsec3HashAlgorithm {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Nsec3HashAlgorithm::SHA1,
+            _ => Nsec3HashAlgorithm::Unknown(value),
+        }
+    }
+}
+
+impl From<Nsec3HashAlgorithm> for u8 {
+    fn from(val: Nsec3HashAlgorithm) -> Self {
+        match val {
+            Nsec3HashAlgorithm::SHA1 => 1,
+            Nsec3HashAlgorithm::Unknown(value) => value,
+        }
+    }
+}
+
+// Presentation format uses the numeric values (RFC 4034 sections 2.2 and 5.3)
+impl Display for DnssecAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl Display for DigestType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl Display for Nsec3HashAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
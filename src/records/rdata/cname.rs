@@ -0,0 +1,46 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::record_data::RecordDataError;
+use std::fmt::{Display, Formatter};
+
+type CnameRecordResult = Result<CNAME, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CNAME {
+    canonical_name: Fqdn,
+}
+
+impl TryFromBytes for CNAME {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> CnameRecordResult {
+        let canonical_name =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+
+        Ok(CNAME { canonical_name })
+    }
+}
+
+impl ToBytes for CNAME {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.canonical_name.to_bytes(encoder);
+    }
+}
+
+impl Display for CNAME {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.canonical_name)
+    }
+}
+
+impl CNAME {
+    pub fn new(canonical_name: Fqdn) -> Self {
+        Self { canonical_name }
+    }
+
+    pub fn canonical_name(&self) -> &Fqdn {
+        &self.canonical_name
+    }
+}
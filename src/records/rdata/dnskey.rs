@@ -0,0 +1,185 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::DnssecAlgorithm;
+use crate::records::record_data::RecordDataError;
+use data_encoding::BASE64;
+use std::fmt::{Display, Formatter};
+
+const ZONE_KEY_FLAG: u16 = 1 << 8;
+const REVOKE_FLAG: u16 = 1 << 7;
+const SECURE_ENTRY_POINT_FLAG: u16 = 1;
+
+type DnskeyRecordResult = Result<DNSKEY, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DNSKEY {
+    flags: u16,
+    protocol: u8,
+    algorithm: DnssecAlgorithm,
+    public_key: Vec<u8>,
+}
+
+impl TryFromBytes for DNSKEY {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> DnskeyRecordResult {
+        let flags = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadDnskey)?;
+        let protocol = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadDnskey)?;
+        let algorithm = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadDnskey)?;
+        let public_key = decoder
+            .read_n_bytes(decoder.remaining())
+            .map_err(|_| RecordDataError::UnableToReadDnskey)?
+            .to_vec();
+
+        Ok(DNSKEY {
+            flags,
+            protocol,
+            algorithm: DnssecAlgorithm::from(algorithm),
+            public_key,
+        })
+    }
+}
+
+impl ToBytes for DNSKEY {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        encoder.write_u16(self.flags);
+        encoder.write_u8(self.protocol);
+        encoder.write_u8(self.algorithm.into());
+        encoder.write_n_bytes(self.public_key.clone());
+    }
+}
+
+impl Display for DNSKEY {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.flags,
+            self.protocol,
+            self.algorithm,
+            BASE64.encode(&self.public_key)
+        )
+    }
+}
+
+impl DNSKEY {
+    pub const PROTOCOL: u8 = 3;
+
+    pub fn new(flags: u16, algorithm: DnssecAlgorithm, public_key: Vec<u8>) -> Self {
+        Self {
+            flags,
+            protocol: DNSKEY::PROTOCOL,
+            algorithm,
+            public_key,
+        }
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    pub fn algorithm(&self) -> DnssecAlgorithm {
+        self.algorithm
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & ZONE_KEY_FLAG != 0
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.flags & REVOKE_FLAG != 0
+    }
+
+    pub fn is_secure_entry_point(&self) -> bool {
+        self.flags & SECURE_ENTRY_POINT_FLAG != 0
+    }
+
+    /// Key tag as computed in RFC 4034 Appendix B.
+    pub fn key_tag(&self) -> u16 {
+        if self.algorithm == DnssecAlgorithm::RSAMD5 {
+            // Appendix B.1: the tag is the most significant 16 of the least significant 24 bits
+            let length = self.public_key.len();
+            if length < 3 {
+                return 0;
+            }
+
+            return u16::from_be_bytes([self.public_key[length - 3], self.public_key[length - 2]]);
+        }
+
+        let mut encoder = Serialize::new();
+        self.to_bytes(&mut encoder);
+
+        let mut accumulator: u32 = 0;
+        for (i, byte) in encoder.bin_data().iter().enumerate() {
+            accumulator += match i & 1 {
+                0 => (*byte as u32) << 8,
+                _ => *byte as u32,
+            };
+        }
+
+        accumulator += (accumulator >> 16) & 0xffff;
+
+        (accumulator & 0xffff) as u16
+    }
+}
+
+#[cfg(test)]
+pub mod dnskey_unittest {
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+    use crate::records::rdata::dnskey::DNSKEY;
+    use data_encoding::BASE64;
+
+    pub const RFC4034_DS_KEY: &str = "AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==";
+
+    // dskey.example.com. DNSKEY from RFC 4034 section 5.4
+    pub fn get_rfc4034_dnskey() -> DNSKEY {
+        DNSKEY::new(256, DnssecAlgorithm::RSASHA1, BASE64.decode(RFC4034_DS_KEY.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn rfc4034_key_tag() {
+        assert_eq!(get_rfc4034_dnskey().key_tag(), 60485);
+    }
+
+    #[test]
+    fn dnskey_flags() {
+        let zsk = get_rfc4034_dnskey();
+        let ksk = DNSKEY::new(257, DnssecAlgorithm::ED25519, vec![0; 32]);
+
+        assert!(zsk.is_zone_key() && !zsk.is_secure_entry_point());
+        assert!(ksk.is_zone_key() && ksk.is_secure_entry_point());
+        assert!(!ksk.is_revoked());
+    }
+
+    #[test]
+    fn dnskey_round_trip_and_presentation() {
+        let dnskey = get_rfc4034_dnskey();
+
+        let mut encoder = Serialize::new();
+        dnskey.to_bytes(&mut encoder);
+        let wire_data = encoder.bin_data();
+
+        assert_eq!(&wire_data[..4], &[0x01, 0x00, 0x03, 0x05]);
+
+        let mut decoder = Deserialize::new(&wire_data);
+        assert_eq!(DNSKEY::try_from_bytes(&mut decoder).unwrap(), dnskey);
+
+        assert_eq!(dnskey.to_string(), format!("256 3 5 {}", RFC4034_DS_KEY));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+use crate::records::record_data::RecordDataError;
+use data_encoding::HEXUPPER;
+use std::fmt::{Display, Formatter};
+
+type DsRecordResult = Result<DS, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DS {
+    key_tag: u16,
+    algorithm: DnssecAlgorithm,
+    digest_type: DigestType,
+    digest: Vec<u8>,
+}
+
+impl TryFromBytes for DS {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> DsRecordResult {
+        let key_tag = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadDs)?;
+        let algorithm = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadDs)?;
+        let digest_type = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadDs)?;
+        let digest = decoder
+            .read_n_bytes(decoder.remaining())
+            .map_err(|_| RecordDataError::UnableToReadDs)?
+            .to_vec();
+
+        Ok(DS {
+            key_tag,
+            algorithm: DnssecAlgorithm::from(algorithm),
+            digest_type: DigestType::from(digest_type),
+            digest,
+        })
+    }
+}
+
+impl ToBytes for DS {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        encoder.write_u16(self.key_tag);
+        encoder.write_u8(self.algorithm.into());
+        encoder.write_u8(self.digest_type.into());
+        encoder.write_n_bytes(self.digest.clone());
+    }
+}
+
+impl Display for DS {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag,
+            self.algorithm,
+            self.digest_type,
+            HEXUPPER.encode(&self.digest)
+        )
+    }
+}
+
+impl DS {
+    pub fn new(key_tag: u16, algorithm: DnssecAlgorithm, digest_type: DigestType, digest: Vec<u8>) -> Self {
+        Self {
+            key_tag,
+            algorithm,
+            digest_type,
+            digest,
+        }
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    pub fn algorithm(&self) -> DnssecAlgorithm {
+        self.algorithm
+    }
+
+    pub fn digest_type(&self) -> DigestType {
+        self.digest_type
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+#[cfg(test)]
+mod ds_unittest {
+    use crate::packet::seder::{deserializer::Deserialize, TryFromBytes};
+    use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm};
+    use crate::records::rdata::ds::DS;
+
+    #[test]
+    fn read_rfc4034_ds() {
+        let wire_data: [u8; 24] = [
+            0xec, 0x45, 0x05, 0x01, 0x2b, 0xb1, 0x83, 0xaf, 0x5f, 0x22, 0x58, 0x81, 0x79, 0xa5,
+            0x3b, 0x0a, 0x98, 0x63, 0x1f, 0xad, 0x1a, 0x29, 0x21, 0x18,
+        ];
+
+        let mut decoder = Deserialize::new(&wire_data);
+        let ds = DS::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(ds.key_tag(), 60485);
+        assert_eq!(ds.algorithm(), DnssecAlgorithm::RSASHA1);
+        assert_eq!(ds.digest_type(), DigestType::SHA1);
+        assert_eq!(ds.to_string(), "60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118");
+    }
+}
@@ -1,2 +1,15 @@
 pub mod a;
-pub mod aaaa;
\ No newline at end of file
+pub mod aaaa;
+pub mod algorithm;
+pub mod cname;
+pub mod dnskey;
+pub mod ds;
+pub mod ns;
+pub mod nsec;
+pub mod nsec3;
+pub mod nsec3param;
+pub mod rrsig;
+pub mod soa;
+pub mod tsig;
+pub mod type_bitmap;
+pub mod unknown;
@@ -0,0 +1,46 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::record_data::RecordDataError;
+use std::fmt::{Display, Formatter};
+
+type NsRecordResult = Result<NS, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NS {
+    name_server: Fqdn,
+}
+
+impl TryFromBytes for NS {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> NsRecordResult {
+        let name_server =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+
+        Ok(NS { name_server })
+    }
+}
+
+impl ToBytes for NS {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.name_server.to_bytes(encoder);
+    }
+}
+
+impl Display for NS {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name_server)
+    }
+}
+
+impl NS {
+    pub fn new(name_server: Fqdn) -> Self {
+        Self { name_server }
+    }
+
+    pub fn name_server(&self) -> &Fqdn {
+        &self.name_server
+    }
+}
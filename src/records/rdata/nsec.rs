@@ -0,0 +1,60 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::type_bitmap::TypeBitmap;
+use crate::records::record_data::RecordDataError;
+use std::fmt::{Display, Formatter};
+
+type NsecRecordResult = Result<NSEC, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NSEC {
+    next_domain_name: Fqdn,
+    type_bitmap: TypeBitmap,
+}
+
+impl TryFromBytes for NSEC {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> NsecRecordResult {
+        let next_domain_name =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+        let type_bitmap = TypeBitmap::try_from_bytes(decoder)?;
+
+        Ok(NSEC {
+            next_domain_name,
+            type_bitmap,
+        })
+    }
+}
+
+impl ToBytes for NSEC {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.next_domain_name.to_bytes_uncompressed(encoder);
+        self.type_bitmap.to_bytes(encoder);
+    }
+}
+
+impl Display for NSEC {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.next_domain_name, self.type_bitmap)
+    }
+}
+
+impl NSEC {
+    pub fn new(next_domain_name: Fqdn, type_bitmap: TypeBitmap) -> Self {
+        Self {
+            next_domain_name,
+            type_bitmap,
+        }
+    }
+
+    pub fn next_domain_name(&self) -> &Fqdn {
+        &self.next_domain_name
+    }
+
+    pub fn type_bitmap(&self) -> &TypeBitmap {
+        &self.type_bitmap
+    }
+}
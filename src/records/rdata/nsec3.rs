@@ -0,0 +1,192 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::Nsec3HashAlgorithm;
+use crate::records::rdata::type_bitmap::TypeBitmap;
+use crate::records::record_data::RecordDataError;
+use data_encoding::{BASE32HEX_NOPAD, HEXUPPER};
+use std::fmt::{Display, Formatter};
+
+const OPT_OUT_FLAG: u8 = 1;
+
+type Nsec3RecordResult = Result<NSEC3, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NSEC3 {
+    hash_algorithm: Nsec3HashAlgorithm,
+    flags: u8,
+    iterations: u16,
+    salt: Vec<u8>,
+    next_hashed_owner_name: Vec<u8>,
+    type_bitmap: TypeBitmap,
+}
+
+impl TryFromBytes for NSEC3 {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> Nsec3RecordResult {
+        let hash_algorithm = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadNsec3)?;
+        let flags = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadNsec3)?;
+        let iterations = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadNsec3)?;
+        let salt = read_length_prefixed(decoder).map_err(|_| RecordDataError::UnableToReadNsec3)?;
+        let next_hashed_owner_name =
+            read_length_prefixed(decoder).map_err(|_| RecordDataError::UnableToReadNsec3)?;
+        let type_bitmap = TypeBitmap::try_from_bytes(decoder)?;
+
+        Ok(NSEC3 {
+            hash_algorithm: Nsec3HashAlgorithm::from(hash_algorithm),
+            flags,
+            iterations,
+            salt,
+            next_hashed_owner_name,
+            type_bitmap,
+        })
+    }
+}
+
+impl ToBytes for NSEC3 {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        encoder.write_u8(self.hash_algorithm.into());
+        encoder.write_u8(self.flags);
+        encoder.write_u16(self.iterations);
+        encoder.write_u8(self.salt.len() as u8);
+        encoder.write_n_bytes(self.salt.clone());
+        encoder.write_u8(self.next_hashed_owner_name.len() as u8);
+        encoder.write_n_bytes(self.next_hashed_owner_name.clone());
+        self.type_bitmap.to_bytes(encoder);
+    }
+}
+
+impl Display for NSEC3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.hash_algorithm,
+            self.flags,
+            self.iterations,
+            format_salt(&self.salt),
+            BASE32HEX_NOPAD.encode(&self.next_hashed_owner_name).to_lowercase()
+        )?;
+
+        if !self.type_bitmap.types().is_empty() {
+            write!(f, " {}", self.type_bitmap)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl NSEC3 {
+    pub fn new(
+        hash_algorithm: Nsec3HashAlgorithm,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+        next_hashed_owner_name: Vec<u8>,
+        type_bitmap: TypeBitmap,
+    ) -> Self {
+        Self {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+            next_hashed_owner_name,
+            type_bitmap,
+        }
+    }
+
+    pub fn hash_algorithm(&self) -> Nsec3HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn is_opt_out(&self) -> bool {
+        self.flags & OPT_OUT_FLAG != 0
+    }
+
+    pub fn iterations(&self) -> u16 {
+        self.iterations
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    pub fn next_hashed_owner_name(&self) -> &[u8] {
+        &self.next_hashed_owner_name
+    }
+
+    pub fn type_bitmap(&self) -> &TypeBitmap {
+        &self.type_bitmap
+    }
+}
+
+pub(crate) fn read_length_prefixed(decoder: &mut Deserialize) -> Result<Vec<u8>, RecordDataError> {
+    let length = decoder
+        .read_u8()
+        .map_err(|_| RecordDataError::InsufficientData)?;
+
+    let data = decoder
+        .read_n_bytes(length as u16)
+        .map_err(|_| RecordDataError::InsufficientData)?;
+
+    Ok(data.to_vec())
+}
+
+/// Salt presentation is hex, or "-" when empty (RFC 5155 section 3.3).
+pub(crate) fn format_salt(salt: &[u8]) -> String {
+    match salt.is_empty() {
+        true => String::from("-"),
+        false => HEXUPPER.encode(salt),
+    }
+}
+
+#[cfg(test)]
+mod nsec3_unittest {
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::algorithm::Nsec3HashAlgorithm;
+    use crate::records::rdata::nsec3::NSEC3;
+    use crate::records::rdata::type_bitmap::TypeBitmap;
+    use data_encoding::BASE32HEX_NOPAD;
+
+    // 0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example. from RFC 5155 Appendix A
+    #[test]
+    fn nsec3_round_trip_and_presentation() {
+        let next_hashed_owner_name = BASE32HEX_NOPAD
+            .decode("2T7B4G4VSA5SMI47K61MV5BV1A22BOJR".as_bytes())
+            .unwrap();
+
+        let nsec3 = NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            1,
+            12,
+            vec![0xaa, 0xbb, 0xcc, 0xdd],
+            next_hashed_owner_name,
+            TypeBitmap::new([2, 6, 46, 48, 51]),
+        );
+
+        let mut encoder = Serialize::new();
+        nsec3.to_bytes(&mut encoder);
+        let wire_data = encoder.bin_data();
+
+        let mut decoder = Deserialize::new(&wire_data);
+        let actual = NSEC3::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(actual, nsec3);
+        assert!(actual.is_opt_out());
+        assert_eq!(
+            nsec3.to_string(),
+            "1 1 12 AABBCCDD 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA RRSIG DNSKEY NSEC3PARAM"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::Nsec3HashAlgorithm;
+use crate::records::rdata::nsec3::{format_salt, read_length_prefixed};
+use crate::records::record_data::RecordDataError;
+use std::fmt::{Display, Formatter};
+
+type Nsec3ParamRecordResult = Result<NSEC3PARAM, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NSEC3PARAM {
+    hash_algorithm: Nsec3HashAlgorithm,
+    flags: u8,
+    iterations: u16,
+    salt: Vec<u8>,
+}
+
+impl TryFromBytes for NSEC3PARAM {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> Nsec3ParamRecordResult {
+        let hash_algorithm = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadNsec3Param)?;
+        let flags = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadNsec3Param)?;
+        let iterations = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadNsec3Param)?;
+        let salt =
+            read_length_prefixed(decoder).map_err(|_| RecordDataError::UnableToReadNsec3Param)?;
+
+        Ok(NSEC3PARAM {
+            hash_algorithm: Nsec3HashAlgorithm::from(hash_algorithm),
+            flags,
+            iterations,
+            salt,
+        })
+    }
+}
+
+impl ToBytes for NSEC3PARAM {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        encoder.write_u8(self.hash_algorithm.into());
+        encoder.write_u8(self.flags);
+        encoder.write_u16(self.iterations);
+        encoder.write_u8(self.salt.len() as u8);
+        encoder.write_n_bytes(self.salt.clone());
+    }
+}
+
+impl Display for NSEC3PARAM {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.hash_algorithm,
+            self.flags,
+            self.iterations,
+            format_salt(&self.salt)
+        )
+    }
+}
+
+impl NSEC3PARAM {
+    pub fn new(hash_algorithm: Nsec3HashAlgorithm, flags: u8, iterations: u16, salt: Vec<u8>) -> Self {
+        Self {
+            hash_algorithm,
+            flags,
+            iterations,
+            salt,
+        }
+    }
+
+    pub fn hash_algorithm(&self) -> Nsec3HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn iterations(&self) -> u16 {
+        self.iterations
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+}
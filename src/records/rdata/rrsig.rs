@@ -0,0 +1,296 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::rdata::algorithm::DnssecAlgorithm;
+use crate::records::record_data::RecordDataError;
+use crate::records::record_type::RecordType;
+use data_encoding::BASE64;
+use std::fmt::{Display, Formatter};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+type RrsigRecordResult = Result<RRSIG, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RRSIG {
+    type_covered: RecordType,
+    algorithm: DnssecAlgorithm,
+    labels: u8,
+    original_ttl: u32,
+    signature_expiration: u32,
+    signature_inception: u32,
+    key_tag: u16,
+    signer_name: Fqdn,
+    signature: Vec<u8>,
+}
+
+impl TryFromBytes for RRSIG {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> RrsigRecordResult {
+        let type_covered = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let type_covered =
+            RecordType::try_from(type_covered).map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let algorithm = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let labels = decoder
+            .read_u8()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let original_ttl = decoder
+            .read_u32()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let signature_expiration = decoder
+            .read_u32()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let signature_inception = decoder
+            .read_u32()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let key_tag = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?;
+        let signer_name =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+        let signature = decoder
+            .read_n_bytes(decoder.remaining())
+            .map_err(|_| RecordDataError::UnableToReadRrsig)?
+            .to_vec();
+
+        Ok(RRSIG {
+            type_covered,
+            algorithm: DnssecAlgorithm::from(algorithm),
+            labels,
+            original_ttl,
+            signature_expiration,
+            signature_inception,
+            key_tag,
+            signer_name,
+            signature,
+        })
+    }
+}
+
+impl ToBytes for RRSIG {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.to_bytes_without_signature(encoder);
+        encoder.write_n_bytes(self.signature.clone());
+    }
+}
+
+impl Display for RRSIG {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {}",
+            self.type_covered,
+            self.algorithm,
+            self.labels,
+            self.original_ttl,
+            format_timestamp(self.signature_expiration),
+            format_timestamp(self.signature_inception),
+            self.key_tag,
+            self.signer_name,
+            BASE64.encode(&self.signature)
+        )
+    }
+}
+
+impl RRSIG {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        type_covered: RecordType,
+        algorithm: DnssecAlgorithm,
+        labels: u8,
+        original_ttl: u32,
+        signature_expiration: u32,
+        signature_inception: u32,
+        key_tag: u16,
+        signer_name: Fqdn,
+        signature: Vec<u8>,
+    ) -> Self {
+        Self {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            signature_expiration,
+            signature_inception,
+            key_tag,
+            signer_name,
+            signature,
+        }
+    }
+
+    pub fn type_covered(&self) -> RecordType {
+        self.type_covered
+    }
+
+    pub fn algorithm(&self) -> DnssecAlgorithm {
+        self.algorithm
+    }
+
+    pub fn labels(&self) -> u8 {
+        self.labels
+    }
+
+    pub fn original_ttl(&self) -> u32 {
+        self.original_ttl
+    }
+
+    pub fn signature_expiration(&self) -> u32 {
+        self.signature_expiration
+    }
+
+    pub fn signature_inception(&self) -> u32 {
+        self.signature_inception
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    pub fn signer_name(&self) -> &Fqdn {
+        &self.signer_name
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The RRSIG RDATA fields that precede the signature, which is the
+    /// prefix of the data covered by the signature (RFC 4034 section 3.1.8.1).
+    pub fn to_bytes_without_signature(&self, encoder: &mut Serialize) {
+        encoder.write_u16(self.type_covered.into());
+        encoder.write_u8(self.algorithm.into());
+        encoder.write_u8(self.labels);
+        encoder.write_u32(self.original_ttl);
+        encoder.write_u32(self.signature_expiration);
+        encoder.write_u32(self.signature_inception);
+        encoder.write_u16(self.key_tag);
+        self.signer_name.to_bytes_uncompressed(encoder);
+    }
+}
+
+/// Renders a signature timestamp as YYYYMMDDHHmmSS in UTC (RFC 4034 section 3.2).
+pub(crate) fn format_timestamp(timestamp: u32) -> String {
+    let seconds = timestamp as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let seconds_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Parses a YYYYMMDDHHmmSS timestamp in UTC back to seconds since the epoch.
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<u32> {
+    if timestamp.len() != 14 || !timestamp.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let field = |range: std::ops::Range<usize>| timestamp[range].parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second;
+
+    u32::try_from(seconds).ok()
+}
+
+// Proleptic Gregorian date to days since 1970-01-01, the inverse of civil_from_days
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date, after Howard Hinnant's algorithm
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod rrsig_unittest {
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::algorithm::DnssecAlgorithm;
+    use crate::records::rdata::rrsig::{format_timestamp, parse_timestamp, RRSIG};
+    use crate::records::record_type::RecordType;
+
+    #[test]
+    fn timestamp_presentation() {
+        assert_eq!(format_timestamp(0), "19700101000000");
+        assert_eq!(format_timestamp(1_081_539_377), "20040409193617");
+        assert_eq!(format_timestamp(u32::MAX), "21060207062815");
+    }
+
+    #[test]
+    fn timestamp_parsing() {
+        assert_eq!(parse_timestamp("19700101000000"), Some(0));
+        assert_eq!(parse_timestamp("20040409193617"), Some(1_081_539_377));
+        assert_eq!(parse_timestamp("21060207062815"), Some(u32::MAX));
+        assert_eq!(parse_timestamp("21060207062816"), None);
+        assert_eq!(parse_timestamp("2004040919361"), None);
+        assert_eq!(parse_timestamp("20041309193617"), None);
+    }
+
+    #[test]
+    fn rrsig_round_trip_and_presentation() {
+        let signer_name = FqdnBuilder::new()
+            .generate_from_string(String::from("example.net."))
+            .build();
+
+        let rrsig = RRSIG::new(
+            RecordType::A,
+            DnssecAlgorithm::RSASHA256,
+            3,
+            3600,
+            1_893_456_000,
+            946_684_800,
+            9033,
+            signer_name,
+            vec![0xde, 0xad, 0xbe, 0xef],
+        );
+
+        let mut encoder = Serialize::new();
+        rrsig.to_bytes(&mut encoder);
+        let wire_data = encoder.bin_data();
+
+        let mut decoder = Deserialize::new(&wire_data);
+        assert_eq!(RRSIG::try_from_bytes(&mut decoder).unwrap(), rrsig);
+
+        assert_eq!(
+            rrsig.to_string(),
+            "A 8 3 3600 20300101000000 20000101000000 9033 example.net. 3q2+7w=="
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::record_data::RecordDataError;
+use std::fmt::{Display, Formatter};
+
+type SoaRecordResult = Result<SOA, RecordDataError>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SOA {
+    primary_name_server: Fqdn,
+    responsible_mailbox: Fqdn,
+    serial: u32,
+    refresh: u32,
+    retry: u32,
+    expire: u32,
+    minimum: u32,
+}
+
+impl TryFromBytes for SOA {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> SoaRecordResult {
+        let primary_name_server =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+        let responsible_mailbox =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+
+        let mut timers = [0u32; 5];
+        for timer in timers.iter_mut() {
+            *timer = decoder
+                .read_u32()
+                .map_err(|_| RecordDataError::UnableToReadSoa)?;
+        }
+        let [serial, refresh, retry, expire, minimum] = timers;
+
+        Ok(SOA {
+            primary_name_server,
+            responsible_mailbox,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+        })
+    }
+}
+
+impl ToBytes for SOA {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.primary_name_server.to_bytes(encoder);
+        self.responsible_mailbox.to_bytes(encoder);
+        encoder.write_u32(self.serial);
+        encoder.write_u32(self.refresh);
+        encoder.write_u32(self.retry);
+        encoder.write_u32(self.expire);
+        encoder.write_u32(self.minimum);
+    }
+}
+
+impl Display for SOA {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.primary_name_server,
+            self.responsible_mailbox,
+            self.serial,
+            self.refresh,
+            self.retry,
+            self.expire,
+            self.minimum
+        )
+    }
+}
+
+impl SOA {
+    pub fn new(
+        primary_name_server: Fqdn,
+        responsible_mailbox: Fqdn,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    ) -> Self {
+        Self {
+            primary_name_server,
+            responsible_mailbox,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+        }
+    }
+
+    pub fn primary_name_server(&self) -> &Fqdn {
+        &self.primary_name_server
+    }
+
+    pub fn responsible_mailbox(&self) -> &Fqdn {
+        &self.responsible_mailbox
+    }
+
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    pub fn refresh(&self) -> u32 {
+        self.refresh
+    }
+
+    pub fn retry(&self) -> u32 {
+        self.retry
+    }
+
+    pub fn expire(&self) -> u32 {
+        self.expire
+    }
+
+    /// TTL for negative answers from this zone (RFC 2308 section 4).
+    pub fn minimum(&self) -> u32 {
+        self.minimum
+    }
+}
+
+#[cfg(test)]
+mod soa_unittest {
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::soa::SOA;
+
+    #[test]
+    fn soa_round_trip_and_presentation() {
+        let soa = SOA::new(
+            FqdnBuilder::new().generate_from_string(String::from("ns1.example.com")).build(),
+            FqdnBuilder::new().generate_from_string(String::from("hostmaster.example.com")).build(),
+            2024010101,
+            7200,
+            3600,
+            1209600,
+            300,
+        );
+
+        let mut encoder = Serialize::new();
+        soa.to_bytes(&mut encoder);
+        let wire_data = encoder.bin_data();
+
+        // The mailbox reuses "example.com" from the primary name server
+        assert_eq!(wire_data.len(), 17 + 13 + 20);
+
+        let mut decoder = Deserialize::new(&wire_data);
+        assert_eq!(SOA::try_from_bytes(&mut decoder).unwrap(), soa);
+
+        assert_eq!(
+            soa.to_string(),
+            "ns1.example.com. hostmaster.example.com. 2024010101 7200 3600 1209600 300"
+        );
+    }
+}
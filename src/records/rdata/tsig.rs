@@ -0,0 +1,185 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::record_data::RecordDataError;
+use data_encoding::BASE64;
+use std::fmt::{Display, Formatter};
+
+type TsigRecordResult = Result<TSIG, RecordDataError>;
+
+/// TSIG RDATA (RFC 8945 section 4.2). Time signed is a 48-bit count of
+/// seconds since the epoch.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TSIG {
+    algorithm_name: Fqdn,
+    time_signed: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    original_id: u16,
+    error: u16,
+    other_data: Vec<u8>,
+}
+
+impl TryFromBytes for TSIG {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> TsigRecordResult {
+        let algorithm_name =
+            Fqdn::try_from_bytes(decoder).map_err(|_| RecordDataError::UnableToReadName)?;
+        let time_high = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadTsig)?;
+        let time_low = decoder
+            .read_u32()
+            .map_err(|_| RecordDataError::UnableToReadTsig)?;
+        let fudge = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadTsig)?;
+        let mac = read_length_prefixed(decoder)?;
+        let original_id = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadTsig)?;
+        let error = decoder
+            .read_u16()
+            .map_err(|_| RecordDataError::UnableToReadTsig)?;
+        let other_data = read_length_prefixed(decoder)?;
+
+        Ok(TSIG {
+            algorithm_name,
+            time_signed: ((time_high as u64) << 32) | time_low as u64,
+            fudge,
+            mac,
+            original_id,
+            error,
+            other_data,
+        })
+    }
+}
+
+impl ToBytes for TSIG {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        self.algorithm_name.to_bytes_uncompressed(encoder);
+        encoder.write_u16((self.time_signed >> 32) as u16);
+        encoder.write_u32(self.time_signed as u32);
+        encoder.write_u16(self.fudge);
+        encoder.write_u16(self.mac.len() as u16);
+        encoder.write_n_bytes(self.mac.clone());
+        encoder.write_u16(self.original_id);
+        encoder.write_u16(self.error);
+        encoder.write_u16(self.other_data.len() as u16);
+        encoder.write_n_bytes(self.other_data.clone());
+    }
+}
+
+impl Display for TSIG {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {}",
+            self.algorithm_name,
+            self.time_signed,
+            self.fudge,
+            self.mac.len(),
+            BASE64.encode(&self.mac),
+            self.original_id,
+            self.error,
+            self.other_data.len()
+        )?;
+
+        if !self.other_data.is_empty() {
+            write!(f, " {}", BASE64.encode(&self.other_data))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TSIG {
+    pub fn new(
+        algorithm_name: Fqdn,
+        time_signed: u64,
+        fudge: u16,
+        mac: Vec<u8>,
+        original_id: u16,
+        error: u16,
+        other_data: Vec<u8>,
+    ) -> Self {
+        Self {
+            algorithm_name,
+            time_signed,
+            fudge,
+            mac,
+            original_id,
+            error,
+            other_data,
+        }
+    }
+
+    pub fn algorithm_name(&self) -> &Fqdn {
+        &self.algorithm_name
+    }
+
+    pub fn time_signed(&self) -> u64 {
+        self.time_signed
+    }
+
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    pub fn original_id(&self) -> u16 {
+        self.original_id
+    }
+
+    /// Extended RCODE, such as BADSIG or BADTIME, set by the responder.
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// Holds the server time in BADTIME responses.
+    pub fn other_data(&self) -> &[u8] {
+        &self.other_data
+    }
+}
+
+fn read_length_prefixed(decoder: &mut Deserialize) -> Result<Vec<u8>, RecordDataError> {
+    let length = decoder
+        .read_u16()
+        .map_err(|_| RecordDataError::UnableToReadTsig)?;
+
+    decoder
+        .read_n_bytes(length)
+        .map(|data| data.to_vec())
+        .map_err(|_| RecordDataError::UnableToReadTsig)
+}
+
+#[cfg(test)]
+mod tsig_unittest {
+    use crate::packet::fqdn::FqdnBuilder;
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::tsig::TSIG;
+
+    #[test]
+    fn round_trip_with_48_bit_time() {
+        let algorithm_name = FqdnBuilder::new()
+            .generate_from_string(String::from("hmac-sha256."))
+            .build();
+        let tsig = TSIG::new(algorithm_name, 0x0001_0000_0002, 300, vec![0xaa; 32], 0x1234, 18, vec![0, 1, 2, 3, 4, 5]);
+
+        let mut encoder = Serialize::new();
+        tsig.to_bytes(&mut encoder);
+        let wire_data = encoder.bin_data();
+
+        assert_eq!(&wire_data[13..19], &[0x00, 0x01, 0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(TSIG::try_from_bytes(&mut Deserialize::new(&wire_data)).unwrap(), tsig);
+        assert_eq!(
+            tsig.to_string(),
+            "hmac-sha256. 4294967298 300 32 qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= 4660 18 6 AAECAwQF"
+        );
+    }
+}
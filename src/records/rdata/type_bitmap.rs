@@ -0,0 +1,171 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::records::record_data::RecordDataError;
+use crate::records::record_type::RecordType;
+use std::fmt::{Display, Formatter};
+
+const MAX_BITMAP_LENGTH: u8 = 32;
+
+type TypeBitmapResult = Result<TypeBitmap, RecordDataError>;
+
+/// The window-block encoded type list shared by NSEC and NSEC3 (RFC 4034
+/// section 4.1.2). Types are kept as raw numbers so bitmaps naming types
+/// this crate does not model can still be decoded and re-encoded.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TypeBitmap {
+    types: Vec<u16>,
+}
+
+impl TryFromBytes for TypeBitmap {
+    type Error = RecordDataError;
+
+    fn try_from_bytes(decoder: &mut Deserialize) -> TypeBitmapResult {
+        let mut types = Vec::new();
+        let mut last_window: Option<u8> = None;
+
+        while decoder.remaining() > 0 {
+            let window = decoder
+                .read_u8()
+                .map_err(|_| RecordDataError::MalformedTypeBitmap)?;
+            let length = decoder
+                .read_u8()
+                .map_err(|_| RecordDataError::MalformedTypeBitmap)?;
+
+            if last_window.is_some_and(|last| window <= last) {
+                return Err(RecordDataError::MalformedTypeBitmap);
+            }
+
+            if length == 0 || length > MAX_BITMAP_LENGTH {
+                return Err(RecordDataError::MalformedTypeBitmap);
+            }
+
+            let bitmap = decoder
+                .read_n_bytes(length as u16)
+                .map_err(|_| RecordDataError::MalformedTypeBitmap)?;
+
+            for (octet_index, octet) in bitmap.iter().enumerate() {
+                for bit in 0..8 {
+                    if octet & (0x80 >> bit) != 0 {
+                        types.push(((window as u16) << 8) | (octet_index as u16 * 8 + bit));
+                    }
+                }
+            }
+
+            last_window = Some(window);
+        }
+
+        Ok(TypeBitmap { types })
+    }
+}
+
+impl ToBytes for TypeBitmap {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        let mut types = self.types.iter().peekable();
+
+        while let Some(&first) = types.peek() {
+            let window = (first >> 8) as u8;
+            let mut bitmap = [0u8; MAX_BITMAP_LENGTH as usize];
+            let mut length = 0;
+
+            while let Some(&&record_type) = types.peek() {
+                if (record_type >> 8) as u8 != window {
+                    break;
+                }
+
+                let low = (record_type & 0xff) as usize;
+                bitmap[low / 8] |= 0x80 >> (low % 8);
+                length = low / 8 + 1;
+                types.next();
+            }
+
+            encoder.write_u8(window);
+            encoder.write_u8(length as u8);
+            encoder.write_n_bytes(bitmap[..length].to_vec());
+        }
+    }
+}
+
+impl Display for TypeBitmap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mnemonics: Vec<String> = self
+            .types
+            .iter()
+            .map(|&record_type| match RecordType::try_from(record_type) {
+                Ok(known) => known.to_string(),
+                Err(_) => format!("TYPE{}", record_type),
+            })
+            .collect();
+
+        write!(f, "{}", mnemonics.join(" "))
+    }
+}
+
+impl TypeBitmap {
+    pub fn new(types: impl IntoIterator<Item = u16>) -> Self {
+        let mut types: Vec<u16> = types.into_iter().collect();
+        types.sort_unstable();
+        types.dedup();
+
+        TypeBitmap { types }
+    }
+
+    pub fn contains(&self, record_type: u16) -> bool {
+        self.types.binary_search(&record_type).is_ok()
+    }
+
+    pub fn types(&self) -> &[u16] {
+        &self.types
+    }
+}
+
+impl From<&[RecordType]> for TypeBitmap {
+    fn from(value: &[RecordType]) -> Self {
+        TypeBitmap::new(value.iter().map(|&record_type| u16::from(record_type)))
+    }
+}
+
+#[cfg(test)]
+mod type_bitmap_unittest {
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::rdata::type_bitmap::TypeBitmap;
+    use crate::records::record_data::RecordDataError;
+
+    // A MX RRSIG NSEC TYPE1234 from RFC 4034 section 4.3
+    const RFC4034_BITMAP: [u8; 37] = [
+        0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03, 0x04, 0x1b, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20,
+    ];
+
+    #[test]
+    fn read_rfc4034_bitmap() {
+        let mut decoder = Deserialize::new(&RFC4034_BITMAP);
+        let bitmap = TypeBitmap::try_from_bytes(&mut decoder).unwrap();
+
+        assert_eq!(bitmap.types(), &[1, 15, 46, 47, 1234]);
+        assert_eq!(bitmap.to_string(), "A TYPE15 RRSIG NSEC TYPE1234");
+    }
+
+    #[test]
+    fn serialize_rfc4034_bitmap() {
+        let bitmap = TypeBitmap::new([1234, 47, 46, 15, 1]);
+
+        let mut encoder = Serialize::new();
+        bitmap.to_bytes(&mut encoder);
+
+        assert_eq!(encoder.bin_data(), RFC4034_BITMAP);
+    }
+
+    #[test]
+    fn error_windows_out_of_order() {
+        let bitmap_bytes: [u8; 6] = [0x04, 0x01, 0x80, 0x00, 0x01, 0x40];
+
+        let mut decoder = Deserialize::new(&bitmap_bytes);
+
+        assert!(matches!(
+            TypeBitmap::try_from_bytes(&mut decoder),
+            Err(RecordDataError::MalformedTypeBitmap)
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::records::record_data::RecordDataError;
+use crate::records::record_type::RecordType;
+use data_encoding::HEXUPPER;
+use std::fmt::{Display, Formatter};
+
+/// RDATA kept as opaque bytes (RFC 3597), for types this crate does not
+/// know how to decode.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Unknown {
+    record_type: RecordType,
+    data: Vec<u8>,
+}
+
+impl Unknown {
+    pub fn new(record_type: RecordType, data: Vec<u8>) -> Self {
+        Self { record_type, data }
+    }
+
+    /// Takes every byte left in the decoder, which is limited to the RDATA.
+    pub fn try_from_bytes(decoder: &mut Deserialize, record_type: RecordType) -> Result<Self, RecordDataError> {
+        let data = decoder
+            .read_n_bytes(decoder.remaining())
+            .map_err(|_| RecordDataError::InsufficientData)?
+            .to_vec();
+
+        Ok(Self { record_type, data })
+    }
+
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl ToBytes for Unknown {
+    fn to_bytes(&self, encoder: &mut Serialize) {
+        encoder.write_n_bytes(self.data.clone());
+    }
+}
+
+/// The generic `\# length hex` form (RFC 3597 section 5).
+impl Display for Unknown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.data.is_empty() {
+            true => write!(f, "\\# 0"),
+            false => write!(f, "\\# {} {}", self.data.len(), HEXUPPER.encode(&self.data)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unknown_unittest {
+    use crate::packet::seder::deserializer::Deserialize;
+    use crate::records::rdata::unknown::Unknown;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+
+    #[test]
+    fn keep_unknown_rdata_opaque() {
+        let wire = [0, 10, 4, b'm', b'a', b'i', b'l', 0];
+        let data = RecordData::from_bytes(&mut Deserialize::new(&wire), &RecordType::try_from(15).unwrap(), 8).unwrap();
+
+        assert_eq!(data, RecordData::Unknown(Unknown::new(RecordType::try_from(15).unwrap(), wire.to_vec())));
+        assert_eq!(data.record_type(), RecordType::try_from(15).unwrap());
+        assert_eq!(data.to_string(), "\\# 8 000A046D61696C00");
+
+        let mut encoder = crate::packet::seder::serializer::Serialize::new();
+        data.rdata_to_bytes(&mut encoder);
+        assert_eq!(encoder.bin_data(), wire.to_vec());
+    }
+}
@@ -1,18 +1,36 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum RecordClassError
 {
-    #[error("Record class {0} in message is invalid according to RFC or is not supported")]
-    UnknownRecordClass(u16)
+    #[error("Record class {0} is reserved according to RFC 6895")]
+    ReservedRecordClass(u16),
+    #[error("Class {0} is only valid in the question section")]
+    QueryOnlyClass(u16),
+    #[error("Unknown class mnemonic {0}")]
+    UnknownMnemonic(String),
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum RecordClass {
     IN,
     CS,
     CH,
     HS,
+    Unknown(UnknownClass),
+}
+
+/// The code of a class without its own variant. Only `RecordClass::try_from`
+/// builds one, so known, reserved and query-only codes never end up here.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct UnknownClass(u16);
+
+impl UnknownClass {
+    pub fn code(&self) -> u16 {
+        self.0
+    }
 }
 
 impl TryFrom<u16> for RecordClass
@@ -25,7 +43,9 @@ impl TryFrom<u16> for RecordClass
             2 => Ok(RecordClass::CS),
             3 => Ok(RecordClass::CH),
             4 => Ok(RecordClass::HS),
-            _ => Err(RecordClassError::UnknownRecordClass(value))
+            254 | 255 => Err(RecordClassError::QueryOnlyClass(value)),
+            0 | 65535 => Err(RecordClassError::ReservedRecordClass(value)),
+            _ => Ok(RecordClass::Unknown(UnknownClass(value))),
         }
     }
 }
@@ -37,6 +57,38 @@ impl From<RecordClass> for u16 {
             RecordClass::CS => 2,
             RecordClass::CH => 3,
             RecordClass::HS => 4,
+            RecordClass::Unknown(unknown) => unknown.code(),
+        }
+    }
+}
+
+impl Display for RecordClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordClass::IN => write!(f, "IN"),
+            RecordClass::CS => write!(f, "CS"),
+            RecordClass::CH => write!(f, "CH"),
+            RecordClass::HS => write!(f, "HS"),
+            RecordClass::Unknown(unknown) => write!(f, "CLASS{}", unknown.code()),
         }
     }
-}
\ No newline at end of file
+}
+
+// Mnemonics or the generic CLASSnnn form (RFC 3597 section 5)
+impl FromStr for RecordClass {
+    type Err = RecordClassError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_uppercase().as_str() {
+            "IN" => Ok(RecordClass::IN),
+            "CS" => Ok(RecordClass::CS),
+            "CH" => Ok(RecordClass::CH),
+            "HS" => Ok(RecordClass::HS),
+            mnemonic => mnemonic
+                .strip_prefix("CLASS")
+                .and_then(|number| number.parse::<u16>().ok())
+                .ok_or_else(|| RecordClassError::UnknownMnemonic(value.to_string()))
+                .and_then(RecordClass::try_from),
+        }
+    }
+}
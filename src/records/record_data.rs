@@ -3,8 +3,18 @@ use crate::packet::seder::serializer::Serialize;
 use crate::packet::seder::{TryFromBytes, ToBytes};
 use crate::records::rdata::a::A;
 use crate::records::rdata::aaaa::AAAA;
+use crate::records::rdata::cname::CNAME;
+use crate::records::rdata::dnskey::DNSKEY;
+use crate::records::rdata::ds::DS;
+use crate::records::rdata::ns::NS;
+use crate::records::rdata::nsec::NSEC;
+use crate::records::rdata::nsec3::NSEC3;
+use crate::records::rdata::nsec3param::NSEC3PARAM;
+use crate::records::rdata::rrsig::RRSIG;
+use crate::records::rdata::soa::SOA;
+use crate::records::rdata::unknown::Unknown;
 use crate::records::record_type::RecordType;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,35 +23,162 @@ pub enum RecordDataError {
     UnableToReadIpv4Address,
     #[error("Unable to read IPv6 address from response")]
     UnableToReadIpv6Address,
+    #[error("Unable to read domain name inside record data")]
+    UnableToReadName,
+    #[error("Unable to read SOA record data")]
+    UnableToReadSoa,
+    #[error("Unable to read DNSKEY record data")]
+    UnableToReadDnskey,
+    #[error("Unable to read DS record data")]
+    UnableToReadDs,
+    #[error("Unable to read RRSIG record data")]
+    UnableToReadRrsig,
+    #[error("Unable to read NSEC3 record data")]
+    UnableToReadNsec3,
+    #[error("Unable to read NSEC3PARAM record data")]
+    UnableToReadNsec3Param,
+    #[error("Unable to read TSIG record data")]
+    UnableToReadTsig,
+    #[error("Type bitmap is not encoded according to RFC 4034")]
+    MalformedTypeBitmap,
+    #[error("Record data is shorter than its declared length")]
+    InsufficientData,
+    #[error("Record data length {0} does not match the data read")]
+    DataLengthMismatch(u16),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum RecordData {
     A(A),
     AAAA(AAAA),
+    NS(NS),
+    CNAME(CNAME),
+    SOA(SOA),
+    KEY(DNSKEY),
+    DS(DS),
+    RRSIG(RRSIG),
+    NSEC(NSEC),
+    DNSKEY(DNSKEY),
+    NSEC3(NSEC3),
+    NSEC3PARAM(NSEC3PARAM),
+    CDS(DS),
+    CDNSKEY(DNSKEY),
+    Unknown(Unknown),
+}
+
+/// RDATA in presentation format (RFC 1035 section 5.1).
+impl Display for RecordData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordData::A(a_rdata) => write!(f, "{}", a_rdata),
+            RecordData::AAAA(aaaa_rdata) => write!(f, "{}", aaaa_rdata),
+            RecordData::NS(ns_rdata) => write!(f, "{}", ns_rdata),
+            RecordData::CNAME(cname_rdata) => write!(f, "{}", cname_rdata),
+            RecordData::SOA(soa_rdata) => write!(f, "{}", soa_rdata),
+            RecordData::KEY(key_rdata) => write!(f, "{}", key_rdata),
+            RecordData::DS(ds_rdata) => write!(f, "{}", ds_rdata),
+            RecordData::RRSIG(rrsig_rdata) => write!(f, "{}", rrsig_rdata),
+            RecordData::NSEC(nsec_rdata) => write!(f, "{}", nsec_rdata),
+            RecordData::DNSKEY(dnskey_rdata) => write!(f, "{}", dnskey_rdata),
+            RecordData::NSEC3(nsec3_rdata) => write!(f, "{}", nsec3_rdata),
+            RecordData::NSEC3PARAM(nsec3param_rdata) => write!(f, "{}", nsec3param_rdata),
+            RecordData::CDS(cds_rdata) => write!(f, "{}", cds_rdata),
+            RecordData::CDNSKEY(cdnskey_rdata) => write!(f, "{}", cdnskey_rdata),
+            RecordData::Unknown(unknown_rdata) => write!(f, "{}", unknown_rdata),
+        }
+    }
 }
 
 impl RecordData {
     pub fn from_bytes(
         decoder: &mut Deserialize,
         record_type: &RecordType,
+        data_length: u16,
     ) -> Result<Self, RecordDataError> {
-        match record_type {
-            RecordType::A => {
-                let data = A::try_from_bytes(decoder)?;
-                Ok(RecordData::A(data))
-            }
-            RecordType::AAAA => {
-                let data = AAAA::try_from_bytes(decoder)?;
-                Ok(RecordData::AAAA(data))
+        let mut rdata_decoder = decoder
+            .limit(data_length)
+            .map_err(|_| RecordDataError::InsufficientData)?;
+
+        let data = match record_type {
+            RecordType::A => RecordData::A(A::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::AAAA => RecordData::AAAA(AAAA::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::NS => RecordData::NS(NS::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::CNAME => RecordData::CNAME(CNAME::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::SOA => RecordData::SOA(SOA::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::KEY => RecordData::KEY(DNSKEY::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::DS => RecordData::DS(DS::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::RRSIG => RecordData::RRSIG(RRSIG::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::NSEC => RecordData::NSEC(NSEC::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::DNSKEY => RecordData::DNSKEY(DNSKEY::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::NSEC3 => RecordData::NSEC3(NSEC3::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::NSEC3PARAM => {
+                RecordData::NSEC3PARAM(NSEC3PARAM::try_from_bytes(&mut rdata_decoder)?)
             }
+            RecordType::CDS => RecordData::CDS(DS::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::CDNSKEY => RecordData::CDNSKEY(DNSKEY::try_from_bytes(&mut rdata_decoder)?),
+            RecordType::Unknown(_) => RecordData::Unknown(Unknown::try_from_bytes(&mut rdata_decoder, *record_type)?),
+        };
+
+        if rdata_decoder.remaining() != 0 {
+            return Err(RecordDataError::DataLengthMismatch(data_length));
         }
+
+        decoder
+            .skip(data_length)
+            .map_err(|_| RecordDataError::InsufficientData)?;
+
+        Ok(data)
     }
 
+    /// Writes RDLENGTH followed by the RDATA.
     pub fn to_bytes(&self, encoder: &mut Serialize) {
+        let length_position = encoder.cursor();
+        encoder.write_u16(0);
+
+        self.rdata_to_bytes(encoder);
+
+        let data_length = encoder.cursor() - length_position - 2;
+        encoder.overwrite_u16(length_position, data_length);
+    }
+
+    /// Writes only the RDATA, without the RDLENGTH prefix.
+    pub fn rdata_to_bytes(&self, encoder: &mut Serialize) {
         match self {
             RecordData::A(a_rdata) => a_rdata.to_bytes(encoder),
             RecordData::AAAA(aaaa_rdata) => aaaa_rdata.to_bytes(encoder),
+            RecordData::NS(ns_rdata) => ns_rdata.to_bytes(encoder),
+            RecordData::CNAME(cname_rdata) => cname_rdata.to_bytes(encoder),
+            RecordData::SOA(soa_rdata) => soa_rdata.to_bytes(encoder),
+            RecordData::KEY(key_rdata) => key_rdata.to_bytes(encoder),
+            RecordData::DS(ds_rdata) => ds_rdata.to_bytes(encoder),
+            RecordData::RRSIG(rrsig_rdata) => rrsig_rdata.to_bytes(encoder),
+            RecordData::NSEC(nsec_rdata) => nsec_rdata.to_bytes(encoder),
+            RecordData::DNSKEY(dnskey_rdata) => dnskey_rdata.to_bytes(encoder),
+            RecordData::NSEC3(nsec3_rdata) => nsec3_rdata.to_bytes(encoder),
+            RecordData::NSEC3PARAM(nsec3param_rdata) => nsec3param_rdata.to_bytes(encoder),
+            RecordData::CDS(cds_rdata) => cds_rdata.to_bytes(encoder),
+            RecordData::CDNSKEY(cdnskey_rdata) => cdnskey_rdata.to_bytes(encoder),
+            RecordData::Unknown(unknown_rdata) => unknown_rdata.to_bytes(encoder),
+        }
+    }
+
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            RecordData::A(_) => RecordType::A,
+            RecordData::AAAA(_) => RecordType::AAAA,
+            RecordData::NS(_) => RecordType::NS,
+            RecordData::CNAME(_) => RecordType::CNAME,
+            RecordData::SOA(_) => RecordType::SOA,
+            RecordData::KEY(_) => RecordType::KEY,
+            RecordData::DS(_) => RecordType::DS,
+            RecordData::RRSIG(_) => RecordType::RRSIG,
+            RecordData::NSEC(_) => RecordType::NSEC,
+            RecordData::DNSKEY(_) => RecordType::DNSKEY,
+            RecordData::NSEC3(_) => RecordType::NSEC3,
+            RecordData::NSEC3PARAM(_) => RecordType::NSEC3PARAM,
+            RecordData::CDS(_) => RecordType::CDS,
+            RecordData::CDNSKEY(_) => RecordType::CDNSKEY,
+            RecordData::Unknown(unknown_rdata) => unknown_rdata.record_type(),
         }
     }
 }
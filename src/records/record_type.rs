@@ -1,27 +1,51 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use thiserror::Error;
-use crate::records::record_type::RecordTypeError::UnknownRecordType;
+use crate::records::record_type::RecordTypeError::QueryOnlyType;
 
 #[derive(Error, Debug)]
 pub enum RecordTypeError {
-    #[error("Message contains unknown record type. Record type provided {0}")]
-    UnknownRecordType(u16)
+    #[error("Type {0} is only valid in the question section")]
+    QueryOnlyType(u16),
+    #[error("Unknown record type mnemonic {0}")]
+    UnknownMnemonic(String),
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum RecordType {
     A,
     AAAA,
-    // NS,
-    // CNAME,
-    // SOA,
+    NS,
+    CNAME,
+    SOA,
+    KEY,
     // WKS,
     // PTR,
     // INFO,
     // MINFO,
     // MX,
     // TXT,
-    // AXFR,
-    // ALL,
+    DS,
+    RRSIG,
+    NSEC,
+    DNSKEY,
+    NSEC3,
+    NSEC3PARAM,
+    CDS,
+    CDNSKEY,
+    /// Any other type, whose RDATA is kept opaque (RFC 3597)
+    Unknown(UnknownType),
+}
+
+/// The code of a type without its own variant. Only `RecordType::try_from`
+/// builds one, so known and query-only codes never end up here.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct UnknownType(u16);
+
+impl UnknownType {
+    pub fn code(&self) -> u16 {
+        self.0
+    }
 }
 
 impl TryFrom<u16> for RecordType {
@@ -30,8 +54,21 @@ impl TryFrom<u16> for RecordType {
     fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
             1 => Ok(RecordType::A),
+            2 => Ok(RecordType::NS),
+            5 => Ok(RecordType::CNAME),
+            6 => Ok(RecordType::SOA),
+            25 => Ok(RecordType::KEY),
             28 => Ok(RecordType::AAAA),
-            _ => Err(UnknownRecordType(value))
+            43 => Ok(RecordType::DS),
+            46 => Ok(RecordType::RRSIG),
+            47 => Ok(RecordType::NSEC),
+            48 => Ok(RecordType::DNSKEY),
+            50 => Ok(RecordType::NSEC3),
+            51 => Ok(RecordType::NSEC3PARAM),
+            59 => Ok(RecordType::CDS),
+            60 => Ok(RecordType::CDNSKEY),
+            251..=255 => Err(QueryOnlyType(value)),
+            _ => Ok(RecordType::Unknown(UnknownType(value))),
         }
     }
 }
@@ -40,7 +77,73 @@ impl From<RecordType> for u16 {
     fn from(val: RecordType) -> Self {
         match val {
             RecordType::A => 1,
+            RecordType::NS => 2,
+            RecordType::CNAME => 5,
+            RecordType::SOA => 6,
+            RecordType::KEY => 25,
             RecordType::AAAA => 28,
+            RecordType::DS => 43,
+            RecordType::RRSIG => 46,
+            RecordType::NSEC => 47,
+            RecordType::DNSKEY => 48,
+            RecordType::NSEC3 => 50,
+            RecordType::NSEC3PARAM => 51,
+            RecordType::CDS => 59,
+            RecordType::CDNSKEY => 60,
+            RecordType::Unknown(unknown) => unknown.code(),
+        }
+    }
+}
+
+impl Display for RecordType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = match self {
+            RecordType::A => "A",
+            RecordType::AAAA => "AAAA",
+            RecordType::NS => "NS",
+            RecordType::CNAME => "CNAME",
+            RecordType::SOA => "SOA",
+            RecordType::KEY => "KEY",
+            RecordType::DS => "DS",
+            RecordType::RRSIG => "RRSIG",
+            RecordType::NSEC => "NSEC",
+            RecordType::DNSKEY => "DNSKEY",
+            RecordType::NSEC3 => "NSEC3",
+            RecordType::NSEC3PARAM => "NSEC3PARAM",
+            RecordType::CDS => "CDS",
+            RecordType::CDNSKEY => "CDNSKEY",
+            RecordType::Unknown(unknown) => return write!(f, "TYPE{}", unknown.code()),
+        };
+
+        write!(f, "{}", mnemonic)
+    }
+}
+
+// Mnemonics or the generic TYPEnnn form (RFC 3597 section 5)
+impl FromStr for RecordType {
+    type Err = RecordTypeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_uppercase().as_str() {
+            "A" => Ok(RecordType::A),
+            "AAAA" => Ok(RecordType::AAAA),
+            "NS" => Ok(RecordType::NS),
+            "CNAME" => Ok(RecordType::CNAME),
+            "SOA" => Ok(RecordType::SOA),
+            "KEY" => Ok(RecordType::KEY),
+            "DS" => Ok(RecordType::DS),
+            "RRSIG" => Ok(RecordType::RRSIG),
+            "NSEC" => Ok(RecordType::NSEC),
+            "DNSKEY" => Ok(RecordType::DNSKEY),
+            "NSEC3" => Ok(RecordType::NSEC3),
+            "NSEC3PARAM" => Ok(RecordType::NSEC3PARAM),
+            "CDS" => Ok(RecordType::CDS),
+            "CDNSKEY" => Ok(RecordType::CDNSKEY),
+            mnemonic => mnemonic
+                .strip_prefix("TYPE")
+                .and_then(|number| number.parse::<u16>().ok())
+                .ok_or_else(|| RecordTypeError::UnknownMnemonic(value.to_string()))
+                .and_then(RecordType::try_from),
         }
     }
-}
\ No newline at end of file
+}
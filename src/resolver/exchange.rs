@@ -0,0 +1,147 @@
+use crate::packet::framing::{read_frame, write_frame};
+use crate::packet::message::Message;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::TryFromBytes;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Sends one query to one name server and returns its response. The
+/// resolver only talks to the network through this, so tests can answer
+/// from in-memory zones instead.
+pub trait Exchanger: Send + Sync {
+    fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message>;
+}
+
+/// Queries over UDP and retries over TCP when the response is truncated.
+#[derive(Debug, Clone)]
+pub struct NetworkExchanger {
+    timeout: Duration,
+}
+
+impl Default for NetworkExchanger {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+impl NetworkExchanger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long to wait for each server before giving up on it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Datagrams with another id or question are dropped rather than
+    /// accepted, as they are either late or spoofed.
+    pub(crate) fn exchange_udp(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let local: SocketAddr = match server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(server)?;
+        socket.send(&query.wire())?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut buffer = vec![0u8; u16::MAX as usize];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(ErrorKind::TimedOut, "no response from name server"));
+            }
+            socket.set_read_timeout(Some(remaining))?;
+
+            let length = socket.recv(&mut buffer)?;
+            if let Some(response) = parse_response(&buffer[..length], query) {
+                return Ok(response);
+            }
+        }
+    }
+
+    fn exchange_tcp(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let mut stream = TcpStream::connect_timeout(&server, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        exchange_stream(&mut stream, query)
+    }
+}
+
+impl Exchanger for NetworkExchanger {
+    fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let response = self.exchange_udp(server, query)?;
+        match response.header().flags().is_truncated() {
+            true => self.exchange_tcp(server, query),
+            false => Ok(response),
+        }
+    }
+}
+
+/// Sends one length prefixed query on a stream and reads the response.
+pub(crate) fn exchange_stream(stream: &mut (impl Read + Write), query: &Message) -> io::Result<Message> {
+    write_frame(stream, &query.wire())?;
+    stream.flush()?;
+
+    let wire = read_frame(stream)?.ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+    parse_response(&wire, query).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "response does not match the query"))
+}
+
+/// The message if it decodes and answers the query.
+pub(crate) fn parse_response(wire: &[u8], query: &Message) -> Option<Message> {
+    let response = Message::try_from_bytes(&mut Deserialize::new(wire)).ok()?;
+
+    let matches = response.header().id() == query.header().id() && response.question() == query.question();
+    matches.then_some(response)
+}
+
+#[cfg(test)]
+mod exchange_unittest {
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::{Exchanger, NetworkExchanger};
+    use crate::resolver::resolver_unittest::query;
+    use crate::server::handler::handler_unittest::catalog;
+    use crate::server::Server;
+    use crate::zone::zone_unittest::{name, records};
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn exchange_with_server() {
+        let mut catalog = catalog();
+        let mut zone = catalog.get(&name("example.com.")).unwrap().clone();
+        let text: String = (1..=40).map(|host| format!("big A 192.0.2.{}\n", host)).collect();
+        for record in records(&text) {
+            zone.insert(record).unwrap();
+        }
+        catalog.insert(zone);
+        let server = Server::bind("127.0.0.1:0", catalog).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+        let exchanger = NetworkExchanger::new();
+
+        let response = exchanger.exchange(address, &query("www.example.com.", RecordType::A)).unwrap();
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        let response = exchanger.exchange(address, &query("big.example.com.", RecordType::A)).unwrap();
+        assert!(!response.header().flags().is_truncated());
+        assert_eq!(response.answers().len(), 40);
+    }
+
+    #[test]
+    fn error_on_timeout() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let exchanger = NetworkExchanger::new().timeout(Duration::from_millis(50));
+
+        assert!(exchanger.exchange(silent.local_addr().unwrap(), &query("www.example.com.", RecordType::A)).is_err());
+    }
+}
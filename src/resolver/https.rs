@@ -0,0 +1,171 @@
+use crate::packet::message::Message;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::resolver::exchange::{parse_response, Exchanger};
+use crate::server::https::{read_body, DEFAULT_PATH, DNS_MESSAGE, H2_ALPN};
+use crate::tls::{ServerIdentity, TlsResult};
+use bytes::Bytes;
+use data_encoding::BASE64URL_NOPAD;
+use http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use http::{Request, StatusCode};
+use rustls::pki_types::{IpAddr, ServerName};
+use rustls::ClientConfig;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HttpsMethod {
+    /// The query goes in the URL, so HTTP caches can answer it
+    Get,
+    Post,
+}
+
+/// Queries over DNS over HTTPS (RFC 8484) on HTTP/2, refusing any server
+/// that does not prove the configured identity. Queries go out with id 0
+/// to keep GET requests cacheable, and responses get the query id back.
+#[derive(Debug, Clone)]
+pub struct HttpsExchanger {
+    config: Arc<ClientConfig>,
+    name: ServerName<'static>,
+    path: String,
+    method: HttpsMethod,
+    timeout: Duration,
+}
+
+impl HttpsExchanger {
+    pub fn new(identity: &ServerIdentity) -> TlsResult<Self> {
+        Ok(Self {
+            config: identity.client_config(&[H2_ALPN])?,
+            name: identity.name().clone(),
+            path: String::from(DEFAULT_PATH),
+            method: HttpsMethod::Post,
+            timeout: Duration::from_secs(2),
+        })
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_owned();
+        self
+    }
+
+    pub fn method(mut self, method: HttpsMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// How long to wait for each server before giving up on it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn exchange_https(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let stream = tokio::net::TcpStream::connect(server).await?;
+        let stream = TlsConnector::from(Arc::clone(&self.config)).connect(self.name.clone(), stream).await?;
+        let (client, connection) = h2::client::handshake(stream).await.map_err(io::Error::other)?;
+        tokio::spawn(connection);
+
+        let mut encoder = Serialize::new();
+        query.to_bytes(&mut encoder);
+        let mut wire = encoder.bin_data();
+        wire[..2].fill(0);
+
+        let url = format!("https://{}{}", authority(&self.name), self.path);
+        let (request, body) = match self.method {
+            HttpsMethod::Get => (Request::get(format!("{}?dns={}", url, BASE64URL_NOPAD.encode(&wire))), None),
+            HttpsMethod::Post => (
+                Request::post(url).header(CONTENT_TYPE, DNS_MESSAGE).header(CONTENT_LENGTH, wire.len()),
+                Some(wire),
+            ),
+        };
+        let request = request.header(ACCEPT, DNS_MESSAGE).body(()).map_err(io::Error::other)?;
+
+        let mut client = client.ready().await.map_err(io::Error::other)?;
+        let (response, mut stream) = client.send_request(request, body.is_none()).map_err(io::Error::other)?;
+        if let Some(body) = body {
+            stream.send_data(Bytes::from(body), true).map_err(io::Error::other)?;
+        }
+
+        let (head, body) = response.await.map_err(io::Error::other)?.into_parts();
+        if head.status != StatusCode::OK {
+            return Err(io::Error::other(format!("server answered HTTP {}", head.status)));
+        }
+        if head.headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()) != Some(DNS_MESSAGE) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "response is not a DNS message"));
+        }
+
+        let mut wire = read_body(body)
+            .await
+            .map_err(io::Error::other)?
+            .filter(|wire| wire.len() >= 2)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "response is not a DNS message"))?;
+        wire[..2].copy_from_slice(&query.header().id().to_be_bytes());
+        parse_response(&wire, query).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "response does not match the query"))
+    }
+}
+
+impl Exchanger for HttpsExchanger {
+    fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+        runtime.block_on(async {
+            timeout(self.timeout, self.exchange_https(server, query))
+                .await
+                .map_err(|_| io::Error::new(ErrorKind::TimedOut, "no response from name server"))?
+        })
+    }
+}
+
+/// The host part of the URL, with IPv6 addresses in brackets.
+fn authority(name: &ServerName<'_>) -> String {
+    match name {
+        ServerName::IpAddress(IpAddr::V6(_)) => format!("[{}]", name.to_str()),
+        _ => name.to_str().into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod https_exchange_unittest {
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::Exchanger;
+    use crate::resolver::https::{HttpsExchanger, HttpsMethod};
+    use crate::resolver::resolver_unittest::query;
+    use crate::server::https::https_server_unittest::start;
+    use crate::tls::{spki_pin, ServerIdentity};
+
+    #[test]
+    fn exchange_with_get_and_post() {
+        let (address, certificate) = start("doh.example", "/dns-query");
+        let identity = ServerIdentity::new("doh.example").unwrap().roots(std::slice::from_ref(&certificate)).unwrap();
+
+        for method in [HttpsMethod::Get, HttpsMethod::Post] {
+            let query = query("www.example.com.", RecordType::A);
+            let response = HttpsExchanger::new(&identity).unwrap().method(method).exchange(address, &query).unwrap();
+            assert_eq!(response.header().id(), query.header().id());
+            assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+            assert_eq!(response.answers().len(), 1);
+        }
+
+        let pinned = ServerIdentity::new("doh.example").unwrap().pin(spki_pin(&certificate).unwrap());
+        assert!(HttpsExchanger::new(&pinned).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).is_ok());
+    }
+
+    #[test]
+    fn fail_on_wrong_path_or_identity() {
+        let (address, certificate) = start("doh.example", "/resolve");
+        let identity = ServerIdentity::new("doh.example").unwrap().roots(std::slice::from_ref(&certificate)).unwrap();
+
+        let exchanger = HttpsExchanger::new(&identity).unwrap();
+        assert!(exchanger.exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+        assert!(exchanger.path("/resolve").exchange(address, &query("www.example.com.", RecordType::A)).is_ok());
+
+        let other_name = ServerIdentity::new("other.example").unwrap().roots(&[certificate]).unwrap();
+        let exchanger = HttpsExchanger::new(&other_name).unwrap().path("/resolve");
+        assert!(exchanger.exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+    }
+}
@@ -0,0 +1,969 @@
+pub mod exchange;
+pub mod hints;
+#[cfg(feature = "doh")]
+pub mod https;
+#[cfg(feature = "doq")]
+pub mod quic;
+pub mod tls;
+pub mod transfer;
+
+use crate::cache::{Cache, Cached};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Rcode};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::question::QuestionBuilder;
+use crate::packet::record::Record;
+use crate::records::query_class::QueryClass;
+use crate::records::query_type::QueryType;
+use crate::records::record_class::RecordClass;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use crate::resolver::exchange::Exchanger;
+use crate::resolver::hints::root_hints;
+use ring::rand::{generate, SystemRandom};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use thiserror::Error;
+
+const DNS_PORT: u16 = 53;
+
+/// CNAMEs followed for one question before giving up on the chain.
+const MAX_CNAME_CHAIN: usize = 8;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ResolverError {
+    #[error("Resolution needs more than {0} queries")]
+    BudgetExhausted(usize),
+    #[error("Name server lookups are nested deeper than {0} levels")]
+    TooDeep(usize),
+    #[error("CNAME chain loops back to {0}")]
+    CnameLoop(Fqdn),
+    #[error("CNAME chain is longer than {0} records")]
+    CnameChainTooLong(usize),
+    #[error("No name server for {0} gave a usable response")]
+    NoUsableServer(Fqdn),
+    #[error("No free query id for {0}")]
+    NoFreeId(SocketAddr),
+}
+
+pub type ResolverResult<T> = Result<T, ResolverError>;
+
+/// The outcome of resolving a question: the CNAMEs followed and the
+/// records found, or the SOA that proves the name or type absent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Resolution {
+    response_code: Rcode,
+    answers: Vec<Record>,
+    authorities: Vec<Record>,
+}
+
+impl Resolution {
+    fn new(response_code: Rcode, answers: Vec<Record>, authorities: Vec<Record>) -> Self {
+        Self {
+            response_code,
+            answers,
+            authorities,
+        }
+    }
+
+    pub fn response_code(&self) -> Rcode {
+        self.response_code
+    }
+
+    pub fn answers(&self) -> &[Record] {
+        &self.answers
+    }
+
+    pub fn authorities(&self) -> &[Record] {
+        &self.authorities
+    }
+}
+
+/// A response from a name server that either ends the walk down the tree
+/// or refers it to the name servers of a zone closer to the name.
+enum Reply {
+    Final(Message),
+    Referral(Fqdn, Message),
+}
+
+/// Bookkeeping for one resolution, shared with the name server address
+/// lookups it starts. A refresh skips the cache for the first name.
+#[derive(Default)]
+struct State {
+    queries: usize,
+    depth: usize,
+    pending: Vec<Fqdn>,
+    refresh: bool,
+}
+
+/// An iterative resolver that walks from the root servers down to the
+/// zone holding a name (RFC 1034 section 5.3.3).
+///
+/// Referrals are followed with the glue that is within the bailiwick of
+/// the referring zone. When there is none the name server addresses are
+/// resolved first, which is where dependency loops between zones are cut
+/// short. Every query sent counts against one budget per resolution.
+///
+/// With a cache, answers, negative answers and delegations are kept, so
+/// later walks start at the closest known zone, and cached answers are
+/// served stale when the servers cannot be reached.
+pub struct Resolver {
+    exchanger: Arc<dyn Exchanger>,
+    cache: Option<Arc<Cache>>,
+    hints: Vec<SocketAddr>,
+    max_queries: usize,
+    max_depth: usize,
+}
+
+impl Resolver {
+    pub fn new(exchanger: Arc<dyn Exchanger>) -> Self {
+        Self {
+            exchanger,
+            cache: None,
+            hints: root_hints(),
+            max_queries: 64,
+            max_depth: 4,
+        }
+    }
+
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Servers to start from instead of the root servers.
+    pub fn hints(mut self, hints: Vec<SocketAddr>) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    pub fn max_queries(mut self, max_queries: usize) -> Self {
+        self.max_queries = max_queries;
+        self
+    }
+
+    /// How many name server address lookups may be nested inside each
+    /// other.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Falls back to stale cache entries when resolution fails
+    /// (RFC 8767 section 5).
+    pub fn resolve(&self, qname: &Fqdn, qtype: QueryType) -> ResolverResult<Resolution> {
+        match self.resolve_with(qname, qtype, &mut State::default()) {
+            Err(error) => self.stale(qname, qtype).ok_or(error),
+            resolution => resolution,
+        }
+    }
+
+    /// Refreshes the popular cache entries that are about to expire. Meant
+    /// to run periodically, away from the path of client queries.
+    pub fn prefetch(&self) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+
+        for (name, record_type, class) in cache.take_prefetch() {
+            if class != RecordClass::IN {
+                continue;
+            }
+            let mut state = State {
+                refresh: true,
+                ..State::default()
+            };
+            // A failed refresh leaves the entry to expire as usual
+            let _ = self.resolve_with(&name, QueryType::Type(record_type), &mut state);
+        }
+    }
+
+    /// Builds the response to a client query with RA set. Failures to
+    /// resolve become SERVFAIL and classes other than IN are refused.
+    pub fn answer(&self, query: &Message) -> Message {
+        let question = query.question();
+        let resolution = match question.qclass() {
+            QueryClass::Class(RecordClass::IN) => self.resolve(question.qname(), question.qtype()),
+            _ => Ok(Resolution::new(Rcode::Refused, Vec::new(), Vec::new())),
+        };
+        let resolution = resolution.unwrap_or(Resolution::new(Rcode::ServerFailure, Vec::new(), Vec::new()));
+
+        let flags = HeaderFlagsBuilder::new()
+            .response(query.header().flags())
+            .authoritative_answer(false)
+            .truncation(false)
+            .recursion_available(true)
+            .response_code(resolution.response_code)
+            .build();
+        let header = HeaderBuilder::new()
+            .id(query.header().id())
+            .flags(flags)
+            .question_count(1)
+            .answer_count(resolution.answers.len() as u16)
+            .authoritative_count(resolution.authorities.len() as u16)
+            .build();
+
+        MessageBuilder::new()
+            .header(header)
+            .question(question.clone())
+            .answer(resolution.answers)
+            .authority(resolution.authorities)
+            .build()
+    }
+
+    /// Resolves the name and follows CNAMEs, both those answered by the
+    /// same server and those that need another walk from the root.
+    fn resolve_with(&self, qname: &Fqdn, qtype: QueryType, state: &mut State) -> ResolverResult<Resolution> {
+        let mut answers: Vec<Record> = Vec::new();
+        let mut name = qname.clone();
+        let mut visited = vec![name.clone()];
+        let mut skip_cache = std::mem::take(&mut state.refresh);
+
+        loop {
+            match self.cached(&name, qtype).filter(|_| !skip_cache) {
+                Some(Cached::Records(records)) if is_cname_step(&records, qtype) => {
+                    name = follow_cname(&records[0], &mut answers, &mut visited)?;
+                    continue;
+                }
+                Some(Cached::Records(records)) => {
+                    answers.extend(records);
+                    return Ok(Resolution::new(Rcode::NoError, answers, Vec::new()));
+                }
+                Some(Cached::NameError(soa)) => return Ok(Resolution::new(Rcode::NameError, answers, vec![soa])),
+                Some(Cached::NoData(soa)) => return Ok(Resolution::new(Rcode::NoError, answers, vec![soa])),
+                None => {}
+            }
+            skip_cache = false;
+
+            let asked = name.clone();
+            let (zone, response) = self.iterate(&name, qtype, state)?;
+            let response = in_bailiwick(&response, &zone);
+            if let Some(cache) = &self.cache {
+                cache.insert_response(&response);
+            }
+
+            loop {
+                let matching: Vec<&Record> = response
+                    .answers()
+                    .iter()
+                    .filter(|record| record.owner_name() == &name && matches_type(qtype, record.record_type()))
+                    .collect();
+                if !matching.is_empty() {
+                    answers.extend(matching.into_iter().cloned());
+                    return Ok(Resolution::new(Rcode::NoError, answers, Vec::new()));
+                }
+
+                let Some(cname) = response
+                    .answers()
+                    .iter()
+                    .find(|record| record.owner_name() == &name && record.record_type() == RecordType::CNAME)
+                else {
+                    break;
+                };
+                name = follow_cname(cname, &mut answers, &mut visited)?;
+            }
+
+            // A negative answer covers the end of the chain only if it
+            // comes from the zone of that name
+            let soa = response
+                .authorities()
+                .iter()
+                .find(|record| record.record_type() == RecordType::SOA && name.is_subdomain_of(record.owner_name()));
+            if soa.is_some() || name == asked {
+                let response_code = response.header().flags().response_code();
+                return Ok(Resolution::new(response_code, answers, soa.into_iter().cloned().collect()));
+            }
+        }
+    }
+
+    /// The cached answer for the name, or the CNAME to follow from it.
+    fn cached(&self, name: &Fqdn, qtype: QueryType) -> Option<Cached> {
+        let cache = self.cache.as_ref()?;
+        let QueryType::Type(record_type) = qtype else {
+            return None;
+        };
+
+        if let Some(hit) = cache.get(name, record_type, RecordClass::IN) {
+            return Some(hit.into_cached());
+        }
+        match cache.get(name, RecordType::CNAME, RecordClass::IN)?.into_cached() {
+            cnames @ Cached::Records(_) => Some(cnames),
+            _ => None,
+        }
+    }
+
+    /// A stale answer for the name itself, without following CNAMEs.
+    fn stale(&self, name: &Fqdn, qtype: QueryType) -> Option<Resolution> {
+        let QueryType::Type(record_type) = qtype else {
+            return None;
+        };
+
+        let resolution = match self.cache.as_ref()?.get_stale(name, record_type, RecordClass::IN)?.into_cached() {
+            Cached::Records(records) => Resolution::new(Rcode::NoError, records, Vec::new()),
+            Cached::NameError(soa) => Resolution::new(Rcode::NameError, Vec::new(), vec![soa]),
+            Cached::NoData(soa) => Resolution::new(Rcode::NoError, Vec::new(), vec![soa]),
+        };
+        Some(resolution)
+    }
+
+    /// The deepest zone above the name with cached name servers whose
+    /// addresses are cached too. A DS question starts above the name, as
+    /// the DS RRset belongs to the parent side of a zone cut.
+    fn closest_delegation(&self, name: &Fqdn, qtype: QueryType) -> Option<(Fqdn, Vec<SocketAddr>)> {
+        let cache = self.cache.as_ref()?;
+        let deepest = match qtype {
+            QueryType::Type(RecordType::DS) => name.labels().len().saturating_sub(1),
+            _ => name.labels().len(),
+        };
+
+        (0..=deepest).rev().map(|labels| name.ancestor(labels)).find_map(|zone| {
+            let Cached::Records(name_servers) = cache.get(&zone, RecordType::NS, RecordClass::IN)?.into_cached() else {
+                return None;
+            };
+            let servers: Vec<SocketAddr> = name_servers
+                .iter()
+                .filter_map(|record| match record.data() {
+                    RecordData::NS(ns) => Some(ns.name_server().clone()),
+                    _ => None,
+                })
+                .flat_map(|name_server| {
+                    [RecordType::A, RecordType::AAAA]
+                        .into_iter()
+                        .filter_map(move |record_type| cache.get(&name_server, record_type, RecordClass::IN))
+                })
+                .filter_map(|hit| match hit.into_cached() {
+                    Cached::Records(records) => Some(records),
+                    _ => None,
+                })
+                .flatten()
+                .filter_map(|record| address(&record))
+                .collect();
+
+            (!servers.is_empty()).then_some((zone, servers))
+        })
+    }
+
+    /// Walks down from the closest known zone, or the hints, until a
+    /// server answers for the name. Returns the zone that server was
+    /// asked as with its response.
+    fn iterate(&self, name: &Fqdn, qtype: QueryType, state: &mut State) -> ResolverResult<(Fqdn, Message)> {
+        let (mut zone, mut servers) = self
+            .closest_delegation(name, qtype)
+            .unwrap_or_else(|| (Fqdn::root(), self.hints.clone()));
+
+        loop {
+            match self.ask(&servers, &zone, name, qtype, state)? {
+                Reply::Final(response) => return Ok((zone, response)),
+                Reply::Referral(cut, response) => {
+                    servers = self.server_addresses(&response, &zone, &cut, state)?;
+                    if servers.is_empty() {
+                        return Err(ResolverError::NoUsableServer(cut));
+                    }
+                    zone = cut;
+                }
+            }
+        }
+    }
+
+    /// Tries the servers of a zone in turn until one gives a response
+    /// that is either final or refers closer to the name. Unreachable
+    /// servers, errors and lame referrals move on to the next server.
+    fn ask(
+        &self,
+        servers: &[SocketAddr],
+        zone: &Fqdn,
+        name: &Fqdn,
+        qtype: QueryType,
+        state: &mut State,
+    ) -> ResolverResult<Reply> {
+        let query = build_query(name, qtype, false);
+
+        for server in servers {
+            if state.queries >= self.max_queries {
+                return Err(ResolverError::BudgetExhausted(self.max_queries));
+            }
+            state.queries += 1;
+
+            let Ok(response) = self.exchanger.exchange(*server, &query) else {
+                continue;
+            };
+            if response.header().id() != query.header().id() || response.question() != query.question() {
+                continue;
+            }
+            if !matches!(response.header().flags().response_code(), Rcode::NoError | Rcode::NameError) {
+                continue;
+            }
+
+            match referral_cut(&response) {
+                None => return Ok(Reply::Final(response)),
+                Some(cut) if cut != *zone && cut.is_subdomain_of(zone) && name.is_subdomain_of(&cut) => {
+                    return Ok(Reply::Referral(cut, response))
+                }
+                Some(_) => continue,
+            }
+        }
+
+        Err(ResolverError::NoUsableServer(zone.clone()))
+    }
+
+    /// Addresses of the name servers of a referral: the glue the
+    /// referring zone is authoritative for, or failing that the IPv4
+    /// addresses found by resolving the name servers.
+    fn server_addresses(
+        &self,
+        response: &Message,
+        zone: &Fqdn,
+        cut: &Fqdn,
+        state: &mut State,
+    ) -> ResolverResult<Vec<SocketAddr>> {
+        let delegation: Vec<Record> = response
+            .authorities()
+            .iter()
+            .filter(|record| record.owner_name() == cut && record.record_type() == RecordType::NS)
+            .cloned()
+            .collect();
+        let name_servers: Vec<Fqdn> = delegation
+            .iter()
+            .filter_map(|record| match record.data() {
+                RecordData::NS(ns) => Some(ns.name_server().clone()),
+                _ => None,
+            })
+            .collect();
+        let glue: Vec<Record> = response
+            .additionals()
+            .iter()
+            .filter(|record| name_servers.contains(record.owner_name()) && record.owner_name().is_subdomain_of(zone))
+            .filter(|record| address(record).is_some())
+            .cloned()
+            .collect();
+
+        if let Some(cache) = &self.cache {
+            cache.insert_records(&delegation);
+            cache.insert_records(&glue);
+        }
+        if !glue.is_empty() {
+            return Ok(glue.iter().filter_map(address).collect());
+        }
+
+        for name_server in name_servers {
+            if state.pending.contains(&name_server) {
+                continue;
+            }
+            if state.depth >= self.max_depth {
+                return Err(ResolverError::TooDeep(self.max_depth));
+            }
+
+            state.depth += 1;
+            state.pending.push(name_server.clone());
+            let resolution = self.resolve_with(&name_server, QueryType::Type(RecordType::A), state);
+            state.pending.pop();
+            state.depth -= 1;
+
+            match resolution {
+                Ok(resolution) => {
+                    let addresses: Vec<SocketAddr> = resolution.answers.iter().filter_map(address).collect();
+                    if !addresses.is_empty() {
+                        return Ok(addresses);
+                    }
+                }
+                Err(error @ (ResolverError::BudgetExhausted(_) | ResolverError::TooDeep(_))) => return Err(error),
+                Err(_) => {}
+            }
+        }
+
+        Ok(Vec::new())
+    }
+}
+
+/// A query with a random id, which together with the question is what
+/// ties a response to it.
+pub fn build_query(qname: &Fqdn, qtype: QueryType, recursion_desired: bool) -> Message {
+    let id = random_id();
+    let flags = HeaderFlagsBuilder::new()
+        .query()
+        .recursion_desired(recursion_desired)
+        .build();
+    let question = QuestionBuilder::new()
+        .question_name(qname.clone())
+        .question_type(qtype)
+        .question_class(QueryClass::Class(RecordClass::IN))
+        .build();
+
+    MessageBuilder::new()
+        .header(HeaderBuilder::new().id(id).flags(flags).question_count(1).build())
+        .question(question)
+        .build()
+}
+
+/// The zone cut a response refers to: no answers, no SOA and NS records
+/// in the authority section.
+fn referral_cut(response: &Message) -> Option<Fqdn> {
+    let authorities = response.authorities();
+    let is_referral = response.answers().is_empty()
+        && response.header().flags().response_code() == Rcode::NoError
+        && !authorities.iter().any(|record| record.record_type() == RecordType::SOA);
+    if !is_referral {
+        return None;
+    }
+
+    authorities
+        .iter()
+        .find(|record| record.record_type() == RecordType::NS)
+        .map(|record| record.owner_name().clone())
+}
+
+/// A message id that off-path attackers cannot guess.
+pub(crate) fn random_id() -> u16 {
+    generate::<[u8; 2]>(&SystemRandom::new())
+        .map(|id| u16::from_be_bytes(id.expose()))
+        .unwrap_or_default()
+}
+
+/// Whether cached records are a CNAME to follow rather than the answer.
+fn is_cname_step(records: &[Record], qtype: QueryType) -> bool {
+    records[0].record_type() == RecordType::CNAME && !matches_type(qtype, RecordType::CNAME)
+}
+
+/// Adds a CNAME to the chain and returns its target, failing on loops and
+/// overlong chains.
+fn follow_cname(cname: &Record, answers: &mut Vec<Record>, visited: &mut Vec<Fqdn>) -> ResolverResult<Fqdn> {
+    if answers.len() >= MAX_CNAME_CHAIN {
+        return Err(ResolverError::CnameChainTooLong(MAX_CNAME_CHAIN));
+    }
+    let RecordData::CNAME(data) = cname.data() else {
+        unreachable!("record type is CNAME");
+    };
+    let target = data.canonical_name().clone();
+    answers.push(cname.clone());
+
+    if visited.contains(&target) {
+        return Err(ResolverError::CnameLoop(target));
+    }
+    visited.push(target.clone());
+
+    Ok(target)
+}
+
+/// The answer and authority records of a response at or below the zone
+/// its server was asked as. Anything else it has no say over, and
+/// believing it would let any server poison names of other zones.
+fn in_bailiwick(response: &Message, zone: &Fqdn) -> Message {
+    let within = |section: &[Record]| -> Vec<Record> {
+        section
+            .iter()
+            .filter(|record| record.owner_name().is_subdomain_of(zone))
+            .cloned()
+            .collect()
+    };
+
+    MessageBuilder::new()
+        .header(response.header().clone())
+        .question(response.question().clone())
+        .answer(within(response.answers()))
+        .authority(within(response.authorities()))
+        .build()
+}
+
+fn matches_type(qtype: QueryType, record_type: RecordType) -> bool {
+    match qtype {
+        QueryType::Type(wanted) => wanted == record_type,
+        QueryType::ANY => true,
+        _ => false,
+    }
+}
+
+fn address(record: &Record) -> Option<SocketAddr> {
+    match record.data() {
+        RecordData::A(a) => Some(SocketAddr::new(IpAddr::V4(a.address()), DNS_PORT)),
+        RecordData::AAAA(aaaa) => Some(SocketAddr::new(IpAddr::V6(aaaa.address()), DNS_PORT)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod resolver_unittest {
+    use crate::cache::{Cache, CacheHit, Cached};
+    use crate::clock::ManualClock;
+    use crate::packet::fqdn::Fqdn;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::record::Record;
+    use crate::records::query_type::QueryType;
+    use crate::records::record_class::RecordClass;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::Exchanger;
+    use crate::resolver::{build_query, Resolver, ResolverError};
+    use crate::server::handler::handler_unittest::parse;
+    use crate::server::handler::{respond, Transport};
+    use crate::zone::catalog::Catalog;
+    use crate::zone::zone_unittest::name;
+    use crate::zone::Zone;
+    use crate::zonefile::parser::ZoneParser;
+    use std::collections::HashMap;
+    use std::io::{self, ErrorKind};
+    use std::net::{IpAddr, SocketAddr};
+    use std::sync::{Arc, Mutex};
+
+    const ROOT: &str = "\
+.                   SOA a.root-servers.net. nstld.verisign-grs.com. 1 1800 900 604800 86400
+.                   NS  a.root-servers.net.
+a.root-servers.net. A   198.41.0.4
+com.                NS  a.gtld-servers.net.
+net.                NS  a.gtld-servers.net.
+a.gtld-servers.net. A   192.5.6.30
+";
+
+    const COM: &str = "\
+com.                SOA a.gtld-servers.net. nstld.verisign-grs.com. 1 1800 900 604800 86400
+com.                NS  a.gtld-servers.net.
+example.com.        NS  ns1.example.com.
+ns1.example.com.    A   192.0.2.1
+glueless.com.       NS  ns.example.net.
+lame.com.           NS  ns.lame.com.
+ns.lame.com.        A   192.0.2.99
+loop.com.           NS  ns.loop.net.
+";
+
+    const NET: &str = "\
+net.                SOA a.gtld-servers.net. nstld.verisign-grs.com. 1 1800 900 604800 86400
+net.                NS  a.gtld-servers.net.
+a.gtld-servers.net. A   192.5.6.30
+example.net.        NS  ns.example.net.
+ns.example.net.     A   192.0.2.2
+loop.net.           NS  ns.loop.com.
+";
+
+    const EXAMPLE_COM: &str = "\
+example.com.        SOA ns1.example.com. hostmaster.example.com. 1 7200 3600 1209600 300
+example.com.        NS  ns1.example.com.
+ns1.example.com.    A   192.0.2.1
+www.example.com.    A   192.0.2.10
+alias.example.com.  CNAME www.example.com.
+external.example.com. CNAME www.example.net.
+cycle.example.com.  CNAME cycle.example.net.
+";
+
+    const EXAMPLE_NET: &str = "\
+example.net.        SOA ns.example.net. hostmaster.example.net. 1 7200 3600 1209600 300
+example.net.        NS  ns.example.net.
+ns.example.net.     A   192.0.2.2
+www.example.net.    A   192.0.2.20
+cycle.example.net.  CNAME cycle.example.com.
+";
+
+    const GLUELESS_COM: &str = "\
+glueless.com.       SOA ns.example.net. hostmaster.example.net. 1 7200 3600 1209600 300
+glueless.com.       NS  ns.example.net.
+www.glueless.com.   A   192.0.2.30
+";
+
+    /// Name servers answering from in-memory zones through the server
+    /// handler, so every exchange goes through the wire format.
+    pub(crate) struct SimulatedNetwork {
+        servers: HashMap<IpAddr, Catalog>,
+        queries: Mutex<Vec<(IpAddr, Fqdn)>>,
+    }
+
+    impl SimulatedNetwork {
+        pub(crate) fn queries(&self) -> Vec<(IpAddr, Fqdn)> {
+            self.queries.lock().unwrap().clone()
+        }
+    }
+
+    impl Exchanger for SimulatedNetwork {
+        fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+            self.queries.lock().unwrap().push((server.ip(), query.question().qname().clone()));
+            let catalog = self.servers.get(&server.ip()).ok_or(ErrorKind::TimedOut)?;
+
+            let wire = respond(catalog, &query.wire(), Transport::Tcp).ok_or(ErrorKind::InvalidData)?;
+            Ok(parse(&wire))
+        }
+    }
+
+    fn zone(apex: &str, text: &str) -> Zone {
+        let records = ZoneParser::new(Fqdn::root()).default_ttl(3600).parse_str(apex, text).unwrap();
+        Zone::new(name(apex), records).unwrap()
+    }
+
+    pub(crate) fn hierarchy() -> SimulatedNetwork {
+        let mut servers: HashMap<IpAddr, Catalog> = HashMap::new();
+        for (address, apex, text) in [
+            ("198.41.0.4", ".", ROOT),
+            ("192.5.6.30", "com.", COM),
+            ("192.5.6.30", "net.", NET),
+            ("192.0.2.1", "example.com.", EXAMPLE_COM),
+            ("192.0.2.2", "example.net.", EXAMPLE_NET),
+            ("192.0.2.2", "glueless.com.", GLUELESS_COM),
+        ] {
+            servers.entry(address.parse().unwrap()).or_default().insert(zone(apex, text));
+        }
+
+        SimulatedNetwork {
+            servers,
+            queries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn root() -> Vec<SocketAddr> {
+        vec!["198.41.0.4:53".parse().unwrap()]
+    }
+
+    pub(crate) fn query(qname: &str, qtype: RecordType) -> Message {
+        build_query(&name(qname), QueryType::Type(qtype), false)
+    }
+
+    fn resolver(network: &Arc<SimulatedNetwork>) -> Resolver {
+        Resolver::new(network.clone()).hints(root())
+    }
+
+    fn answer_data(network: &Arc<SimulatedNetwork>, qname: &str, qtype: RecordType) -> Vec<String> {
+        resolver(network)
+            .resolve(&name(qname), QueryType::Type(qtype))
+            .unwrap()
+            .answers()
+            .iter()
+            .map(|record| format!("{} {}", record.owner_name(), record.data()))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_from_root() {
+        let network = Arc::new(hierarchy());
+
+        assert_eq!(answer_data(&network, "www.example.com.", RecordType::A), vec!["www.example.com. 192.0.2.10"]);
+        let asked: Vec<String> = network.queries().iter().map(|(address, _)| address.to_string()).collect();
+        assert_eq!(asked, vec!["198.41.0.4", "192.5.6.30", "192.0.2.1"]);
+    }
+
+    #[test]
+    fn skip_unreachable_hints() {
+        let network = Arc::new(hierarchy());
+        let hints = vec!["192.0.2.250:53".parse().unwrap(), root()[0]];
+        let resolution = Resolver::new(network.clone())
+            .hints(hints)
+            .resolve(&name("www.example.com."), QueryType::Type(RecordType::A))
+            .unwrap();
+
+        assert_eq!(resolution.answers().len(), 1);
+        assert_eq!(network.queries().len(), 4);
+    }
+
+    #[test]
+    fn chase_cnames() {
+        let network = Arc::new(hierarchy());
+
+        assert_eq!(
+            answer_data(&network, "alias.example.com.", RecordType::A),
+            vec!["alias.example.com. www.example.com.", "www.example.com. 192.0.2.10"]
+        );
+        assert_eq!(
+            answer_data(&network, "external.example.com.", RecordType::A),
+            vec!["external.example.com. www.example.net.", "www.example.net. 192.0.2.20"]
+        );
+        assert_eq!(
+            resolver(&network).resolve(&name("cycle.example.com."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::CnameLoop(name("cycle.example.com.")))
+        );
+    }
+
+    #[test]
+    fn negative_answers() {
+        let network = Arc::new(hierarchy());
+        let resolver = resolver(&network);
+
+        let nxdomain = resolver.resolve(&name("missing.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(nxdomain.response_code(), Rcode::NameError);
+        assert_eq!(nxdomain.authorities()[0].owner_name(), &name("example.com."));
+
+        let nodata = resolver.resolve(&name("www.example.com."), QueryType::Type(RecordType::AAAA)).unwrap();
+        assert_eq!(nodata.response_code(), Rcode::NoError);
+        assert!(nodata.answers().is_empty());
+        assert_eq!(nodata.authorities().len(), 1);
+
+        let after_cname = resolver.resolve(&name("external.example.com."), QueryType::Type(RecordType::AAAA)).unwrap();
+        assert_eq!(after_cname.answers().len(), 1);
+        assert_eq!(after_cname.authorities()[0].owner_name(), &name("example.net."));
+    }
+
+    #[test]
+    fn resolve_glueless_delegation() {
+        let network = Arc::new(hierarchy());
+
+        assert_eq!(answer_data(&network, "www.glueless.com.", RecordType::A), vec!["www.glueless.com. 192.0.2.30"]);
+        assert!(network.queries().iter().any(|(_, qname)| qname == &name("ns.example.net.")));
+    }
+
+    #[test]
+    fn error_on_unresolvable_delegations() {
+        let network = Arc::new(hierarchy());
+        let resolver = resolver(&network);
+
+        assert_eq!(
+            resolver.resolve(&name("www.lame.com."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::NoUsableServer(name("lame.com.")))
+        );
+        assert_eq!(
+            resolver.resolve(&name("www.loop.com."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::NoUsableServer(name("loop.com.")))
+        );
+    }
+
+    #[test]
+    fn enforce_limits() {
+        let network = Arc::new(hierarchy());
+
+        assert_eq!(
+            resolver(&network).max_queries(2).resolve(&name("www.example.com."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::BudgetExhausted(2))
+        );
+        assert_eq!(
+            resolver(&network).max_depth(0).resolve(&name("www.glueless.com."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::TooDeep(0))
+        );
+    }
+
+    #[test]
+    fn answer_client_query() {
+        let network = Arc::new(hierarchy());
+        let resolver = resolver(&network);
+
+        let response = resolver.answer(&build_query(&name("alias.example.com."), QueryType::Type(RecordType::A), true));
+        assert!(response.header().flags().is_recursion_available());
+        assert!(response.header().flags().is_recursion_desired());
+        assert!(!response.header().flags().is_authoritative_answer());
+        assert_eq!(response.header().answer_count(), 2);
+        assert_eq!(response.answers().len(), 2);
+
+        let response = resolver.answer(&build_query(&name("www.lame.com."), QueryType::Type(RecordType::A), true));
+        assert_eq!(response.header().flags().response_code(), Rcode::ServerFailure);
+    }
+
+    #[test]
+    fn answer_from_cache() {
+        let network = Arc::new(hierarchy());
+        let clock = Arc::new(ManualClock::new(0));
+        let resolver = resolver(&network).cache(Arc::new(Cache::new(clock.clone())));
+        let www = name("www.example.com.");
+
+        resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        clock.advance(600);
+        let cached = resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(cached.answers()[0].ttl(), 3000);
+        assert_eq!(network.queries().len(), 3);
+
+        resolver.resolve(&name("missing.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        let nxdomain = resolver.resolve(&name("missing.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(nxdomain.response_code(), Rcode::NameError);
+        assert_eq!(network.queries().len(), 4);
+
+        let alias = resolver.resolve(&name("alias.example.com."), QueryType::Type(RecordType::AAAA)).unwrap();
+        assert_eq!(alias.answers().len(), 1);
+        assert_eq!(network.queries().len(), 5);
+    }
+
+    #[test]
+    fn start_from_cached_delegation() {
+        let network = Arc::new(hierarchy());
+        let resolver = resolver(&network).cache(Arc::new(Cache::new(Arc::new(ManualClock::new(0)))));
+
+        resolver.resolve(&name("www.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        resolver.resolve(&name("alias.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        resolver.resolve(&name("www.example.net."), QueryType::Type(RecordType::A)).unwrap();
+
+        let asked: Vec<String> = network.queries().iter().map(|(address, _)| address.to_string()).collect();
+        assert_eq!(asked, vec!["198.41.0.4", "192.5.6.30", "192.0.2.1", "192.0.2.1", "198.41.0.4", "192.5.6.30", "192.0.2.2"]);
+    }
+
+    #[test]
+    fn serve_stale_when_servers_fail() {
+        let network = Arc::new(hierarchy());
+        let clock = Arc::new(ManualClock::new(0));
+        let cache = Arc::new(Cache::new(clock.clone()));
+        resolver(&network).cache(cache.clone()).resolve(&name("www.example.com."), QueryType::Type(RecordType::A)).unwrap();
+
+        clock.advance(7200);
+        let unreachable = Resolver::new(network.clone()).hints(vec!["192.0.2.250:53".parse().unwrap()]).cache(cache);
+        let stale = unreachable.resolve(&name("www.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(stale.answers().len(), 1);
+        assert_eq!(stale.answers()[0].ttl(), 30);
+
+        assert_eq!(
+            unreachable.resolve(&name("www.example.net."), QueryType::Type(RecordType::A)),
+            Err(ResolverError::NoUsableServer(Fqdn::root()))
+        );
+    }
+
+    #[test]
+    fn prefetch_popular_entries() {
+        let network = Arc::new(hierarchy());
+        let clock = Arc::new(ManualClock::new(0));
+        let cache = Arc::new(Cache::new(clock.clone()).prefetch_hits(2));
+        let resolver = resolver(&network).cache(cache.clone());
+        let www = name("www.example.com.");
+
+        resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        clock.advance(3300);
+        resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(network.queries().len(), 3);
+
+        resolver.prefetch();
+        assert_eq!(network.queries().len(), 4);
+        let refreshed = resolver.resolve(&www, QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(refreshed.answers()[0].ttl(), 3600);
+        assert!(cache.take_prefetch().is_empty());
+    }
+
+    /// The network, with records added to the answers of one server.
+    struct Injecting {
+        network: SimulatedNetwork,
+        server: IpAddr,
+        records: Vec<Record>,
+    }
+
+    impl Exchanger for Injecting {
+        fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+            let response = self.network.exchange(server, query)?;
+            if server.ip() != self.server {
+                return Ok(response);
+            }
+
+            let mut answers = response.answers().to_vec();
+            answers.extend(self.records.iter().cloned());
+            Ok(MessageBuilder::new()
+                .header(response.header().clone())
+                .question(response.question().clone())
+                .answer(answers)
+                .authority(response.authorities().to_vec())
+                .additional(response.additionals().to_vec())
+                .build())
+        }
+    }
+
+    #[test]
+    fn ignore_records_out_of_bailiwick() {
+        let forged = ZoneParser::new(Fqdn::root())
+            .default_ttl(3600)
+            .parse_str("forged", "www.example.net. A 6.6.6.6\ncom. NS ns.evil.example.\n")
+            .unwrap();
+        let network = Arc::new(Injecting {
+            network: hierarchy(),
+            server: "192.0.2.1".parse().unwrap(),
+            records: forged,
+        });
+        let cache = Arc::new(Cache::new(Arc::new(ManualClock::new(0))));
+        let resolver = Resolver::new(network).hints(root()).cache(cache.clone());
+
+        let external = resolver.resolve(&name("external.example.com."), QueryType::Type(RecordType::A)).unwrap();
+        let addresses: Vec<String> = external.answers().iter().map(|record| record.data().to_string()).collect();
+        assert_eq!(addresses, vec!["www.example.net.", "192.0.2.20"]);
+
+        let www = resolver.resolve(&name("www.example.net."), QueryType::Type(RecordType::A)).unwrap();
+        assert_eq!(www.answers()[0].data().to_string(), "192.0.2.20");
+        let Some(Cached::Records(com)) = cache.get(&name("com."), RecordType::NS, RecordClass::IN).map(CacheHit::into_cached) else {
+            panic!("the com. delegation is cached");
+        };
+        assert!(com.iter().all(|record| record.data().to_string() == "a.gtld-servers.net."));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::packet::framing::{frame, unframe};
+use crate::packet::message::Message;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::resolver::exchange::{parse_response, Exchanger};
+use crate::server::quic::{DoqError, DOQ_ALPN, MAX_STREAM_LENGTH};
+use crate::tls::{ServerIdentity, TlsError, TlsResult};
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{ClientConfig, Connection, Endpoint};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::time::{timeout_at, Instant};
+
+/// Queries over DNS over QUIC (RFC 9250), refusing any server that does
+/// not prove the configured identity. Connections stay open and every
+/// query takes a stream of its own; queries go out with id 0 as the RFC
+/// requires, and responses get the query id back.
+pub struct QuicExchanger {
+    runtime: Runtime,
+    config: ClientConfig,
+    name: String,
+    endpoints: Mutex<HashMap<bool, Endpoint>>,
+    connections: Mutex<HashMap<SocketAddr, Connection>>,
+    timeout: Duration,
+}
+
+impl QuicExchanger {
+    pub fn new(identity: &ServerIdentity) -> TlsResult<Self> {
+        let tls = QuicClientConfig::try_from(identity.client_config(&[DOQ_ALPN])?)
+            .map_err(|error| TlsError::Tls(rustls::Error::General(error.to_string())))?;
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build()?;
+
+        Ok(Self {
+            runtime,
+            config: ClientConfig::new(Arc::new(tls)),
+            name: identity.name().to_str().into_owned(),
+            endpoints: Mutex::new(HashMap::new()),
+            connections: Mutex::new(HashMap::new()),
+            timeout: Duration::from_secs(2),
+        })
+    }
+
+    /// How long to wait for each server before giving up on it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The endpoint for the server's address family, bound on first use.
+    fn endpoint(&self, server: SocketAddr) -> io::Result<Endpoint> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        if let Some(endpoint) = endpoints.get(&server.is_ipv4()) {
+            return Ok(endpoint.clone());
+        }
+
+        let local = match server {
+            SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+            SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+        };
+        let _guard = self.runtime.enter();
+        let mut endpoint = Endpoint::client(local)?;
+        endpoint.set_default_client_config(self.config.clone());
+        endpoints.insert(server.is_ipv4(), endpoint.clone());
+
+        Ok(endpoint)
+    }
+
+    /// The open connection to the server, or a new one.
+    async fn connection(&self, server: SocketAddr) -> io::Result<Connection> {
+        let open = self.connections.lock().unwrap().get(&server).cloned();
+        if let Some(connection) = open.filter(|connection| connection.close_reason().is_none()) {
+            return Ok(connection);
+        }
+
+        let connecting = self.endpoint(server)?.connect(server, &self.name).map_err(io::Error::other)?;
+        let connection = connecting.await?;
+        self.connections.lock().unwrap().insert(server, connection.clone());
+
+        Ok(connection)
+    }
+
+    async fn exchange_quic(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let mut encoder = Serialize::new();
+        query.to_bytes(&mut encoder);
+        let mut wire = encoder.bin_data();
+        wire[..2].fill(0);
+
+        let deadline = Instant::now() + self.timeout;
+        let connection = timeout_at(deadline, self.connection(server)).await.map_err(|_| timed_out())??;
+        let (mut send, mut recv) = timeout_at(deadline, connection.open_bi()).await.map_err(|_| timed_out())??;
+        let exchange = async {
+            send.write_all(&frame(&wire)?).await?;
+            send.finish().map_err(io::Error::other)?;
+            recv.read_to_end(MAX_STREAM_LENGTH).await.map_err(io::Error::other)
+        };
+
+        let data = match timeout_at(deadline, exchange).await {
+            Ok(data) => data?,
+            Err(_) => {
+                // Tell the server not to bother answering
+                let _ = send.reset(DoqError::RequestCancelled.into());
+                let _ = recv.stop(DoqError::RequestCancelled.into());
+                return Err(timed_out());
+            }
+        };
+
+        let Some(response) = unframe(&data).filter(|response| response.len() >= 2) else {
+            connection.close(DoqError::ProtocolError.into(), b"malformed response");
+            return Err(io::Error::new(ErrorKind::InvalidData, "response is not a DNS message"));
+        };
+        let mut response = response.to_vec();
+        response[..2].copy_from_slice(&query.header().id().to_be_bytes());
+        parse_response(&response, query).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "response does not match the query"))
+    }
+}
+
+impl Exchanger for QuicExchanger {
+    fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        self.runtime.block_on(self.exchange_quic(server, query))
+    }
+}
+
+impl Drop for QuicExchanger {
+    fn drop(&mut self) {
+        for connection in self.connections.lock().unwrap().values() {
+            connection.close(DoqError::NoError.into(), b"");
+        }
+    }
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(ErrorKind::TimedOut, "no response from name server")
+}
+
+#[cfg(test)]
+mod quic_exchange_unittest {
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::Exchanger;
+    use crate::resolver::quic::QuicExchanger;
+    use crate::resolver::resolver_unittest::query;
+    use crate::server::quic::quic_server_unittest::start;
+    use crate::tls::{spki_pin, ServerIdentity};
+
+    #[test]
+    fn exchange_on_one_connection() {
+        let (address, certificate) = start("doq.example");
+        let identity = ServerIdentity::new("doq.example").unwrap().roots(std::slice::from_ref(&certificate)).unwrap();
+        let exchanger = QuicExchanger::new(&identity).unwrap();
+
+        let mut connections = Vec::new();
+        for qname in ["www.example.com.", "alias.example.com."] {
+            let query = query(qname, RecordType::A);
+            let response = exchanger.exchange(address, &query).unwrap();
+            assert_eq!(response.header().id(), query.header().id());
+            assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+            assert!(!response.answers().is_empty());
+            connections.push(exchanger.connections.lock().unwrap()[&address].stable_id());
+        }
+        assert_eq!(connections[0], connections[1]);
+
+        let pinned = ServerIdentity::new("doq.example").unwrap().pin(spki_pin(&certificate).unwrap());
+        assert!(QuicExchanger::new(&pinned).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).is_ok());
+    }
+
+    #[test]
+    fn fail_on_wrong_identity() {
+        let (address, certificate) = start("doq.example");
+        let other_name = ServerIdentity::new("other.example").unwrap().roots(&[certificate]).unwrap();
+
+        let exchanger = QuicExchanger::new(&other_name).unwrap();
+        assert!(exchanger.exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::packet::message::Message;
+use crate::resolver::exchange::{exchange_stream, Exchanger};
+use crate::server::tls::DOT_ALPN;
+use crate::tls::{ServerIdentity, TlsResult};
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Queries over DNS over TLS (RFC 7858), refusing any server that does
+/// not prove the configured identity.
+#[derive(Debug, Clone)]
+pub struct TlsExchanger {
+    config: Arc<ClientConfig>,
+    name: ServerName<'static>,
+    timeout: Duration,
+}
+
+impl TlsExchanger {
+    pub fn new(identity: &ServerIdentity) -> TlsResult<Self> {
+        Ok(Self {
+            config: identity.client_config(&[DOT_ALPN])?,
+            name: identity.name().clone(),
+            timeout: Duration::from_secs(2),
+        })
+    }
+
+    /// How long to wait for each server before giving up on it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Exchanger for TlsExchanger {
+    fn exchange(&self, server: SocketAddr, query: &Message) -> io::Result<Message> {
+        let stream = TcpStream::connect_timeout(&server, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let connection = ClientConnection::new(Arc::clone(&self.config), self.name.clone()).map_err(io::Error::other)?;
+
+        exchange_stream(&mut StreamOwned::new(connection, stream), query)
+    }
+}
+
+#[cfg(test)]
+mod tls_exchange_unittest {
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::records::record_type::RecordType;
+    use crate::resolver::exchange::Exchanger;
+    use crate::resolver::resolver_unittest::query;
+    use crate::resolver::tls::TlsExchanger;
+    use crate::server::tls::tls_server_unittest::start;
+    use crate::tls::tls_unittest::certificate;
+    use crate::tls::{spki_pin, ServerIdentity};
+
+    #[test]
+    fn verify_server_name() {
+        let (address, certificate) = start("dns.example");
+        let trusted = ServerIdentity::new("dns.example").unwrap().roots(std::slice::from_ref(&certificate)).unwrap();
+
+        let response = TlsExchanger::new(&trusted).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).unwrap();
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        let other_name = ServerIdentity::new("other.example").unwrap().roots(&[certificate]).unwrap();
+        assert!(TlsExchanger::new(&other_name).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+
+        let web_pki = ServerIdentity::new("dns.example").unwrap();
+        assert!(TlsExchanger::new(&web_pki).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+    }
+
+    #[test]
+    fn verify_spki_pin() {
+        let (address, certificate) = start("dns.example");
+        let (other, _) = self::certificate("dns.example");
+
+        let pinned = ServerIdentity::new("any.example").unwrap().pin(spki_pin(&certificate).unwrap());
+        let response = TlsExchanger::new(&pinned).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).unwrap();
+        assert_eq!(response.answers().len(), 1);
+
+        let wrong_pin = ServerIdentity::new("dns.example").unwrap().pin(spki_pin(&other).unwrap());
+        assert!(TlsExchanger::new(&wrong_pin).unwrap().exchange(address, &query("www.example.com.", RecordType::A)).is_err());
+    }
+}
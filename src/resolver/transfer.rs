@@ -0,0 +1,334 @@
+use crate::clock::{Clock, SystemClock};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::framing::{read_frame, write_frame};
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{Rcode, QR};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::record::Record;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::TryFromBytes;
+use crate::packet::tsig::{TsigError, TsigKey, TsigStream};
+use crate::records::query_type::QueryType;
+use crate::resolver::build_query;
+use crate::resolver::exchange::NetworkExchanger;
+use crate::zone::transfer::{AxfrReader, IxfrReader, TransferError, TransferResult};
+use crate::zone::Zone;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Pulls zones from a primary, whole over TCP (RFC 5936) or as the
+/// changes since the version held (RFC 1995). The SOA of the zone must
+/// open and close the transfer. With a key the request is signed and so
+/// must the responses be, the last one included.
+#[derive(Clone)]
+pub struct ZoneTransfer {
+    key: Option<TsigKey>,
+    clock: Arc<dyn Clock>,
+    timeout: Duration,
+}
+
+impl Default for ZoneTransfer {
+    fn default() -> Self {
+        Self {
+            key: None,
+            clock: Arc::new(SystemClock),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ZoneTransfer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: TsigKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// The clock TSIG times are checked and signed against.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// How long to wait for the server before each message.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn axfr(&self, server: SocketAddr, apex: &Fqdn) -> TransferResult<Zone> {
+        let query = self.sign(build_query(apex, QueryType::AXFR, false))?;
+
+        let mut reader = AxfrReader::new(apex.clone());
+        self.stream(server, &query, |records| {
+            reader.read(records)?;
+            Ok(reader.is_complete())
+        })?;
+
+        reader.finish()
+    }
+
+    /// Brings the zone up to date, asking over UDP first and over TCP when
+    /// the changes do not fit in a datagram. The server may send the whole
+    /// zone instead of the changes. Returns whether the zone changed.
+    pub fn ixfr(&self, server: SocketAddr, zone: &mut Zone) -> TransferResult<bool> {
+        let query = self.sign(ixfr_query(zone))?;
+
+        let mut reader = IxfrReader::new(zone.apex().clone(), zone.serial());
+        if !self.ixfr_over_udp(server, &query, &mut reader)? {
+            reader = IxfrReader::new(zone.apex().clone(), zone.serial());
+            self.stream(server, &query, |records| {
+                reader.read(records)?;
+                Ok(reader.is_complete())
+            })?;
+        }
+
+        match reader.finish(zone)? {
+            Some(updated) => {
+                *zone = updated;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn sign(&self, mut query: Message) -> TransferResult<Message> {
+        if let Some(key) = &self.key {
+            query.sign_tsig(key, None, self.clock.as_ref())?;
+        }
+
+        Ok(query)
+    }
+
+    fn verifier<'a>(&'a self, query: &Message) -> Option<TsigStream<'a>> {
+        let request_mac = query.tsig().map(|tsig| tsig.mac()).unwrap_or_default();
+
+        self.key.as_ref().map(|key| TsigStream::new(key, request_mac, self.clock.as_ref()))
+    }
+
+    /// Sends the request over TCP and hands the answers of each response
+    /// to `read` until it tells the transfer is complete.
+    fn stream(
+        &self,
+        server: SocketAddr,
+        query: &Message,
+        mut read: impl FnMut(&[Record]) -> TransferResult<bool>,
+    ) -> TransferResult<()> {
+        let mut stream = TcpStream::connect_timeout(&server, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        write_frame(&mut stream, &query.wire())?;
+
+        let mut verifier = self.verifier(query);
+        loop {
+            let wire = read_frame(&mut stream)?.ok_or(TransferError::Incomplete)?;
+            let message = Message::try_from_bytes(&mut Deserialize::new(&wire))?;
+            check(query, &message, verifier.as_mut())?;
+            if read(message.answers())? {
+                break;
+            }
+        }
+
+        if let Some(verifier) = &verifier {
+            verifier.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the UDP response into the reader, returning false when the
+    /// request must go again over TCP: the server is silent, truncates or
+    /// does not do IXFR over UDP, or sent its SOA alone to say the changes
+    /// do not fit.
+    fn ixfr_over_udp(&self, server: SocketAddr, query: &Message, reader: &mut IxfrReader) -> TransferResult<bool> {
+        let exchanger = NetworkExchanger::new().timeout(self.timeout);
+        let Ok(message) = exchanger.exchange_udp(server, query) else {
+            return Ok(false);
+        };
+        let flags = message.header().flags();
+        if flags.is_truncated() || flags.response_code() == Rcode::NotImplemented {
+            return Ok(false);
+        }
+
+        let mut verifier = self.verifier(query);
+        check(query, &message, verifier.as_mut())?;
+        if let Some(verifier) = &verifier {
+            verifier.finish()?;
+        }
+        reader.read(message.answers())?;
+
+        Ok(reader.is_complete())
+    }
+}
+
+/// An IXFR request telling the version held with the SOA of the zone in
+/// the authority section (RFC 1995 section 3).
+fn ixfr_query(zone: &Zone) -> Message {
+    let query = build_query(zone.apex(), QueryType::IXFR, false);
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(query.header().flags().clone())
+        .question_count(1)
+        .authoritative_count(1)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .authority(vec![zone.soa().clone()])
+        .build()
+}
+
+/// Whether a message of the transfer answers the query and, with a key,
+/// carries a MAC that chains to the messages before.
+fn check(query: &Message, message: &Message, verifier: Option<&mut TsigStream>) -> TransferResult<()> {
+    let answers_query = message.header().id() == query.header().id()
+        && message.header().flags().query_or_response() == QR::Response
+        && message.question() == query.question();
+    if !answers_query {
+        return Err(TransferError::Mismatch);
+    }
+
+    let response_code = message.header().flags().response_code();
+    if response_code != Rcode::NoError {
+        return Err(match message.tsig() {
+            Some(tsig) if tsig.data().error() != 0 => TsigError::PeerError(tsig.data().error()).into(),
+            _ => TransferError::Refused(response_code),
+        });
+    }
+
+    if let Some(verifier) = verifier {
+        verifier.verify(message)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod transfer_client_unittest {
+    use crate::clock::ManualClock;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::tsig::TsigError;
+    use crate::resolver::transfer::ZoneTransfer;
+    use crate::server::transfer::transfer_server_unittest::{key, next_version, primary, NOW};
+    use crate::server::transfer::TransferAcl;
+    use crate::server::Server;
+    use crate::zone::transfer::transfer_unittest::big_zone;
+    use crate::zone::transfer::TransferError;
+    use crate::zone::zone_unittest::{name, records};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn start(acl: Option<TransferAcl>) -> SocketAddr {
+        let primary = match acl {
+            Some(acl) => primary().acl(acl),
+            None => primary(),
+        };
+        let server = Server::bind("127.0.0.1:0", primary).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        address
+    }
+
+    #[test]
+    fn transfer_by_address() {
+        let address = start(Some(TransferAcl::new().allow_network("127.0.0.1".parse().unwrap(), 8)));
+
+        let zone = ZoneTransfer::new().axfr(address, &name("example.com.")).unwrap();
+        assert_eq!(zone.records().collect::<Vec<_>>(), big_zone().records().collect::<Vec<_>>());
+
+        let error = ZoneTransfer::new().axfr(address, &name("www.example.com.")).unwrap_err();
+        assert!(matches!(error, TransferError::Refused(Rcode::NotAuth)));
+    }
+
+    #[test]
+    fn transfer_with_tsig() {
+        let address = start(None);
+        let transfer = ZoneTransfer::new().clock(Arc::new(ManualClock::new(NOW)));
+
+        let zone = transfer.clone().key(key(b"secret")).axfr(address, &name("example.com.")).unwrap();
+        assert_eq!(zone.serial(), big_zone().serial());
+        assert_eq!(zone.records().count(), big_zone().records().count());
+
+        let error = transfer.clone().axfr(address, &name("example.com.")).unwrap_err();
+        assert!(matches!(error, TransferError::Refused(Rcode::Refused)));
+
+        let error = transfer.key(key(b"guess")).axfr(address, &name("example.com.")).unwrap_err();
+        assert!(matches!(error, TransferError::Tsig(TsigError::PeerError(16))));
+    }
+
+    #[test]
+    fn incremental_transfer() {
+        let primary = Arc::new(primary().acl(TransferAcl::new().allow_network("127.0.0.1".parse().unwrap(), 32)));
+        let server = Server::bind("127.0.0.1:0", Arc::clone(&primary)).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        let first = big_zone();
+        let second = next_version(&first, 200);
+        let third = next_version(&second, 1);
+        primary.reload(second.clone());
+        primary.reload(third.clone());
+        let transfer = ZoneTransfer::new();
+
+        // Too many changes for a datagram, so they come over TCP
+        let mut zone = first.clone();
+        assert!(transfer.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.records().collect::<Vec<_>>(), third.records().collect::<Vec<_>>());
+        assert!(!transfer.ixfr(address, &mut zone).unwrap());
+
+        let mut zone = second.clone();
+        assert!(transfer.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.serial(), third.serial());
+
+        let fourth = next_version(&third, 200);
+        primary.reload(fourth.clone());
+        let mut zone = third.clone();
+        assert!(transfer.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.records().collect::<Vec<_>>(), fourth.records().collect::<Vec<_>>());
+
+        // Without the history the whole zone comes instead
+        primary.reload(first.clone());
+        let mut zone = fourth.clone();
+        zone.insert(records("@ SOA ns1 hostmaster 2000000000 7200 3600 1209600 300\n").remove(0))
+            .unwrap();
+        assert!(transfer.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.records().collect::<Vec<_>>(), first.records().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn incremental_transfer_with_tsig() {
+        let primary = Arc::new(primary());
+        let server = Server::bind("127.0.0.1:0", Arc::clone(&primary)).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        let first = big_zone();
+        let second = next_version(&first, 200);
+        let transfer = ZoneTransfer::new().clock(Arc::new(ManualClock::new(NOW)));
+        let signed = transfer.clone().key(key(b"secret"));
+
+        // Few enough changes for a datagram, then too many
+        primary.reload(second.clone());
+        let mut zone = first.clone();
+        assert!(signed.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.serial(), second.serial());
+
+        let third = next_version(&second, 1);
+        primary.reload(third.clone());
+        assert!(signed.ixfr(address, &mut zone).unwrap());
+        assert_eq!(zone.records().collect::<Vec<_>>(), third.records().collect::<Vec<_>>());
+
+        let mut zone = second.clone();
+        let error = transfer.clone().ixfr(address, &mut zone).unwrap_err();
+        assert!(matches!(error, TransferError::Refused(Rcode::Refused)));
+        let error = transfer.key(key(b"guess")).ixfr(address, &mut zone).unwrap_err();
+        assert!(matches!(error, TransferError::Tsig(TsigError::PeerError(16))));
+        assert_eq!(zone.serial(), second.serial());
+    }
+}
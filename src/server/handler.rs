@@ -0,0 +1,332 @@
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Opcode, Rcode};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::TryFromBytes;
+use crate::records::query_type::QueryType;
+use crate::zone::catalog::Catalog;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Largest response sent over UDP without EDNS (RFC 1035 section 4.2.1).
+pub const MAX_UDP_PAYLOAD: usize = 512;
+
+const HEADER_LENGTH: usize = 12;
+
+// Bits of the third header byte
+const QR_BIT: u8 = 0x80;
+const OPCODE_BITS: u8 = 0x78;
+const RD_BIT: u8 = 0x01;
+
+// RCODE values for the fourth header byte
+const FORMERR: u8 = 1;
+const NOTIMP: u8 = 4;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// Answers decoded standard queries. Decoding, opcodes other than QUERY
+/// and truncation are dealt with before and after it.
+pub trait Handler: Send + Sync {
+    fn handle(&self, query: &Message) -> Message;
+
+    /// The messages answering a zone transfer request from the peer. Over
+    /// UDP only IXFR gets here, and only the first message is sent. None
+    /// when the handler does not serve transfers.
+    fn transfer(&self, query: &Message, peer: IpAddr, transport: Transport) -> Option<Vec<Message>> {
+        let _ = (query, peer, transport);
+        None
+    }
+}
+
+impl<T: Handler + ?Sized> Handler for Arc<T> {
+    fn handle(&self, query: &Message) -> Message {
+        self.as_ref().handle(query)
+    }
+
+    fn transfer(&self, query: &Message, peer: IpAddr, transport: Transport) -> Option<Vec<Message>> {
+        self.as_ref().transfer(query, peer, transport)
+    }
+}
+
+/// Answers from the zone closest to the name, refusing names outside
+/// every zone.
+impl Handler for Catalog {
+    fn handle(&self, query: &Message) -> Message {
+        match self.find(query.question().qname()) {
+            Some(zone) => zone.answer(query),
+            None => error_response(query, Rcode::Refused),
+        }
+    }
+}
+
+/// Answers one message from the wire. Responses and messages too short
+/// to carry a header get no answer, so two servers cannot loop on each
+/// other's errors.
+pub fn respond(handler: &dyn Handler, wire: &[u8], transport: Transport) -> Option<Vec<u8>> {
+    let query = match decode(wire) {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
+
+    Some(encode(&route(handler, &query), transport))
+}
+
+/// Answers one datagram from the peer, which may ask for an IXFR
+/// (RFC 1995 section 2). An AXFR cannot be answered in a datagram.
+pub fn respond_datagram(handler: &dyn Handler, wire: &[u8], peer: IpAddr) -> Option<Vec<u8>> {
+    let query = match decode(wire) {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
+
+    if !is_transfer(&query) || query.question().qtype() != QueryType::IXFR {
+        return Some(encode(&route(handler, &query), Transport::Udp));
+    }
+    let response = handler
+        .transfer(&query, peer, Transport::Udp)
+        .and_then(|messages| messages.into_iter().next())
+        .unwrap_or_else(|| error_response(&query, Rcode::NotImplemented));
+
+    Some(encode(&response, Transport::Udp))
+}
+
+/// Answers one message from a stream, where a zone transfer request
+/// from the peer may take many response messages.
+pub fn respond_stream(handler: &dyn Handler, wire: &[u8], peer: IpAddr) -> Vec<Vec<u8>> {
+    let query = match decode(wire) {
+        Ok(query) => query,
+        Err(response) => return response.into_iter().collect(),
+    };
+
+    if !is_transfer(&query) {
+        return vec![encode(&route(handler, &query), Transport::Tcp)];
+    }
+    match handler.transfer(&query, peer, Transport::Tcp) {
+        Some(messages) => messages.iter().map(Message::wire).collect(),
+        None => vec![error_response(&query, Rcode::NotImplemented).wire()],
+    }
+}
+
+/// Hands standard queries to the handler. Other opcodes, and zone
+/// transfers outside the transports that carry them, get NOTIMP.
+pub fn route(handler: &dyn Handler, query: &Message) -> Message {
+    match query.header().flags().opcode() {
+        Opcode::Query if !is_transfer(query) => handler.handle(query),
+        _ => error_response(query, Rcode::NotImplemented),
+    }
+}
+
+fn is_transfer(query: &Message) -> bool {
+    query.header().flags().opcode() == Opcode::Query
+        && matches!(query.question().qtype(), QueryType::AXFR | QueryType::IXFR)
+}
+
+/// The query in the wire, or what to answer instead: nothing for
+/// responses and runts, an error for messages that do not decode.
+fn decode(wire: &[u8]) -> Result<Message, Option<Vec<u8>>> {
+    if wire.len() < HEADER_LENGTH || wire[2] & QR_BIT != 0 {
+        return Err(None);
+    }
+
+    Message::try_from_bytes(&mut Deserialize::new(wire)).map_err(|_| Some(undecodable(wire)))
+}
+
+/// A response without records that echoes the question of the query.
+pub fn error_response(query: &Message, response_code: Rcode) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .response(query.header().flags())
+        .authoritative_answer(false)
+        .truncation(false)
+        .recursion_available(false)
+        .response_code(response_code)
+        .build();
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(flags)
+        .question_count(1)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .build()
+}
+
+/// The message could not be decoded, so the response is a bare header
+/// echoing the id, opcode and RD bit. Opcodes this crate does not know
+/// get NOTIMP, anything else FORMERR.
+fn undecodable(wire: &[u8]) -> Vec<u8> {
+    let opcode = (wire[2] & OPCODE_BITS) >> 3;
+    let response_code = match Opcode::try_from(u16::from(opcode)) {
+        Ok(_) => FORMERR,
+        Err(_) => NOTIMP,
+    };
+
+    let mut response = vec![wire[0], wire[1], QR_BIT | (wire[2] & (OPCODE_BITS | RD_BIT)), response_code];
+    response.resize(HEADER_LENGTH, 0);
+
+    response
+}
+
+/// Over UDP a response that does not fit is replaced by its header and
+/// question with TC set, telling the client to retry over TCP.
+fn encode(response: &Message, transport: Transport) -> Vec<u8> {
+    let wire = response.wire();
+    if transport == Transport::Tcp || wire.len() <= MAX_UDP_PAYLOAD {
+        return wire;
+    }
+
+    let mut flags = response.header().flags().clone();
+    flags.truncation(true);
+    let header = HeaderBuilder::new()
+        .id(response.header().id())
+        .flags(flags)
+        .question_count(1)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(response.question().clone())
+        .build()
+        .wire()
+}
+
+#[cfg(test)]
+pub(crate) mod handler_unittest {
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Opcode, Rcode};
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::seder::deserializer::Deserialize;
+    use crate::packet::seder::TryFromBytes;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::{respond, Transport, MAX_UDP_PAYLOAD};
+    use crate::zone::catalog::Catalog;
+    use crate::zone::zone_unittest::{example_zone, records};
+
+    pub(crate) fn query(id: u16, opcode: Opcode, qname: &str, qtype: RecordType) -> Vec<u8> {
+        let flags = HeaderFlagsBuilder::new()
+            .query()
+            .recursion_desired(true)
+            .build();
+        let mut wire = MessageBuilder::new()
+            .header(HeaderBuilder::new().id(id).flags(flags).question_count(1).build())
+            .question(generate_question(qname, qtype))
+            .build()
+            .wire();
+        wire[2] |= opcode_value(opcode) << 3;
+
+        wire
+    }
+
+    fn opcode_value(opcode: Opcode) -> u8 {
+        match opcode {
+            Opcode::Query => 0,
+            Opcode::Iquery => 1,
+            Opcode::Status => 2,
+            Opcode::Notify => 4,
+            Opcode::Update => 5,
+        }
+    }
+
+    pub(crate) fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        catalog.insert(example_zone());
+
+        catalog
+    }
+
+    pub(crate) fn parse(wire: &[u8]) -> Message {
+        Message::try_from_bytes(&mut Deserialize::new(wire)).unwrap()
+    }
+
+    #[test]
+    fn answer_from_zone() {
+        let wire = respond(&catalog(), &query(7, Opcode::Query, "www.example.com.", RecordType::A), Transport::Udp).unwrap();
+        let response = parse(&wire);
+
+        assert_eq!(response.header().id(), 7);
+        assert!(response.header().flags().is_authoritative_answer());
+        assert!(response.header().flags().is_recursion_desired());
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 1);
+    }
+
+    #[test]
+    fn answer_unknown_types_without_data() {
+        let mx = query(7, Opcode::Query, "www.example.com.", RecordType::try_from(15).unwrap());
+        let response = parse(&respond(&catalog(), &mx, Transport::Udp).unwrap());
+
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert!(response.header().flags().is_authoritative_answer());
+        assert!(response.answers().is_empty());
+        assert_eq!(response.question().qtype().to_string(), "TYPE15");
+    }
+
+    #[test]
+    fn refuse_unknown_zone() {
+        let wire = respond(&catalog(), &query(7, Opcode::Query, "www.example.org.", RecordType::A), Transport::Udp).unwrap();
+        let response = parse(&wire);
+
+        assert_eq!(response.header().flags().response_code(), Rcode::Refused);
+        assert!(!response.header().flags().is_authoritative_answer());
+        assert_eq!(response.question().qname().to_string(), "www.example.org.");
+    }
+
+    #[test]
+    fn reject_unsupported_opcodes() {
+        let wire = respond(&catalog(), &query(7, Opcode::Status, "www.example.com.", RecordType::A), Transport::Udp).unwrap();
+        let response = parse(&wire);
+        assert_eq!(response.header().flags().response_code(), Rcode::NotImplemented);
+        assert_eq!(response.header().flags().opcode(), Opcode::Status);
+
+        let mut unassigned = query(7, Opcode::Query, "www.example.com.", RecordType::A);
+        unassigned[2] |= 3 << 3;
+        let wire = respond(&catalog(), &unassigned, Transport::Udp).unwrap();
+        assert_eq!(wire, vec![0, 7, 0x99, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn reject_malformed_queries() {
+        let mut truncated = query(7, Opcode::Query, "www.example.com.", RecordType::A);
+        truncated.truncate(20);
+        let wire = respond(&catalog(), &truncated, Transport::Udp).unwrap();
+
+        assert_eq!(wire, vec![0, 7, 0x81, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn ignore_responses_and_runts() {
+        let mut response = query(7, Opcode::Query, "www.example.com.", RecordType::A);
+        response[2] |= 0x80;
+
+        assert!(respond(&catalog(), &response, Transport::Udp).is_none());
+        assert!(respond(&catalog(), &[0, 7, 1], Transport::Udp).is_none());
+    }
+
+    #[test]
+    fn truncate_large_udp_responses() {
+        let text: String = (1..=40).map(|host| format!("big A 192.0.2.{}\n", host)).collect();
+        let mut zone = example_zone();
+        for record in records(&text) {
+            zone.insert(record).unwrap();
+        }
+        let mut catalog = Catalog::new();
+        catalog.insert(zone);
+        let big = query(7, Opcode::Query, "big.example.com.", RecordType::A);
+
+        let udp = respond(&catalog, &big, Transport::Udp).unwrap();
+        let response = parse(&udp);
+        assert!(udp.len() <= MAX_UDP_PAYLOAD);
+        assert!(response.header().flags().is_truncated());
+        assert!(response.answers().is_empty());
+
+        let tcp = parse(&respond(&catalog, &big, Transport::Tcp).unwrap());
+        assert!(!tcp.header().flags().is_truncated());
+        assert_eq!(tcp.answers().len(), 40);
+    }
+}
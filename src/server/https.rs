@@ -0,0 +1,317 @@
+use crate::packet::headers::header_flags::QR;
+use crate::packet::message::Message;
+use crate::packet::record::Record;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::{ToBytes, TryFromBytes};
+use crate::server::handler::{route, Handler};
+use crate::server::{ServerResult, TCP_IDLE_TIMEOUT};
+use crate::tls::server_config;
+use bytes::Bytes;
+use data_encoding::BASE64URL_NOPAD;
+use h2::server::SendResponse;
+use h2::RecvStream;
+use http::header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE};
+use http::{Method, Request, Response, StatusCode};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::error::Error;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::time::timeout;
+use tokio_rustls::TlsAcceptor;
+
+/// Path queries are answered on unless configured otherwise.
+pub const DEFAULT_PATH: &str = "/dns-query";
+
+/// Media type of a DNS message in wire format (RFC 8484 section 6).
+pub const DNS_MESSAGE: &str = "application/dns-message";
+
+/// ALPN protocol id for HTTP/2.
+pub const H2_ALPN: &[u8] = b"h2";
+
+/// Largest DNS message a request or response body may hold.
+const MAX_MESSAGE_LENGTH: usize = u16::MAX as usize;
+
+/// A server answering DNS over HTTPS (RFC 8484) on HTTP/2. Queries come
+/// as the `dns` parameter of a GET or as the body of a POST, and every
+/// answer may be cached by HTTP caches for as long as its shortest TTL.
+pub struct HttpsServer {
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    handler: Arc<dyn Handler>,
+    path: String,
+}
+
+impl HttpsServer {
+    /// Presents the certificate chain, end entity first, signed by the key.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        certificates: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+        handler: impl Handler + 'static,
+    ) -> ServerResult<Self> {
+        let config = server_config(certificates, key, &[H2_ALPN])?;
+
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            config,
+            handler: Arc::new(handler),
+            path: String::from(DEFAULT_PATH),
+        })
+    }
+
+    /// The path queries are answered on; any other path gets 404.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_owned();
+        self
+    }
+
+    pub fn local_addr(&self) -> ServerResult<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves forever on a runtime of its own. Handlers run on blocking
+    /// threads, so they may take their time.
+    pub fn run(self) -> ServerResult<()> {
+        self.listener.set_nonblocking(true)?;
+        let runtime = Runtime::new()?;
+        let _guard = runtime.enter();
+        let listener = tokio::net::TcpListener::from_std(self.listener)?;
+        let acceptor = TlsAcceptor::from(self.config);
+        let path: Arc<str> = Arc::from(self.path);
+
+        runtime.block_on(async move {
+            loop {
+                // A failed accept only loses that connection
+                let Ok((stream, _)) = listener.accept().await else { continue };
+                let connection = serve_connection(acceptor.clone(), stream, Arc::clone(&self.handler), Arc::clone(&path));
+                tokio::spawn(connection);
+            }
+        })
+    }
+}
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+async fn serve_connection(
+    acceptor: TlsAcceptor,
+    stream: tokio::net::TcpStream,
+    handler: Arc<dyn Handler>,
+    path: Arc<str>,
+) -> Result<(), BoxError> {
+    let stream = timeout(TCP_IDLE_TIMEOUT, acceptor.accept(stream)).await??;
+    let mut connection = h2::server::handshake(stream).await?;
+
+    while let Some(request) = connection.accept().await {
+        let (request, respond) = request?;
+        tokio::spawn(serve_request(request, respond, Arc::clone(&handler), Arc::clone(&path)));
+    }
+
+    Ok(())
+}
+
+/// Answers one request. Failing to send only means the client is gone.
+async fn serve_request(request: Request<RecvStream>, mut respond: SendResponse<Bytes>, handler: Arc<dyn Handler>, path: Arc<str>) {
+    let (head, body) = match read_query(request, &path).await {
+        Ok(query) => match tokio::task::spawn_blocking(move || route(handler.as_ref(), &query)).await {
+            Ok(response) => dns_response(&response),
+            Err(_) => (status_response(StatusCode::INTERNAL_SERVER_ERROR), Bytes::new()),
+        },
+        Err(status) => (status_response(status), Bytes::new()),
+    };
+
+    let end_of_stream = body.is_empty();
+    if let Ok(mut stream) = respond.send_response(head, end_of_stream) {
+        if !end_of_stream {
+            let _ = stream.send_data(body, true);
+        }
+    }
+}
+
+/// The query a request carries, or the status to turn it away with.
+async fn read_query(request: Request<RecvStream>, path: &str) -> Result<Message, StatusCode> {
+    if request.uri().path() != path {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let wire = match *request.method() {
+        Method::GET => {
+            let dns = request
+                .uri()
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .find_map(|parameter| parameter.strip_prefix("dns="))
+                .ok_or(StatusCode::BAD_REQUEST)?;
+            BASE64URL_NOPAD.decode(dns.as_bytes()).map_err(|_| StatusCode::BAD_REQUEST)?
+        }
+        Method::POST => {
+            if request.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()) != Some(DNS_MESSAGE) {
+                return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            }
+            read_body(request.into_body())
+                .await
+                .map_err(|_| StatusCode::BAD_REQUEST)?
+                .ok_or(StatusCode::PAYLOAD_TOO_LARGE)?
+        }
+        _ => return Err(StatusCode::METHOD_NOT_ALLOWED),
+    };
+    if wire.len() > MAX_MESSAGE_LENGTH {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let query = Message::try_from_bytes(&mut Deserialize::new(&wire)).map_err(|_| StatusCode::BAD_REQUEST)?;
+    match query.header().flags().query_or_response() {
+        QR::Query => Ok(query),
+        QR::Response => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Reads a whole body, or None once it grows past the longest message.
+pub(crate) async fn read_body(mut body: RecvStream) -> Result<Option<Vec<u8>>, h2::Error> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        body.flow_control().release_capacity(chunk.len())?;
+        data.extend_from_slice(&chunk);
+        if data.len() > MAX_MESSAGE_LENGTH {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(data))
+}
+
+/// A 200 carrying the message, fresh for as long as its shortest TTL
+/// (RFC 8484 section 5.1). Answers without records get no lifetime.
+fn dns_response(response: &Message) -> (Response<()>, Bytes) {
+    let mut encoder = Serialize::new();
+    response.to_bytes(&mut encoder);
+    let body = encoder.bin_data();
+
+    let mut head = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, DNS_MESSAGE)
+        .header(CONTENT_LENGTH, body.len());
+    if let Some(ttl) = response.answers().iter().chain(response.authorities()).map(Record::ttl).min() {
+        head = head.header(CACHE_CONTROL, format!("max-age={}", ttl));
+    }
+
+    (head.body(()).expect("header values are valid"), Bytes::from(body))
+}
+
+fn status_response(status: StatusCode) -> Response<()> {
+    let mut response = Response::new(());
+    *response.status_mut() = status;
+
+    response
+}
+
+#[cfg(test)]
+pub(crate) mod https_server_unittest {
+    use crate::packet::headers::header_flags::Opcode;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::handler_unittest::{catalog, parse, query};
+    use crate::server::https::{read_body, HttpsServer, DNS_MESSAGE, H2_ALPN};
+    use crate::tls::tls_unittest::certificate;
+    use crate::tls::ServerIdentity;
+    use crate::zone::zone_unittest::records;
+    use bytes::Bytes;
+    use data_encoding::BASE64URL_NOPAD;
+    use http::header::{CACHE_CONTROL, CONTENT_TYPE};
+    use http::{HeaderMap, Method, Request, StatusCode};
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use std::net::SocketAddr;
+    use std::thread;
+    use tokio_rustls::TlsConnector;
+
+    /// A DoH server for the name on the path, answering from the test
+    /// catalog, and its self-signed certificate.
+    pub(crate) fn start(name: &str, path: &str) -> (SocketAddr, CertificateDer<'static>) {
+        let mut catalog = catalog();
+        let mut zone = catalog.get(&crate::zone::zone_unittest::name("example.com.")).unwrap().clone();
+        for record in records("short 60 CNAME www\n") {
+            zone.insert(record).unwrap();
+        }
+        catalog.insert(zone);
+
+        let (certificate, key) = certificate(name);
+        let server = HttpsServer::bind("127.0.0.1:0", vec![certificate.clone()], key, catalog).unwrap().path(path);
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        (address, certificate)
+    }
+
+    /// Sends one request and returns the status, headers and body.
+    fn send(address: SocketAddr, certificate: CertificateDer<'static>, method: Method, path_and_query: &str, content_type: &str, body: Vec<u8>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async move {
+            let identity = ServerIdentity::new("doh.example").unwrap().roots(&[certificate]).unwrap();
+            let connector = TlsConnector::from(identity.client_config(&[H2_ALPN]).unwrap());
+            let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            let stream = connector.connect(ServerName::try_from("doh.example").unwrap(), stream).await.unwrap();
+            let (client, connection) = h2::client::handshake(stream).await.unwrap();
+            tokio::spawn(connection);
+
+            let request = Request::builder()
+                .method(method)
+                .uri(format!("https://doh.example{}", path_and_query))
+                .header(CONTENT_TYPE, content_type)
+                .body(())
+                .unwrap();
+            let mut client = client.ready().await.unwrap();
+            let (response, mut stream) = client.send_request(request, body.is_empty()).unwrap();
+            if !body.is_empty() {
+                stream.send_data(Bytes::from(body), true).unwrap();
+            }
+
+            let (head, body) = response.await.unwrap().into_parts();
+            (head.status, head.headers, read_body(body).await.unwrap().unwrap())
+        })
+    }
+
+    fn get(address: SocketAddr, certificate: &CertificateDer<'static>, qname: &str) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let dns = BASE64URL_NOPAD.encode(&query(0, Opcode::Query, qname, RecordType::A));
+        send(address, certificate.clone(), Method::GET, &format!("/dns-query?dns={}", dns), DNS_MESSAGE, Vec::new())
+    }
+
+    #[test]
+    fn cache_for_shortest_ttl() {
+        let (address, certificate) = start("doh.example", "/dns-query");
+
+        let (status, headers, body) = get(address, &certificate, "www.example.com.");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[CONTENT_TYPE], DNS_MESSAGE);
+        assert_eq!(headers[CACHE_CONTROL], "max-age=3600");
+        assert_eq!(parse(&body).answers().len(), 1);
+
+        let (_, headers, _) = get(address, &certificate, "short.example.com.");
+        assert_eq!(headers[CACHE_CONTROL], "max-age=60");
+
+        let (_, headers, _) = get(address, &certificate, "missing.example.com.");
+        assert_eq!(headers[CACHE_CONTROL], "max-age=300");
+
+        let (_, headers, _) = get(address, &certificate, "www.example.org.");
+        assert!(!headers.contains_key(CACHE_CONTROL));
+    }
+
+    #[test]
+    fn reject_invalid_requests() {
+        let (address, certificate) = start("doh.example", "/custom");
+        let wire = query(0, Opcode::Query, "www.example.com.", RecordType::A);
+        let send = |method: Method, path: &str, content_type: &str, body: &[u8]| {
+            send(address, certificate.clone(), method, path, content_type, body.to_vec()).0
+        };
+
+        assert_eq!(send(Method::POST, "/custom", DNS_MESSAGE, &wire), StatusCode::OK);
+        assert_eq!(send(Method::POST, "/dns-query", DNS_MESSAGE, &wire), StatusCode::NOT_FOUND);
+        assert_eq!(send(Method::PUT, "/custom", DNS_MESSAGE, &wire), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(send(Method::POST, "/custom", "text/plain", &wire), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(send(Method::POST, "/custom", DNS_MESSAGE, &wire[..13]), StatusCode::BAD_REQUEST);
+        assert_eq!(send(Method::GET, "/custom", DNS_MESSAGE, &[]), StatusCode::BAD_REQUEST);
+        assert_eq!(send(Method::GET, "/custom?dns=!!", DNS_MESSAGE, &[]), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,222 @@
+pub mod handler;
+#[cfg(feature = "doh")]
+pub mod https;
+#[cfg(feature = "doq")]
+pub mod quic;
+pub mod tls;
+pub mod transfer;
+
+use crate::packet::fqdn::Fqdn;
+use crate::packet::framing::{read_frame, write_frame};
+use crate::server::handler::{respond_datagram, respond_stream, Handler};
+use crate::tls::TlsError;
+use crate::zone::{Zone, ZoneError};
+use crate::zonefile::parser::ZoneParser;
+use crate::zonefile::ZoneFileError;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+/// How long a TCP connection may stay idle between queries.
+pub(crate) const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed receive or accept, so a socket that keeps failing
+/// does not spin its thread.
+const RECEIVE_BACKOFF: Duration = Duration::from_millis(50);
+
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    ZoneFile(#[from] ZoneFileError),
+    #[error(transparent)]
+    Zone(#[from] ZoneError),
+    #[error(transparent)]
+    Tls(#[from] TlsError),
+}
+
+pub type ServerResult<T> = Result<T, ServerError>;
+
+/// A server answering through a handler, such as a catalog of zones, over
+/// UDP and TCP on the same address.
+pub struct Server {
+    udp: UdpSocket,
+    tcp: TcpListener,
+    handler: Arc<dyn Handler>,
+}
+
+impl Server {
+    /// Binds TCP first and UDP on the address TCP got, so port 0 gives
+    /// both sockets the same ephemeral port.
+    pub fn bind(address: impl ToSocketAddrs, handler: impl Handler + 'static) -> ServerResult<Self> {
+        let tcp = TcpListener::bind(address)?;
+        let udp = UdpSocket::bind(tcp.local_addr()?)?;
+
+        Ok(Self {
+            udp,
+            tcp,
+            handler: Arc::new(handler),
+        })
+    }
+
+    pub fn local_addr(&self) -> ServerResult<SocketAddr> {
+        Ok(self.tcp.local_addr()?)
+    }
+
+    /// Serves forever. UDP is answered on its own thread and every TCP
+    /// connection gets a thread of its own.
+    pub fn run(self) -> ServerResult<()> {
+        let handler = Arc::clone(&self.handler);
+        let udp = self.udp;
+        thread::spawn(move || serve_udp(&udp, handler.as_ref()));
+
+        for stream in self.tcp.incoming() {
+            // A failed accept only loses that connection
+            let Ok(stream) = stream else {
+                thread::sleep(RECEIVE_BACKOFF);
+                continue;
+            };
+            let handler = Arc::clone(&self.handler);
+            thread::spawn(move || serve_tcp(stream, handler.as_ref()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a master file and builds the zone it holds.
+pub fn load_zone(apex: Fqdn, path: impl AsRef<Path>) -> ServerResult<Zone> {
+    let records = ZoneParser::new(apex.clone()).parse_file(path)?;
+
+    Ok(Zone::new(apex, records)?)
+}
+
+fn serve_udp(socket: &UdpSocket, handler: &dyn Handler) {
+    let mut buffer = vec![0u8; u16::MAX as usize];
+    loop {
+        // A failed receive only loses that datagram
+        let Ok((length, peer)) = socket.recv_from(&mut buffer) else {
+            thread::sleep(RECEIVE_BACKOFF);
+            continue;
+        };
+        if let Some(response) = respond_datagram(handler, &buffer[..length], peer.ip()) {
+            // The client may be gone; that is no reason to stop serving
+            let _ = socket.send_to(&response, peer);
+        }
+    }
+}
+
+fn serve_tcp(mut stream: TcpStream, handler: &dyn Handler) -> io::Result<()> {
+    stream.set_read_timeout(Some(TCP_IDLE_TIMEOUT))?;
+    let peer = stream.peer_addr()?.ip();
+    serve_stream(&mut stream, peer, handler)
+}
+
+/// Answers length prefixed queries from the peer until it closes the
+/// stream.
+pub(crate) fn serve_stream(stream: &mut (impl Read + Write), peer: IpAddr, handler: &dyn Handler) -> io::Result<()> {
+    while let Some(query) = read_frame(stream)? {
+        for response in respond_stream(handler, &query, peer) {
+            write_frame(stream, &response)?;
+        }
+        stream.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod server_unittest {
+    use crate::packet::framing::{read_frame, write_frame};
+    use crate::packet::headers::header_flags::{Opcode, Rcode};
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::handler_unittest::{catalog, parse, query};
+    use crate::server::{load_zone, Server, ServerError};
+    use crate::zone::zone_unittest::{name, ZONE};
+    use crate::zone::ZoneError;
+    use std::fs;
+    use std::net::{SocketAddr, TcpStream, UdpSocket};
+    use std::thread;
+    use std::time::Duration;
+
+    fn start() -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0", catalog()).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        address
+    }
+
+    fn udp_exchange(address: SocketAddr, wire: &[u8]) -> Vec<u8> {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        socket.send_to(wire, address).unwrap();
+
+        let mut buffer = [0u8; 512];
+        let (length, _) = socket.recv_from(&mut buffer).unwrap();
+        buffer[..length].to_vec()
+    }
+
+    #[test]
+    fn serve_udp() {
+        let address = start();
+
+        let response = parse(&udp_exchange(address, &query(1, Opcode::Query, "www.example.com.", RecordType::A)));
+        assert_eq!(response.header().id(), 1);
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        let response = parse(&udp_exchange(address, &query(2, Opcode::Query, "example.org.", RecordType::A)));
+        assert_eq!(response.header().flags().response_code(), Rcode::Refused);
+
+        let response = parse(&udp_exchange(address, &query(3, Opcode::Notify, "example.com.", RecordType::SOA)));
+        assert_eq!(response.header().flags().response_code(), Rcode::NotImplemented);
+
+        let response = udp_exchange(address, &[0, 4, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0xff]);
+        assert_eq!(response, vec![0, 4, 0x80, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn serve_tcp() {
+        let address = start();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        for (id, qname) in [(1, "www.example.com."), (2, "missing.example.com.")] {
+            write_frame(&mut stream, &query(id, Opcode::Query, qname, RecordType::A)).unwrap();
+        }
+
+        let found = parse(&read_frame(&mut stream).unwrap().unwrap());
+        let missing = parse(&read_frame(&mut stream).unwrap().unwrap());
+        assert_eq!(found.header().id(), 1);
+        assert_eq!(found.answers().len(), 1);
+        assert_eq!(missing.header().id(), 2);
+        assert_eq!(missing.header().flags().response_code(), Rcode::NameError);
+        assert_eq!(missing.authorities().len(), 1);
+    }
+
+    #[test]
+    fn load_zone_file() {
+        let directory = std::env::temp_dir().join(format!("rdns-server-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("example.com.zone"), ZONE).unwrap();
+        fs::write(directory.join("no-soa.zone"), "www 60 A 192.0.2.1\n").unwrap();
+
+        let zone = load_zone(name("example.com."), directory.join("example.com.zone")).unwrap();
+        assert_eq!(zone.serial(), 2024010101);
+
+        assert!(matches!(
+            load_zone(name("example.com."), directory.join("no-soa.zone")),
+            Err(ServerError::Zone(ZoneError::MissingSoa(_)))
+        ));
+        assert!(matches!(
+            load_zone(name("example.com."), directory.join("missing.zone")),
+            Err(ServerError::ZoneFile(_))
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::packet::framing::{frame, unframe};
+use crate::server::handler::{respond, Handler, Transport};
+use crate::server::ServerResult;
+use crate::tls::{server_config, TlsError};
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, EndpointConfig, Incoming, RecvStream, SendStream, TokioRuntime, VarInt};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// ALPN protocol id for DNS over QUIC (RFC 9250 section 4.1.1).
+pub const DOQ_ALPN: &[u8] = b"doq";
+
+/// Longest stream a query or response may take: one length prefixed
+/// message.
+pub(crate) const MAX_STREAM_LENGTH: usize = 2 + u16::MAX as usize;
+
+/// Application error codes for streams and connections (RFC 9250
+/// section 4.3).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DoqError {
+    NoError,
+    InternalError,
+    ProtocolError,
+    RequestCancelled,
+    ExcessiveLoad,
+    UnspecifiedError,
+}
+
+impl From<DoqError> for VarInt {
+    fn from(error: DoqError) -> Self {
+        VarInt::from_u32(match error {
+            DoqError::NoError => 0x0,
+            DoqError::InternalError => 0x1,
+            DoqError::ProtocolError => 0x2,
+            DoqError::RequestCancelled => 0x3,
+            DoqError::ExcessiveLoad => 0x4,
+            DoqError::UnspecifiedError => 0x5,
+        })
+    }
+}
+
+/// A server answering DNS over QUIC (RFC 9250). Every query comes on a
+/// bidirectional stream of its own, with message id 0; any other id, a
+/// malformed stream or a unidirectional stream closes the connection with
+/// DOQ_PROTOCOL_ERROR.
+pub struct QuicServer {
+    socket: UdpSocket,
+    config: quinn::ServerConfig,
+    handler: Arc<dyn Handler>,
+}
+
+impl QuicServer {
+    /// Presents the certificate chain, end entity first, signed by the key.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        certificates: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+        handler: impl Handler + 'static,
+    ) -> ServerResult<Self> {
+        let tls = server_config(certificates, key, &[DOQ_ALPN])?;
+        let tls = QuicServerConfig::try_from(tls).map_err(|error| TlsError::Tls(rustls::Error::General(error.to_string())))?;
+
+        Ok(Self {
+            socket: UdpSocket::bind(address)?,
+            config: quinn::ServerConfig::with_crypto(Arc::new(tls)),
+            handler: Arc::new(handler),
+        })
+    }
+
+    pub fn local_addr(&self) -> ServerResult<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Serves forever on a runtime of its own. Handlers run on blocking
+    /// threads, so they may take their time.
+    pub fn run(self) -> ServerResult<()> {
+        let runtime = Runtime::new()?;
+        let _guard = runtime.enter();
+        let endpoint = Endpoint::new(EndpointConfig::default(), Some(self.config), self.socket, Arc::new(TokioRuntime))?;
+
+        runtime.block_on(async move {
+            while let Some(incoming) = endpoint.accept().await {
+                tokio::spawn(serve_connection(incoming, Arc::clone(&self.handler)));
+            }
+        });
+
+        Ok(())
+    }
+}
+
+async fn serve_connection(incoming: Incoming, handler: Arc<dyn Handler>) {
+    // A failed handshake only loses that connection
+    let Ok(connection) = incoming.await else { return };
+
+    let watched = connection.clone();
+    tokio::spawn(async move {
+        if watched.accept_uni().await.is_ok() {
+            watched.close(DoqError::ProtocolError.into(), b"unidirectional stream");
+        }
+    });
+
+    // Ends when the client closes the connection
+    while let Ok((send, recv)) = connection.accept_bi().await {
+        tokio::spawn(serve_stream(connection.clone(), send, recv, Arc::clone(&handler)));
+    }
+}
+
+/// Answers the query on one stream and finishes it.
+async fn serve_stream(connection: Connection, mut send: SendStream, mut recv: RecvStream, handler: Arc<dyn Handler>) {
+    // A reset stream was cancelled by the client, which wants no answer
+    let Ok(data) = recv.read_to_end(MAX_STREAM_LENGTH).await else { return };
+    let Some(query) = unframe(&data).filter(|query| query.len() >= 2 && query[..2] == [0, 0]) else {
+        connection.close(DoqError::ProtocolError.into(), b"malformed query");
+        return;
+    };
+
+    let query = query.to_vec();
+    let response = tokio::task::spawn_blocking(move || respond(handler.as_ref(), &query, Transport::Tcp)).await;
+    match response {
+        Ok(Some(response)) => {
+            let Ok(response) = frame(&response) else {
+                let _ = send.reset(DoqError::InternalError.into());
+                return;
+            };
+            if send.write_all(&response).await.is_ok() {
+                let _ = send.finish();
+            }
+        }
+        // Responses and runts are not queries
+        Ok(None) => connection.close(DoqError::ProtocolError.into(), b"not a query"),
+        Err(_) => {
+            let _ = send.reset(DoqError::InternalError.into());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod quic_server_unittest {
+    use crate::packet::framing::frame;
+    use crate::packet::headers::header_flags::Opcode;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::handler_unittest::{catalog, query};
+    use crate::server::quic::{DoqError, QuicServer, DOQ_ALPN};
+    use crate::tls::tls_unittest::certificate;
+    use crate::tls::ServerIdentity;
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::{ConnectionError, Endpoint, VarInt};
+    use rustls::pki_types::CertificateDer;
+    use std::future::Future;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// A DoQ server for the name answering from the test catalog, and its
+    /// self-signed certificate.
+    pub(crate) fn start(name: &str) -> (SocketAddr, CertificateDer<'static>) {
+        let (certificate, key) = certificate(name);
+        let server = QuicServer::bind("127.0.0.1:0", vec![certificate.clone()], key, catalog()).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        (address, certificate)
+    }
+
+    /// Opens a connection, lets the test misbehave on it and returns why
+    /// the server closed it.
+    fn closed_by_server<F, T>(misbehave: F) -> ConnectionError
+    where
+        F: FnOnce(quinn::Connection) -> T,
+        T: Future<Output = ()>,
+    {
+        let (address, certificate) = start("doq.example");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        runtime.block_on(async move {
+            let identity = ServerIdentity::new("doq.example").unwrap().roots(&[certificate]).unwrap();
+            let tls = QuicClientConfig::try_from(identity.client_config(&[DOQ_ALPN]).unwrap()).unwrap();
+            let mut endpoint = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+            endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(tls)));
+            let connection = endpoint.connect(address, "doq.example").unwrap().await.unwrap();
+
+            misbehave(connection.clone()).await;
+            connection.closed().await
+        })
+    }
+
+    fn error_code(error: ConnectionError) -> Option<VarInt> {
+        match error {
+            ConnectionError::ApplicationClosed(close) => Some(close.error_code),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn close_on_nonzero_id() {
+        let error = closed_by_server(|connection| async move {
+            let (mut send, mut recv) = connection.open_bi().await.unwrap();
+            send.write_all(&frame(&query(7, Opcode::Query, "www.example.com.", RecordType::A)).unwrap()).await.unwrap();
+            send.finish().unwrap();
+            let _ = recv.read_to_end(1024).await;
+        });
+
+        assert_eq!(error_code(error), Some(DoqError::ProtocolError.into()));
+    }
+
+    #[test]
+    fn close_on_bad_framing() {
+        let error = closed_by_server(|connection| async move {
+            let (mut send, mut recv) = connection.open_bi().await.unwrap();
+            let mut wire = frame(&query(0, Opcode::Query, "www.example.com.", RecordType::A)).unwrap();
+            wire.push(0);
+            send.write_all(&wire).await.unwrap();
+            send.finish().unwrap();
+            let _ = recv.read_to_end(1024).await;
+        });
+
+        assert_eq!(error_code(error), Some(DoqError::ProtocolError.into()));
+    }
+
+    #[test]
+    fn close_on_unidirectional_stream() {
+        let error = closed_by_server(|connection| async move {
+            let mut send = connection.open_uni().await.unwrap();
+            send.write_all(&[0, 0]).await.unwrap();
+            send.finish().unwrap();
+        });
+
+        assert_eq!(error_code(error), Some(DoqError::ProtocolError.into()));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::server::handler::Handler;
+use crate::server::{serve_stream, ServerResult, TCP_IDLE_TIMEOUT};
+use crate::tls::server_config;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+
+/// Port for DNS over TLS (RFC 7858 section 3.1).
+pub const DOT_PORT: u16 = 853;
+
+/// ALPN protocol id for DNS over TLS.
+pub const DOT_ALPN: &[u8] = b"dot";
+
+/// A server answering DNS over TLS (RFC 7858): the TCP framing inside a
+/// TLS session, with every connection on a thread of its own.
+pub struct TlsServer {
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    handler: Arc<dyn Handler>,
+}
+
+impl TlsServer {
+    /// Presents the certificate chain, end entity first, signed by the key.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        certificates: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+        handler: impl Handler + 'static,
+    ) -> ServerResult<Self> {
+        let config = server_config(certificates, key, &[DOT_ALPN])?;
+
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            config,
+            handler: Arc::new(handler),
+        })
+    }
+
+    pub fn local_addr(&self) -> ServerResult<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves forever.
+    pub fn run(self) -> ServerResult<()> {
+        for stream in self.listener.incoming() {
+            // A failed accept only loses that connection
+            let Ok(stream) = stream else { continue };
+            let config = Arc::clone(&self.config);
+            let handler = Arc::clone(&self.handler);
+            thread::spawn(move || serve_tls(stream, config, handler.as_ref()));
+        }
+
+        Ok(())
+    }
+}
+
+fn serve_tls(stream: TcpStream, config: Arc<ServerConfig>, handler: &dyn Handler) -> io::Result<()> {
+    stream.set_read_timeout(Some(TCP_IDLE_TIMEOUT))?;
+    let peer = stream.peer_addr()?.ip();
+    let connection = ServerConnection::new(config).map_err(io::Error::other)?;
+
+    serve_stream(&mut StreamOwned::new(connection, stream), peer, handler)
+}
+
+#[cfg(test)]
+pub(crate) mod tls_server_unittest {
+    use crate::packet::framing::{read_frame, write_frame};
+    use crate::packet::headers::header_flags::Opcode;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::handler_unittest::{catalog, parse, query};
+    use crate::server::tls::{TlsServer, DOT_ALPN};
+    use crate::tls::tls_unittest::certificate;
+    use crate::tls::ServerIdentity;
+    use rustls::pki_types::CertificateDer;
+    use rustls::{ClientConnection, StreamOwned};
+    use std::net::{SocketAddr, TcpStream};
+    use std::thread;
+
+    /// A DoT server for the name answering from the test catalog, and its
+    /// self-signed certificate.
+    pub(crate) fn start(name: &str) -> (SocketAddr, CertificateDer<'static>) {
+        let (certificate, key) = certificate(name);
+        let server = TlsServer::bind("127.0.0.1:0", vec![certificate.clone()], key, catalog()).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        (address, certificate)
+    }
+
+    #[test]
+    fn serve_queries_on_one_connection() {
+        let (address, certificate) = start("dns.example");
+        let identity = ServerIdentity::new("dns.example").unwrap().roots(&[certificate]).unwrap();
+        let connection = ClientConnection::new(identity.client_config(&[DOT_ALPN]).unwrap(), identity.name().clone()).unwrap();
+        let mut stream = StreamOwned::new(connection, TcpStream::connect(address).unwrap());
+
+        for (id, qname) in [(1, "www.example.com."), (2, "alias.example.com.")] {
+            write_frame(&mut stream, &query(id, Opcode::Query, qname, RecordType::A)).unwrap();
+            let response = parse(&read_frame(&mut stream).unwrap().unwrap());
+            assert_eq!(response.header().id(), id);
+            assert!(!response.answers().is_empty());
+        }
+        assert_eq!(stream.conn.alpn_protocol(), Some(DOT_ALPN));
+    }
+}
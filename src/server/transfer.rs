@@ -0,0 +1,512 @@
+use crate::clock::{Clock, SystemClock};
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header_flags::Rcode;
+use crate::packet::message::Message;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::packet::tsig::{TsigError, TsigKey, TsigRecord, TsigStream};
+use crate::records::query_type::QueryType;
+use crate::records::rdata::tsig::TSIG;
+use crate::records::record_type::RecordType;
+use crate::server::handler::{error_response, Handler, Transport, MAX_UDP_PAYLOAD};
+use crate::zone::catalog::Catalog;
+use crate::zone::journal::{is_newer_serial, soa_serial, Diff, Journal};
+use crate::zone::transfer::pack;
+use crate::zone::Zone;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+/// Longest MAC of the TSIG algorithms, HMAC-SHA512.
+const MAX_MAC_LENGTH: usize = 64;
+
+const DEFAULT_JOURNAL_SIZE: usize = 100;
+
+/// Who may transfer zones: peers with an address in one of the networks,
+/// and peers that sign their request with one of the keys, from anywhere.
+#[derive(Debug, Clone, Default)]
+pub struct TransferAcl {
+    networks: Vec<(IpAddr, u8)>,
+    keys: Vec<TsigKey>,
+}
+
+impl TransferAcl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the addresses sharing the first `prefix_length` bits with
+    /// `address`. A single host is a /32 or a /128.
+    pub fn allow_network(mut self, address: IpAddr, prefix_length: u8) -> Self {
+        self.networks.push((address, prefix_length));
+        self
+    }
+
+    pub fn allow_key(mut self, key: TsigKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    pub fn allows_address(&self, peer: IpAddr) -> bool {
+        self.networks
+            .iter()
+            .any(|(network, prefix_length)| in_network(peer.to_canonical(), *network, *prefix_length))
+    }
+
+    fn key(&self, name: &Fqdn) -> Option<&TsigKey> {
+        self.keys.iter().find(|key| key.name() == name)
+    }
+}
+
+fn in_network(address: IpAddr, network: IpAddr, prefix_length: u8) -> bool {
+    let (difference, bits) = match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => (u128::from(u32::from(address) ^ u32::from(network)), 32),
+        (IpAddr::V6(address), IpAddr::V6(network)) => (u128::from(address) ^ u128::from(network), 128),
+        _ => return false,
+    };
+
+    // A shift by all the bits compares nothing, as a /0 should
+    difference.checked_shr(bits - u32::from(prefix_length.min(bits as u8))).unwrap_or(0) == 0
+}
+
+/// Answers queries from a catalog and sends zones to the peers the ACL
+/// allows, whole (RFC 5936) or as the changes since the version a peer
+/// holds (RFC 1995). Transfers requested with TSIG are signed with the
+/// same key, each message chained to the one before.
+pub struct Primary {
+    zones: RwLock<Zones>,
+    acl: TransferAcl,
+    clock: Arc<dyn Clock>,
+    max_message_size: usize,
+    journal_size: usize,
+}
+
+/// The zones served and the journal of each zone reloaded since.
+struct Zones {
+    catalog: Catalog,
+    journals: HashMap<Fqdn, Journal>,
+}
+
+impl Primary {
+    /// Refuses every transfer until an ACL allows some.
+    pub fn new(catalog: Catalog) -> Self {
+        Self {
+            zones: RwLock::new(Zones {
+                catalog,
+                journals: HashMap::new(),
+            }),
+            acl: TransferAcl::new(),
+            clock: Arc::new(SystemClock),
+            max_message_size: u16::MAX as usize,
+            journal_size: DEFAULT_JOURNAL_SIZE,
+        }
+    }
+
+    pub fn acl(mut self, acl: TransferAcl) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// The clock TSIG times are checked and signed against.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Largest transfer message, TSIG included, at most 65535 bytes.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size.min(u16::MAX as usize);
+        self
+    }
+
+    /// How many diffs each zone keeps for IXFR, 100 by default.
+    pub fn journal_size(mut self, journal_size: usize) -> Self {
+        self.journal_size = journal_size;
+        self
+    }
+
+    /// Serves a new version of a zone. When its serial is newer than the
+    /// one served so far the changes go in the journal of the zone;
+    /// otherwise the journal starts over, as it cannot lead to this
+    /// version.
+    pub fn reload(&self, zone: Zone) {
+        let mut zones = self.zones.write().unwrap_or_else(PoisonError::into_inner);
+        let Zones { catalog, journals } = &mut *zones;
+
+        let diff = catalog
+            .get(zone.apex())
+            .filter(|served| is_newer_serial(zone.serial(), served.serial()))
+            .map(|served| Diff::between(served, &zone));
+        let journal = journals.entry(zone.apex().clone()).or_insert_with(|| Journal::new(self.journal_size));
+        match diff {
+            Some(diff) => journal.push(diff),
+            None => journal.clear(),
+        }
+
+        catalog.insert(zone);
+    }
+
+    fn zones(&self) -> RwLockReadGuard<'_, Zones> {
+        self.zones.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn answer_transfer(&self, query: &Message, peer: IpAddr, transport: Transport) -> Vec<Message> {
+        let zones = self.zones();
+        let Some(zone) = zones.catalog.get(query.question().qname()) else {
+            return vec![error_response(query, Rcode::NotAuth)];
+        };
+
+        let key = match query.tsig() {
+            Some(tsig) => match self.verify(query, tsig) {
+                Ok(key) => Some((key, tsig.mac())),
+                Err(error) => return vec![tsig_failure(query, tsig, &error, self.acl.key(tsig.key_name()), self.clock.as_ref())],
+            },
+            None => None,
+        };
+        if key.is_none() && !self.acl.allows_address(peer) {
+            return vec![error_response(query, Rcode::Refused)];
+        }
+
+        let records = match query.question().qtype() {
+            QueryType::IXFR => {
+                // The client tells its version with its SOA in the
+                // authority section (RFC 1995 section 3)
+                let Some(serial) = client_serial(query) else {
+                    return vec![error_response(query, Rcode::FormatError)];
+                };
+                let no_journal = Journal::new(0);
+                zone.ixfr_records(zones.journals.get(zone.apex()).unwrap_or(&no_journal), serial)
+            }
+            _ => zone.transfer_records(),
+        };
+        let reserve = key.map_or(0, |(key, _)| tsig_length(key));
+        let mut messages = match transport {
+            Transport::Tcp => pack(query, records, self.max_message_size, reserve),
+            Transport::Udp => {
+                // What does not fit in a datagram is replaced by the SOA
+                // alone, telling the client to ask again over TCP
+                // (RFC 1995 section 2)
+                let messages = pack(query, records, MAX_UDP_PAYLOAD, reserve);
+                match messages.len() {
+                    1 => messages,
+                    _ => pack(query, vec![zone.soa().clone()], MAX_UDP_PAYLOAD, reserve),
+                }
+            }
+        };
+
+        let Some((key, request_mac)) = key else {
+            return messages;
+        };
+        let mut signer = TsigStream::new(key, request_mac, self.clock.as_ref());
+        for message in &mut messages {
+            // Signing only fails for keys it cannot use, and those were
+            // refused above
+            let _ = signer.sign(message);
+        }
+
+        messages
+    }
+
+    fn verify(&self, query: &Message, tsig: &TsigRecord) -> Result<&TsigKey, TsigError> {
+        let key = self.acl.key(tsig.key_name()).ok_or(TsigError::BadKey)?;
+        query.verify_tsig(key, None, self.clock.as_ref())?;
+
+        Ok(key)
+    }
+}
+
+impl Handler for Primary {
+    fn handle(&self, query: &Message) -> Message {
+        self.zones().catalog.handle(query)
+    }
+
+    fn transfer(&self, query: &Message, peer: IpAddr, transport: Transport) -> Option<Vec<Message>> {
+        Some(self.answer_transfer(query, peer, transport))
+    }
+}
+
+/// The serial of the SOA of the zone in the authority section.
+fn client_serial(query: &Message) -> Option<u32> {
+    query
+        .authorities()
+        .iter()
+        .find(|record| record.owner_name() == query.question().qname() && record.record_type() == RecordType::SOA)
+        .map(soa_serial)
+}
+
+/// NOTAUTH with a TSIG carrying the error. A request that only failed the
+/// time check verified with the key, so the answer is signed and tells the
+/// server's time (RFC 8945 section 5.2.3). Otherwise the TSIG goes
+/// unsigned, which is all a peer that failed the check can be told
+/// (section 5.3.2).
+fn tsig_failure(query: &Message, tsig: &TsigRecord, error: &TsigError, key: Option<&TsigKey>, clock: &dyn Clock) -> Message {
+    let mut response = error_response(query, Rcode::NotAuth);
+    if let (TsigError::BadTime(_), Some(key)) = (error, key) {
+        // Signing only fails for keys it cannot use, and this one verified
+        let _ = response.sign_tsig_bad_time(key, tsig.mac(), clock);
+        return response;
+    }
+
+    let data = TSIG::new(
+        tsig.data().algorithm_name().clone(),
+        clock.now(),
+        tsig.data().fudge(),
+        Vec::new(),
+        query.header().id(),
+        error.rcode().unwrap_or_default(),
+        Vec::new(),
+    );
+
+    response.set_tsig(TsigRecord::new(tsig.key_name().clone(), data));
+    response
+}
+
+/// Room a TSIG record with the key takes at most.
+fn tsig_length(key: &TsigKey) -> usize {
+    let data = TSIG::new(key.algorithm().name(), 0, 0, vec![0; MAX_MAC_LENGTH], 0, 0, Vec::new());
+
+    let mut encoder = Serialize::new();
+    TsigRecord::new(key.name().clone(), data).to_bytes(&mut encoder);
+    encoder.bin_data().len()
+}
+
+#[cfg(test)]
+pub(crate) mod transfer_server_unittest {
+    use crate::clock::ManualClock;
+    use crate::packet::headers::header_flags::Rcode;
+    use crate::packet::message::Message;
+    use crate::packet::seder::deserializer::Deserialize;
+    use crate::packet::seder::TryFromBytes;
+    use crate::packet::tsig::{TsigAlgorithm, TsigError, TsigKey, TsigStream};
+    use crate::records::query_type::QueryType;
+    use crate::resolver::build_query;
+    use crate::server::handler::handler_unittest::catalog;
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::message::MessageBuilder;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::{respond, respond_datagram, respond_stream, Handler, Transport, MAX_UDP_PAYLOAD};
+    use crate::server::transfer::{Primary, TransferAcl};
+    use crate::zone::catalog::Catalog;
+    use crate::zone::transfer::transfer_unittest::big_zone;
+    use crate::zone::zone_unittest::{name, records};
+    use crate::zone::Zone;
+    use std::net::IpAddr;
+    use std::sync::Arc;
+
+    pub(crate) const NOW: u64 = 1_700_000_000;
+
+    pub(crate) fn key(secret: &[u8]) -> TsigKey {
+        TsigKey::new(name("transfer.key."), TsigAlgorithm::HmacSha256, secret)
+    }
+
+    /// A primary for the big example zone, letting 192.0.2.0/24 and the
+    /// key holders transfer it in messages of at most 2048 bytes.
+    pub(crate) fn primary() -> Primary {
+        let mut catalog = Catalog::new();
+        catalog.insert(big_zone());
+        let acl = TransferAcl::new().allow_network(ip("192.0.2.0"), 24).allow_key(key(b"secret"));
+
+        Primary::new(catalog).acl(acl).clock(Arc::new(ManualClock::new(NOW))).max_message_size(2048)
+    }
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    fn axfr(apex: &str) -> Message {
+        build_query(&name(apex), QueryType::AXFR, false)
+    }
+
+    /// An IXFR request from a secondary holding the zone at the serial.
+    pub(crate) fn ixfr(zone: &Zone) -> Message {
+        let query = build_query(zone.apex(), QueryType::IXFR, false);
+        let header = HeaderBuilder::new()
+            .id(query.header().id())
+            .flags(query.header().flags().clone())
+            .question_count(1)
+            .authoritative_count(1)
+            .build();
+
+        MessageBuilder::new()
+            .header(header)
+            .question(query.question().clone())
+            .authority(vec![zone.soa().clone()])
+            .build()
+    }
+
+    /// The big zone at the next serial, with the hosts from `first` on
+    /// renumbered.
+    pub(crate) fn next_version(zone: &Zone, first: u32) -> Zone {
+        let mut next = zone.clone();
+        let soa = format!("@ SOA ns1 hostmaster {} 7200 3600 1209600 300\n", zone.serial() + 1);
+        let hosts: String = (first..=200).map(|host| format!("host{} A 198.51.100.{}\n", host, host % 250)).collect();
+        for record in records(&soa) {
+            next.insert(record).unwrap();
+        }
+        for host in first..=200 {
+            for record in next.rrset(&name(&format!("host{}.example.com.", host)), RecordType::A).unwrap().to_vec() {
+                next.remove(&record);
+            }
+        }
+        for record in records(&hosts) {
+            next.insert(record).unwrap();
+        }
+
+        next
+    }
+
+    fn parse(wire: &[u8]) -> Message {
+        Message::try_from_bytes(&mut Deserialize::new(wire)).unwrap()
+    }
+
+    #[test]
+    fn match_networks() {
+        let acl = TransferAcl::new().allow_network(ip("192.0.2.0"), 24).allow_network(ip("2001:db8::1"), 128);
+
+        assert!(acl.allows_address(ip("192.0.2.200")));
+        assert!(acl.allows_address(ip("::ffff:192.0.2.1")));
+        assert!(!acl.allows_address(ip("192.0.3.1")));
+        assert!(acl.allows_address(ip("2001:db8::1")));
+        assert!(!acl.allows_address(ip("2001:db8::2")));
+        assert!(TransferAcl::new().allow_network(ip("0.0.0.0"), 0).allows_address(ip("198.51.100.1")));
+        assert!(!TransferAcl::new().allows_address(ip("192.0.2.1")));
+    }
+
+    #[test]
+    fn transfer_to_allowed_networks() {
+        let primary = primary();
+
+        let messages = primary.transfer(&axfr("example.com."), ip("192.0.2.10"), Transport::Tcp).unwrap();
+        assert!(messages.len() > 1);
+        assert!(messages.iter().all(|message| message.wire().len() <= 2048 && message.tsig().is_none()));
+
+        let refused = primary.transfer(&axfr("example.com."), ip("198.51.100.1"), Transport::Tcp).unwrap();
+        assert_eq!(refused.len(), 1);
+        assert_eq!(refused[0].header().flags().response_code(), Rcode::Refused);
+
+        let not_apex = primary.transfer(&axfr("www.example.com."), ip("192.0.2.10"), Transport::Tcp).unwrap();
+        assert_eq!(not_apex[0].header().flags().response_code(), Rcode::NotAuth);
+    }
+
+    #[test]
+    fn sign_transfers_requested_with_tsig() {
+        let primary = primary();
+        let clock = ManualClock::new(NOW);
+
+        let mut query = axfr("example.com.");
+        query.sign_tsig(&key(b"secret"), None, &clock).unwrap();
+        let messages = primary.transfer(&parse(&query.wire()), ip("198.51.100.1"), Transport::Tcp).unwrap();
+        assert!(messages.len() > 1);
+
+        let transfer_key = key(b"secret");
+        let mut verifier = TsigStream::new(&transfer_key, query.tsig().unwrap().mac(), &clock);
+        for message in &messages {
+            assert!(message.wire().len() <= 2048);
+            verifier.verify(&parse(&message.wire())).unwrap();
+        }
+        assert_eq!(verifier.finish(), Ok(()));
+
+        let mut forged = axfr("example.com.");
+        forged.sign_tsig(&key(b"guess"), None, &clock).unwrap();
+        let failed = primary.transfer(&parse(&forged.wire()), ip("198.51.100.1"), Transport::Tcp).unwrap();
+        assert_eq!(failed[0].header().flags().response_code(), Rcode::NotAuth);
+        assert_eq!(failed[0].tsig().unwrap().data().error(), TsigError::BadSignature.rcode().unwrap());
+        assert!(failed[0].tsig().unwrap().mac().is_empty());
+
+        // Off by more than the fudge: signed, with the server's time
+        let mut late = axfr("example.com.");
+        late.sign_tsig(&transfer_key, None, &ManualClock::new(NOW - 600)).unwrap();
+        let failed = primary.transfer(&parse(&late.wire()), ip("198.51.100.1"), Transport::Tcp).unwrap();
+        let tsig = failed[0].tsig().unwrap();
+        assert_eq!(failed[0].header().flags().response_code(), Rcode::NotAuth);
+        assert_eq!(tsig.data().error(), TsigError::BadTime(0).rcode().unwrap());
+        assert_eq!(tsig.data().other_data(), &NOW.to_be_bytes()[2..]);
+        assert_eq!(
+            parse(&failed[0].wire()).verify_tsig(&transfer_key, Some(late.tsig().unwrap().mac()), &clock),
+            Err(TsigError::PeerError(18))
+        );
+        assert_eq!(
+            parse(&failed[0].wire()).verify_tsig(&key(b"guess"), Some(late.tsig().unwrap().mac()), &clock),
+            Err(TsigError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn transfer_only_over_streams() {
+        let query = axfr("example.com.").wire();
+
+        let udp = parse(&respond(&primary(), &query, Transport::Udp).unwrap());
+        assert_eq!(udp.header().flags().response_code(), Rcode::NotImplemented);
+
+        let responses = respond_stream(&catalog(), &query, ip("192.0.2.10"));
+        assert_eq!(parse(&responses[0]).header().flags().response_code(), Rcode::NotImplemented);
+
+        let responses = respond_stream(&primary(), &query, ip("192.0.2.10"));
+        assert!(responses.len() > 1);
+    }
+
+    #[test]
+    fn serve_incremental_transfers() {
+        let primary = primary();
+        let first = big_zone();
+        let second = next_version(&first, 200);
+        let third = next_version(&second, 1);
+        primary.reload(second.clone());
+        primary.reload(third.clone());
+
+        let answers = |zone: &Zone, transport| -> Vec<_> {
+            let messages = primary.transfer(&ixfr(zone), ip("192.0.2.10"), transport).unwrap();
+            messages.iter().flat_map(|message| message.answers().to_vec()).collect()
+        };
+        assert_eq!(answers(&first, Transport::Tcp), third.ixfr_records(&primary.zones().journals[first.apex()], first.serial()));
+        assert_eq!(answers(&second, Transport::Tcp).len(), 2 + 2 + 199 + 199);
+        assert_eq!(answers(&third, Transport::Tcp), vec![third.soa().clone()]);
+
+        assert_eq!(answers(&first, Transport::Udp), vec![third.soa().clone()]);
+        assert_eq!(answers(&third, Transport::Udp), vec![third.soa().clone()]);
+
+        // A version the journal does not reach back to gets the whole
+        // zone, one ahead of the primary the SOA alone
+        let mut ancient = first.clone();
+        ancient.insert(records("@ SOA ns1 hostmaster 2024010001 7200 3600 1209600 300\n").remove(0)).unwrap();
+        assert_eq!(answers(&ancient, Transport::Tcp), third.transfer_records());
+        assert_eq!(answers(&next_version(&third, 100), Transport::Tcp), vec![third.soa().clone()]);
+
+        // Going back to an older serial loses the history
+        primary.reload(first.clone());
+        assert_eq!(answers(&second, Transport::Tcp), vec![first.soa().clone()]);
+        primary.reload(second.clone());
+        assert_eq!(answers(&first, Transport::Udp).len(), 2 + 2 + 2);
+        assert_eq!(answers(&ancient, Transport::Tcp), second.transfer_records());
+    }
+
+    #[test]
+    fn answer_ixfr_over_udp() {
+        let primary = primary();
+        let first = big_zone();
+        primary.reload(next_version(&first, 200));
+
+        let response = parse(&respond_datagram(&primary, &ixfr(&first).wire(), ip("192.0.2.10")).unwrap());
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.answers().len(), 6);
+
+        let refused = parse(&respond_datagram(&primary, &ixfr(&first).wire(), ip("198.51.100.1")).unwrap());
+        assert_eq!(refused.header().flags().response_code(), Rcode::Refused);
+
+        let axfr = parse(&respond_datagram(&primary, &axfr("example.com.").wire(), ip("192.0.2.10")).unwrap());
+        assert_eq!(axfr.header().flags().response_code(), Rcode::NotImplemented);
+
+        let no_soa = build_query(&name("example.com."), QueryType::IXFR, false);
+        let malformed = parse(&respond_datagram(&primary, &no_soa.wire(), ip("192.0.2.10")).unwrap());
+        assert_eq!(malformed.header().flags().response_code(), Rcode::FormatError);
+
+        let clock = ManualClock::new(NOW);
+        let mut signed = ixfr(&big_zone());
+        signed.sign_tsig(&key(b"secret"), None, &clock).unwrap();
+        let wire = respond_datagram(&primary, &signed.wire(), ip("198.51.100.1")).unwrap();
+        assert!(wire.len() <= MAX_UDP_PAYLOAD);
+        let response = parse(&wire);
+        assert_eq!(response.answers().len(), 6);
+        assert_eq!(response.verify_tsig(&key(b"secret"), Some(signed.tsig().unwrap().mac()), &clock), Ok(()));
+    }
+}
@@ -0,0 +1,244 @@
+use data_encoding::BASE64;
+use ring::digest::{digest, SHA256};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring as provider, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::{self, PemObject};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, ServerConfig, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Cannot read PEM data: {0}")]
+    Pem(#[from] pem::Error),
+    #[error("No certificate in {0}")]
+    NoCertificate(String),
+    #[error("Invalid server name {0}")]
+    InvalidName(String),
+    #[error("Invalid SPKI pin {0}")]
+    InvalidPin(String),
+    #[error("Cannot parse certificate: {0}")]
+    Certificate(#[from] webpki::Error),
+    #[error(transparent)]
+    Tls(#[from] rustls::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type TlsResult<T> = Result<T, TlsError>;
+
+/// SHA-256 of a certificate's SubjectPublicKeyInfo.
+pub type SpkiPin = [u8; 32];
+
+/// The server a client expects on the other end of a TLS connection.
+/// Without pins the certificate must be valid for the name under the
+/// trust anchors, the web PKI unless replaced. With pins the certificate
+/// is only accepted when its key matches one of them, and the name just
+/// goes into SNI (RFC 7858 section 4.2).
+#[derive(Debug, Clone)]
+pub struct ServerIdentity {
+    name: ServerName<'static>,
+    roots: RootCertStore,
+    pins: Vec<SpkiPin>,
+}
+
+impl ServerIdentity {
+    pub fn new(name: &str) -> TlsResult<Self> {
+        let name = ServerName::try_from(name.to_owned()).map_err(|_| TlsError::InvalidName(name.to_owned()))?;
+
+        Ok(Self {
+            name,
+            roots: RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            },
+            pins: Vec::new(),
+        })
+    }
+
+    /// Trusts only these certificates as roots instead of the web PKI.
+    pub fn roots(mut self, certificates: &[CertificateDer<'_>]) -> TlsResult<Self> {
+        self.roots = RootCertStore::empty();
+        for certificate in certificates {
+            self.roots.add(certificate.clone().into_owned())?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn pin(mut self, pin: SpkiPin) -> Self {
+        self.pins.push(pin);
+        self
+    }
+
+    pub fn name(&self) -> &ServerName<'static> {
+        &self.name
+    }
+
+    /// Client configuration offering the ALPN protocols, if any.
+    pub(crate) fn client_config(&self, alpn: &[&[u8]]) -> TlsResult<Arc<ClientConfig>> {
+        let provider = Arc::new(provider::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+
+        let mut config = match self.pins.is_empty() {
+            true => builder.with_root_certificates(self.roots.clone()).with_no_client_auth(),
+            false => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinVerifier {
+                    pins: self.pins.clone(),
+                    provider,
+                }))
+                .with_no_client_auth(),
+        };
+        config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+
+        Ok(Arc::new(config))
+    }
+}
+
+/// Server configuration presenting the chain, end entity first, and
+/// accepting the ALPN protocols, if any.
+pub(crate) fn server_config(
+    certificates: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    alpn: &[&[u8]],
+) -> TlsResult<Arc<ServerConfig>> {
+    let mut config = ServerConfig::builder_with_provider(Arc::new(provider::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certificates, key)?;
+    config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+
+    Ok(Arc::new(config))
+}
+
+/// The pin of the key a certificate holds.
+pub fn spki_pin(certificate: &CertificateDer<'_>) -> TlsResult<SpkiPin> {
+    let certificate = webpki::EndEntityCert::try_from(certificate)?;
+    let spki = certificate.subject_public_key_info();
+
+    let mut pin = [0u8; 32];
+    pin.copy_from_slice(digest(&SHA256, spki.as_ref()).as_ref());
+    Ok(pin)
+}
+
+/// A pin in the base64 form used by pin-sha256 (RFC 7469 section 2.4).
+pub fn parse_spki_pin(text: &str) -> TlsResult<SpkiPin> {
+    BASE64
+        .decode(text.as_bytes())
+        .ok()
+        .and_then(|pin| SpkiPin::try_from(pin).ok())
+        .ok_or_else(|| TlsError::InvalidPin(text.to_owned()))
+}
+
+/// Every certificate in a PEM file, in file order.
+pub fn load_certificates(path: impl AsRef<Path>) -> TlsResult<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(&path)?.collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(TlsError::NoCertificate(path.as_ref().display().to_string()));
+    }
+
+    Ok(certificates)
+}
+
+/// The first private key in a PEM file, in PKCS #8, PKCS #1 or SEC1 form.
+pub fn load_private_key(path: impl AsRef<Path>) -> TlsResult<PrivateKeyDer<'static>> {
+    Ok(PrivateKeyDer::from_pem_file(path)?)
+}
+
+/// Accepts certificates by key alone. Expiry, issuer and names do not
+/// matter once the key is the pinned one, but handshake signatures are
+/// still checked against it.
+#[derive(Debug)]
+struct PinVerifier {
+    pins: Vec<SpkiPin>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        match spki_pin(end_entity) {
+            Ok(pin) if self.pins.contains(&pin) => Ok(ServerCertVerified::assertion()),
+            Ok(_) => Err(CertificateError::ApplicationVerificationFailure.into()),
+            Err(_) => Err(CertificateError::BadEncoding.into()),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, certificate, signature, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, certificate, signature, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tls_unittest {
+    use crate::tls::{load_certificates, load_private_key, parse_spki_pin, spki_pin, ServerIdentity, TlsError};
+    use data_encoding::BASE64;
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+    use std::fs;
+
+    /// A self-signed certificate for the name and its key.
+    pub(crate) fn certificate(name: &str) -> (CertificateDer<'static>, PrivateKeyDer<'static>) {
+        let CertifiedKey { cert, key_pair } = generate_simple_self_signed(vec![name.to_owned()]).unwrap();
+        let key = PrivatePkcs8KeyDer::from(key_pair.serialize_der());
+
+        (cert.der().clone(), key.into())
+    }
+
+    #[test]
+    fn load_pem_files() {
+        let CertifiedKey { cert, key_pair } = generate_simple_self_signed(vec!["dns.example".to_owned()]).unwrap();
+        let directory = std::env::temp_dir().join(format!("rdns-tls-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("cert.pem"), cert.pem()).unwrap();
+        fs::write(directory.join("key.pem"), key_pair.serialize_pem()).unwrap();
+        fs::write(directory.join("empty.pem"), "").unwrap();
+
+        assert_eq!(load_certificates(directory.join("cert.pem")).unwrap(), vec![cert.der().clone()]);
+        assert_eq!(load_private_key(directory.join("key.pem")).unwrap().secret_der(), key_pair.serialize_der());
+        assert!(matches!(load_certificates(directory.join("empty.pem")), Err(TlsError::NoCertificate(_))));
+        assert!(matches!(load_private_key(directory.join("cert.pem")), Err(TlsError::Pem(_))));
+    }
+
+    #[test]
+    fn parse_pins() {
+        let (certificate, _) = certificate("dns.example");
+        let pin = spki_pin(&certificate).unwrap();
+
+        assert_eq!(parse_spki_pin(&BASE64.encode(&pin)).unwrap(), pin);
+        assert!(matches!(parse_spki_pin("c2hvcnQ="), Err(TlsError::InvalidPin(_))));
+        assert!(matches!(parse_spki_pin("not base64"), Err(TlsError::InvalidPin(_))));
+    }
+
+    #[test]
+    fn reject_invalid_names() {
+        assert!(ServerIdentity::new("dns.example").is_ok());
+        assert!(matches!(ServerIdentity::new("not a name"), Err(TlsError::InvalidName(_))));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::packet::fqdn::Fqdn;
+use crate::zone::Zone;
+use std::collections::BTreeMap;
+
+/// The zones a server is authoritative for, keyed by apex.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    zones: BTreeMap<Fqdn, Zone>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a zone, returning the zone it replaces if the apex was
+    /// already served.
+    pub fn insert(&mut self, zone: Zone) -> Option<Zone> {
+        self.zones.insert(zone.apex().clone(), zone)
+    }
+
+    pub fn get(&self, apex: &Fqdn) -> Option<&Zone> {
+        self.zones.get(apex)
+    }
+
+    /// The zone with the longest apex that the name is in, so that a
+    /// child zone served next to its parent answers for its own names.
+    pub fn find(&self, name: &Fqdn) -> Option<&Zone> {
+        (0..=name.labels().len())
+            .rev()
+            .find_map(|labels| self.zones.get(&name.ancestor(labels)))
+    }
+
+    pub fn zones(&self) -> impl Iterator<Item = &Zone> {
+        self.zones.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod catalog_unittest {
+    use crate::zone::catalog::Catalog;
+    use crate::zone::zone_unittest::{example_zone, name, records};
+    use crate::zone::Zone;
+
+    #[test]
+    fn find_closest_zone() {
+        let mut catalog = Catalog::new();
+        assert!(catalog.find(&name("www.example.com.")).is_none());
+
+        catalog.insert(example_zone());
+        let child = records("sub 3600 SOA ns1.sub hostmaster 1 7200 3600 1209600 300\n");
+        catalog.insert(Zone::new(name("sub.example.com."), child).unwrap());
+
+        assert_eq!(catalog.find(&name("www.example.com.")).unwrap().apex(), &name("example.com."));
+        assert_eq!(catalog.find(&name("example.com.")).unwrap().apex(), &name("example.com."));
+        assert_eq!(catalog.find(&name("host.sub.example.com.")).unwrap().apex(), &name("sub.example.com."));
+        assert!(catalog.find(&name("example.org.")).is_none());
+        assert!(catalog.find(&name("com.")).is_none());
+        assert_eq!(catalog.zones().count(), 2);
+    }
+
+    #[test]
+    fn replace_zone() {
+        let mut catalog = Catalog::new();
+
+        assert!(catalog.insert(example_zone()).is_none());
+        assert!(catalog.insert(example_zone()).is_some());
+        assert_eq!(catalog.zones().count(), 1);
+    }
+}
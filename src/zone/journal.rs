@@ -0,0 +1,246 @@
+use crate::packet::record::Record;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use crate::zone::{Zone, ZoneError, ZoneResult};
+use std::collections::VecDeque;
+
+/// Whether serial `a` is newer than `b` in sequence space arithmetic
+/// (RFC 1982 section 3.2), so serials may wrap around.
+pub fn is_newer_serial(a: u32, b: u32) -> bool {
+    a != b && a.wrapping_sub(b) < 1 << 31
+}
+
+/// The serial of an SOA record, 0 for anything else.
+pub(crate) fn soa_serial(record: &Record) -> u32 {
+    match record.data() {
+        RecordData::SOA(soa) => soa.serial(),
+        _ => 0,
+    }
+}
+
+/// What changed between two versions of a zone: the records deleted from
+/// the version with the old SOA and those added to make the version with
+/// the new SOA (RFC 1995 section 4). Neither list holds the SOA itself.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diff {
+    old_soa: Record,
+    deleted: Vec<Record>,
+    new_soa: Record,
+    added: Vec<Record>,
+}
+
+impl Diff {
+    pub fn new(old_soa: Record, deleted: Vec<Record>, new_soa: Record, added: Vec<Record>) -> Self {
+        Self {
+            old_soa,
+            deleted,
+            new_soa,
+            added,
+        }
+    }
+
+    /// The changes turning `old` into `new`. A record whose TTL changed is
+    /// deleted and added again.
+    pub fn between(old: &Zone, new: &Zone) -> Self {
+        let missing_from = |zone: &Zone, record: &&Record| {
+            record.record_type() != RecordType::SOA
+                && !zone
+                    .rrset(record.owner_name(), record.record_type())
+                    .is_some_and(|rrset| rrset.contains(record))
+        };
+
+        Self {
+            old_soa: old.soa().clone(),
+            deleted: old.records().filter(|record| missing_from(new, record)).cloned().collect(),
+            new_soa: new.soa().clone(),
+            added: new.records().filter(|record| missing_from(old, record)).cloned().collect(),
+        }
+    }
+
+    pub fn old_soa(&self) -> &Record {
+        &self.old_soa
+    }
+
+    pub fn deleted(&self) -> &[Record] {
+        &self.deleted
+    }
+
+    pub fn new_soa(&self) -> &Record {
+        &self.new_soa
+    }
+
+    pub fn added(&self) -> &[Record] {
+        &self.added
+    }
+
+    pub fn old_serial(&self) -> u32 {
+        soa_serial(&self.old_soa)
+    }
+
+    pub fn new_serial(&self) -> u32 {
+        soa_serial(&self.new_soa)
+    }
+
+    /// The diff as it goes in an IXFR response: old SOA, deletions, new
+    /// SOA, additions.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        std::iter::once(&self.old_soa)
+            .chain(&self.deleted)
+            .chain(std::iter::once(&self.new_soa))
+            .chain(&self.added)
+    }
+}
+
+/// The latest diffs of one zone, oldest first, each starting at the
+/// serial the one before ends at. The oldest are dropped past the limit.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    diffs: VecDeque<Diff>,
+    max_diffs: usize,
+}
+
+impl Journal {
+    pub fn new(max_diffs: usize) -> Self {
+        Self {
+            diffs: VecDeque::new(),
+            max_diffs,
+        }
+    }
+
+    /// Records the next change. A diff that does not start where the
+    /// last one ended breaks the history, which then starts over.
+    pub fn push(&mut self, diff: Diff) {
+        if self.diffs.back().is_some_and(|last| last.new_serial() != diff.old_serial()) {
+            self.diffs.clear();
+        }
+
+        self.diffs.push_back(diff);
+        while self.diffs.len() > self.max_diffs {
+            self.diffs.pop_front();
+        }
+    }
+
+    /// The diffs leading from the serial to the latest version, or None
+    /// when the journal does not reach back that far.
+    pub fn since(&self, serial: u32) -> Option<impl Iterator<Item = &Diff>> {
+        let start = self.diffs.iter().position(|diff| diff.old_serial() == serial)?;
+
+        Some(self.diffs.range(start..))
+    }
+
+    pub fn clear(&mut self) {
+        self.diffs.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.diffs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+impl Zone {
+    /// Applies a diff to the version with its old serial. Nothing changes
+    /// when any part of it fails.
+    pub fn apply(&mut self, diff: &Diff) -> ZoneResult<()> {
+        if self.serial() != diff.old_serial() {
+            return Err(ZoneError::SerialMismatch(diff.old_serial(), self.serial()));
+        }
+
+        let mut zone = self.clone();
+        for record in &diff.deleted {
+            zone.remove(record);
+        }
+        for record in diff.added.iter().chain(std::iter::once(&diff.new_soa)) {
+            zone.insert(record.clone())?;
+        }
+        *self = zone;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod journal_unittest {
+    use crate::records::record_type::RecordType;
+    use crate::zone::journal::{is_newer_serial, Diff, Journal};
+    use crate::zone::zone_unittest::{example_zone, name, records};
+    use crate::zone::{Zone, ZoneError};
+
+    /// The example zone at the serial with the records changed.
+    pub(crate) fn version(serial: u32, text: &str) -> Zone {
+        let mut zone = example_zone();
+        let soa = format!("@ SOA ns1 hostmaster {} 7200 3600 1209600 300\n", serial);
+        for record in records(&(soa + text)) {
+            zone.insert(record).unwrap();
+        }
+
+        zone
+    }
+
+    #[test]
+    fn compare_serials() {
+        assert!(is_newer_serial(2, 1));
+        assert!(!is_newer_serial(1, 2));
+        assert!(!is_newer_serial(1, 1));
+        assert!(is_newer_serial(3, u32::MAX));
+        assert!(!is_newer_serial(u32::MAX, 3));
+    }
+
+    #[test]
+    fn diff_and_apply() {
+        let old = example_zone();
+        let mut new = version(2024010102, "mail A 192.0.2.25\n");
+        new.remove(&records("www A 192.0.2.1\n")[0]);
+
+        let diff = Diff::between(&old, &new);
+        assert_eq!(diff.deleted(), records("www A 192.0.2.1\n").as_slice());
+        assert_eq!(diff.added(), records("mail A 192.0.2.25\n").as_slice());
+        assert_eq!((diff.old_serial(), diff.new_serial()), (2024010101, 2024010102));
+        assert_eq!(diff.records().filter(|record| record.record_type() == RecordType::SOA).count(), 2);
+
+        let mut zone = old.clone();
+        zone.apply(&diff).unwrap();
+        assert_eq!(zone.records().collect::<Vec<_>>(), new.records().collect::<Vec<_>>());
+        assert!(zone.rrset(&name("www.example.com."), RecordType::A).is_none());
+
+        assert_eq!(zone.apply(&diff), Err(ZoneError::SerialMismatch(2024010101, 2024010102)));
+    }
+
+    #[test]
+    fn apply_all_or_nothing() {
+        let old = example_zone();
+        let new = version(2024010102, "mail A 192.0.2.25\n");
+        let mut diff = Diff::between(&old, &new);
+        diff.added.push(records("alias A 192.0.2.1\n").remove(0));
+
+        let mut zone = old.clone();
+        assert!(matches!(zone.apply(&diff), Err(ZoneError::CnameConflict(_))));
+        assert_eq!(zone.serial(), 2024010101);
+        assert!(zone.rrset(&name("mail.example.com."), RecordType::A).is_none());
+    }
+
+    #[test]
+    fn journal_chains_diffs() {
+        let versions = [example_zone(), version(2024010102, "a A 192.0.2.10\n"), version(2024010103, "b A 192.0.2.11\n")];
+        let mut journal = Journal::new(10);
+        journal.push(Diff::between(&versions[0], &versions[1]));
+        journal.push(Diff::between(&versions[1], &versions[2]));
+
+        let serials: Vec<u32> = journal.since(2024010101).unwrap().map(|diff| diff.new_serial()).collect();
+        assert_eq!(serials, vec![2024010102, 2024010103]);
+        assert_eq!(journal.since(2024010102).unwrap().count(), 1);
+        assert!(journal.since(2024010100).is_none());
+
+        let mut short = Journal::new(1);
+        short.push(Diff::between(&versions[0], &versions[1]));
+        short.push(Diff::between(&versions[1], &versions[2]));
+        assert!(short.since(2024010101).is_none());
+        assert_eq!(short.len(), 1);
+
+        journal.push(Diff::between(&versions[0], &versions[2]));
+        assert_eq!(journal.len(), 1);
+    }
+}
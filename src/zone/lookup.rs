@@ -0,0 +1,441 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Rcode};
+use crate::packet::message::{Message, MessageBuilder};
+use crate::packet::record::{Record, RecordBuilder};
+use crate::records::query_class::QueryClass;
+use crate::records::query_type::QueryType;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use crate::zone::Zone;
+
+/// CNAMEs followed inside the zone before giving up on a chain.
+const MAX_CNAME_CHAIN: usize = 8;
+
+/// The sections of an answer from a zone, before they are put in a
+/// message.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lookup {
+    response_code: Rcode,
+    authoritative: bool,
+    answers: Vec<Record>,
+    authorities: Vec<Record>,
+    additionals: Vec<Record>,
+}
+
+impl Lookup {
+    fn new() -> Self {
+        Self {
+            response_code: Rcode::NoError,
+            authoritative: true,
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    fn refused() -> Self {
+        Self {
+            response_code: Rcode::Refused,
+            authoritative: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn response_code(&self) -> Rcode {
+        self.response_code
+    }
+
+    pub fn is_authoritative(&self) -> bool {
+        self.authoritative
+    }
+
+    pub fn answers(&self) -> &[Record] {
+        &self.answers
+    }
+
+    pub fn authorities(&self) -> &[Record] {
+        &self.authorities
+    }
+
+    pub fn additionals(&self) -> &[Record] {
+        &self.additionals
+    }
+}
+
+impl Zone {
+    /// Answers a question for a name in this zone following RFC 1034
+    /// section 4.3.2: referrals at zone cuts, CNAMEs chased while they
+    /// stay inside the zone, wildcard synthesis and negative answers with
+    /// the SOA in the authority section (RFC 2308 section 3).
+    pub fn lookup(&self, qname: &Fqdn, qtype: QueryType) -> Lookup {
+        if !qname.is_subdomain_of(self.apex()) {
+            return Lookup::refused();
+        }
+
+        let mut lookup = Lookup::new();
+        let mut name = qname.clone();
+        let mut visited = vec![qname.clone()];
+        for _ in 0..MAX_CNAME_CHAIN {
+            if let Some(cut) = self.zone_cut(&name, qtype) {
+                self.refer(&cut, &mut lookup);
+                return lookup;
+            }
+
+            let (rrsets, synthesized) = match self.nodes.get(&name) {
+                Some(rrsets) => (rrsets, false),
+                None if self.has_descendants(&name) => {
+                    self.deny(Rcode::NoError, &mut lookup);
+                    return lookup;
+                }
+                None => match self.wildcard(&name) {
+                    Some(rrsets) => (rrsets, true),
+                    None => {
+                        self.deny(Rcode::NameError, &mut lookup);
+                        return lookup;
+                    }
+                },
+            };
+            let owned = |record: &Record| match synthesized {
+                true => with_owner(record, &name),
+                false => record.clone(),
+            };
+
+            let mut matching: Vec<(&RecordType, &Vec<Record>)> = rrsets
+                .iter()
+                .filter(|(record_type, _)| matches_type(qtype, **record_type))
+                .collect();
+            if !matching.is_empty() {
+                matching.sort_by_key(|(record_type, _)| u16::from(**record_type));
+                for (_, rrset) in matching {
+                    lookup.answers.extend(rrset.iter().map(owned));
+                }
+                self.add_glue(&mut lookup);
+                return lookup;
+            }
+
+            let Some(cname) = rrsets.get(&RecordType::CNAME) else {
+                self.deny(Rcode::NoError, &mut lookup);
+                return lookup;
+            };
+            lookup.answers.push(owned(&cname[0]));
+            name = match cname[0].data() {
+                RecordData::CNAME(cname) => cname.canonical_name().clone(),
+                _ => unreachable!("CNAME RRset holds CNAME data"),
+            };
+            // The CNAME leads out of the zone or back to a name already seen
+            if !name.is_subdomain_of(self.apex()) || visited.contains(&name) {
+                return lookup;
+            }
+            visited.push(name.clone());
+        }
+
+        lookup
+    }
+
+    /// Builds the response to a query with the header echoing the id,
+    /// opcode and RD bit of the query. Questions for another class or a
+    /// name outside the zone are refused.
+    pub fn answer(&self, query: &Message) -> Message {
+        let question = query.question();
+        let lookup = match question.qclass() {
+            QueryClass::Class(class) if class == self.class() => self.lookup(question.qname(), question.qtype()),
+            QueryClass::ANY => self.lookup(question.qname(), question.qtype()),
+            _ => Lookup::refused(),
+        };
+
+        let flags = HeaderFlagsBuilder::new()
+            .response(query.header().flags())
+            .authoritative_answer(lookup.authoritative)
+            .truncation(false)
+            .recursion_available(false)
+            .response_code(lookup.response_code)
+            .build();
+        let header = HeaderBuilder::new()
+            .id(query.header().id())
+            .flags(flags)
+            .question_count(1)
+            .answer_count(lookup.answers.len() as u16)
+            .authoritative_count(lookup.authorities.len() as u16)
+            .additional_count(lookup.additionals.len() as u16)
+            .build();
+
+        MessageBuilder::new()
+            .header(header)
+            .question(question.clone())
+            .answer(lookup.answers)
+            .authority(lookup.authorities)
+            .additional(lookup.additionals)
+            .build()
+    }
+
+    /// The highest delegation point at or above `name`, below the apex.
+    /// DS records live on the parent side of a cut, so a DS question for
+    /// the cut itself is not a referral.
+    fn zone_cut(&self, name: &Fqdn, qtype: QueryType) -> Option<Fqdn> {
+        let apex_labels = self.apex().labels().len();
+        let name_labels = name.labels().len();
+
+        (apex_labels + 1..=name_labels)
+            .filter(|labels| !(*labels == name_labels && qtype == QueryType::Type(RecordType::DS)))
+            .map(|labels| name.ancestor(labels))
+            .find(|ancestor| self.rrset(ancestor, RecordType::NS).is_some())
+    }
+
+    /// A referral is not authoritative unless CNAMEs from this zone led
+    /// to it.
+    fn refer(&self, cut: &Fqdn, lookup: &mut Lookup) {
+        lookup.authoritative = !lookup.answers.is_empty();
+        lookup.authorities.extend_from_slice(self.rrset(cut, RecordType::NS).unwrap_or_default());
+        self.add_glue(lookup);
+    }
+
+    /// Addresses of the in-zone name servers named by NS records in the
+    /// answer or authority sections.
+    fn add_glue(&self, lookup: &mut Lookup) {
+        let name_servers: Vec<Fqdn> = lookup
+            .answers
+            .iter()
+            .chain(lookup.authorities.iter())
+            .filter_map(|record| match record.data() {
+                RecordData::NS(ns) => Some(ns.name_server().clone()),
+                _ => None,
+            })
+            .collect();
+
+        for name_server in name_servers.iter().filter(|name| name.is_subdomain_of(self.apex())) {
+            for record_type in [RecordType::A, RecordType::AAAA] {
+                for record in self.rrset(name_server, record_type).unwrap_or_default() {
+                    if !lookup.additionals.contains(record) {
+                        lookup.additionals.push(record.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// NXDOMAIN or NODATA, with the SOA as negative caching TTL source.
+    fn deny(&self, response_code: Rcode, lookup: &mut Lookup) {
+        lookup.response_code = response_code;
+        lookup.authorities.push(self.negative_soa());
+    }
+
+    /// The SOA with its TTL capped at the SOA minimum (RFC 2308 section
+    /// 3).
+    fn negative_soa(&self) -> Record {
+        let soa = self.soa();
+        let minimum = match soa.data() {
+            RecordData::SOA(data) => data.minimum(),
+            _ => unreachable!("SOA RRset holds SOA data"),
+        };
+
+        RecordBuilder::new()
+            .owner_name(soa.owner_name().clone())
+            .record_type(RecordType::SOA)
+            .data(soa.data().clone())
+            .class(soa.class())
+            .ttl(soa.ttl().min(minimum))
+            .build()
+    }
+
+    /// The RRsets of the wildcard at the closest encloser of a name that
+    /// does not exist (RFC 4592 section 3.3.1).
+    fn wildcard(&self, name: &Fqdn) -> Option<&super::Rrsets> {
+        let apex_labels = self.apex().labels().len();
+        let closest_encloser = (apex_labels..name.labels().len())
+            .rev()
+            .map(|labels| name.ancestor(labels))
+            .find(|ancestor| self.contains_name(ancestor))?;
+
+        self.nodes.get(&closest_encloser.prepend_label("*"))
+    }
+}
+
+fn matches_type(qtype: QueryType, record_type: RecordType) -> bool {
+    match qtype {
+        QueryType::Type(wanted) => wanted == record_type,
+        QueryType::ANY => true,
+        _ => false,
+    }
+}
+
+fn with_owner(record: &Record, owner: &Fqdn) -> Record {
+    RecordBuilder::new()
+        .owner_name(owner.clone())
+        .record_type(record.record_type())
+        .data(record.data().clone())
+        .class(record.class())
+        .ttl(record.ttl())
+        .build()
+}
+
+#[cfg(test)]
+mod lookup_unittest {
+    use crate::packet::headers::header::HeaderBuilder;
+    use crate::packet::headers::header_flags::{header_flags_unittest::generate_query_header_flags, Rcode};
+    use crate::packet::message::{Message, MessageBuilder};
+    use crate::packet::question::question_unittest::generate_question;
+    use crate::packet::record::Record;
+    use crate::packet::seder::{deserializer::Deserialize, serializer::Serialize, ToBytes, TryFromBytes};
+    use crate::records::query_type::QueryType;
+    use crate::records::record_type::RecordType;
+    use crate::zone::zone_unittest::{example_zone, name};
+
+    fn owners_and_types(records: &[Record]) -> Vec<(String, RecordType)> {
+        records
+            .iter()
+            .map(|record| (record.owner_name().to_string(), record.record_type()))
+            .collect()
+    }
+
+    #[test]
+    fn exact_match() {
+        let lookup = example_zone().lookup(&name("www.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(lookup.response_code(), Rcode::NoError);
+        assert!(lookup.is_authoritative());
+        assert_eq!(owners_and_types(lookup.answers()), vec![(String::from("www.example.com."), RecordType::A)]);
+        assert!(lookup.authorities().is_empty());
+
+        let lookup = example_zone().lookup(&name("www.example.com."), QueryType::ANY);
+        assert_eq!(lookup.answers().len(), 2);
+    }
+
+    #[test]
+    fn apex_ns_with_glue() {
+        let lookup = example_zone().lookup(&name("example.com."), QueryType::Type(RecordType::NS));
+
+        assert!(lookup.is_authoritative());
+        assert_eq!(lookup.answers().len(), 1);
+        assert_eq!(owners_and_types(lookup.additionals()), vec![(String::from("ns1.example.com."), RecordType::A)]);
+    }
+
+    #[test]
+    fn chase_cname() {
+        let zone = example_zone();
+        let lookup = zone.lookup(&name("chain.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(
+            owners_and_types(lookup.answers()),
+            vec![
+                (String::from("chain.example.com."), RecordType::CNAME),
+                (String::from("alias.example.com."), RecordType::CNAME),
+                (String::from("www.example.com."), RecordType::A),
+            ]
+        );
+
+        let lookup = zone.lookup(&name("alias.example.com."), QueryType::Type(RecordType::CNAME));
+        assert_eq!(owners_and_types(lookup.answers()), vec![(String::from("alias.example.com."), RecordType::CNAME)]);
+
+        let lookup = zone.lookup(&name("out.example.com."), QueryType::Type(RecordType::A));
+        assert_eq!(lookup.response_code(), Rcode::NoError);
+        assert_eq!(lookup.answers().len(), 1);
+        assert!(lookup.authorities().is_empty());
+    }
+
+    #[test]
+    fn stop_at_cname_loop() {
+        let lookup = example_zone().lookup(&name("loop1.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(lookup.response_code(), Rcode::NoError);
+        assert_eq!(
+            owners_and_types(lookup.answers()),
+            vec![
+                (String::from("loop1.example.com."), RecordType::CNAME),
+                (String::from("loop2.example.com."), RecordType::CNAME),
+            ]
+        );
+    }
+
+    #[test]
+    fn synthesize_wildcard() {
+        let zone = example_zone();
+        let lookup = zone.lookup(&name("host.wild.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(owners_and_types(lookup.answers()), vec![(String::from("host.wild.example.com."), RecordType::A)]);
+        assert_eq!(lookup.answers()[0].data().to_string(), "192.0.2.99");
+
+        let lookup = zone.lookup(&name("host.wild.example.com."), QueryType::Type(RecordType::AAAA));
+        assert_eq!(lookup.response_code(), Rcode::NoError);
+        assert!(lookup.answers().is_empty());
+        assert_eq!(lookup.authorities().len(), 1);
+
+        let lookup = zone.lookup(&name("host.www.example.com."), QueryType::Type(RecordType::A));
+        assert_eq!(lookup.response_code(), Rcode::NameError);
+    }
+
+    #[test]
+    fn negative_answers() {
+        let zone = example_zone();
+        let nxdomain = zone.lookup(&name("missing.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(nxdomain.response_code(), Rcode::NameError);
+        assert!(nxdomain.is_authoritative());
+        assert_eq!(owners_and_types(nxdomain.authorities()), vec![(String::from("example.com."), RecordType::SOA)]);
+        assert_eq!(nxdomain.authorities()[0].ttl(), 300);
+
+        let nodata = zone.lookup(&name("www.example.com."), QueryType::Type(RecordType::DNSKEY));
+        assert_eq!(nodata.response_code(), Rcode::NoError);
+        assert!(nodata.answers().is_empty());
+        assert_eq!(nodata.authorities().len(), 1);
+
+        let empty_non_terminal = zone.lookup(&name("b.c.example.com."), QueryType::Type(RecordType::A));
+        assert_eq!(empty_non_terminal.response_code(), Rcode::NoError);
+        assert_eq!(empty_non_terminal.authorities().len(), 1);
+    }
+
+    #[test]
+    fn refer_at_zone_cut() {
+        let zone = example_zone();
+        let referral = zone.lookup(&name("host.sub.example.com."), QueryType::Type(RecordType::A));
+
+        assert_eq!(referral.response_code(), Rcode::NoError);
+        assert!(!referral.is_authoritative());
+        assert!(referral.answers().is_empty());
+        assert_eq!(referral.authorities().len(), 2);
+        assert_eq!(owners_and_types(referral.additionals()), vec![(String::from("ns1.sub.example.com."), RecordType::A)]);
+
+        let glue = zone.lookup(&name("ns1.sub.example.com."), QueryType::Type(RecordType::A));
+        assert!(!glue.is_authoritative());
+        assert!(glue.answers().is_empty());
+
+        let ds = zone.lookup(&name("sub.example.com."), QueryType::Type(RecordType::DS));
+        assert!(ds.is_authoritative());
+        assert_eq!(owners_and_types(ds.answers()), vec![(String::from("sub.example.com."), RecordType::DS)]);
+    }
+
+    #[test]
+    fn refuse_out_of_zone() {
+        let lookup = example_zone().lookup(&name("www.example.org."), QueryType::Type(RecordType::A));
+
+        assert_eq!(lookup.response_code(), Rcode::Refused);
+        assert!(!lookup.is_authoritative());
+    }
+
+    #[test]
+    fn answer_query() {
+        let header = HeaderBuilder::new()
+            .id(0x1234)
+            .flags(generate_query_header_flags(true))
+            .question_count(1)
+            .build();
+        let query = MessageBuilder::new()
+            .header(header)
+            .question(generate_question("www.example.com.", RecordType::A))
+            .build();
+        let response = example_zone().answer(&query);
+
+        assert_eq!(response.header().id(), 0x1234);
+        assert!(response.header().flags().is_authoritative_answer());
+        assert!(response.header().flags().is_recursion_desired());
+        assert!(!response.header().flags().is_recursion_available());
+        assert_eq!(response.header().flags().response_code(), Rcode::NoError);
+        assert_eq!(response.header().answer_count(), 1);
+        assert_eq!(response.answers().len(), 1);
+
+        let mut encoder = Serialize::new();
+        response.to_bytes(&mut encoder);
+        let decoded = Message::try_from_bytes(&mut Deserialize::new(&encoder.bin_data())).unwrap();
+        assert_eq!(decoded, response);
+    }
+}
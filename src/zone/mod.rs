@@ -0,0 +1,305 @@
+pub mod catalog;
+pub mod journal;
+pub mod lookup;
+pub mod transfer;
+
+use crate::packet::fqdn::Fqdn;
+use crate::packet::record::Record;
+use crate::records::record_class::RecordClass;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ZoneError {
+    #[error("Zone {0} has no SOA record at its apex")]
+    MissingSoa(Fqdn),
+    #[error("Record {0} is outside the zone")]
+    OutOfZone(Fqdn),
+    #[error("Record {0} has a different class than the zone")]
+    ClassMismatch(Fqdn),
+    #[error("CNAME at {0} cannot coexist with other data")]
+    CnameConflict(Fqdn),
+    #[error("Diff applies to serial {0}, not to serial {1}")]
+    SerialMismatch(u32, u32),
+}
+
+pub type ZoneResult<T> = Result<T, ZoneError>;
+
+type Rrsets = HashMap<RecordType, Vec<Record>>;
+
+/// The authoritative data of one zone, held as RRsets per owner name.
+///
+/// Owners are kept in canonical order so that the names below a node can
+/// be found with a range scan, which is what empty non-terminal and
+/// closest encloser checks need.
+#[derive(Debug, Clone)]
+pub struct Zone {
+    apex: Fqdn,
+    class: RecordClass,
+    nodes: BTreeMap<Fqdn, Rrsets>,
+}
+
+impl Zone {
+    /// Builds the zone from its records, which must include the SOA at
+    /// the apex. The class of the zone is the class of the SOA.
+    pub fn new(apex: Fqdn, records: impl IntoIterator<Item = Record>) -> ZoneResult<Self> {
+        let records: Vec<Record> = records.into_iter().collect();
+        let soa = records
+            .iter()
+            .find(|record| record.owner_name() == &apex && record.record_type() == RecordType::SOA)
+            .ok_or_else(|| ZoneError::MissingSoa(apex.clone()))?;
+
+        let mut zone = Self {
+            class: soa.class(),
+            apex,
+            nodes: BTreeMap::new(),
+        };
+        for record in records {
+            zone.insert(record)?;
+        }
+
+        Ok(zone)
+    }
+
+    pub fn apex(&self) -> &Fqdn {
+        &self.apex
+    }
+
+    pub fn class(&self) -> RecordClass {
+        self.class
+    }
+
+    pub fn soa(&self) -> &Record {
+        &self.nodes[&self.apex][&RecordType::SOA][0]
+    }
+
+    pub fn serial(&self) -> u32 {
+        match self.soa().data() {
+            RecordData::SOA(soa) => soa.serial(),
+            _ => unreachable!("SOA RRset holds SOA data"),
+        }
+    }
+
+    pub fn rrset(&self, name: &Fqdn, record_type: RecordType) -> Option<&[Record]> {
+        self.nodes.get(name)?.get(&record_type).map(Vec::as_slice)
+    }
+
+    /// Every record, owners in canonical order and RRsets by type number
+    /// with the SOA first, so the apex SOA is the first record.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.nodes.values().flat_map(|rrsets| {
+            let mut rrsets: Vec<(&RecordType, &Vec<Record>)> = rrsets.iter().collect();
+            rrsets.sort_by_key(|(record_type, _)| (**record_type != RecordType::SOA, u16::from(**record_type)));
+            rrsets.into_iter().flat_map(|(_, records)| records.iter())
+        })
+    }
+
+    /// Adds a record to its RRset, returning false if it was already
+    /// there. An SOA replaces the existing one, as the SOA RRset holds a
+    /// single record.
+    pub fn insert(&mut self, record: Record) -> ZoneResult<bool> {
+        let owner = record.owner_name().clone();
+        if !owner.is_subdomain_of(&self.apex) {
+            return Err(ZoneError::OutOfZone(owner));
+        }
+        if record.class() != self.class {
+            return Err(ZoneError::ClassMismatch(owner));
+        }
+
+        let record_type = record.record_type();
+        let conflicts = self.nodes.get(&owner).is_some_and(|rrsets| match record_type {
+            RecordType::CNAME => {
+                rrsets
+                    .keys()
+                    .any(|existing| *existing != RecordType::CNAME && !coexists_with_cname(*existing))
+                    || rrsets.get(&RecordType::CNAME).is_some_and(|cnames| !cnames.contains(&record))
+            }
+            other => !coexists_with_cname(other) && rrsets.contains_key(&RecordType::CNAME),
+        });
+        if conflicts {
+            return Err(ZoneError::CnameConflict(owner));
+        }
+
+        let rrset = self.nodes.entry(owner.clone()).or_default().entry(record_type).or_default();
+        if record_type == RecordType::SOA && owner == self.apex {
+            let replaced = rrset.first() != Some(&record);
+            *rrset = vec![record];
+            return Ok(replaced);
+        }
+        if rrset.contains(&record) {
+            return Ok(false);
+        }
+        rrset.push(record);
+
+        Ok(true)
+    }
+
+    /// Takes a record out of its RRset, returning false if it was not
+    /// there. The apex SOA stays, as a zone cannot go without it.
+    pub fn remove(&mut self, record: &Record) -> bool {
+        let owner = record.owner_name();
+        let record_type = record.record_type();
+        if record_type == RecordType::SOA && owner == &self.apex {
+            return false;
+        }
+        let Some(rrsets) = self.nodes.get_mut(owner) else {
+            return false;
+        };
+        let Some(rrset) = rrsets.get_mut(&record_type) else {
+            return false;
+        };
+        let Some(position) = rrset.iter().position(|existing| existing == record) else {
+            return false;
+        };
+
+        rrset.remove(position);
+        if rrset.is_empty() {
+            rrsets.remove(&record_type);
+        }
+        if rrsets.is_empty() {
+            self.nodes.remove(owner);
+        }
+
+        true
+    }
+
+    /// Whether the name owns records or is an empty non-terminal above
+    /// names that do.
+    pub fn contains_name(&self, name: &Fqdn) -> bool {
+        self.nodes.contains_key(name) || self.has_descendants(name)
+    }
+
+    /// Descendants sort right after their ancestor, so the first name
+    /// after `name` is below it if any name is.
+    fn has_descendants(&self, name: &Fqdn) -> bool {
+        self.nodes
+            .range((Bound::Excluded(name), Bound::Unbounded))
+            .next()
+            .is_some_and(|(next, _)| next.is_subdomain_of(name))
+    }
+}
+
+/// Types allowed next to a CNAME (RFC 2181 section 10.1, RFC 4035
+/// section 2.5).
+fn coexists_with_cname(record_type: RecordType) -> bool {
+    matches!(record_type, RecordType::RRSIG | RecordType::NSEC)
+}
+
+#[cfg(test)]
+pub(crate) mod zone_unittest {
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::record::Record;
+    use crate::records::record_type::RecordType;
+    use crate::zone::{Zone, ZoneError};
+    use crate::zonefile::parser::ZoneParser;
+
+    pub(crate) const ZONE: &str = "\
+$ORIGIN example.com.
+$TTL 3600
+@       SOA     ns1 hostmaster 2024010101 7200 3600 1209600 300
+        NS      ns1
+ns1     A       192.0.2.53
+www     A       192.0.2.1
+        AAAA    2001:db8::1
+alias   CNAME   www
+chain   CNAME   alias
+out     CNAME   www.example.net.
+loop1   CNAME   loop2
+loop2   CNAME   loop1
+*.wild  A       192.0.2.99
+a.b.c   A       192.0.2.3
+sub     NS      ns1.sub
+        NS      ns.example.net.
+        DS      12345 13 2 0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef
+ns1.sub A       192.0.2.54
+";
+
+    pub(crate) fn name(name: &str) -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from(name)).build()
+    }
+
+    pub(crate) fn records(text: &str) -> Vec<Record> {
+        ZoneParser::new(name("example.com.")).default_ttl(3600).parse_str("zone", text).unwrap()
+    }
+
+    pub(crate) fn example_zone() -> Zone {
+        Zone::new(name("example.com."), records(ZONE)).unwrap()
+    }
+
+    #[test]
+    fn build_zone() {
+        let zone = example_zone();
+
+        assert_eq!(zone.serial(), 2024010101);
+        assert_eq!(zone.rrset(&name("www.example.com."), RecordType::A).unwrap().len(), 1);
+        assert_eq!(zone.rrset(&name("sub.example.com."), RecordType::NS).unwrap().len(), 2);
+        assert!(zone.rrset(&name("www.example.com."), RecordType::DNSKEY).is_none());
+        assert!(zone.contains_name(&name("c.example.com.")));
+        assert!(!zone.contains_name(&name("d.example.com.")));
+        assert_eq!(zone.records().count(), records(ZONE).len());
+        assert_eq!(zone.records().next().unwrap().record_type(), RecordType::SOA);
+    }
+
+    #[test]
+    fn insert_records() {
+        let mut zone = example_zone();
+        let www = records("www A 192.0.2.1\nwww A 192.0.2.2\n");
+
+        assert!(!zone.insert(www[0].clone()).unwrap());
+        assert!(zone.insert(www[1].clone()).unwrap());
+        assert_eq!(zone.rrset(&name("www.example.com."), RecordType::A).unwrap().len(), 2);
+
+        let soa = records("@ 60 SOA ns1 hostmaster 2024010102 7200 3600 1209600 300\n");
+        assert!(zone.insert(soa[0].clone()).unwrap());
+        assert_eq!(zone.serial(), 2024010102);
+    }
+
+    #[test]
+    fn reject_invalid_records() {
+        let mut zone = example_zone();
+
+        assert_eq!(
+            Zone::new(name("example.com."), records("www A 192.0.2.1\n")).unwrap_err(),
+            ZoneError::MissingSoa(name("example.com."))
+        );
+        assert_eq!(
+            zone.insert(records("www.example.net. A 192.0.2.1\n").remove(0)),
+            Err(ZoneError::OutOfZone(name("www.example.net.")))
+        );
+        assert_eq!(
+            zone.insert(records("www CH A 192.0.2.1\n").remove(0)),
+            Err(ZoneError::ClassMismatch(name("www.example.com.")))
+        );
+        assert_eq!(
+            zone.insert(records("www CNAME alias\n").remove(0)),
+            Err(ZoneError::CnameConflict(name("www.example.com.")))
+        );
+        assert_eq!(
+            zone.insert(records("alias A 192.0.2.1\n").remove(0)),
+            Err(ZoneError::CnameConflict(name("alias.example.com.")))
+        );
+        assert_eq!(
+            zone.insert(records("alias CNAME chain\n").remove(0)),
+            Err(ZoneError::CnameConflict(name("alias.example.com.")))
+        );
+        assert!(zone.insert(records("new CNAME www\n").remove(0)).unwrap());
+    }
+
+    #[test]
+    fn remove_records() {
+        let mut zone = example_zone();
+        let removed = records("a.b.c A 192.0.2.3\nwww A 192.0.2.1\n");
+
+        assert!(zone.remove(&removed[0]));
+        assert!(!zone.remove(&removed[0]));
+        assert!(!zone.contains_name(&name("b.example.com.")));
+        assert!(zone.remove(&removed[1]));
+        assert!(zone.rrset(&name("www.example.com."), RecordType::A).is_none());
+        assert_eq!(zone.rrset(&name("www.example.com."), RecordType::AAAA).unwrap().len(), 1);
+        assert!(!zone.remove(&zone.soa().clone()));
+        assert_eq!(zone.serial(), 2024010101);
+    }
+}
@@ -0,0 +1,497 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::headers::header::HeaderBuilder;
+use crate::packet::headers::header_flags::{HeaderFlagsBuilder, Rcode};
+use crate::packet::message::{Message, MessageBuilder, MessageError};
+use crate::packet::record::Record;
+use crate::packet::seder::serializer::Serialize;
+use crate::packet::seder::ToBytes;
+use crate::packet::tsig::TsigError;
+use crate::records::record_type::RecordType;
+use crate::zone::journal::{is_newer_serial, soa_serial, Diff, Journal};
+use crate::zone::{Zone, ZoneError};
+use std::io;
+use thiserror::Error;
+
+const HEADER_LENGTH: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Cannot decode transfer message: {0}")]
+    Message(#[from] MessageError),
+    #[error("Server answered the transfer with {0}")]
+    Refused(Rcode),
+    #[error(transparent)]
+    Tsig(#[from] TsigError),
+    #[error("Transfer message does not answer the request")]
+    Mismatch,
+    #[error("Transfer of {0} does not start with its SOA")]
+    MissingSoa(Fqdn),
+    #[error("Transfer ends with another SOA than it started with")]
+    SoaMismatch,
+    #[error("Records follow the closing SOA")]
+    TrailingRecords,
+    #[error("Transfer ended before the closing SOA")]
+    Incomplete,
+    #[error(transparent)]
+    Zone(#[from] ZoneError),
+}
+
+pub type TransferResult<T> = Result<T, TransferError>;
+
+impl Zone {
+    /// The records of an AXFR response: the SOA, every other record and
+    /// the SOA again (RFC 5936 section 2.2).
+    pub fn transfer_records(&self) -> Vec<Record> {
+        let mut records: Vec<Record> = self.records().cloned().collect();
+        records.push(self.soa().clone());
+
+        records
+    }
+
+    /// The records of an IXFR response to a client holding the serial
+    /// (RFC 1995 section 4): the SOA alone when the client is up to date,
+    /// the diffs since its serial between two copies of the SOA when the
+    /// journal reaches back that far, and the whole zone as in AXFR when it
+    /// does not.
+    pub fn ixfr_records(&self, journal: &Journal, serial: u32) -> Vec<Record> {
+        let soa = self.soa();
+        if !is_newer_serial(self.serial(), serial) {
+            return vec![soa.clone()];
+        }
+
+        let diffs: Vec<&Diff> = journal.since(serial).map(Iterator::collect).unwrap_or_default();
+        if diffs.last().is_none_or(|diff| diff.new_serial() != self.serial()) {
+            return self.transfer_records();
+        }
+
+        std::iter::once(soa)
+            .chain(diffs.into_iter().flat_map(Diff::records))
+            .chain(std::iter::once(soa))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Splits the records of a transfer into responses to the query, each
+/// holding as many records as fit in `max_size` bytes with `reserve`
+/// bytes to spare for a TSIG record. A record too big for any message
+/// goes alone.
+pub(crate) fn pack(query: &Message, records: Vec<Record>, max_size: usize, reserve: usize) -> Vec<Message> {
+    let mut encoder = Serialize::new();
+    query.question().to_bytes(&mut encoder);
+    let overhead = HEADER_LENGTH + encoder.bin_data().len() + reserve;
+
+    let mut messages = Vec::new();
+    let mut batch = Vec::new();
+    let mut size = overhead;
+    for record in records {
+        // Encoded alone the record cannot compress against the rest, so
+        // this is an upper bound on what it adds
+        let mut encoder = Serialize::new();
+        record.to_bytes(&mut encoder);
+        let length = encoder.bin_data().len();
+
+        if !batch.is_empty() && size + length > max_size {
+            messages.push(response(query, std::mem::take(&mut batch)));
+            size = overhead;
+        }
+        batch.push(record);
+        size += length;
+    }
+    if !batch.is_empty() {
+        messages.push(response(query, batch));
+    }
+
+    messages
+}
+
+fn response(query: &Message, answers: Vec<Record>) -> Message {
+    let flags = HeaderFlagsBuilder::new()
+        .response(query.header().flags())
+        .authoritative_answer(true)
+        .truncation(false)
+        .recursion_available(false)
+        .response_code(Rcode::NoError)
+        .build();
+    let header = HeaderBuilder::new()
+        .id(query.header().id())
+        .flags(flags)
+        .question_count(1)
+        .answer_count(answers.len() as u16)
+        .build();
+
+    MessageBuilder::new()
+        .header(header)
+        .question(query.question().clone())
+        .answer(answers)
+        .build()
+}
+
+/// Rebuilds a zone from the answers of an AXFR response as its messages
+/// come in. The first record must be the SOA of the zone and the
+/// transfer is complete when that SOA shows up again.
+#[derive(Debug)]
+pub struct AxfrReader {
+    apex: Fqdn,
+    soa: Option<Record>,
+    records: Vec<Record>,
+    complete: bool,
+}
+
+impl AxfrReader {
+    pub fn new(apex: Fqdn) -> Self {
+        Self {
+            apex,
+            soa: None,
+            records: Vec::new(),
+            complete: false,
+        }
+    }
+
+    /// Takes the answer records of the next message.
+    pub fn read(&mut self, records: &[Record]) -> TransferResult<()> {
+        for record in records {
+            if self.complete {
+                return Err(TransferError::TrailingRecords);
+            }
+
+            let is_soa = record.owner_name() == &self.apex && record.record_type() == RecordType::SOA;
+            match &self.soa {
+                None if is_soa => self.soa = Some(record.clone()),
+                None => return Err(TransferError::MissingSoa(self.apex.clone())),
+                Some(soa) if is_soa => match record == soa {
+                    true => self.complete = true,
+                    false => return Err(TransferError::SoaMismatch),
+                },
+                Some(_) => self.records.push(record.clone()),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    pub fn finish(self) -> TransferResult<Zone> {
+        let Some(soa) = self.soa.filter(|_| self.complete) else {
+            return Err(TransferError::Incomplete);
+        };
+
+        Ok(Zone::new(self.apex, std::iter::once(soa).chain(self.records))?)
+    }
+}
+
+#[derive(Debug)]
+enum IxfrState {
+    /// Before the SOA of the version the server holds
+    Opening,
+    /// After that SOA, before the record telling which kind of response
+    /// this is
+    Opened,
+    /// Going through a whole zone, as in AXFR
+    Full,
+    /// Between the old and the new SOA of a diff
+    Deleting { old_soa: Record, deleted: Vec<Record> },
+    /// After the new SOA of a diff
+    Adding { old_soa: Record, deleted: Vec<Record>, new_soa: Record, added: Vec<Record> },
+    /// The response was the SOA alone, of a version the client has
+    UpToDate,
+    Complete,
+}
+
+/// Follows the answers of an IXFR response from a client holding the
+/// serial. Whether the response carries diffs or the whole zone shows in
+/// its second record: the old SOA of a first diff, at the client's serial,
+/// or anything else (RFC 1995 section 4). A first message holding the SOA
+/// alone tells the client it is up to date, or, over UDP, that it must
+/// ask again over TCP when the serial is newer.
+#[derive(Debug)]
+pub struct IxfrReader {
+    apex: Fqdn,
+    serial: u32,
+    soa: Option<Record>,
+    state: IxfrState,
+    records: Vec<Record>,
+    diffs: Vec<Diff>,
+}
+
+impl IxfrReader {
+    pub fn new(apex: Fqdn, serial: u32) -> Self {
+        Self {
+            apex,
+            serial,
+            soa: None,
+            state: IxfrState::Opening,
+            records: Vec::new(),
+            diffs: Vec::new(),
+        }
+    }
+
+    /// Takes the answer records of the next message.
+    pub fn read(&mut self, records: &[Record]) -> TransferResult<()> {
+        let first_message = self.soa.is_none();
+        for record in records {
+            self.read_record(record)?;
+        }
+
+        let soa_only = first_message && records.len() == 1;
+        if soa_only && self.soa.as_ref().is_some_and(|soa| !is_newer_serial(soa_serial(soa), self.serial)) {
+            self.state = IxfrState::UpToDate;
+        }
+
+        Ok(())
+    }
+
+    fn read_record(&mut self, record: &Record) -> TransferResult<()> {
+        let is_soa = record.owner_name() == &self.apex && record.record_type() == RecordType::SOA;
+        let Some(soa) = &self.soa else {
+            if !is_soa {
+                return Err(TransferError::MissingSoa(self.apex.clone()));
+            }
+            self.soa = Some(record.clone());
+            self.state = IxfrState::Opened;
+            return Ok(());
+        };
+        let is_current = is_soa && record == soa;
+
+        match &mut self.state {
+            IxfrState::Opening => unreachable!("the opening SOA was read"),
+            IxfrState::Opened | IxfrState::Full if is_current => self.state = IxfrState::Complete,
+            IxfrState::Opened if is_soa && soa_serial(record) == self.serial => {
+                self.state = IxfrState::Deleting {
+                    old_soa: record.clone(),
+                    deleted: Vec::new(),
+                }
+            }
+            IxfrState::Opened | IxfrState::Full if is_soa => return Err(TransferError::SoaMismatch),
+            IxfrState::Opened | IxfrState::Full => {
+                self.records.push(record.clone());
+                self.state = IxfrState::Full;
+            }
+            IxfrState::Deleting { old_soa, deleted } if is_soa => {
+                let next = IxfrState::Adding {
+                    old_soa: old_soa.clone(),
+                    deleted: std::mem::take(deleted),
+                    new_soa: record.clone(),
+                    added: Vec::new(),
+                };
+                self.state = next;
+            }
+            IxfrState::Deleting { deleted, .. } => deleted.push(record.clone()),
+            IxfrState::Adding { old_soa, deleted, new_soa, added } if is_soa => {
+                // The next SOA opens a diff from where this one ends, or
+                // closes the response
+                if soa_serial(record) != soa_serial(new_soa) {
+                    return Err(TransferError::SoaMismatch);
+                }
+                let diff = Diff::new(old_soa.clone(), std::mem::take(deleted), new_soa.clone(), std::mem::take(added));
+                self.diffs.push(diff);
+                self.state = match is_current {
+                    true => IxfrState::Complete,
+                    false => IxfrState::Deleting {
+                        old_soa: record.clone(),
+                        deleted: Vec::new(),
+                    },
+                };
+            }
+            IxfrState::Adding { added, .. } => added.push(record.clone()),
+            IxfrState::UpToDate | IxfrState::Complete => return Err(TransferError::TrailingRecords),
+        }
+
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self.state, IxfrState::UpToDate | IxfrState::Complete)
+    }
+
+    /// The zone the response leads to from the client's version, or None
+    /// when the client was up to date.
+    pub fn finish(self, zone: &Zone) -> TransferResult<Option<Zone>> {
+        match (self.state, self.soa) {
+            (IxfrState::UpToDate, _) => Ok(None),
+            (IxfrState::Complete, Some(soa)) if self.diffs.is_empty() => {
+                Ok(Some(Zone::new(self.apex, std::iter::once(soa).chain(self.records))?))
+            }
+            (IxfrState::Complete, _) => {
+                let mut zone = zone.clone();
+                for diff in &self.diffs {
+                    zone.apply(diff)?;
+                }
+                Ok(Some(zone))
+            }
+            _ => Err(TransferError::Incomplete),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod transfer_unittest {
+    use crate::packet::headers::header_flags::{Opcode, QR};
+    use crate::packet::message::Message;
+    use crate::records::record_type::RecordType;
+    use crate::server::handler::handler_unittest::{parse, query};
+    use crate::zone::journal::journal_unittest::version;
+    use crate::packet::record::Record;
+    use crate::zone::journal::{soa_serial, Diff, Journal};
+    use crate::zone::transfer::{pack, AxfrReader, IxfrReader, TransferError};
+    use crate::zone::zone_unittest::{example_zone, name, records};
+    use crate::zone::Zone;
+
+    /// The example zone with enough hosts to need several messages.
+    pub(crate) fn big_zone() -> Zone {
+        let mut zone = example_zone();
+        let text: String = (1..=200).map(|host| format!("host{} A 192.0.2.{}\n", host, host % 250)).collect();
+        for record in records(&text) {
+            zone.insert(record).unwrap();
+        }
+
+        zone
+    }
+
+    /// Packing only looks at the id and question of the query.
+    fn transfer_query() -> Message {
+        parse(&query(42, Opcode::Query, "example.com.", RecordType::A))
+    }
+
+    #[test]
+    fn pack_within_message_size() {
+        let zone = big_zone();
+        let messages = pack(&transfer_query(), zone.transfer_records(), 1024, 100);
+
+        assert!(messages.len() > 1);
+        for message in &messages {
+            assert!(message.wire().len() <= 924);
+            assert_eq!(message.header().id(), 42);
+            assert_eq!(message.header().flags().query_or_response(), QR::Response);
+            assert!(message.header().flags().is_authoritative_answer());
+        }
+
+        let answers: Vec<_> = messages.iter().flat_map(|message| message.answers().iter().cloned()).collect();
+        assert_eq!(answers, zone.transfer_records());
+        assert_eq!(answers.first().unwrap().record_type(), RecordType::SOA);
+        assert_eq!(answers.last().unwrap().record_type(), RecordType::SOA);
+    }
+
+    #[test]
+    fn read_transfer_into_zone() {
+        let zone = big_zone();
+        let mut reader = AxfrReader::new(name("example.com."));
+
+        for message in pack(&transfer_query(), zone.transfer_records(), 1024, 0) {
+            assert!(!reader.is_complete());
+            reader.read(message.answers()).unwrap();
+        }
+        assert!(reader.is_complete());
+
+        let transferred = reader.finish().unwrap();
+        assert_eq!(transferred.serial(), zone.serial());
+        assert_eq!(transferred.records().collect::<Vec<_>>(), zone.records().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reject_bad_bracketing() {
+        let other_soa = records("@ SOA ns1 hostmaster 2024010102 7200 3600 1209600 300\n").remove(0);
+        let records = example_zone().transfer_records();
+
+        let mut reader = AxfrReader::new(name("example.com."));
+        assert!(matches!(reader.read(&records[1..]), Err(TransferError::MissingSoa(_))));
+
+        let mut reader = AxfrReader::new(name("example.com."));
+        assert!(matches!(reader.read(&[records[0].clone(), other_soa]), Err(TransferError::SoaMismatch)));
+
+        let mut reader = AxfrReader::new(name("example.com."));
+        assert!(matches!(reader.read(&[records.as_slice(), &records[1..2]].concat()), Err(TransferError::TrailingRecords)));
+
+        let mut reader = AxfrReader::new(name("example.com."));
+        reader.read(&records[..records.len() - 1]).unwrap();
+        assert!(matches!(reader.finish(), Err(TransferError::Incomplete)));
+    }
+
+    /// Three versions of the example zone and the journal between them.
+    fn history() -> (Vec<Zone>, Journal) {
+        let mut third = version(2024010103, "b A 192.0.2.11\n");
+        third.remove(&records("www A 192.0.2.1\n")[0]);
+        let versions = vec![example_zone(), version(2024010102, "a A 192.0.2.10\n"), third];
+
+        let mut journal = Journal::new(10);
+        journal.push(Diff::between(&versions[0], &versions[1]));
+        journal.push(Diff::between(&versions[1], &versions[2]));
+
+        (versions, journal)
+    }
+
+    fn read_ixfr(zone: &Zone, answers: &[Record]) -> Option<Zone> {
+        let mut reader = IxfrReader::new(name("example.com."), zone.serial());
+        for message in pack(&transfer_query(), answers.to_vec(), 512, 0) {
+            assert!(!reader.is_complete());
+            reader.read(message.answers()).unwrap();
+        }
+        assert!(reader.is_complete());
+
+        reader.finish(zone).unwrap()
+    }
+
+    #[test]
+    fn serve_ixfr_records() {
+        let (versions, journal) = history();
+        let latest = &versions[2];
+
+        let incremental = latest.ixfr_records(&journal, 2024010101);
+        let soas: Vec<u32> = incremental
+            .iter()
+            .filter(|record| record.record_type() == RecordType::SOA)
+            .map(soa_serial)
+            .collect();
+        assert_eq!(soas, vec![2024010103, 2024010101, 2024010102, 2024010102, 2024010103, 2024010103]);
+        assert_eq!(incremental.len(), 6 + 4);
+
+        assert_eq!(latest.ixfr_records(&journal, 2024010102).len(), 4 + 3);
+        assert_eq!(latest.ixfr_records(&journal, 2024010103), vec![latest.soa().clone()]);
+        assert_eq!(latest.ixfr_records(&journal, 2024010200), vec![latest.soa().clone()]);
+        assert_eq!(latest.ixfr_records(&journal, 2024010100), latest.transfer_records());
+        assert_eq!(versions[1].ixfr_records(&journal, 2024010101), versions[1].transfer_records());
+    }
+
+    #[test]
+    fn read_ixfr_into_zone() {
+        let (versions, journal) = history();
+        let latest = &versions[2];
+
+        let updated = read_ixfr(&versions[0], &latest.ixfr_records(&journal, 2024010101)).unwrap();
+        assert_eq!(updated.records().collect::<Vec<_>>(), latest.records().collect::<Vec<_>>());
+
+        let updated = read_ixfr(&versions[0], &latest.transfer_records()).unwrap();
+        assert_eq!(updated.records().collect::<Vec<_>>(), latest.records().collect::<Vec<_>>());
+
+        assert!(read_ixfr(latest, &[latest.soa().clone()]).is_none());
+
+        let mut reader = IxfrReader::new(name("example.com."), 2024010101);
+        reader.read(&[latest.soa().clone()]).unwrap();
+        assert!(!reader.is_complete());
+    }
+
+    #[test]
+    fn reject_broken_ixfr() {
+        let (versions, journal) = history();
+        let records = versions[2].ixfr_records(&journal, 2024010101);
+
+        let mut reader = IxfrReader::new(name("example.com."), 2024010100);
+        assert!(matches!(reader.read(&records), Err(TransferError::SoaMismatch)));
+
+        let mut reader = IxfrReader::new(name("example.com."), 2024010101);
+        assert!(matches!(reader.read(&[records.as_slice(), &records[..1]].concat()), Err(TransferError::TrailingRecords)));
+
+        let mut reader = IxfrReader::new(name("example.com."), 2024010101);
+        reader.read(&records[..records.len() - 1]).unwrap();
+        assert!(matches!(reader.finish(&versions[0]), Err(TransferError::Incomplete)));
+
+        let mut reader = IxfrReader::new(name("example.com."), 2024010101);
+        reader.read(&records).unwrap();
+        assert!(matches!(reader.finish(&versions[1]), Err(TransferError::Zone(_))));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::zonefile::{Located, Position, ZoneFileErrorKind};
+
+/// A word or quoted string. Escapes are kept as written so names can
+/// tell an escaped dot from a label separator.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    pub text: String,
+    pub quoted: bool,
+    pub position: Position,
+}
+
+/// One logical line: the tokens of a physical line, or of several lines
+/// joined by parentheses.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Entry {
+    pub tokens: Vec<Token>,
+    /// The line started with a blank, so the owner is inherited.
+    pub blank_owner: bool,
+    pub position: Position,
+}
+
+/// Splits master file text into entries (RFC 1035 section 5.1).
+pub struct Lexer {
+    chars: Vec<char>,
+    index: usize,
+    position: Position,
+}
+
+impl Lexer {
+    pub fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            index: 0,
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    /// Returns the next entry holding at least one token, skipping blank
+    /// and comment-only lines.
+    pub(crate) fn next_entry(&mut self) -> Located<Option<Entry>> {
+        while self.peek().is_some() {
+            let position = self.position;
+            let blank_owner = matches!(self.peek(), Some(' ' | '\t'));
+            let mut tokens = Vec::new();
+            let mut open_parenthesis: Option<Position> = None;
+
+            loop {
+                match self.peek() {
+                    None => match open_parenthesis {
+                        Some(open) => return Err((open, ZoneFileErrorKind::UnclosedParenthesis)),
+                        None => break,
+                    },
+                    Some('\n') => {
+                        self.bump();
+                        if open_parenthesis.is_none() {
+                            break;
+                        }
+                    }
+                    Some(' ' | '\t' | '\r') => {
+                        self.bump();
+                    }
+                    Some(';') => {
+                        while self.peek().is_some_and(|char| char != '\n') {
+                            self.bump();
+                        }
+                    }
+                    Some('(') => {
+                        if open_parenthesis.is_some() {
+                            return Err((self.position, ZoneFileErrorKind::UnexpectedParenthesis));
+                        }
+                        open_parenthesis = Some(self.position);
+                        self.bump();
+                    }
+                    Some(')') => {
+                        if open_parenthesis.take().is_none() {
+                            return Err((self.position, ZoneFileErrorKind::UnexpectedParenthesis));
+                        }
+                        self.bump();
+                    }
+                    Some('"') => tokens.push(self.quoted()?),
+                    Some(_) => tokens.push(self.word()),
+                }
+            }
+
+            if !tokens.is_empty() {
+                return Ok(Some(Entry {
+                    tokens,
+                    blank_owner,
+                    position,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn word(&mut self) -> Token {
+        let position = self.position;
+        let mut text = String::new();
+
+        while let Some(char) = self.peek() {
+            if matches!(char, ' ' | '\t' | '\r' | '\n' | ';' | '(' | ')' | '"') {
+                break;
+            }
+
+            self.bump();
+            text.push(char);
+            if char == '\\' {
+                if let Some(escaped) = self.bump() {
+                    text.push(escaped);
+                }
+            }
+        }
+
+        Token {
+            text,
+            quoted: false,
+            position,
+        }
+    }
+
+    fn quoted(&mut self) -> Located<Token> {
+        let position = self.position;
+        let mut text = String::new();
+        self.bump();
+
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err((position, ZoneFileErrorKind::UnterminatedString)),
+                Some('"') => break,
+                Some('\\') => {
+                    text.push('\\');
+                    match self.bump() {
+                        Some(escaped) => text.push(escaped),
+                        None => return Err((position, ZoneFileErrorKind::UnterminatedString)),
+                    }
+                }
+                Some(char) => text.push(char),
+            }
+        }
+
+        Ok(Token {
+            text,
+            quoted: true,
+            position,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.index += 1;
+
+        match char {
+            '\n' => {
+                self.position.line += 1;
+                self.position.column = 1;
+            }
+            _ => self.position.column += 1,
+        }
+
+        Some(char)
+    }
+}
+
+#[cfg(test)]
+mod lexer_unittest {
+    use crate::zonefile::lexer::Lexer;
+    use crate::zonefile::{Position, ZoneFileErrorKind};
+
+    fn texts(lexer: &mut Lexer) -> Vec<Vec<String>> {
+        let mut entries = Vec::new();
+        while let Some(entry) = lexer.next_entry().unwrap() {
+            entries.push(entry.tokens.into_iter().map(|token| token.text).collect());
+        }
+
+        entries
+    }
+
+    #[test]
+    fn parentheses_join_lines_and_comments_are_dropped() {
+        let mut lexer = Lexer::new(
+            "@ IN SOA ns1 admin ( ; primary\n    1 ; serial\n    2 3 4 5 )\n\n; only a comment\n  A 192.0.2.1\n",
+        );
+
+        assert_eq!(
+            texts(&mut lexer),
+            vec![
+                vec!["@", "IN", "SOA", "ns1", "admin", "1", "2", "3", "4", "5"],
+                vec!["A", "192.0.2.1"],
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_owner_and_positions() {
+        let mut lexer = Lexer::new("www A 192.0.2.1\n\tAAAA 2001:db8::1\n");
+
+        let first = lexer.next_entry().unwrap().unwrap();
+        let second = lexer.next_entry().unwrap().unwrap();
+
+        assert!(!first.blank_owner);
+        assert!(second.blank_owner);
+        assert_eq!(second.tokens[1].position, Position { line: 2, column: 7 });
+        assert_eq!(lexer.next_entry().unwrap(), None);
+    }
+
+    #[test]
+    fn escapes_and_quotes_are_kept() {
+        let mut lexer = Lexer::new("a\\.b\\;c \"quoted ; text\" \\(x\n");
+
+        assert_eq!(texts(&mut lexer), vec![vec!["a\\.b\\;c", "quoted ; text", "\\(x"]]);
+    }
+
+    #[test]
+    fn error_unbalanced() {
+        let unclosed = Lexer::new("@ SOA (\n 1 2\n").next_entry().unwrap_err();
+        let unexpected = Lexer::new("@ A 1 )\n").next_entry().unwrap_err();
+        let unterminated = Lexer::new("@ TXT \"open\n").next_entry().unwrap_err();
+
+        assert_eq!(unclosed, (Position { line: 1, column: 7 }, ZoneFileErrorKind::UnclosedParenthesis));
+        assert_eq!(unexpected, (Position { line: 1, column: 7 }, ZoneFileErrorKind::UnexpectedParenthesis));
+        assert_eq!(unterminated, (Position { line: 1, column: 7 }, ZoneFileErrorKind::UnterminatedString));
+    }
+}
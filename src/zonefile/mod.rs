@@ -0,0 +1,95 @@
+pub(crate) mod lexer;
+pub mod parser;
+pub mod rdata;
+pub mod writer;
+
+use thiserror::Error;
+
+/// What went wrong while reading a master file, without the location.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ZoneFileErrorKind {
+    #[error("Quoted string is not terminated")]
+    UnterminatedString,
+    #[error("Parenthesis is never closed")]
+    UnclosedParenthesis,
+    #[error("Closing parenthesis without an opening one")]
+    UnexpectedParenthesis,
+    #[error("Unknown directive {0}")]
+    UnknownDirective(String),
+    #[error("Record has no owner and there is no previous owner to inherit")]
+    MissingOwner,
+    #[error("Record type is missing")]
+    MissingType,
+    #[error("Unknown record type {0}")]
+    UnknownType(String),
+    #[error("Invalid class {0}")]
+    InvalidClass(String),
+    #[error("Invalid TTL {0}")]
+    InvalidTtl(String),
+    #[error("Record has no TTL and neither $TTL nor a previous TTL applies")]
+    MissingTtl,
+    #[error("Invalid domain name {0}")]
+    InvalidName(String),
+    #[error("Missing {0}")]
+    MissingField(&'static str),
+    #[error("Invalid {0} {1}")]
+    InvalidField(&'static str, String),
+    #[error("Unexpected data {0} after the record")]
+    TrailingData(String),
+    #[error("Unable to read {0}: {1}")]
+    Unreadable(String, String),
+    #[error("$INCLUDE nesting is deeper than {0} files")]
+    IncludeTooDeep(usize),
+    #[error("Invalid $GENERATE {0}")]
+    InvalidGenerate(String),
+}
+
+/// A master file error with the file, line and column it was found at.
+/// Lines and columns count from 1.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[error("{file}:{line}:{column}: {kind}")]
+pub struct ZoneFileError {
+    file: String,
+    line: usize,
+    column: usize,
+    kind: ZoneFileErrorKind,
+}
+
+pub type ZoneFileResult<T> = Result<T, ZoneFileError>;
+
+impl ZoneFileError {
+    pub fn new(file: &str, position: Position, kind: ZoneFileErrorKind) -> Self {
+        Self {
+            file: file.to_string(),
+            line: position.line,
+            column: position.column,
+            kind,
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn kind(&self) -> &ZoneFileErrorKind {
+        &self.kind
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Errors inside a single file carry a position; the file name is added
+/// by the parser.
+pub(crate) type Located<T> = Result<T, (Position, ZoneFileErrorKind)>;
@@ -0,0 +1,507 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::record::{Record, RecordBuilder};
+use crate::records::record_class::RecordClass;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use crate::zonefile::lexer::{Entry, Lexer, Token};
+use crate::zonefile::rdata::{parse_name, parse_rdata, parse_ttl};
+use crate::zonefile::{Located, Position, ZoneFileError, ZoneFileErrorKind, ZoneFileResult};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// Stops $INCLUDE loops
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// What a record inherits from the lines before it.
+#[derive(Debug, Clone)]
+struct State {
+    origin: Fqdn,
+    default_ttl: Option<u32>,
+    last_ttl: Option<u32>,
+    last_owner: Option<Fqdn>,
+    last_class: RecordClass,
+}
+
+/// Reads master files (RFC 1035 section 5) into records, in file order.
+///
+/// A record without a TTL takes the `$TTL` value, then the last TTL given
+/// explicitly, and an SOA falls back to its minimum field. `$INCLUDE`
+/// paths are relative to the including file and an included file cannot
+/// change the origin of the file including it.
+#[derive(Debug, Clone)]
+pub struct ZoneParser {
+    origin: Fqdn,
+    default_ttl: Option<u32>,
+}
+
+impl ZoneParser {
+    pub fn new(origin: Fqdn) -> Self {
+        Self {
+            origin,
+            default_ttl: None,
+        }
+    }
+
+    /// TTL for records without one until the file sets `$TTL`.
+    pub fn default_ttl(mut self, ttl: u32) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    pub fn parse_file(&self, path: impl AsRef<Path>) -> ZoneFileResult<Vec<Record>> {
+        let mut records = Vec::new();
+        self.parse_path(path.as_ref(), &mut self.initial_state(), 0, &mut records)
+            .map_err(|(file, position, kind)| ZoneFileError::new(&file, position, kind))?;
+
+        Ok(records)
+    }
+
+    /// Parses text in memory. `file` names it in errors, and relative
+    /// `$INCLUDE` paths are taken from the working directory.
+    pub fn parse_str(&self, file: &str, text: &str) -> ZoneFileResult<Vec<Record>> {
+        let mut records = Vec::new();
+        self.parse_text(file, None, text, &mut self.initial_state(), 0, &mut records)
+            .map_err(|(file, position, kind)| ZoneFileError::new(&file, position, kind))?;
+
+        Ok(records)
+    }
+
+    fn initial_state(&self) -> State {
+        State {
+            origin: self.origin.clone(),
+            default_ttl: self.default_ttl,
+            last_ttl: None,
+            last_owner: None,
+            last_class: RecordClass::IN,
+        }
+    }
+
+    fn parse_path(
+        &self,
+        path: &Path,
+        state: &mut State,
+        depth: usize,
+        records: &mut Vec<Record>,
+    ) -> Result<(), (String, Position, ZoneFileErrorKind)> {
+        let file = path.display().to_string();
+        let text = fs::read_to_string(path).map_err(|error| {
+            (
+                file.clone(),
+                Position { line: 1, column: 1 },
+                ZoneFileErrorKind::Unreadable(file.clone(), error.to_string()),
+            )
+        })?;
+
+        self.parse_text(&file, path.parent(), &text, state, depth, records)
+    }
+
+    fn parse_text(
+        &self,
+        file: &str,
+        directory: Option<&Path>,
+        text: &str,
+        state: &mut State,
+        depth: usize,
+        records: &mut Vec<Record>,
+    ) -> Result<(), (String, Position, ZoneFileErrorKind)> {
+        let locate = |(position, kind): (Position, ZoneFileErrorKind)| (file.to_string(), position, kind);
+        let mut lexer = Lexer::new(text);
+
+        while let Some(entry) = lexer.next_entry().map_err(locate)? {
+            let directive = &entry.tokens[0];
+            if entry.blank_owner || directive.quoted || !directive.text.starts_with('$') {
+                records.push(parse_record(&entry.tokens, entry.blank_owner, entry.position, state).map_err(locate)?);
+                continue;
+            }
+
+            let arguments = &entry.tokens[1..];
+            match directive.text.to_ascii_uppercase().as_str() {
+                "$ORIGIN" => {
+                    let origin = single_argument(directive, arguments, "origin").map_err(locate)?;
+                    state.origin = parse_name(&origin.text, &state.origin)
+                        .map_err(|kind| locate((origin.position, kind)))?;
+                }
+                "$TTL" => {
+                    let ttl = single_argument(directive, arguments, "TTL").map_err(locate)?;
+                    state.default_ttl = Some(
+                        parse_ttl(&ttl.text)
+                            .ok_or_else(|| locate((ttl.position, ZoneFileErrorKind::InvalidTtl(ttl.text.clone()))))?,
+                    );
+                }
+                "$INCLUDE" => {
+                    let path = arguments
+                        .first()
+                        .ok_or_else(|| locate((directive.position, ZoneFileErrorKind::MissingField("file name"))))?;
+                    let mut included = state.clone();
+                    if let Some(origin) = arguments.get(1) {
+                        included.origin = parse_name(&origin.text, &state.origin)
+                            .map_err(|kind| locate((origin.position, kind)))?;
+                        included.last_owner = None;
+                    }
+                    if let Some(extra) = arguments.get(2) {
+                        return Err(locate((extra.position, ZoneFileErrorKind::TrailingData(extra.text.clone()))));
+                    }
+                    if depth == MAX_INCLUDE_DEPTH {
+                        return Err(locate((directive.position, ZoneFileErrorKind::IncludeTooDeep(MAX_INCLUDE_DEPTH))));
+                    }
+
+                    let relative = unescape(&path.text);
+                    let path = match directory {
+                        Some(directory) => directory.join(relative),
+                        None => Path::new(&relative).to_path_buf(),
+                    };
+                    self.parse_path(&path, &mut included, depth + 1, records)?;
+                }
+                "$GENERATE" => generate(&entry, state, records).map_err(locate)?,
+                _ => {
+                    return Err(locate((
+                        directive.position,
+                        ZoneFileErrorKind::UnknownDirective(directive.text.clone()),
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn single_argument<'a>(directive: &Token, arguments: &'a [Token], field: &'static str) -> Located<&'a Token> {
+    match arguments {
+        [argument] => Ok(argument),
+        [] => Err((directive.position, ZoneFileErrorKind::MissingField(field))),
+        [_, extra, ..] => Err((extra.position, ZoneFileErrorKind::TrailingData(extra.text.clone()))),
+    }
+}
+
+fn parse_record(tokens: &[Token], blank_owner: bool, position: Position, state: &mut State) -> Located<Record> {
+    let mut index = 0;
+    let owner_name = match blank_owner {
+        true => state
+            .last_owner
+            .clone()
+            .ok_or((position, ZoneFileErrorKind::MissingOwner))?,
+        false => {
+            index = 1;
+            parse_name(&tokens[0].text, &state.origin).map_err(|kind| (tokens[0].position, kind))?
+        }
+    };
+
+    // TTL and class may come in either order
+    let mut ttl = None;
+    let mut class = None;
+    while let Some(token) = tokens.get(index) {
+        let upper = token.text.to_ascii_uppercase();
+        if class.is_none() && (matches!(upper.as_str(), "IN" | "CS" | "CH" | "HS") || upper.starts_with("CLASS")) {
+            class = Some(
+                RecordClass::from_str(&token.text)
+                    .map_err(|_| (token.position, ZoneFileErrorKind::InvalidClass(token.text.clone())))?,
+            );
+        } else if ttl.is_none() && token.text.starts_with(|char: char| char.is_ascii_digit()) {
+            ttl = Some(parse_ttl(&token.text).ok_or((token.position, ZoneFileErrorKind::InvalidTtl(token.text.clone())))?);
+        } else {
+            break;
+        }
+        index += 1;
+    }
+
+    let type_token = tokens.get(index).ok_or_else(|| {
+        let last = tokens.last().map_or(position, |token| token.position);
+        (last, ZoneFileErrorKind::MissingType)
+    })?;
+    let record_type = RecordType::from_str(&type_token.text)
+        .map_err(|_| (type_token.position, ZoneFileErrorKind::UnknownType(type_token.text.clone())))?;
+    let data = parse_rdata(record_type, &tokens[index + 1..], &state.origin, type_token.position)?;
+
+    let ttl = match (ttl, state.default_ttl, state.last_ttl, &data) {
+        (Some(ttl), _, _, _) => {
+            state.last_ttl = Some(ttl);
+            ttl
+        }
+        (None, Some(default_ttl), _, _) => default_ttl,
+        (None, None, Some(last_ttl), _) => last_ttl,
+        (None, None, None, RecordData::SOA(soa)) => {
+            state.last_ttl = Some(soa.minimum());
+            soa.minimum()
+        }
+        (None, None, None, _) => return Err((type_token.position, ZoneFileErrorKind::MissingTtl)),
+    };
+
+    let class = class.unwrap_or(state.last_class);
+    state.last_class = class;
+    state.last_owner = Some(owner_name.clone());
+
+    Ok(RecordBuilder::new()
+        .owner_name(owner_name)
+        .record_type(record_type)
+        .data(data)
+        .class(class)
+        .ttl(ttl)
+        .build())
+}
+
+/// `$GENERATE start-stop[/step] lhs [ttl] [class] type rhs`, the BIND
+/// extension that expands `$` in the owner and rdata for each value.
+fn generate(entry: &Entry, state: &mut State, records: &mut Vec<Record>) -> Located<()> {
+    let directive = &entry.tokens[0];
+    let range = entry
+        .tokens
+        .get(1)
+        .ok_or((directive.position, ZoneFileErrorKind::MissingField("range")))?;
+    if entry.tokens.len() < 4 {
+        let last = entry.tokens.last().map_or(directive.position, |token| token.position);
+        return Err((last, ZoneFileErrorKind::MissingField("record")));
+    }
+
+    let (start, stop, step) =
+        parse_range(&range.text).ok_or((range.position, ZoneFileErrorKind::InvalidGenerate(range.text.clone())))?;
+
+    for value in (start..=stop).step_by(step) {
+        let tokens = entry.tokens[2..]
+            .iter()
+            .map(|token| {
+                let text = substitute(&token.text, value)
+                    .ok_or((token.position, ZoneFileErrorKind::InvalidGenerate(token.text.clone())))?;
+
+                Ok(Token { text, ..token.clone() })
+            })
+            .collect::<Located<Vec<Token>>>()?;
+
+        records.push(parse_record(&tokens, false, entry.position, state)?);
+    }
+
+    Ok(())
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32, usize)> {
+    let (bounds, step) = match range.split_once('/') {
+        Some((bounds, step)) => (bounds, step.parse::<usize>().ok().filter(|&step| step > 0)?),
+        None => (range, 1),
+    };
+    let (start, stop) = bounds.split_once('-')?;
+    let (start, stop) = (start.parse::<u32>().ok()?, stop.parse::<u32>().ok()?);
+
+    match start <= stop {
+        true => Some((start, stop, step)),
+        false => None,
+    }
+}
+
+/// Replaces `$` with the value and `${offset[,width[,base]]}` with the
+/// value plus offset, zero padded to width in base d, o, x or X. `\$` is
+/// a literal dollar sign.
+fn substitute(text: &str, value: u32) -> Option<String> {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some('$') => result.push('$'),
+                Some(escaped) => {
+                    result.push('\\');
+                    result.push(escaped);
+                }
+                None => result.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let modifier: String = chars.by_ref().take_while(|&char| char != '}').collect();
+                let mut parts = modifier.split(',');
+
+                let offset = parts.next().filter(|part| !part.is_empty()).map_or(Some(0), |part| part.parse::<i64>().ok())?;
+                let width = parts.next().map_or(Some(0), |part| part.parse::<usize>().ok())?;
+                let base = parts.next().unwrap_or("d");
+                if parts.next().is_some() {
+                    return None;
+                }
+
+                let value = u64::try_from(value as i64 + offset).ok()?;
+                let formatted = match base {
+                    "d" => format!("{:0width$}", value),
+                    "o" => format!("{:0width$o}", value),
+                    "x" => format!("{:0width$x}", value),
+                    "X" => format!("{:0width$X}", value),
+                    _ => return None,
+                };
+                result.push_str(&formatted);
+            }
+            '$' => result.push_str(&value.to_string()),
+            _ => result.push(char),
+        }
+    }
+
+    Some(result)
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => result.extend(chars.next()),
+            _ => result.push(char),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod parser_unittest {
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::record::Record;
+    use crate::records::record_class::RecordClass;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use crate::zonefile::parser::ZoneParser;
+    use crate::zonefile::ZoneFileErrorKind;
+    use std::fs;
+
+    const ZONE: &str = "\
+$ORIGIN example.com.
+$TTL 1h
+@   IN  SOA ns1 hostmaster ( 2024010101 ; serial
+            2h 30m 2w
+            5m )  ; minimum
+    IN  NS  ns1
+    NS  ns2.example.net.
+ns1 300 A   192.0.2.1
+    IN 600 AAAA 2001:db8::1
+www CNAME   @
+$ORIGIN sub
+host    A   192.0.2.2
+";
+
+    fn origin() -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from("example.com.")).build()
+    }
+
+    fn summary(records: &[Record]) -> Vec<(String, RecordType, u32)> {
+        records
+            .iter()
+            .map(|record| (record.owner_name().to_string(), record.record_type(), record.ttl()))
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join(format!("rdns-zonefile-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn parse_zone() {
+        let records = ZoneParser::new(Fqdn::root()).parse_str("example.com.zone", ZONE).unwrap();
+
+        assert_eq!(
+            summary(&records),
+            vec![
+                (String::from("example.com."), RecordType::SOA, 3600),
+                (String::from("example.com."), RecordType::NS, 3600),
+                (String::from("example.com."), RecordType::NS, 3600),
+                (String::from("ns1.example.com."), RecordType::A, 300),
+                (String::from("ns1.example.com."), RecordType::AAAA, 600),
+                (String::from("www.example.com."), RecordType::CNAME, 3600),
+                (String::from("host.sub.example.com."), RecordType::A, 3600),
+            ]
+        );
+        assert!(records.iter().all(|record| record.class() == RecordClass::IN));
+
+        let RecordData::SOA(soa) = records[0].data() else { panic!() };
+        assert_eq!(soa.responsible_mailbox().to_string(), "hostmaster.example.com.");
+        assert_eq!((soa.serial(), soa.refresh(), soa.expire(), soa.minimum()), (2024010101, 7200, 1_209_600, 300));
+
+        let RecordData::NS(ns) = records[2].data() else { panic!() };
+        assert_eq!(ns.name_server().to_string(), "ns2.example.net.");
+    }
+
+    #[test]
+    fn ttl_inheritance_without_default() {
+        let text = "@ SOA ns hostmaster 1 2 3 4 60\nwww 120 A 192.0.2.1\nftp A 192.0.2.2\n";
+        let records = ZoneParser::new(origin()).parse_str("zone", text).unwrap();
+        assert_eq!(records.iter().map(Record::ttl).collect::<Vec<_>>(), vec![60, 120, 120]);
+
+        let records = ZoneParser::new(origin()).default_ttl(900).parse_str("zone", "www A 192.0.2.1\n").unwrap();
+        assert_eq!(records[0].ttl(), 900);
+
+        let error = ZoneParser::new(origin()).parse_str("zone", "www A 192.0.2.1\n").unwrap_err();
+        assert_eq!(*error.kind(), ZoneFileErrorKind::MissingTtl);
+    }
+
+    #[test]
+    fn include_with_origin() {
+        let directory = temp_dir("include");
+        fs::write(directory.join("hosts.inc"), "$TTL 60\nmail A 192.0.2.25\n").unwrap();
+        fs::write(
+            directory.join("example.com.zone"),
+            "$TTL 300\n$INCLUDE hosts.inc lan\nwww A 192.0.2.80\n",
+        )
+        .unwrap();
+
+        let records = ZoneParser::new(origin()).parse_file(directory.join("example.com.zone")).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            summary(&records),
+            vec![
+                (String::from("mail.lan.example.com."), RecordType::A, 60),
+                (String::from("www.example.com."), RecordType::A, 300),
+            ]
+        );
+    }
+
+    #[test]
+    fn error_in_include_reports_included_file() {
+        let directory = temp_dir("include-error");
+        fs::write(directory.join("bad.inc"), "\nmail A 192.0.2.300\n").unwrap();
+        fs::write(directory.join("zone"), "$TTL 300\n$INCLUDE \"bad.inc\"\n").unwrap();
+
+        let error = ZoneParser::new(origin()).parse_file(directory.join("zone")).unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(error.file().ends_with("bad.inc"));
+        assert_eq!((error.line(), error.column()), (2, 8));
+    }
+
+    #[test]
+    fn generate() {
+        let text = "$TTL 60\n$GENERATE 1-5/2 host-${0,2,x} A 192.0.2.${10}\n$GENERATE 3-3 \\$$ CNAME h$\n";
+        let records = ZoneParser::new(origin()).parse_str("zone", text).unwrap();
+
+        let owners: Vec<String> = records.iter().map(|record| record.owner_name().to_string()).collect();
+        assert_eq!(
+            owners,
+            vec!["host-01.example.com.", "host-03.example.com.", "host-05.example.com.", "\\$3.example.com."]
+        );
+
+        let RecordData::A(a) = records[2].data() else { panic!() };
+        assert_eq!(a.address().to_string(), "192.0.2.15");
+        let RecordData::CNAME(cname) = records[3].data() else { panic!() };
+        assert_eq!(cname.canonical_name().to_string(), "h3.example.com.");
+    }
+
+    #[test]
+    fn error_locations() {
+        let parser = ZoneParser::new(origin()).default_ttl(60);
+
+        let error = parser.parse_str("db.example", "www A 192.0.2.1\n\n  IN FOO bar\n").unwrap_err();
+        assert_eq!(error.to_string(), "db.example:3:6: Unknown record type FOO");
+
+        let error = parser.parse_str("db.example", "$INCLUDES file\n").unwrap_err();
+        assert_eq!((error.line(), error.column()), (1, 1));
+        assert_eq!(*error.kind(), ZoneFileErrorKind::UnknownDirective(String::from("$INCLUDES")));
+
+        let error = parser.parse_str("db.example", "  A 192.0.2.1\n").unwrap_err();
+        assert_eq!(*error.kind(), ZoneFileErrorKind::MissingOwner);
+
+        let error = parser.parse_str("db.example", "www CLASS0 A 192.0.2.1\n").unwrap_err();
+        assert_eq!((error.column(), error.kind().clone()), (5, ZoneFileErrorKind::InvalidClass(String::from("CLASS0"))));
+
+        let error = parser.parse_str("db.example", "$GENERATE 5-1 h$ A 192.0.2.$\n").unwrap_err();
+        assert_eq!(error.column(), 11);
+    }
+}
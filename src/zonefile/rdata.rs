@@ -0,0 +1,477 @@
+use crate::packet::fqdn::Fqdn;
+use crate::packet::seder::deserializer::Deserialize;
+use crate::records::rdata::a::A;
+use crate::records::rdata::aaaa::AAAA;
+use crate::records::rdata::algorithm::{DigestType, DnssecAlgorithm, Nsec3HashAlgorithm};
+use crate::records::rdata::cname::CNAME;
+use crate::records::rdata::dnskey::DNSKEY;
+use crate::records::rdata::ds::DS;
+use crate::records::rdata::ns::NS;
+use crate::records::rdata::nsec::NSEC;
+use crate::records::rdata::nsec3::NSEC3;
+use crate::records::rdata::nsec3param::NSEC3PARAM;
+use crate::records::rdata::rrsig::{parse_timestamp, RRSIG};
+use crate::records::rdata::soa::SOA;
+use crate::records::rdata::type_bitmap::TypeBitmap;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use crate::zonefile::lexer::Token;
+use crate::zonefile::{Located, Position, ZoneFileErrorKind};
+use data_encoding::{BASE32HEX_NOPAD, BASE64, HEXUPPER_PERMISSIVE};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+const MAX_LABEL_LENGTH: usize = 63;
+const MAX_NAME_LENGTH: usize = 255;
+
+// TTLs with the most significant bit set are invalid (RFC 2181 section 8)
+const MAX_TTL: u64 = i32::MAX as u64;
+
+/// Parses a presentation format name. `@` is the origin and names without
+/// a trailing dot are relative to it. `\X` and `\DDD` escape a character.
+pub fn parse_name(text: &str, origin: &Fqdn) -> Result<Fqdn, ZoneFileErrorKind> {
+    let invalid = || ZoneFileErrorKind::InvalidName(text.to_string());
+
+    match text {
+        "" => return Err(invalid()),
+        "@" => return Ok(origin.clone()),
+        "." => return Ok(Fqdn::root()),
+        _ => {}
+    }
+
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut absolute = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                let escaped = chars.next().ok_or_else(invalid)?;
+                if escaped.is_ascii_digit() {
+                    let digits: String = [Some(escaped), chars.next(), chars.next()].into_iter().flatten().collect();
+                    let value = digits
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|value| value.is_ascii() && digits.len() == 3)
+                        .ok_or_else(invalid)?;
+                    label.push(value as char);
+                } else {
+                    label.push(escaped);
+                }
+            }
+            '.' => {
+                if label.is_empty() {
+                    return Err(invalid());
+                }
+                labels.push(std::mem::take(&mut label));
+                absolute = chars.peek().is_none();
+            }
+            _ if char.is_ascii() => label.push(char),
+            _ => return Err(invalid()),
+        }
+    }
+
+    if !label.is_empty() {
+        labels.push(label);
+    }
+
+    if labels.iter().any(|label| label.len() > MAX_LABEL_LENGTH) {
+        return Err(invalid());
+    }
+
+    let base = match absolute {
+        true => Fqdn::root(),
+        false => origin.clone(),
+    };
+    let name = labels.iter().rev().fold(base, |name, label| name.prepend_label(label));
+
+    let wire_length: usize = name.labels().iter().map(|label| label.len() + 1).sum::<usize>() + 1;
+    if wire_length > MAX_NAME_LENGTH {
+        return Err(invalid());
+    }
+
+    Ok(name)
+}
+
+/// Parses a TTL given in seconds or with BIND style units, such as `1h30m`.
+pub fn parse_ttl(text: &str) -> Option<u32> {
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut total: u64 = 0;
+    let mut number: Option<u64> = None;
+
+    for char in text.chars() {
+        match char.to_ascii_lowercase() {
+            digit @ '0'..='9' => {
+                let value = number.unwrap_or(0).checked_mul(10)? + digit.to_digit(10)? as u64;
+                number = Some(value.min(MAX_TTL + 1));
+            }
+            unit => {
+                let multiplier = match unit {
+                    's' => 1,
+                    'm' => 60,
+                    'h' => 3600,
+                    'd' => 86_400,
+                    'w' => 604_800,
+                    _ => return None,
+                };
+                total += number.take()? * multiplier;
+            }
+        }
+
+        if total > MAX_TTL {
+            return None;
+        }
+    }
+
+    total += number.unwrap_or(0);
+
+    match total <= MAX_TTL {
+        true => Some(total as u32),
+        false => None,
+    }
+}
+
+/// Cursor over the rdata tokens of one record.
+struct Fields<'a> {
+    tokens: &'a [Token],
+    index: usize,
+    origin: &'a Fqdn,
+    // Reported for fields missing at the end of the record
+    end: Position,
+}
+
+impl<'a> Fields<'a> {
+    fn next(&mut self, field: &'static str) -> Located<&'a Token> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .ok_or((self.end, ZoneFileErrorKind::MissingField(field)))?;
+        self.index += 1;
+
+        Ok(token)
+    }
+
+    fn parse<T>(&mut self, field: &'static str, parse: impl FnOnce(&str) -> Option<T>) -> Located<T> {
+        let token = self.next(field)?;
+
+        parse(&token.text).ok_or_else(|| invalid(token, field))
+    }
+
+    fn number<T: FromStr>(&mut self, field: &'static str) -> Located<T> {
+        self.parse(field, |text| text.parse::<T>().ok())
+    }
+
+    fn ttl(&mut self, field: &'static str) -> Located<u32> {
+        self.parse(field, parse_ttl)
+    }
+
+    fn name(&mut self, field: &'static str) -> Located<Fqdn> {
+        let token = self.next(field)?;
+
+        parse_name(&token.text, self.origin).map_err(|kind| (token.position, kind))
+    }
+
+    fn timestamp(&mut self, field: &'static str) -> Located<u32> {
+        self.parse(field, |text| match text.len() {
+            14 => parse_timestamp(text),
+            _ => text.parse::<u32>().ok(),
+        })
+    }
+
+    fn salt(&mut self) -> Located<Vec<u8>> {
+        self.parse("salt", |text| match text {
+            "-" => Some(Vec::new()),
+            _ => HEXUPPER_PERMISSIVE.decode(text.as_bytes()).ok(),
+        })
+    }
+
+    /// Joins the remaining tokens, which base64 and hex fields may be
+    /// split across.
+    fn rest(&mut self, field: &'static str) -> Located<(String, Position)> {
+        let first = self.next(field)?;
+        let mut text = first.text.clone();
+
+        for token in &self.tokens[self.index..] {
+            text.push_str(&token.text);
+        }
+        self.index = self.tokens.len();
+
+        Ok((text, first.position))
+    }
+
+    fn base64(&mut self, field: &'static str) -> Located<Vec<u8>> {
+        let (text, position) = self.rest(field)?;
+
+        BASE64
+            .decode(text.as_bytes())
+            .map_err(|_| (position, ZoneFileErrorKind::InvalidField(field, text)))
+    }
+
+    fn hex(&mut self, field: &'static str) -> Located<Vec<u8>> {
+        let (text, position) = self.rest(field)?;
+
+        HEXUPPER_PERMISSIVE
+            .decode(text.as_bytes())
+            .map_err(|_| (position, ZoneFileErrorKind::InvalidField(field, text)))
+    }
+
+    fn types(&mut self) -> Located<TypeBitmap> {
+        let mut types = Vec::new();
+
+        for token in &self.tokens[self.index..] {
+            let record_type = match token.text.to_ascii_uppercase().strip_prefix("TYPE") {
+                Some(number) => number.parse::<u16>().ok(),
+                None => RecordType::from_str(&token.text).ok().map(u16::from),
+            };
+            types.push(record_type.ok_or_else(|| invalid(token, "type"))?);
+        }
+        self.index = self.tokens.len();
+
+        Ok(TypeBitmap::new(types))
+    }
+
+    fn finish(self) -> Located<()> {
+        match self.tokens.get(self.index) {
+            Some(token) => Err((token.position, ZoneFileErrorKind::TrailingData(token.text.clone()))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn invalid(token: &Token, field: &'static str) -> (Position, ZoneFileErrorKind) {
+    (token.position, ZoneFileErrorKind::InvalidField(field, token.text.clone()))
+}
+
+/// Parses the rdata of one record, in its type's presentation format or
+/// in the generic `\# length hex` form (RFC 3597 section 5).
+pub(crate) fn parse_rdata(
+    record_type: RecordType,
+    tokens: &[Token],
+    origin: &Fqdn,
+    end: Position,
+) -> Located<RecordData> {
+    let mut fields = Fields {
+        tokens,
+        index: 0,
+        origin,
+        end,
+    };
+
+    if tokens.first().is_some_and(|token| token.text == "\\#" && !token.quoted) {
+        fields.index = 1;
+        return parse_generic(record_type, fields);
+    }
+
+    let data = match record_type {
+        RecordType::A => RecordData::A(A::new(fields.parse("address", |text| Ipv4Addr::from_str(text).ok())?)),
+        RecordType::AAAA => {
+            RecordData::AAAA(AAAA::new(fields.parse("address", |text| Ipv6Addr::from_str(text).ok())?))
+        }
+        RecordType::NS => RecordData::NS(NS::new(fields.name("name server")?)),
+        RecordType::CNAME => RecordData::CNAME(CNAME::new(fields.name("canonical name")?)),
+        RecordType::SOA => RecordData::SOA(SOA::new(
+            fields.name("primary name server")?,
+            fields.name("responsible mailbox")?,
+            fields.number("serial")?,
+            fields.ttl("refresh")?,
+            fields.ttl("retry")?,
+            fields.ttl("expire")?,
+            fields.ttl("minimum")?,
+        )),
+        RecordType::KEY => RecordData::KEY(parse_dnskey(&mut fields)?),
+        RecordType::DNSKEY => RecordData::DNSKEY(parse_dnskey(&mut fields)?),
+        RecordType::CDNSKEY => RecordData::CDNSKEY(parse_dnskey(&mut fields)?),
+        RecordType::DS => RecordData::DS(parse_ds(&mut fields)?),
+        RecordType::CDS => RecordData::CDS(parse_ds(&mut fields)?),
+        RecordType::RRSIG => RecordData::RRSIG(RRSIG::new(
+            fields.parse("type covered", |text| RecordType::from_str(text).ok())?,
+            DnssecAlgorithm::from(fields.number::<u8>("algorithm")?),
+            fields.number("labels")?,
+            fields.ttl("original TTL")?,
+            fields.timestamp("signature expiration")?,
+            fields.timestamp("signature inception")?,
+            fields.number("key tag")?,
+            fields.name("signer name")?,
+            fields.base64("signature")?,
+        )),
+        RecordType::NSEC => RecordData::NSEC(NSEC::new(fields.name("next domain name")?, fields.types()?)),
+        RecordType::NSEC3 => RecordData::NSEC3(NSEC3::new(
+            Nsec3HashAlgorithm::from(fields.number::<u8>("hash algorithm")?),
+            fields.number("flags")?,
+            fields.number("iterations")?,
+            fields.salt()?,
+            fields.parse("next hashed owner name", |text| {
+                BASE32HEX_NOPAD.decode(text.to_ascii_uppercase().as_bytes()).ok()
+            })?,
+            fields.types()?,
+        )),
+        RecordType::NSEC3PARAM => RecordData::NSEC3PARAM(NSEC3PARAM::new(
+            Nsec3HashAlgorithm::from(fields.number::<u8>("hash algorithm")?),
+            fields.number("flags")?,
+            fields.number("iterations")?,
+            fields.salt()?,
+        )),
+        // Types without a presentation format only have the generic one
+        RecordType::Unknown(_) => {
+            let token = fields.next("generic rdata")?;
+            return Err(invalid(token, "generic rdata"));
+        }
+    };
+
+    fields.finish()?;
+
+    Ok(data)
+}
+
+fn parse_dnskey(fields: &mut Fields) -> Located<DNSKEY> {
+    let flags = fields.number("flags")?;
+    let protocol_token = fields.next("protocol")?;
+    if protocol_token.text.parse::<u8>() != Ok(DNSKEY::PROTOCOL) {
+        return Err(invalid(protocol_token, "protocol"));
+    }
+    let algorithm = DnssecAlgorithm::from(fields.number::<u8>("algorithm")?);
+
+    Ok(DNSKEY::new(flags, algorithm, fields.base64("public key")?))
+}
+
+fn parse_ds(fields: &mut Fields) -> Located<DS> {
+    Ok(DS::new(
+        fields.number("key tag")?,
+        DnssecAlgorithm::from(fields.number::<u8>("algorithm")?),
+        DigestType::from(fields.number::<u8>("digest type")?),
+        fields.hex("digest")?,
+    ))
+}
+
+fn parse_generic(record_type: RecordType, mut fields: Fields) -> Located<RecordData> {
+    let length_token = fields.next("rdata length")?;
+    let length = length_token
+        .text
+        .parse::<u16>()
+        .map_err(|_| invalid(length_token, "rdata length"))?;
+
+    let data = match length {
+        0 => Vec::new(),
+        _ => fields.hex("rdata")?,
+    };
+    fields.finish()?;
+
+    if data.len() != length as usize {
+        return Err(invalid(length_token, "rdata length"));
+    }
+
+    RecordData::from_bytes(&mut Deserialize::new(&data), &record_type, length)
+        .map_err(|error| (length_token.position, ZoneFileErrorKind::InvalidField("rdata", error.to_string())))
+}
+
+#[cfg(test)]
+mod rdata_unittest {
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::records::rdata::dnskey::dnskey_unittest::get_rfc4034_dnskey;
+    use crate::records::record_data::RecordData;
+    use crate::records::record_type::RecordType;
+    use crate::zonefile::lexer::Lexer;
+    use crate::zonefile::rdata::{parse_name, parse_rdata, parse_ttl};
+    use crate::zonefile::{Located, Position, ZoneFileErrorKind};
+
+    fn origin() -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from("example.com.")).build()
+    }
+
+    fn rdata(record_type: RecordType, text: &str) -> Located<RecordData> {
+        let entry = Lexer::new(text).next_entry().unwrap().unwrap();
+
+        parse_rdata(record_type, &entry.tokens, &origin(), Position { line: 1, column: 1 })
+    }
+
+    #[test]
+    fn names_relative_absolute_and_escaped() {
+        assert_eq!(parse_name("@", &origin()).unwrap(), origin());
+        assert_eq!(parse_name("www", &origin()).unwrap().to_string(), "www.example.com.");
+        assert_eq!(parse_name("ns.example.net.", &origin()).unwrap().to_string(), "ns.example.net.");
+        assert_eq!(parse_name("a\\.b", &origin()).unwrap().labels()[0], "a.b");
+        assert_eq!(parse_name("\\065b", &origin()).unwrap().labels()[0], "ab");
+        assert!(parse_name("a..b", &origin()).is_err());
+        assert!(parse_name(&"a".repeat(64), &origin()).is_err());
+    }
+
+    #[test]
+    fn ttl_units() {
+        assert_eq!(parse_ttl("3600"), Some(3600));
+        assert_eq!(parse_ttl("1h30m"), Some(5400));
+        assert_eq!(parse_ttl("1W2D"), Some(777_600));
+        assert_eq!(parse_ttl("2147483647"), Some(i32::MAX as u32));
+        assert_eq!(parse_ttl("2147483648"), None);
+        assert_eq!(parse_ttl("1x"), None);
+        assert_eq!(parse_ttl("h"), None);
+    }
+
+    #[test]
+    fn dnssec_rdata() {
+        let dnskey = rdata(
+            RecordType::DNSKEY,
+            "256 3 5 ( AQOeiiR0GOMYkDshWoSKz9Xz\n fwJr1AYtsmx3TGkJaNXVbfi/\n 2pHm822aJ5iI9BMzNXxeYCmZ\n DRD99WYwYqUSdjMmmAphXdvx\n egXd/M5+X7OrzKBaMbCVdFLU\n Uh6DhweJBjEVv5f2wwjM9Xzc\n nOf+EPbtG9DMBmADjFDc2w/r\n ljwvFw== )",
+        )
+        .unwrap();
+        assert_eq!(dnskey, RecordData::DNSKEY(get_rfc4034_dnskey()));
+
+        let rrsig = rdata(
+            RecordType::RRSIG,
+            "A 5 3 86400 20030322173103 ( 20030220173103 2642 example.com. oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTrPYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6oB9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3tGNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkGJ5D6fwFm8nN+6pBzeDQfsS3Ap3o= )",
+        )
+        .unwrap();
+        let RecordData::RRSIG(rrsig) = rrsig else { panic!() };
+        assert_eq!(rrsig.signature_expiration(), 1_048_354_263);
+        assert_eq!(rrsig.key_tag(), 2642);
+
+        let nsec3 = rdata(RecordType::NSEC3, "1 1 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr A RRSIG TYPE1234").unwrap();
+        let RecordData::NSEC3(nsec3) = nsec3 else { panic!() };
+        assert_eq!(nsec3.salt(), &[0xaa, 0xbb, 0xcc, 0xdd]);
+        assert_eq!(nsec3.type_bitmap().types(), &[1, 46, 1234]);
+
+        let ds = rdata(RecordType::DS, "60485 5 1 ( 2BB183AF5F22588179A53B0A 98631FAD1A292118 )").unwrap();
+        let RecordData::DS(ds) = ds else { panic!() };
+        assert_eq!(ds.digest().len(), 20);
+    }
+
+    #[test]
+    fn generic_rdata() {
+        assert_eq!(rdata(RecordType::A, "\\# 4 C0000201").unwrap(), rdata(RecordType::A, "192.0.2.1").unwrap());
+        assert!(matches!(
+            rdata(RecordType::A, "\\# 5 C0000201"),
+            Err((_, ZoneFileErrorKind::InvalidField("rdata length", _)))
+        ));
+
+        let mx = rdata(RecordType::try_from(15).unwrap(), "\\# 8 000A046D61696C00").unwrap();
+        assert_eq!(mx.record_type(), RecordType::try_from(15).unwrap());
+        assert_eq!(mx.to_string(), "\\# 8 000A046D61696C00");
+        assert!(matches!(
+            rdata(RecordType::try_from(15).unwrap(), "10 mail"),
+            Err((_, ZoneFileErrorKind::InvalidField("generic rdata", _)))
+        ));
+    }
+
+    #[test]
+    fn error_fields() {
+        assert_eq!(
+            rdata(RecordType::A, "192.0.2"),
+            Err((Position { line: 1, column: 1 }, ZoneFileErrorKind::InvalidField("address", String::from("192.0.2"))))
+        );
+        assert_eq!(
+            rdata(RecordType::SOA, "ns admin 1 2 3"),
+            Err((Position { line: 1, column: 1 }, ZoneFileErrorKind::MissingField("expire")))
+        );
+        assert_eq!(
+            rdata(RecordType::NS, "ns1 ns2"),
+            Err((Position { line: 1, column: 5 }, ZoneFileErrorKind::TrailingData(String::from("ns2"))))
+        );
+        assert!(matches!(
+            rdata(RecordType::DNSKEY, "256 2 5 AQAB"),
+            Err((Position { line: 1, column: 5 }, ZoneFileErrorKind::InvalidField("protocol", _)))
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::dnssec::canonical::canonical_rdata;
+use crate::packet::fqdn::{escape_label, Fqdn};
+use crate::packet::record::Record;
+use crate::records::record_data::RecordData;
+use crate::records::record_type::RecordType;
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Renders records as a master file that `ZoneParser` reads back.
+///
+/// Records are sorted by owner in canonical order (RFC 4034 section 6.1),
+/// with the SOA first and the other RRsets by type, each followed by the
+/// RRSIGs covering it. RRs within an RRset are in canonical RDATA order
+/// and duplicates are dropped, so the same records always give the same
+/// text. Owners are written relative to the origin and only on the first
+/// line of each name. Names inside RDATA stay absolute.
+#[derive(Debug, Clone)]
+pub struct ZoneWriter {
+    origin: Fqdn,
+}
+
+struct Line {
+    owner: String,
+    ttl: String,
+    class: String,
+    record_type: String,
+    data: String,
+}
+
+impl ZoneWriter {
+    pub fn new(origin: Fqdn) -> Self {
+        Self { origin }
+    }
+
+    pub fn write(&self, records: &[Record]) -> String {
+        let mut sorted: Vec<(&Record, Vec<u8>)> = records
+            .iter()
+            .map(|record| (record, canonical_rdata(record.data())))
+            .collect();
+        sorted.sort_by(|(record, rdata), (other, other_rdata)| {
+            canonical_order(record, other).then_with(|| rdata.cmp(other_rdata))
+        });
+        sorted.dedup_by(|(record, rdata), (other, other_rdata)| {
+            canonical_order(record, other) == Ordering::Equal && rdata == other_rdata
+        });
+
+        let mut previous_owner: Option<&Fqdn> = None;
+        let lines: Vec<Line> = sorted
+            .iter()
+            .map(|(record, _)| {
+                let owner = match previous_owner == Some(record.owner_name()) {
+                    true => String::new(),
+                    false => self.relative_name(record.owner_name()),
+                };
+                previous_owner = Some(record.owner_name());
+
+                Line {
+                    owner,
+                    ttl: record.ttl().to_string(),
+                    class: record.class().to_string(),
+                    record_type: record.record_type().to_string(),
+                    data: record.data().to_string(),
+                }
+            })
+            .collect();
+
+        let owner_width = lines.iter().map(|line| line.owner.len()).max().unwrap_or(0);
+        let ttl_width = lines.iter().map(|line| line.ttl.len()).max().unwrap_or(0);
+        let class_width = lines.iter().map(|line| line.class.len()).max().unwrap_or(0);
+        let type_width = lines.iter().map(|line| line.record_type.len()).max().unwrap_or(0);
+
+        let mut text = format!("$ORIGIN {}\n", self.origin);
+        for line in lines {
+            text.push_str(&format!(
+                "{:<owner_width$} {:>ttl_width$} {:<class_width$} {:<type_width$} {}\n",
+                line.owner, line.ttl, line.class, line.record_type, line.data
+            ));
+        }
+
+        text
+    }
+
+    pub fn write_file(&self, records: &[Record], path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.write(records))
+    }
+
+    /// `@` for the origin, the labels above it for names inside it and
+    /// the absolute name otherwise.
+    fn relative_name(&self, name: &Fqdn) -> String {
+        if name == &self.origin {
+            return String::from("@");
+        }
+
+        if self.origin.is_root() || !name.is_subdomain_of(&self.origin) {
+            return name.to_string();
+        }
+
+        let relative_labels = name.labels().len() - self.origin.labels().len();
+        name.labels()[..relative_labels]
+            .iter()
+            .map(|label| escape_label(label))
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+}
+
+/// Owner, then RRset position: SOA first, other types by number and each
+/// RRSIG right after the type it covers.
+fn canonical_order(record: &Record, other: &Record) -> Ordering {
+    record
+        .owner_name()
+        .cmp(other.owner_name())
+        .then_with(|| rrset_position(record).cmp(&rrset_position(other)))
+}
+
+fn rrset_position(record: &Record) -> (bool, u16, bool) {
+    let (record_type, is_signature) = match record.data() {
+        RecordData::RRSIG(rrsig) => (rrsig.type_covered(), true),
+        data => (data.record_type(), false),
+    };
+
+    (record_type != RecordType::SOA, u16::from(record_type), is_signature)
+}
+
+#[cfg(test)]
+mod writer_unittest {
+    use crate::clock::ManualClock;
+    use crate::dnssec::keys::keys_unittest::ed25519_key;
+    use crate::dnssec::signer::{DenialOfExistence, Nsec3Config, ZoneSigner};
+    use crate::packet::fqdn::{Fqdn, FqdnBuilder};
+    use crate::packet::record::Record;
+    use crate::zonefile::parser::ZoneParser;
+    use crate::zonefile::writer::ZoneWriter;
+    use std::sync::Arc;
+
+    const ZONE: &str = "\
+$ORIGIN example.com.
+$TTL 3600
+www     300 A   192.0.2.2
+www     300 A   192.0.2.1
+www     300 A   192.0.2.1
+@       NS      ns1
+@       SOA     ns1 hostmaster 2024010101 7200 3600 1209600 300
+ns1     A       192.0.2.53
+a\\.b   AAAA    2001:db8::1
+\\@     CNAME   www
+mail.example.net. A 198.51.100.1
+";
+
+    const WRITTEN: &str = "\
+$ORIGIN example.com.
+@                 3600 IN SOA   ns1.example.com. hostmaster.example.com. 2024010101 7200 3600 1209600 300
+                  3600 IN NS    ns1.example.com.
+\\@                3600 IN CNAME www.example.com.
+a\\.b              3600 IN AAAA  2001:db8::1
+ns1               3600 IN A     192.0.2.53
+www                300 IN A     192.0.2.1
+                   300 IN A     192.0.2.2
+mail.example.net. 3600 IN A     198.51.100.1
+";
+
+    fn origin() -> Fqdn {
+        FqdnBuilder::new().generate_from_string(String::from("example.com.")).build()
+    }
+
+    fn sorted(mut records: Vec<Record>) -> Vec<Record> {
+        records.sort_by_key(|record| (record.owner_name().clone(), u16::from(record.record_type()), record.data().to_string()));
+        records.dedup();
+
+        records
+    }
+
+    #[test]
+    fn write_zone() {
+        let records = ZoneParser::new(origin()).parse_str("zone", ZONE).unwrap();
+        let written = ZoneWriter::new(origin()).write(&records);
+
+        assert_eq!(written, WRITTEN);
+        assert_eq!(
+            sorted(ZoneParser::new(Fqdn::root()).parse_str("written", &written).unwrap()),
+            sorted(records)
+        );
+    }
+
+    #[test]
+    fn signed_zone_round_trip() {
+        let records = ZoneParser::new(origin()).parse_str("zone", ZONE).unwrap();
+        let records: Vec<Record> = records
+            .into_iter()
+            .filter(|record| record.owner_name().is_subdomain_of(&origin()))
+            .collect();
+        let signed = ZoneSigner::new(origin(), vec![ed25519_key()], Arc::new(ManualClock::new(1_700_000_000)))
+            .denial(DenialOfExistence::Nsec3(Nsec3Config::new(0, vec![0xab, 0xcd], false)))
+            .sign_zone(&records)
+            .unwrap();
+
+        let written = ZoneWriter::new(origin()).write(&signed);
+        let lines: Vec<&str> = written.lines().collect();
+        assert!(lines[1].starts_with("@ ") && lines[1].contains(" SOA "));
+        assert!(lines[2].contains(" RRSIG ") && lines[2].contains(" SOA 15 2 "));
+
+        let parsed = ZoneParser::new(origin()).parse_str("written", &written).unwrap();
+        assert_eq!(sorted(parsed), sorted(signed));
+        assert_eq!(ZoneWriter::new(origin()).write(&ZoneParser::new(origin()).parse_str("written", &written).unwrap()), written);
+    }
+}